@@ -0,0 +1,459 @@
+// SPDX-License-Identifier: GPL-2.0-only
+// Modifications Copyright Authors of bpfman
+
+// Derived from:
+// https://github.com/xdp-project/xdp-tools/blob/master/lib/libxdp/xdp-dispatcher.c.in
+
+// clang-format off
+#include <linux/bpf.h>
+#include <linux/in.h>
+#include <bpf/bpf_endian.h>
+#include <bpf/bpf_helpers.h>
+// clang-format on
+
+#define XDP_METADATA_SECTION "xdp_metadata"
+/* The version of libxdp's dispatcher protocol this image follows. */
+#define XDP_DISPATCHER_VERSION 2
+#define XDP_DISPATCHER_MAGIC 236
+#define XDP_DISPATCHER_RETVAL 31
+#define MAX_DISPATCHER_ACTIONS 10
+#define MAX_WIDE_DISPATCHER_ACTIONS 100
+
+struct xdp_dispatcher_conf {
+  __u8 magic;              /* Set to XDP_DISPATCHER_MAGIC */
+  __u8 dispatcher_version; /* Set to XDP_DISPATCHER_VERSION */
+  __u8 num_progs_enabled;  /* Number of active program slots */
+  __u8 is_xdp_frags; /* Whether this dispatcher is loaded with XDP frags support
+                      */
+  __u32 chain_call_actions[MAX_DISPATCHER_ACTIONS];
+  __u32 run_prios[MAX_DISPATCHER_ACTIONS];
+  __u32 program_flags[MAX_DISPATCHER_ACTIONS];
+};
+
+/* While 'const volatile' sounds a little like an oxymoron, there's reason
+ * behind the madness:
+ *
+ * - const places the data in rodata, where libbpf will mark it as read-only and
+ *   frozen on program load, letting the kernel do dead code elimination based
+ *   on the values.
+ *
+ * - volatile prevents the compiler from optimising away the checks based on the
+ *   compile-time value of the variables, which is important since we will be
+ *   changing the values before loading the program into the kernel.
+ */
+static volatile const struct xdp_dispatcher_conf conf = {};
+
+/* The configuration of the wide dispatcher, which has room for more programs
+ * than libxdp's layout of the configuration above.
+ */
+struct xdp_wide_dispatcher_conf {
+  __u8 num_progs_enabled; /* Number of active program slots */
+  __u32 chain_call_actions[MAX_WIDE_DISPATCHER_ACTIONS];
+  __u32 run_prios[MAX_WIDE_DISPATCHER_ACTIONS];
+  __u32 program_flags[MAX_WIDE_DISPATCHER_ACTIONS];
+};
+
+static volatile const struct xdp_wide_dispatcher_conf wide_conf = {};
+
+__attribute__((noinline)) int prog0(struct xdp_md *ctx) {
+  volatile int ret = XDP_DISPATCHER_RETVAL;
+
+  if (!ctx)
+    return XDP_ABORTED;
+  return ret;
+}
+
+__attribute__((noinline)) int prog1(struct xdp_md *ctx) {
+  volatile int ret = XDP_DISPATCHER_RETVAL;
+
+  if (!ctx)
+    return XDP_ABORTED;
+  return ret;
+}
+
+__attribute__((noinline)) int prog2(struct xdp_md *ctx) {
+  volatile int ret = XDP_DISPATCHER_RETVAL;
+
+  if (!ctx)
+    return XDP_ABORTED;
+  return ret;
+}
+
+__attribute__((noinline)) int prog3(struct xdp_md *ctx) {
+  volatile int ret = XDP_DISPATCHER_RETVAL;
+
+  if (!ctx)
+    return XDP_ABORTED;
+  return ret;
+}
+
+__attribute__((noinline)) int prog4(struct xdp_md *ctx) {
+  volatile int ret = XDP_DISPATCHER_RETVAL;
+
+  if (!ctx)
+    return XDP_ABORTED;
+  return ret;
+}
+
+__attribute__((noinline)) int prog5(struct xdp_md *ctx) {
+  volatile int ret = XDP_DISPATCHER_RETVAL;
+
+  if (!ctx)
+    return XDP_ABORTED;
+  return ret;
+}
+
+__attribute__((noinline)) int prog6(struct xdp_md *ctx) {
+  volatile int ret = XDP_DISPATCHER_RETVAL;
+
+  if (!ctx)
+    return XDP_ABORTED;
+  return ret;
+}
+
+__attribute__((noinline)) int prog7(struct xdp_md *ctx) {
+  volatile int ret = XDP_DISPATCHER_RETVAL;
+
+  if (!ctx)
+    return XDP_ABORTED;
+  return ret;
+}
+
+__attribute__((noinline)) int prog8(struct xdp_md *ctx) {
+  volatile int ret = XDP_DISPATCHER_RETVAL;
+
+  if (!ctx)
+    return XDP_ABORTED;
+  return ret;
+}
+
+__attribute__((noinline)) int prog9(struct xdp_md *ctx) {
+  volatile int ret = XDP_DISPATCHER_RETVAL;
+
+  if (!ctx)
+    return XDP_ABORTED;
+  return ret;
+}
+
+/* The slots only the wide dispatcher has. */
+#define WIDE_STUB(n)                                                           \
+  __attribute__((noinline)) int prog##n(struct xdp_md *ctx) {                  \
+    volatile int ret = XDP_DISPATCHER_RETVAL;                                  \
+                                                                               \
+    if (!ctx)                                                                  \
+      return XDP_ABORTED;                                                      \
+    return ret;                                                                \
+  }
+
+WIDE_STUB(10)
+WIDE_STUB(11)
+WIDE_STUB(12)
+WIDE_STUB(13)
+WIDE_STUB(14)
+WIDE_STUB(15)
+WIDE_STUB(16)
+WIDE_STUB(17)
+WIDE_STUB(18)
+WIDE_STUB(19)
+WIDE_STUB(20)
+WIDE_STUB(21)
+WIDE_STUB(22)
+WIDE_STUB(23)
+WIDE_STUB(24)
+WIDE_STUB(25)
+WIDE_STUB(26)
+WIDE_STUB(27)
+WIDE_STUB(28)
+WIDE_STUB(29)
+WIDE_STUB(30)
+WIDE_STUB(31)
+WIDE_STUB(32)
+WIDE_STUB(33)
+WIDE_STUB(34)
+WIDE_STUB(35)
+WIDE_STUB(36)
+WIDE_STUB(37)
+WIDE_STUB(38)
+WIDE_STUB(39)
+WIDE_STUB(40)
+WIDE_STUB(41)
+WIDE_STUB(42)
+WIDE_STUB(43)
+WIDE_STUB(44)
+WIDE_STUB(45)
+WIDE_STUB(46)
+WIDE_STUB(47)
+WIDE_STUB(48)
+WIDE_STUB(49)
+WIDE_STUB(50)
+WIDE_STUB(51)
+WIDE_STUB(52)
+WIDE_STUB(53)
+WIDE_STUB(54)
+WIDE_STUB(55)
+WIDE_STUB(56)
+WIDE_STUB(57)
+WIDE_STUB(58)
+WIDE_STUB(59)
+WIDE_STUB(60)
+WIDE_STUB(61)
+WIDE_STUB(62)
+WIDE_STUB(63)
+WIDE_STUB(64)
+WIDE_STUB(65)
+WIDE_STUB(66)
+WIDE_STUB(67)
+WIDE_STUB(68)
+WIDE_STUB(69)
+WIDE_STUB(70)
+WIDE_STUB(71)
+WIDE_STUB(72)
+WIDE_STUB(73)
+WIDE_STUB(74)
+WIDE_STUB(75)
+WIDE_STUB(76)
+WIDE_STUB(77)
+WIDE_STUB(78)
+WIDE_STUB(79)
+WIDE_STUB(80)
+WIDE_STUB(81)
+WIDE_STUB(82)
+WIDE_STUB(83)
+WIDE_STUB(84)
+WIDE_STUB(85)
+WIDE_STUB(86)
+WIDE_STUB(87)
+WIDE_STUB(88)
+WIDE_STUB(89)
+WIDE_STUB(90)
+WIDE_STUB(91)
+WIDE_STUB(92)
+WIDE_STUB(93)
+WIDE_STUB(94)
+WIDE_STUB(95)
+WIDE_STUB(96)
+WIDE_STUB(97)
+WIDE_STUB(98)
+WIDE_STUB(99)
+
+__attribute__((noinline)) int compat_test(struct xdp_md *ctx) {
+  volatile int ret = XDP_DISPATCHER_RETVAL;
+
+  if (!ctx)
+    return XDP_ABORTED;
+  return ret;
+}
+
+static __always_inline int dispatch(struct xdp_md *ctx) {
+  __u8 num_progs_enabled = conf.num_progs_enabled;
+  int ret;
+
+  if (num_progs_enabled < 1)
+    goto out;
+  ret = prog0(ctx);
+  if (!((1U << ret) & conf.chain_call_actions[0]))
+    return ret;
+
+  if (num_progs_enabled < 2)
+    goto out;
+  ret = prog1(ctx);
+  if (!((1U << ret) & conf.chain_call_actions[1]))
+    return ret;
+
+  if (num_progs_enabled < 3)
+    goto out;
+  ret = prog2(ctx);
+  if (!((1U << ret) & conf.chain_call_actions[2]))
+    return ret;
+
+  if (num_progs_enabled < 4)
+    goto out;
+  ret = prog3(ctx);
+  if (!((1U << ret) & conf.chain_call_actions[3]))
+    return ret;
+
+  if (num_progs_enabled < 5)
+    goto out;
+  ret = prog4(ctx);
+  if (!((1U << ret) & conf.chain_call_actions[4]))
+    return ret;
+
+  if (num_progs_enabled < 6)
+    goto out;
+  ret = prog5(ctx);
+  if (!((1U << ret) & conf.chain_call_actions[5]))
+    return ret;
+
+  if (num_progs_enabled < 7)
+    goto out;
+  ret = prog6(ctx);
+  if (!((1U << ret) & conf.chain_call_actions[6]))
+    return ret;
+
+  if (num_progs_enabled < 8)
+    goto out;
+  ret = prog7(ctx);
+  if (!((1U << ret) & conf.chain_call_actions[7]))
+    return ret;
+
+  if (num_progs_enabled < 9)
+    goto out;
+  ret = prog8(ctx);
+  if (!((1U << ret) & conf.chain_call_actions[8]))
+    return ret;
+
+  if (num_progs_enabled < 10)
+    goto out;
+  ret = prog9(ctx);
+  if (!((1U << ret) & conf.chain_call_actions[9]))
+    return ret;
+
+  /* keep a reference to the compat_test() function so we can use it
+   * as an freplace target in xdp_multiprog__check_compat() in libxdp
+   */
+  if (num_progs_enabled < 11)
+    goto out;
+  ret = compat_test(ctx);
+out:
+  return XDP_PASS;
+}
+
+#define WIDE_SLOT(n)                                                           \
+  if (wide_conf.num_progs_enabled < n + 1)                                     \
+    goto out;                                                                  \
+  ret = prog##n(ctx);                                                          \
+  if (!((1U << ret) & wide_conf.chain_call_actions[n]))                        \
+    return ret;
+
+/* Runs up to MAX_WIDE_DISPATCHER_ACTIONS programs. A second dispatcher can't
+ * be chained from the last slot: it would have to replace that slot as an
+ * extension, and the kernel doesn't let extensions replace the functions of
+ * another extension.
+ */
+static __always_inline int dispatch_wide(struct xdp_md *ctx) {
+  int ret;
+
+  WIDE_SLOT(0)
+  WIDE_SLOT(1)
+  WIDE_SLOT(2)
+  WIDE_SLOT(3)
+  WIDE_SLOT(4)
+  WIDE_SLOT(5)
+  WIDE_SLOT(6)
+  WIDE_SLOT(7)
+  WIDE_SLOT(8)
+  WIDE_SLOT(9)
+  WIDE_SLOT(10)
+  WIDE_SLOT(11)
+  WIDE_SLOT(12)
+  WIDE_SLOT(13)
+  WIDE_SLOT(14)
+  WIDE_SLOT(15)
+  WIDE_SLOT(16)
+  WIDE_SLOT(17)
+  WIDE_SLOT(18)
+  WIDE_SLOT(19)
+  WIDE_SLOT(20)
+  WIDE_SLOT(21)
+  WIDE_SLOT(22)
+  WIDE_SLOT(23)
+  WIDE_SLOT(24)
+  WIDE_SLOT(25)
+  WIDE_SLOT(26)
+  WIDE_SLOT(27)
+  WIDE_SLOT(28)
+  WIDE_SLOT(29)
+  WIDE_SLOT(30)
+  WIDE_SLOT(31)
+  WIDE_SLOT(32)
+  WIDE_SLOT(33)
+  WIDE_SLOT(34)
+  WIDE_SLOT(35)
+  WIDE_SLOT(36)
+  WIDE_SLOT(37)
+  WIDE_SLOT(38)
+  WIDE_SLOT(39)
+  WIDE_SLOT(40)
+  WIDE_SLOT(41)
+  WIDE_SLOT(42)
+  WIDE_SLOT(43)
+  WIDE_SLOT(44)
+  WIDE_SLOT(45)
+  WIDE_SLOT(46)
+  WIDE_SLOT(47)
+  WIDE_SLOT(48)
+  WIDE_SLOT(49)
+  WIDE_SLOT(50)
+  WIDE_SLOT(51)
+  WIDE_SLOT(52)
+  WIDE_SLOT(53)
+  WIDE_SLOT(54)
+  WIDE_SLOT(55)
+  WIDE_SLOT(56)
+  WIDE_SLOT(57)
+  WIDE_SLOT(58)
+  WIDE_SLOT(59)
+  WIDE_SLOT(60)
+  WIDE_SLOT(61)
+  WIDE_SLOT(62)
+  WIDE_SLOT(63)
+  WIDE_SLOT(64)
+  WIDE_SLOT(65)
+  WIDE_SLOT(66)
+  WIDE_SLOT(67)
+  WIDE_SLOT(68)
+  WIDE_SLOT(69)
+  WIDE_SLOT(70)
+  WIDE_SLOT(71)
+  WIDE_SLOT(72)
+  WIDE_SLOT(73)
+  WIDE_SLOT(74)
+  WIDE_SLOT(75)
+  WIDE_SLOT(76)
+  WIDE_SLOT(77)
+  WIDE_SLOT(78)
+  WIDE_SLOT(79)
+  WIDE_SLOT(80)
+  WIDE_SLOT(81)
+  WIDE_SLOT(82)
+  WIDE_SLOT(83)
+  WIDE_SLOT(84)
+  WIDE_SLOT(85)
+  WIDE_SLOT(86)
+  WIDE_SLOT(87)
+  WIDE_SLOT(88)
+  WIDE_SLOT(89)
+  WIDE_SLOT(90)
+  WIDE_SLOT(91)
+  WIDE_SLOT(92)
+  WIDE_SLOT(93)
+  WIDE_SLOT(94)
+  WIDE_SLOT(95)
+  WIDE_SLOT(96)
+  WIDE_SLOT(97)
+  WIDE_SLOT(98)
+  WIDE_SLOT(99)
+out:
+  return XDP_PASS;
+}
+
+SEC("xdp")
+int xdp_dispatcher(struct xdp_md *ctx) { return dispatch(ctx); }
+
+/* The same dispatcher loaded with frags support, for programs which handle
+ * multi-buffer packets. The programs it runs have to support them too.
+ */
+SEC("xdp.frags")
+int xdp_dispatcher_frags(struct xdp_md *ctx) { return dispatch(ctx); }
+
+/* The dispatcher for interfaces with more than MAX_DISPATCHER_ACTIONS
+ * programs, with and without frags support. libxdp doesn't know it.
+ */
+SEC("xdp")
+int xdp_dispatcher_wide(struct xdp_md *ctx) { return dispatch_wide(ctx); }
+
+SEC("xdp.frags")
+int xdp_dispatcher_wide_frags(struct xdp_md *ctx) { return dispatch_wide(ctx); }
+
+char _license[] SEC("license") = "GPL";
+__uint(dispatcher_version, XDP_DISPATCHER_VERSION) SEC(XDP_METADATA_SECTION);
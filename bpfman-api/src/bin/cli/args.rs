@@ -7,10 +7,19 @@ use std::{
     str::FromStr,
 };
 
-use bpfman::types::ProgramType;
-use clap::{ArgGroup, Args, Parser, Subcommand};
+use bpfman::{
+    errors::ParseError,
+    types::{ProgramType, VerifierLogLevel},
+};
+use clap::{
+    builder::{PossibleValuesParser, TypedValueParser},
+    ArgGroup, Args, Parser, Subcommand, ValueEnum,
+};
 use hex::FromHex;
 
+/// The unix socket bpfman-rpc listens on by default.
+const DEFAULT_SOCKET: &str = "unix:/run/bpfman-sock/bpfman.sock";
+
 #[derive(Parser, Debug)]
 #[command(
     long_about = "An eBPF manager focusing on simplifying the deployment and administration of eBPF programs."
@@ -20,6 +29,22 @@ use hex::FromHex;
 pub(crate) struct Cli {
     #[command(subcommand)]
     pub(crate) command: Commands,
+
+    /// Optional: Send requests to a running bpfman-rpc instead of managing
+    /// eBPF programs directly. Takes the path of a unix socket, optionally
+    /// prefixed with "unix:", or an "http://" endpoint. Without a value, the
+    /// default bpfman-rpc socket is used.
+    /// Example: --remote=unix:/run/bpfman-sock/bpfman.sock
+    #[clap(
+        long,
+        global = true,
+        env = "BPFMAN_REMOTE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = DEFAULT_SOCKET,
+        verbatim_doc_comment
+    )]
+    pub(crate) remote: Option<String>,
 }
 
 #[derive(Subcommand, Debug)]
@@ -33,9 +58,25 @@ pub(crate) enum Commands {
     List(ListArgs),
     /// Get an eBPF program using the Program Id.
     Get(GetArgs),
+    /// Attach a loaded eBPF program to another attach point.
+    Attach(AttachArgs),
+    /// Detach an eBPF program from an attach point using the Link Id.
+    Detach(DetachArgs),
+    /// Change the priority or proceed-on actions of an XDP or TC program.
+    Reorder(ReorderArgs),
+    /// Run an XDP or TC program disabled with the disable command again.
+    Enable(EnableArgs),
+    /// Stop running an XDP or TC program without unloading it.
+    Disable(DisableArgs),
     /// eBPF Bytecode Image related commands.
     #[command(subcommand)]
     Image(ImageSubCommand),
+    /// Inspect and modify the maps of an eBPF program loaded via bpfman.
+    #[command(subcommand)]
+    Map(MapSubcommand),
+    /// Inspect the XDP and TC dispatchers which run several programs on an interface.
+    #[command(subcommand)]
+    Dispatcher(DispatcherSubcommand),
 }
 
 #[derive(Subcommand, Debug)]
@@ -83,6 +124,21 @@ pub(crate) struct LoadFileArgs {
     #[clap(long, verbatim_doc_comment)]
     pub(crate) map_owner_id: Option<u32>,
 
+    /// Optional: What the verifier logs while checking the program, which is
+    /// printed if the program is rejected. Multiple levels are combined.
+    /// Example: --verifier-log-level verbose,stats
+    #[clap(long, verbatim_doc_comment, value_delimiter = ',',
+        value_parser = PossibleValuesParser::new(["disable", "debug", "verbose", "stats"])
+            .map(parse_verifier_log_level),
+        default_values = ["debug", "stats"])]
+    pub(crate) verifier_log_level: Vec<VerifierLogLevel>,
+
+    /// Optional: Only load the program to check that it passes the verifier,
+    /// then unload it again without attaching it or saving any state.
+    /// XDP and TC programs are checked on their own, not in a dispatcher.
+    #[clap(long, verbatim_doc_comment)]
+    pub(crate) dry_run: bool,
+
     #[clap(subcommand)]
     pub(crate) command: LoadCommands,
 }
@@ -122,6 +178,21 @@ pub(crate) struct LoadImageArgs {
     #[clap(long, verbatim_doc_comment)]
     pub(crate) map_owner_id: Option<u32>,
 
+    /// Optional: What the verifier logs while checking the program, which is
+    /// printed if the program is rejected. Multiple levels are combined.
+    /// Example: --verifier-log-level verbose,stats
+    #[clap(long, verbatim_doc_comment, value_delimiter = ',',
+        value_parser = PossibleValuesParser::new(["disable", "debug", "verbose", "stats"])
+            .map(parse_verifier_log_level),
+        default_values = ["debug", "stats"])]
+    pub(crate) verifier_log_level: Vec<VerifierLogLevel>,
+
+    /// Optional: Only load the program to check that it passes the verifier,
+    /// then unload it again without attaching it or saving any state.
+    /// XDP and TC programs are checked on their own, not in a dispatcher.
+    #[clap(long, verbatim_doc_comment)]
+    pub(crate) dry_run: bool,
+
     #[clap(subcommand)]
     pub(crate) command: LoadCommands,
 }
@@ -139,12 +210,21 @@ pub(crate) enum LoadCommands {
     /// Install an eBPF program on the XDP hook point for a given interface.
     Xdp {
         /// Required: Interface to load program on.
-        #[clap(short, long)]
+        /// A glob pattern, such as "ens*", loads the program on every
+        /// matching interface, including ones added later.
+        /// A pattern can't be used with --netns.
+        #[clap(short, long, verbatim_doc_comment)]
         iface: String,
 
         /// Required: Priority to run program in chain. Lower value runs first.
-        #[clap(short, long)]
-        priority: i32,
+        /// Not required with --run-config, which defaults it to 50.
+        #[clap(
+            short,
+            long,
+            verbatim_doc_comment,
+            required_unless_present = "run_config"
+        )]
+        priority: Option<i32>,
 
         /// Optional: Proceed to call other programs in chain on this exit code.
         /// Multiple values supported by repeating the parameter.
@@ -155,6 +235,42 @@ pub(crate) enum LoadCommands {
         /// [default: pass, dispatcher_return]
         #[clap(long, verbatim_doc_comment, num_args(1..))]
         proceed_on: Vec<String>,
+
+        /// Optional: Network namespace the interface is in.
+        /// Its interfaces aren't watched for removal by bpfman-rpc.
+        /// Example: --netns /var/run/netns/blue
+        #[clap(long, verbatim_doc_comment, conflicts_with = "container_pid")]
+        netns: Option<String>,
+
+        /// Optional: Host PID of container whose network namespace the
+        /// interface is in.
+        #[clap(short, long)]
+        container_pid: Option<i32>,
+
+        /// Optional: Load the program with support for multi-buffer packets,
+        /// such as jumbo frames, as if it were in an "xdp.frags" section.
+        /// Programs in an "xdp.frags" section are detected without it.
+        /// All of the XDP programs on an interface must support them or not.
+        #[clap(long, verbatim_doc_comment)]
+        frags: bool,
+
+        /// Optional: Mode to attach the interface's XDP dispatcher in,
+        /// overriding the xdp_mode configured for the interface.
+        /// All of the XDP programs on an interface which give a mode must agree.
+        ///
+        /// [possible values: skb, drv, hw]
+        #[clap(long, verbatim_doc_comment)]
+        mode: Option<String>,
+
+        /// Optional: Attach the dispatcher in skb mode if the driver doesn't
+        /// support the mode given with --mode.
+        #[clap(long, verbatim_doc_comment, requires = "mode")]
+        mode_fallback: bool,
+
+        /// Optional: Take the priority and proceed-on actions from the
+        /// XDP_RUN_CONFIG libxdp reads from the program's BTF, if it has one.
+        #[clap(long, verbatim_doc_comment)]
+        run_config: bool,
     },
     #[command(disable_version_flag = true)]
     /// Install an eBPF program on the TC hook point for a given interface.
@@ -166,7 +282,10 @@ pub(crate) enum LoadCommands {
         direction: String,
 
         /// Required: Interface to load program on.
-        #[clap(short, long)]
+        /// A glob pattern, such as "ens*", loads the program on every
+        /// matching interface, including ones added later.
+        /// A pattern can't be used with --netns.
+        #[clap(short, long, verbatim_doc_comment)]
         iface: String,
 
         /// Required: Priority to run program in chain. Lower value runs first.
@@ -183,6 +302,17 @@ pub(crate) enum LoadCommands {
         /// [default: ok, pipe, dispatcher_return]
         #[clap(long, verbatim_doc_comment, num_args(1..))]
         proceed_on: Vec<String>,
+
+        /// Optional: Network namespace the interface is in.
+        /// Its interfaces aren't watched for removal by bpfman-rpc.
+        /// Example: --netns /var/run/netns/blue
+        #[clap(long, verbatim_doc_comment, conflicts_with = "container_pid")]
+        netns: Option<String>,
+
+        /// Optional: Host PID of container whose network namespace the
+        /// interface is in.
+        #[clap(short, long)]
+        container_pid: Option<i32>,
     },
     #[command(disable_version_flag = true)]
     /// Install an eBPF program on a Tracepoint.
@@ -211,7 +341,8 @@ pub(crate) enum LoadCommands {
         retprobe: bool,
 
         /// Optional: Host PID of container to attach the kprobe in.
-        /// (NOT CURRENTLY SUPPORTED)
+        /// The program must read a `bpfman_container_cgroup_id` global,
+        /// which is set to the container's cgroup id, to filter on it.
         #[clap(short, long)]
         container_pid: Option<i32>,
     },
@@ -264,6 +395,47 @@ pub(crate) enum LoadCommands {
         #[clap(short, long)]
         fn_name: String,
     },
+    #[command(disable_version_flag = true)]
+    /// Install a cgroup_skb eBPF program on a cgroup.
+    CgroupSkb {
+        /// Required: Path to the cgroup to attach the program to.
+        /// Example: --cgroup-path "/sys/fs/cgroup/system.slice"
+        #[clap(short, long, verbatim_doc_comment)]
+        cgroup_path: String,
+
+        /// Required: Direction to apply program.
+        ///
+        /// [possible values: ingress, egress]
+        #[clap(short, long, verbatim_doc_comment)]
+        direction: String,
+    },
+    #[command(disable_version_flag = true)]
+    /// Install a cgroup_sock_addr eBPF program on a cgroup.
+    ///
+    /// The hook the program runs on comes from its section name, e.g.
+    /// "cgroup/connect4".
+    CgroupSockAddr {
+        /// Required: Path to the cgroup to attach the program to.
+        /// Example: --cgroup-path "/sys/fs/cgroup/system.slice"
+        #[clap(short, long, verbatim_doc_comment)]
+        cgroup_path: String,
+    },
+    #[command(disable_version_flag = true)]
+    /// Install a sockops eBPF program on a cgroup.
+    Sockops {
+        /// Required: Path to the cgroup to attach the program to.
+        /// Example: --cgroup-path "/sys/fs/cgroup/system.slice"
+        #[clap(short, long, verbatim_doc_comment)]
+        cgroup_path: String,
+    },
+    #[command(disable_version_flag = true)]
+    /// Install an sk_msg eBPF program on a sockmap or sockhash.
+    SkMsg {
+        /// Required: Name of the sockmap or sockhash, from the maps used by
+        /// the program, to attach the program to.
+        #[clap(short, long, verbatim_doc_comment)]
+        map_name: String,
+    },
 }
 
 #[derive(Args, Debug)]
@@ -273,6 +445,210 @@ pub(crate) struct UnloadArgs {
     pub(crate) program_id: u32,
 }
 
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct AttachArgs {
+    /// Required: Program Id of the loaded program to attach.
+    pub(crate) program_id: u32,
+
+    #[clap(subcommand)]
+    pub(crate) command: AttachCommands,
+}
+
+/// AttachCommands are the attach points a loaded program can be attached to.
+/// The other properties of the attachment, such as whether a probe is a
+/// retprobe, are those the program was loaded with.
+#[derive(Subcommand, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) enum AttachCommands {
+    #[command(disable_version_flag = true)]
+    /// Attach an XDP program to the XDP hook point of another interface.
+    Xdp {
+        /// Required: Interface to attach the program to.
+        #[clap(short, long)]
+        iface: String,
+
+        /// Required: Priority to run program in chain. Lower value runs first.
+        #[clap(short, long)]
+        priority: i32,
+
+        /// Optional: Proceed to call other programs in chain on this exit code.
+        /// Multiple values supported by repeating the parameter.
+        /// Example: --proceed-on "pass" --proceed-on "drop"
+        ///
+        /// [possible values: aborted, drop, pass, tx, redirect, dispatcher_return]
+        ///
+        /// [default: pass, dispatcher_return]
+        #[clap(long, verbatim_doc_comment, num_args(1..))]
+        proceed_on: Vec<String>,
+
+        /// Optional: Network namespace the interface is in.
+        /// Its interfaces aren't watched for removal by bpfman-rpc.
+        /// Example: --netns /var/run/netns/blue
+        #[clap(long, verbatim_doc_comment, conflicts_with = "container_pid")]
+        netns: Option<String>,
+
+        /// Optional: Host PID of container whose network namespace the
+        /// interface is in.
+        #[clap(short, long)]
+        container_pid: Option<i32>,
+    },
+    #[command(disable_version_flag = true)]
+    /// Attach a TC program to the TC hook point of another interface.
+    Tc {
+        /// Required: Direction to apply program.
+        ///
+        /// [possible values: ingress, egress]
+        #[clap(short, long, verbatim_doc_comment)]
+        direction: String,
+
+        /// Required: Interface to attach the program to.
+        #[clap(short, long)]
+        iface: String,
+
+        /// Required: Priority to run program in chain. Lower value runs first.
+        #[clap(short, long)]
+        priority: i32,
+
+        /// Optional: Proceed to call other programs in chain on this exit code.
+        /// Multiple values supported by repeating the parameter.
+        /// Example: --proceed-on "ok" --proceed-on "pipe"
+        ///
+        /// [possible values: unspec, ok, reclassify, shot, pipe, stolen, queued,
+        ///                   repeat, redirect, trap, dispatcher_return]
+        ///
+        /// [default: ok, pipe, dispatcher_return]
+        #[clap(long, verbatim_doc_comment, num_args(1..))]
+        proceed_on: Vec<String>,
+
+        /// Optional: Network namespace the interface is in.
+        /// Its interfaces aren't watched for removal by bpfman-rpc.
+        /// Example: --netns /var/run/netns/blue
+        #[clap(long, verbatim_doc_comment, conflicts_with = "container_pid")]
+        netns: Option<String>,
+
+        /// Optional: Host PID of container whose network namespace the
+        /// interface is in.
+        #[clap(short, long)]
+        container_pid: Option<i32>,
+    },
+    #[command(disable_version_flag = true)]
+    /// Attach a tracepoint program to another tracepoint.
+    Tracepoint {
+        /// Required: The tracepoint to attach to.
+        /// Example: --tracepoint "sched/sched_switch"
+        #[clap(short, long, verbatim_doc_comment)]
+        tracepoint: String,
+    },
+    #[command(disable_version_flag = true)]
+    /// Attach a kprobe or kretprobe program to another function.
+    Kprobe {
+        /// Required: Function to attach the kprobe to.
+        #[clap(short, long)]
+        fn_name: String,
+
+        /// Optional: Offset added to the address of the function for kprobe.
+        /// Not allowed for kretprobes.
+        #[clap(short, long, verbatim_doc_comment)]
+        offset: Option<u64>,
+    },
+    #[command(disable_version_flag = true)]
+    /// Attach a uprobe or uretprobe program to another function or target.
+    Uprobe {
+        /// Optional: Function to attach the uprobe to.
+        #[clap(short, long)]
+        fn_name: Option<String>,
+
+        /// Optional: Offset added to the address of the target function (or
+        /// beginning of target if no function is identified).
+        #[clap(short, long, verbatim_doc_comment)]
+        offset: Option<u64>,
+
+        /// Required: Library name or the absolute path to a binary or library.
+        /// Example: --target "libc".
+        #[clap(short, long, verbatim_doc_comment)]
+        target: String,
+
+        /// Optional: Only execute uprobe for given process identification number (PID).
+        /// If PID is not provided, uprobe executes for all PIDs.
+        #[clap(short, long, verbatim_doc_comment)]
+        pid: Option<i32>,
+
+        /// Optional: Host PID of container to attach the uprobe in.
+        #[clap(short, long)]
+        container_pid: Option<i32>,
+    },
+    #[command(disable_version_flag = true)]
+    /// Attach a cgroup_skb program to another cgroup.
+    CgroupSkb {
+        /// Required: Path to the cgroup to attach the program to.
+        /// Example: --cgroup-path "/sys/fs/cgroup/system.slice"
+        #[clap(short, long, verbatim_doc_comment)]
+        cgroup_path: String,
+    },
+    #[command(disable_version_flag = true)]
+    /// Attach a cgroup_sock_addr program to another cgroup.
+    CgroupSockAddr {
+        /// Required: Path to the cgroup to attach the program to.
+        /// Example: --cgroup-path "/sys/fs/cgroup/system.slice"
+        #[clap(short, long, verbatim_doc_comment)]
+        cgroup_path: String,
+    },
+    #[command(disable_version_flag = true)]
+    /// Attach a sockops program to another cgroup.
+    Sockops {
+        /// Required: Path to the cgroup to attach the program to.
+        /// Example: --cgroup-path "/sys/fs/cgroup/system.slice"
+        #[clap(short, long, verbatim_doc_comment)]
+        cgroup_path: String,
+    },
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct DetachArgs {
+    /// Required: Link Id to be detached, as printed by attach and get.
+    pub(crate) link_id: u32,
+}
+
+#[derive(Args, Debug)]
+#[clap(group(
+    ArgGroup::new("changes")
+        .required(true)
+        .multiple(true)
+        .args(&["priority", "proceed_on"]),
+))]
+#[command(disable_version_flag = true)]
+pub(crate) struct ReorderArgs {
+    /// Required: Program Id of the XDP or TC program to be reordered.
+    pub(crate) program_id: u32,
+
+    /// Optional: New priority to run program in chain. Lower value runs first.
+    #[clap(short, long)]
+    pub(crate) priority: Option<i32>,
+
+    /// Optional: New exit codes on which to proceed to call other programs in
+    /// chain, replacing the current ones. Values are the same as for the load
+    /// command of the program's type.
+    /// Example: --proceed-on "pass" --proceed-on "drop"
+    #[clap(long, verbatim_doc_comment, num_args(1..))]
+    pub(crate) proceed_on: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct EnableArgs {
+    /// Required: Program Id of the XDP or TC program to be enabled.
+    pub(crate) program_id: u32,
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct DisableArgs {
+    /// Required: Program Id of the XDP or TC program to be disabled.
+    pub(crate) program_id: u32,
+}
+
 #[derive(Args, Debug)]
 #[command(disable_version_flag = true)]
 pub(crate) struct ListArgs {
@@ -287,7 +663,7 @@ pub(crate) struct ListArgs {
     ///                   sk-reuseport, flow-dissector, cgroup-sysctl,
     ///                   raw-tracepoint-writable, cgroup-sockopt, tracing,
     ///                   struct-ops, ext, lsm, sk-lookup, syscall]
-    #[clap(short, long, verbatim_doc_comment, hide_possible_values = true)]
+    #[clap(short, long, verbatim_doc_comment, value_parser = parse_program_type)]
     pub(crate) program_type: Option<ProgramType>,
 
     /// Optional: List programs which contain a specific set of metadata labels
@@ -301,6 +677,11 @@ pub(crate) struct ListArgs {
     /// Optional: List all programs.
     #[clap(short, long, verbatim_doc_comment)]
     pub(crate) all: bool,
+
+    /// Optional: Output format. "wide" adds where each program is attached
+    /// and its metadata to the table.
+    #[clap(short, long, verbatim_doc_comment, value_enum, default_value_t)]
+    pub(crate) output: OutputFormat,
 }
 
 #[derive(Args, Debug)]
@@ -308,6 +689,114 @@ pub(crate) struct ListArgs {
 pub(crate) struct GetArgs {
     /// Required: Program Id to get.
     pub(crate) program_id: u32,
+
+    /// Optional: Output format.
+    #[clap(short, long, value_enum, default_value_t)]
+    pub(crate) output: OutputFormat,
+}
+
+/// OutputFormat is how the list, get, image pull and dispatcher commands print
+/// their results.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum OutputFormat {
+    /// Tables meant to be read by people.
+    #[default]
+    Table,
+    /// Tables with additional columns.
+    Wide,
+    /// JSON, in the schema described in the CLI guide.
+    Json,
+    /// YAML, in the same schema as JSON.
+    Yaml,
+}
+
+#[derive(Subcommand, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) enum MapSubcommand {
+    /// Get information about a map of a program.
+    Get(MapArgs),
+    /// Print all of the entries in a map of a program.
+    ///
+    /// Keys and values are printed as JSON if the map has BTF for them, and as
+    /// hex otherwise.
+    Dump(MapArgs),
+    /// Set the value of an entry in a map of a program, creating it if needed.
+    ///
+    /// Keys and values are given as JSON if the map has BTF for them, and as
+    /// hex otherwise. A 0x prefixed hex string is always accepted. The value of
+    /// an entry in a per-CPU map is set on every CPU.
+    ///
+    /// Examples:
+    ///    bpfman map update 6371 xdp_stats_map --key 2 --value '{"rx_packets": 0}'
+    ///    bpfman map update 6371 xdp_stats_map --key 0x02000000 --value 0x0000000000000000
+    #[command(verbatim_doc_comment)]
+    Update(MapUpdateArgs),
+    /// Delete an entry from a map of a program.
+    Delete(MapDeleteArgs),
+}
+
+#[derive(Subcommand, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) enum DispatcherSubcommand {
+    /// List the XDP and TC dispatchers of every interface.
+    List(DispatcherListArgs),
+    /// Get the dispatchers of an interface, with the programs they run in order.
+    Get(DispatcherGetArgs),
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct DispatcherListArgs {
+    /// Optional: Output format.
+    #[clap(short, long, value_enum, default_value_t)]
+    pub(crate) output: OutputFormat,
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct DispatcherGetArgs {
+    /// Required: Interface to get the dispatchers of.
+    pub(crate) iface: String,
+
+    /// Optional: Output format.
+    #[clap(short, long, value_enum, default_value_t)]
+    pub(crate) output: OutputFormat,
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct MapArgs {
+    /// Required: Id of the program using the map.
+    pub(crate) program_id: u32,
+
+    /// Required: Name of the map.
+    pub(crate) map_name: String,
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct MapUpdateArgs {
+    #[clap(flatten)]
+    pub(crate) map: MapArgs,
+
+    /// Required: Key of the entry.
+    #[clap(short, long)]
+    pub(crate) key: String,
+
+    /// Required: Value of the entry.
+    #[clap(short, long)]
+    pub(crate) value: String,
+}
+
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct MapDeleteArgs {
+    #[clap(flatten)]
+    pub(crate) map: MapArgs,
+
+    /// Required: Key of the entry.
+    #[clap(short, long)]
+    pub(crate) key: String,
 }
 
 #[derive(Subcommand, Debug)]
@@ -315,7 +804,7 @@ pub(crate) struct GetArgs {
 #[allow(clippy::large_enum_variant)]
 pub(crate) enum ImageSubCommand {
     /// Pull an eBPF bytecode image from a remote registry.
-    Pull(PullBytecodeArgs),
+    Pull(PullArgs),
     /// Build an eBPF bytecode image from local bytecode objects and push to a registry.
     ///
     /// To use, the --container-file and --tag must be included, as well as a pointer to
@@ -599,7 +1088,35 @@ pub(crate) struct PullBytecodeArgs {
     pub(crate) pull_policy: String,
 }
 
+#[derive(Args, Debug)]
+#[command(disable_version_flag = true)]
+pub(crate) struct PullArgs {
+    #[clap(flatten)]
+    pub(crate) pull_args: PullBytecodeArgs,
+
+    /// Optional: Output format.
+    #[clap(short, long, value_enum, default_value_t)]
+    pub(crate) output: OutputFormat,
+}
+
 /// Parse a single key-value pair
+// Program types are given in kebab case, such as socket-filter.
+fn parse_program_type(s: &str) -> Result<ProgramType, ParseError> {
+    match s {
+        "lwt-seg6-local" => Ok(ProgramType::LwtSeg6Local),
+        _ => ProgramType::try_from(s.replace('-', "_")),
+    }
+}
+
+fn parse_verifier_log_level(level: String) -> VerifierLogLevel {
+    match level.as_str() {
+        "disable" => VerifierLogLevel::Disable,
+        "debug" => VerifierLogLevel::Debug,
+        "verbose" => VerifierLogLevel::Verbose,
+        _ => VerifierLogLevel::Stats,
+    }
+}
+
 pub(crate) fn parse_key_val(s: &str) -> Result<(String, String), std::io::Error> {
     let pos = s.find('=').ok_or(std::io::ErrorKind::InvalidInput)?;
     Ok((s[..pos].to_string(), s[pos + 1..].to_string()))
@@ -0,0 +1,116 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use anyhow::bail;
+use bpfman::{
+    attach_program,
+    types::{AttachPoint, TcProceedOn, XdpProceedOn},
+    utils::netns_path,
+};
+use bpfman_api::v1::{AttachRequest, LinkInfo};
+
+use crate::{
+    args::{AttachArgs, AttachCommands},
+    remote::{connect, status_error},
+    table::ProgTable,
+};
+
+pub(crate) async fn execute_attach(args: &AttachArgs, remote: Option<&str>) -> anyhow::Result<()> {
+    let attach = args.command.get_attach_point()?;
+
+    let link: LinkInfo = match remote {
+        None => (&attach_program(args.program_id, attach).await?).try_into()?,
+        Some(endpoint) => connect(endpoint)
+            .await?
+            .attach(AttachRequest {
+                id: args.program_id,
+                attach: Some((&attach).into()),
+            })
+            .await
+            .map_err(status_error)?
+            .into_inner()
+            .link
+            .unwrap_or_default(),
+    };
+
+    ProgTable::new_link(&link).print();
+    Ok(())
+}
+
+impl AttachCommands {
+    pub(crate) fn get_attach_point(&self) -> Result<AttachPoint, anyhow::Error> {
+        match self {
+            AttachCommands::Xdp {
+                iface,
+                priority,
+                proceed_on,
+                netns,
+                container_pid,
+            } => {
+                let proceed_on = match XdpProceedOn::from_strings(proceed_on) {
+                    Ok(p) => XdpProceedOn::from_int32s(p.as_action_vec())?,
+                    Err(e) => bail!("error parsing proceed_on {e}"),
+                };
+                Ok(AttachPoint::Xdp {
+                    iface: iface.to_string(),
+                    priority: *priority,
+                    proceed_on,
+                    netns: netns_path(netns.clone(), *container_pid),
+                })
+            }
+            AttachCommands::Tc {
+                direction,
+                iface,
+                priority,
+                proceed_on,
+                netns,
+                container_pid,
+            } => {
+                match direction.as_str() {
+                    "ingress" | "egress" => (),
+                    other => bail!("{} is not a valid direction", other),
+                };
+                let proceed_on = match TcProceedOn::from_strings(proceed_on) {
+                    Ok(p) => TcProceedOn::from_int32s(p.as_action_vec())?,
+                    Err(e) => bail!("error parsing proceed_on {e}"),
+                };
+                Ok(AttachPoint::Tc {
+                    iface: iface.to_string(),
+                    priority: *priority,
+                    direction: direction.to_string().try_into()?,
+                    proceed_on,
+                    netns: netns_path(netns.clone(), *container_pid),
+                })
+            }
+            AttachCommands::Tracepoint { tracepoint } => Ok(AttachPoint::Tracepoint {
+                tracepoint: tracepoint.to_string(),
+            }),
+            AttachCommands::Kprobe { fn_name, offset } => Ok(AttachPoint::Kprobe {
+                fn_name: fn_name.to_string(),
+                offset: offset.unwrap_or(0),
+            }),
+            AttachCommands::Uprobe {
+                fn_name,
+                offset,
+                target,
+                pid,
+                container_pid,
+            } => Ok(AttachPoint::Uprobe {
+                fn_name: fn_name.clone(),
+                offset: offset.unwrap_or(0),
+                target: target.to_string(),
+                pid: *pid,
+                container_pid: *container_pid,
+            }),
+            AttachCommands::CgroupSkb { cgroup_path } => Ok(AttachPoint::CgroupSkb {
+                cgroup_path: cgroup_path.to_string(),
+            }),
+            AttachCommands::CgroupSockAddr { cgroup_path } => Ok(AttachPoint::CgroupSockAddr {
+                cgroup_path: cgroup_path.to_string(),
+            }),
+            AttachCommands::Sockops { cgroup_path } => Ok(AttachPoint::SockOps {
+                cgroup_path: cgroup_path.to_string(),
+            }),
+        }
+    }
+}
@@ -0,0 +1,29 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use bpfman::detach_link;
+use bpfman_api::v1::DetachRequest;
+
+use crate::{
+    args::DetachArgs,
+    remote::{connect, status_error},
+};
+
+pub(crate) async fn execute_detach(
+    args: &DetachArgs,
+    remote: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    match remote {
+        None => detach_link(args.link_id).await?,
+        Some(endpoint) => {
+            connect(endpoint)
+                .await?
+                .detach(DetachRequest {
+                    link_id: args.link_id,
+                })
+                .await
+                .map_err(status_error)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use anyhow::bail;
+use bpfman::list_dispatchers;
+use bpfman_api::v1::{DispatcherInfo, ListDispatchersRequest};
+
+use crate::{
+    args::{DispatcherSubcommand, OutputFormat},
+    output::{print, DispatcherOutput},
+    remote::{connect, status_error},
+    table::ProgTable,
+};
+
+impl DispatcherSubcommand {
+    pub(crate) async fn execute(&self, remote: Option<&str>) -> anyhow::Result<()> {
+        match self {
+            DispatcherSubcommand::List(args) => {
+                let dispatchers = get_dispatchers(None, remote).await?;
+                match args.output {
+                    OutputFormat::Table | OutputFormat::Wide => {
+                        ProgTable::new_dispatcher_list(&dispatchers).print()
+                    }
+                    format => print(&dispatcher_outputs(&dispatchers)?, format)?,
+                }
+            }
+            DispatcherSubcommand::Get(args) => {
+                let dispatchers = get_dispatchers(Some(&args.iface), remote).await?;
+                if dispatchers.is_empty() {
+                    bail!("interface {} has no dispatchers", args.iface);
+                }
+                match args.output {
+                    OutputFormat::Table | OutputFormat::Wide => {
+                        for d in &dispatchers {
+                            ProgTable::new_dispatcher(d)?.print();
+                            ProgTable::new_dispatcher_slots(d)?.print();
+                        }
+                    }
+                    format => print(&dispatcher_outputs(&dispatchers)?, format)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// Gets the dispatchers, either directly or through bpfman-rpc.
+async fn get_dispatchers(
+    iface: Option<&str>,
+    remote: Option<&str>,
+) -> anyhow::Result<Vec<DispatcherInfo>> {
+    Ok(match remote {
+        None => list_dispatchers(iface)
+            .await?
+            .into_iter()
+            .map(|d| d.into())
+            .collect(),
+        Some(endpoint) => {
+            connect(endpoint)
+                .await?
+                .list_dispatchers(ListDispatchersRequest {
+                    iface: iface.map(|i| i.to_string()),
+                })
+                .await
+                .map_err(status_error)?
+                .into_inner()
+                .dispatchers
+        }
+    })
+}
+
+fn dispatcher_outputs(dispatchers: &[DispatcherInfo]) -> anyhow::Result<Vec<DispatcherOutput>> {
+    dispatchers.iter().map(DispatcherOutput::new).collect()
+}
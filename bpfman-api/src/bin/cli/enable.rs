@@ -0,0 +1,70 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use bpfman::{disable_program, enable_program};
+use bpfman_api::v1::{DisableRequest, EnableRequest, KernelProgramInfo, ProgramInfo};
+
+use crate::{
+    args::{DisableArgs, EnableArgs},
+    remote::{connect, status_error},
+    table::ProgTable,
+};
+
+pub(crate) async fn execute_enable(args: &EnableArgs, remote: Option<&str>) -> anyhow::Result<()> {
+    let (info, kernel_info) = match remote {
+        None => {
+            let program = enable_program(args.program_id).await?;
+            ((&program).try_into()?, (&program).try_into()?)
+        }
+        Some(endpoint) => {
+            let response = connect(endpoint)
+                .await?
+                .enable(EnableRequest {
+                    id: args.program_id,
+                })
+                .await
+                .map_err(status_error)?
+                .into_inner();
+            (
+                response.info.unwrap_or_default(),
+                response.kernel_info.unwrap_or_default(),
+            )
+        }
+    };
+
+    print_program(&info, &kernel_info)
+}
+
+pub(crate) async fn execute_disable(
+    args: &DisableArgs,
+    remote: Option<&str>,
+) -> anyhow::Result<()> {
+    let (info, kernel_info) = match remote {
+        None => {
+            let program = disable_program(args.program_id).await?;
+            ((&program).try_into()?, (&program).try_into()?)
+        }
+        Some(endpoint) => {
+            let response = connect(endpoint)
+                .await?
+                .disable(DisableRequest {
+                    id: args.program_id,
+                })
+                .await
+                .map_err(status_error)?
+                .into_inner();
+            (
+                response.info.unwrap_or_default(),
+                response.kernel_info.unwrap_or_default(),
+            )
+        }
+    };
+
+    print_program(&info, &kernel_info)
+}
+
+fn print_program(info: &ProgramInfo, kernel_info: &KernelProgramInfo) -> anyhow::Result<()> {
+    ProgTable::new_program(info)?.print();
+    ProgTable::new_kernel_info(kernel_info)?.print();
+    Ok(())
+}
@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use bpfman::{get_program, list_links, types::Program};
+use bpfman_api::v1::{GetRequest, ProgramInfo};
+use log::warn;
+
+use crate::{
+    args::{GetArgs, OutputFormat},
+    output::{print, ProgramOutput},
+    remote::{connect, status_error},
+    table::ProgTable,
+};
+
+pub(crate) async fn execute_get(args: &GetArgs, remote: Option<&str>) -> anyhow::Result<()> {
+    let (info, kernel_info) = match remote {
+        None => match get_program(args.program_id).await {
+            Ok(program) => (
+                if let Program::Unsupported(_) = program {
+                    None
+                } else {
+                    let mut info: ProgramInfo = (&program).try_into()?;
+                    for link in list_links(Some(args.program_id)).await? {
+                        info.links.push((&link).try_into()?);
+                    }
+                    Some(info)
+                },
+                Some((&program).try_into()?),
+            ),
+            Err(e) => {
+                warn!("BPFMAN get error: {}", e);
+                return Err(e.into());
+            }
+        },
+        Some(endpoint) => {
+            let response = connect(endpoint)
+                .await?
+                .get(GetRequest {
+                    id: args.program_id,
+                })
+                .await
+                .map_err(status_error)?
+                .into_inner();
+            (response.info, response.kernel_info)
+        }
+    };
+
+    let kernel_info = kernel_info.unwrap_or_default();
+    match args.output {
+        OutputFormat::Table | OutputFormat::Wide => {
+            ProgTable::new_program(&info.unwrap_or_default())?.print();
+            ProgTable::new_kernel_info(&kernel_info)?.print();
+        }
+        format => print(&ProgramOutput::new(info.as_ref(), &kernel_info)?, format)?,
+    }
+    Ok(())
+}
@@ -16,17 +16,24 @@ use bpfman::{
     pull_bytecode,
     types::{BytecodeImage, ImagePullPolicy, MapType, ProgramType},
 };
+use bpfman_api::v1::PullBytecodeRequest;
 use log::{debug, warn};
 use object::Endianness;
 
-use crate::args::{
-    BuildBytecodeArgs, BytecodeFile, GenerateArgs, GoArch, ImageSubCommand, PullBytecodeArgs,
+use crate::{
+    args::{
+        BuildBytecodeArgs, BytecodeFile, GenerateArgs, GoArch, ImageSubCommand, OutputFormat,
+        PullArgs, PullBytecodeArgs,
+    },
+    output::{print, ImageOutput},
+    remote::{connect, status_error},
+    table::ProgTable,
 };
 
 impl ImageSubCommand {
-    pub(crate) async fn execute(&self) -> anyhow::Result<()> {
+    pub(crate) async fn execute(&self, remote: Option<&str>) -> anyhow::Result<()> {
         match self {
-            ImageSubCommand::Pull(args) => execute_pull(args).await,
+            ImageSubCommand::Pull(args) => execute_pull(args, remote).await,
             ImageSubCommand::Build(args) => execute_build(args).await,
             ImageSubCommand::GenerateBuildArgs(args) => execute_build_args(args).await,
         }
@@ -67,9 +74,31 @@ pub(crate) struct ImageBuilder {
     pub(crate) build_args: Vec<String>,
 }
 
-pub(crate) async fn execute_pull(args: &PullBytecodeArgs) -> anyhow::Result<()> {
-    let image: BytecodeImage = args.try_into()?;
-    pull_bytecode(image).await?;
+pub(crate) async fn execute_pull(args: &PullArgs, remote: Option<&str>) -> anyhow::Result<()> {
+    let image: BytecodeImage = (&args.pull_args).try_into()?;
+    let output = ImageOutput {
+        url: image.image_url.clone(),
+        pull_policy: image.image_pull_policy.to_string(),
+        programs: match remote {
+            None => pull_bytecode(image).await?,
+            Some(endpoint) => {
+                connect(endpoint)
+                    .await?
+                    .pull_bytecode(PullBytecodeRequest {
+                        image: Some(image.into()),
+                    })
+                    .await
+                    .map_err(status_error)?
+                    .into_inner()
+                    .programs
+            }
+        },
+    };
+
+    match args.output {
+        OutputFormat::Table | OutputFormat::Wide => ProgTable::new_image(&output).print(),
+        format => print(&output, format)?,
+    }
 
     Ok(())
 }
@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use std::collections::HashMap;
+
+use anyhow::bail;
+use bpfman::{
+    list_links, list_programs,
+    types::{ListFilter, Program},
+};
+use bpfman_api::v1::{KernelProgramInfo, LinkInfo, ListRequest, ProgramInfo};
+
+use crate::{
+    args::{ListArgs, OutputFormat},
+    output::{print, ProgramOutput},
+    remote::{connect, status_error},
+    table::ProgTable,
+};
+
+pub(crate) async fn execute_list(args: &ListArgs, remote: Option<&str>) -> anyhow::Result<()> {
+    let prog_type_filter = args.program_type.map(|p| p as u32);
+
+    let match_metadata: HashMap<String, String> = args
+        .metadata_selector
+        .clone()
+        .unwrap_or_default()
+        .iter()
+        .map(|(k, v)| (k.to_owned(), v.to_owned()))
+        .collect();
+
+    let programs: Vec<(Option<ProgramInfo>, KernelProgramInfo)> = match remote {
+        None => {
+            let filter = ListFilter::new(prog_type_filter, match_metadata, !args.all);
+            let mut links: HashMap<u32, Vec<LinkInfo>> = HashMap::new();
+            for link in list_links(None).await? {
+                links
+                    .entry(link.program_id()?)
+                    .or_default()
+                    .push((&link).try_into()?);
+            }
+            let mut programs = vec![];
+            for p in list_programs(filter).await? {
+                let info = if let Program::Unsupported(_) = p {
+                    None
+                } else {
+                    let mut info: ProgramInfo = (&p).try_into()?;
+                    if let Ok(id) = p.get_data().get_id() {
+                        info.links = links.remove(&id).unwrap_or_default();
+                    }
+                    Some(info)
+                };
+                programs.push((info, (&p).try_into()?));
+            }
+            programs
+        }
+        Some(endpoint) => connect(endpoint)
+            .await?
+            .list(ListRequest {
+                program_type: prog_type_filter,
+                bpfman_programs_only: Some(!args.all),
+                match_metadata,
+            })
+            .await
+            .map_err(status_error)?
+            .into_inner()
+            .results
+            .into_iter()
+            .filter_map(|r| r.kernel_info.map(|k| (r.info, k)))
+            .collect(),
+    };
+
+    match args.output {
+        OutputFormat::Table | OutputFormat::Wide => {
+            let wide = args.output == OutputFormat::Wide;
+            let mut table = ProgTable::new_list(wide);
+
+            for (info, kernel_info) in programs {
+                let res = if wide {
+                    table.add_response_prog_wide(info.as_ref(), &kernel_info)
+                } else {
+                    table.add_response_prog(info.as_ref(), &kernel_info)
+                };
+                if let Err(e) = res {
+                    bail!(e)
+                }
+            }
+            table.print();
+        }
+        format => print(
+            &programs
+                .iter()
+                .map(|(info, kernel_info)| ProgramOutput::new(info.as_ref(), kernel_info))
+                .collect::<Result<Vec<_>, _>>()?,
+            format,
+        )?,
+    }
+    Ok(())
+}
@@ -0,0 +1,300 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use std::{collections::HashMap, fs};
+
+use anyhow::{bail, Context};
+use bpfman::{
+    add_program,
+    types::{
+        CgroupSkbProgram, CgroupSockAddrProgram, FentryProgram, FexitProgram, KprobeProgram,
+        Location, Program, ProgramData, SkMsgProgram, SockOpsProgram, TcProceedOn, TcProgram,
+        TracepointProgram, UprobeProgram, VerifierLogLevel, XdpMode, XdpProceedOn, XdpProgram,
+    },
+    utils::netns_path,
+};
+use bpfman_api::v1::{
+    bytecode_location::Location as V1Location, BytecodeLocation, KernelProgramInfo, LoadRequest,
+    ProgramInfo,
+};
+
+use crate::{
+    args::{GlobalArg, LoadCommands, LoadFileArgs, LoadImageArgs, LoadSubcommand},
+    remote::{connect, status_error},
+    table::ProgTable,
+};
+
+impl LoadSubcommand {
+    pub(crate) async fn execute(&self, remote: Option<&str>) -> anyhow::Result<()> {
+        match self {
+            LoadSubcommand::File(l) => execute_load_file(l, remote).await,
+            LoadSubcommand::Image(l) => execute_load_image(l, remote).await,
+        }
+    }
+}
+
+pub(crate) async fn execute_load_file(
+    args: &LoadFileArgs,
+    remote: Option<&str>,
+) -> anyhow::Result<()> {
+    let bytecode_source = Location::File(args.path.clone());
+
+    let mut data = ProgramData::new(
+        bytecode_source.clone(),
+        args.name.clone(),
+        args.metadata
+            .clone()
+            .unwrap_or_default()
+            .iter()
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .collect(),
+        parse_global(&args.global),
+        args.map_owner_id,
+    )?;
+    data.set_verifier_log_level(verifier_log_level(&args.verifier_log_level))?;
+
+    load(
+        args.command.get_program(data)?,
+        bytecode_source,
+        args.dry_run,
+        remote,
+    )
+    .await
+}
+
+pub(crate) async fn execute_load_image(
+    args: &LoadImageArgs,
+    remote: Option<&str>,
+) -> anyhow::Result<()> {
+    let bytecode_source = Location::Image((&args.pull_args).try_into()?);
+
+    let mut data = ProgramData::new(
+        bytecode_source.clone(),
+        args.name.clone(),
+        args.metadata
+            .clone()
+            .unwrap_or_default()
+            .iter()
+            .map(|(k, v)| (k.to_owned(), v.to_owned()))
+            .collect(),
+        parse_global(&args.global),
+        args.map_owner_id,
+    )?;
+    data.set_verifier_log_level(verifier_log_level(&args.verifier_log_level))?;
+
+    load(
+        args.command.get_program(data)?,
+        bytecode_source,
+        args.dry_run,
+        remote,
+    )
+    .await
+}
+
+// Loads the program, either directly or through bpfman-rpc, and prints it.
+async fn load(
+    program: Program,
+    bytecode_source: Location,
+    dry_run: bool,
+    remote: Option<&str>,
+) -> anyhow::Result<()> {
+    let (info, kernel_info): (ProgramInfo, KernelProgramInfo) = match remote {
+        None => {
+            let program = add_program(program, dry_run).await?;
+            ((&program).try_into()?, (&program).try_into()?)
+        }
+        Some(endpoint) => {
+            let info: ProgramInfo = (&program).try_into()?;
+            // The bytecode location is taken from the arguments, since the
+            // program's own drops the registry credentials. A file's path is
+            // made absolute, since bpfman-rpc has its own working directory.
+            let location = match bytecode_source {
+                Location::File(p) => V1Location::File(
+                    fs::canonicalize(&p)
+                        .with_context(|| format!("unable to find bytecode file {p}"))?
+                        .to_string_lossy()
+                        .into_owned(),
+                ),
+                Location::Image(i) => V1Location::Image(i.into()),
+            };
+            let request = LoadRequest {
+                bytecode: Some(BytecodeLocation {
+                    location: Some(location),
+                }),
+                name: info.name,
+                program_type: program.kind() as u32,
+                attach: info.attach,
+                metadata: info.metadata,
+                global_data: info.global_data,
+                uuid: None,
+                map_owner_id: info.map_owner_id,
+                verifier_log_level: Some(program.get_data().get_verifier_log_level()?),
+                dry_run,
+            };
+            let response = connect(endpoint)
+                .await?
+                .load(request)
+                .await
+                .map_err(status_error)?
+                .into_inner();
+            (
+                response.info.unwrap_or_default(),
+                response.kernel_info.unwrap_or_default(),
+            )
+        }
+    };
+
+    if dry_run {
+        println!("Program {} passed the verifier and was unloaded", info.name);
+    }
+    ProgTable::new_program(&info)?.print();
+    ProgTable::new_kernel_info(&kernel_info)?.print();
+    Ok(())
+}
+
+impl LoadCommands {
+    pub(crate) fn get_program(&self, data: ProgramData) -> Result<Program, anyhow::Error> {
+        match self {
+            LoadCommands::Xdp {
+                iface,
+                priority,
+                proceed_on,
+                netns,
+                container_pid,
+                frags,
+                mode,
+                mode_fallback,
+                run_config,
+            } => {
+                let proc_on = match XdpProceedOn::from_strings(proceed_on) {
+                    Ok(p) => p,
+                    Err(e) => bail!("error parsing proceed_on {e}"),
+                };
+                let mut program = XdpProgram::new(
+                    data,
+                    priority.unwrap_or(50),
+                    iface.to_string(),
+                    XdpProceedOn::from_int32s(proc_on.as_action_vec())?,
+                    netns_path(netns.clone(), *container_pid),
+                )?;
+                program.set_frags(*frags)?;
+                program.set_use_run_config(*run_config)?;
+                if let Some(mode) = mode {
+                    program.set_mode(mode.parse::<XdpMode>()?, *mode_fallback)?;
+                }
+                Ok(Program::Xdp(program))
+            }
+            LoadCommands::Tc {
+                direction,
+                iface,
+                priority,
+                proceed_on,
+                netns,
+                container_pid,
+            } => {
+                match direction.as_str() {
+                    "ingress" | "egress" => (),
+                    other => bail!("{} is not a valid direction", other),
+                };
+                let proc_on = match TcProceedOn::from_strings(proceed_on) {
+                    Ok(p) => p,
+                    Err(e) => bail!("error parsing proceed_on {e}"),
+                };
+                Ok(Program::Tc(TcProgram::new(
+                    data,
+                    *priority,
+                    iface.to_string(),
+                    proc_on,
+                    direction.to_string().try_into()?,
+                    netns_path(netns.clone(), *container_pid),
+                )?))
+            }
+            LoadCommands::Tracepoint { tracepoint } => Ok(Program::Tracepoint(
+                TracepointProgram::new(data, tracepoint.to_string())?,
+            )),
+            LoadCommands::Kprobe {
+                fn_name,
+                offset,
+                retprobe,
+                container_pid,
+            } => {
+                let offset = offset.unwrap_or(0);
+                Ok(Program::Kprobe(KprobeProgram::new(
+                    data,
+                    fn_name.to_string(),
+                    offset,
+                    *retprobe,
+                    *container_pid,
+                )?))
+            }
+            LoadCommands::Uprobe {
+                fn_name,
+                offset,
+                target,
+                retprobe,
+                pid,
+                container_pid,
+            } => {
+                let offset = offset.unwrap_or(0);
+                Ok(Program::Uprobe(UprobeProgram::new(
+                    data,
+                    fn_name.clone(),
+                    offset,
+                    target.to_string(),
+                    *retprobe,
+                    *pid,
+                    *container_pid,
+                )?))
+            }
+            LoadCommands::Fentry { fn_name } => Ok(Program::Fentry(FentryProgram::new(
+                data,
+                fn_name.to_string(),
+            )?)),
+            LoadCommands::Fexit { fn_name } => Ok(Program::Fexit(FexitProgram::new(
+                data,
+                fn_name.to_string(),
+            )?)),
+            LoadCommands::CgroupSkb {
+                cgroup_path,
+                direction,
+            } => {
+                match direction.as_str() {
+                    "ingress" | "egress" => (),
+                    other => bail!("{} is not a valid direction", other),
+                };
+                Ok(Program::CgroupSkb(CgroupSkbProgram::new(
+                    data,
+                    cgroup_path.to_string(),
+                    direction.to_string().try_into()?,
+                )?))
+            }
+            LoadCommands::CgroupSockAddr { cgroup_path } => Ok(Program::CgroupSockAddr(
+                CgroupSockAddrProgram::new(data, cgroup_path.to_string())?,
+            )),
+            LoadCommands::Sockops { cgroup_path } => Ok(Program::SockOps(SockOpsProgram::new(
+                data,
+                cgroup_path.to_string(),
+            )?)),
+            LoadCommands::SkMsg { map_name } => Ok(Program::SkMsg(SkMsgProgram::new(
+                data,
+                map_name.to_string(),
+            )?)),
+        }
+    }
+}
+
+fn parse_global(global: &Option<Vec<GlobalArg>>) -> HashMap<String, Vec<u8>> {
+    let mut global_data: HashMap<String, Vec<u8>> = HashMap::new();
+
+    if let Some(global) = global {
+        for g in global.iter() {
+            global_data.insert(g.name.to_string(), g.value.clone());
+        }
+    }
+    global_data
+}
+
+// Combines the verifier log levels into the flags passed to the kernel.
+fn verifier_log_level(levels: &[VerifierLogLevel]) -> u32 {
+    levels.iter().fold(0, |flags, l| flags | *l as u32)
+}
@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use anyhow::anyhow;
+use args::Commands;
+use attach::execute_attach;
+use clap::Parser;
+use detach::execute_detach;
+use enable::{execute_disable, execute_enable};
+use get::execute_get;
+use list::execute_list;
+use log::debug;
+use reorder::execute_reorder;
+use unload::execute_unload;
+
+mod args;
+mod attach;
+mod detach;
+mod dispatcher;
+mod enable;
+mod get;
+mod image;
+mod list;
+mod load;
+mod map;
+mod output;
+mod remote;
+mod reorder;
+mod table;
+mod unload;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::try_init()?;
+    debug!("Log using env_logger");
+
+    let cli = crate::args::Cli::parse();
+
+    cli.command.execute(cli.remote.as_deref()).await
+}
+
+impl Commands {
+    pub(crate) async fn execute(&self, remote: Option<&str>) -> Result<(), anyhow::Error> {
+        match self {
+            Commands::Load(l) => l.execute(remote).await,
+            Commands::Unload(args) => execute_unload(args, remote).await,
+            Commands::List(args) => execute_list(args, remote).await,
+            Commands::Get(args) => execute_get(args, remote)
+                .await
+                .map_err(|e| anyhow!("get error: {e}")),
+            Commands::Attach(args) => execute_attach(args, remote).await,
+            Commands::Detach(args) => execute_detach(args, remote).await,
+            Commands::Reorder(args) => execute_reorder(args, remote).await,
+            Commands::Enable(args) => execute_enable(args, remote).await,
+            Commands::Disable(args) => execute_disable(args, remote).await,
+            Commands::Image(i) => i.execute(remote).await,
+            Commands::Map(m) => m.execute(remote).await,
+            Commands::Dispatcher(d) => d.execute(remote).await,
+        }?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use anyhow::anyhow;
+use bpfman::{delete_map_entry, dump_map, get_map_info, update_map_entry};
+use bpfman_api::v1::{DeleteMapEntryRequest, DumpMapRequest, GetMapRequest, UpdateMapEntryRequest};
+
+use crate::{
+    args::MapSubcommand,
+    remote::{connect, status_error},
+    table::ProgTable,
+};
+
+impl MapSubcommand {
+    pub(crate) async fn execute(&self, remote: Option<&str>) -> anyhow::Result<()> {
+        match remote {
+            None => self.execute_local().await,
+            Some(endpoint) => self.execute_remote(endpoint).await,
+        }
+    }
+
+    async fn execute_local(&self) -> anyhow::Result<()> {
+        match self {
+            MapSubcommand::Get(args) => {
+                let map = get_map_info(args.program_id, &args.map_name)
+                    .await
+                    .map_err(|e| anyhow!("map get error: {e}"))?;
+                ProgTable::new_map(&map.into()).print();
+            }
+            MapSubcommand::Dump(args) => {
+                let (_, entries) = dump_map(args.program_id, &args.map_name)
+                    .await
+                    .map_err(|e| anyhow!("map dump error: {e}"))?;
+                let entries: Vec<_> = entries.into_iter().map(|e| e.into()).collect();
+                ProgTable::new_map_entries(&entries).print();
+            }
+            MapSubcommand::Update(args) => {
+                update_map_entry(
+                    args.map.program_id,
+                    &args.map.map_name,
+                    &args.key,
+                    &args.value,
+                )
+                .await
+                .map_err(|e| anyhow!("map update error: {e}"))?;
+            }
+            MapSubcommand::Delete(args) => {
+                delete_map_entry(args.map.program_id, &args.map.map_name, &args.key)
+                    .await
+                    .map_err(|e| anyhow!("map delete error: {e}"))?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn execute_remote(&self, endpoint: &str) -> anyhow::Result<()> {
+        let mut client = connect(endpoint).await?;
+        match self {
+            MapSubcommand::Get(args) => {
+                let response = client
+                    .get_map(GetMapRequest {
+                        id: args.program_id,
+                        map_name: args.map_name.clone(),
+                    })
+                    .await
+                    .map_err(|e| anyhow!("map get error: {}", status_error(e)))?
+                    .into_inner();
+                ProgTable::new_map(&response.info.unwrap_or_default()).print();
+            }
+            MapSubcommand::Dump(args) => {
+                let response = client
+                    .dump_map(DumpMapRequest {
+                        id: args.program_id,
+                        map_name: args.map_name.clone(),
+                    })
+                    .await
+                    .map_err(|e| anyhow!("map dump error: {}", status_error(e)))?
+                    .into_inner();
+                ProgTable::new_map_entries(&response.entries).print();
+            }
+            MapSubcommand::Update(args) => {
+                client
+                    .update_map_entry(UpdateMapEntryRequest {
+                        id: args.map.program_id,
+                        map_name: args.map.map_name.clone(),
+                        key: args.key.clone(),
+                        value: args.value.clone(),
+                    })
+                    .await
+                    .map_err(|e| anyhow!("map update error: {}", status_error(e)))?;
+            }
+            MapSubcommand::Delete(args) => {
+                client
+                    .delete_map_entry(DeleteMapEntryRequest {
+                        id: args.map.program_id,
+                        map_name: args.map.map_name.clone(),
+                        key: args.key.clone(),
+                    })
+                    .await
+                    .map_err(|e| anyhow!("map delete error: {}", status_error(e)))?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,466 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! The schema of the JSON and YAML output of the list, get, image pull and
+//! dispatcher commands. Fields are only ever added to it, so that scripts keep working.
+
+use std::collections::BTreeMap;
+
+use anyhow::bail;
+use bpfman::types::{ImagePullPolicy, ProgramType, TcProceedOnEntry, XdpProceedOnEntry};
+use bpfman_api::v1::{
+    attach_info::Info, bytecode_location::Location, DispatcherInfo, KernelProgramInfo, ProgramInfo,
+};
+use hex::encode_upper;
+use serde::Serialize;
+
+use crate::args::OutputFormat;
+
+/// A program, as returned by list and get.
+#[derive(Serialize, Debug)]
+pub(crate) struct ProgramOutput {
+    /// The state bpfman keeps for the program. Not set for programs which
+    /// were not loaded by bpfman.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bpfman: Option<BpfmanOutput>,
+    kernel: KernelOutput,
+}
+
+#[derive(Serialize, Debug)]
+struct BpfmanOutput {
+    name: String,
+    location: LocationOutput,
+    /// Global variables, as upper case hex.
+    global_data: BTreeMap<String, String>,
+    metadata: BTreeMap<String, String>,
+    map_pin_path: Option<String>,
+    map_owner_id: Option<u32>,
+    maps_used_by: Vec<u32>,
+    attach: Option<AttachOutput>,
+    /// The attach points added with attach.
+    links: Vec<LinkOutput>,
+    /// Set while the program waits to be restored, such as after a reboot.
+    restore_pending: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct LinkOutput {
+    id: u32,
+    attach: Option<AttachOutput>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+enum LocationOutput {
+    File(String),
+    Image { url: String, pull_policy: String },
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AttachOutput {
+    Xdp {
+        iface: String,
+        iface_pattern: Option<String>,
+        priority: i32,
+        position: i32,
+        proceed_on: Vec<String>,
+        netns: Option<String>,
+        disabled: bool,
+        detached: bool,
+        frags: bool,
+        mode: Option<String>,
+        mode_fallback: bool,
+        effective_mode: Option<String>,
+        foreign_program_ids: Vec<u32>,
+        adopted_program_ids: Vec<u32>,
+        use_run_config: bool,
+    },
+    Tc {
+        iface: String,
+        iface_pattern: Option<String>,
+        direction: String,
+        priority: i32,
+        position: i32,
+        proceed_on: Vec<String>,
+        netns: Option<String>,
+        disabled: bool,
+        detached: bool,
+        foreign_program_ids: Vec<u32>,
+        adopted_program_ids: Vec<u32>,
+    },
+    Tracepoint {
+        tracepoint: String,
+    },
+    Kprobe {
+        fn_name: String,
+        offset: u64,
+        retprobe: bool,
+        container_pid: Option<i32>,
+    },
+    Uprobe {
+        fn_name: Option<String>,
+        offset: u64,
+        target: String,
+        retprobe: bool,
+        pid: Option<i32>,
+        container_pid: Option<i32>,
+    },
+    Fentry {
+        fn_name: String,
+    },
+    Fexit {
+        fn_name: String,
+    },
+    CgroupSkb {
+        cgroup_path: String,
+        direction: String,
+    },
+    CgroupSockAddr {
+        cgroup_path: String,
+        attach_type: Option<String>,
+    },
+    Sockops {
+        cgroup_path: String,
+    },
+    SkMsg {
+        map_name: String,
+    },
+}
+
+#[derive(Serialize, Debug)]
+struct KernelOutput {
+    id: u32,
+    name: String,
+    #[serde(rename = "type")]
+    program_type: String,
+    loaded_at: String,
+    tag: String,
+    gpl_compatible: bool,
+    map_ids: Vec<u32>,
+    btf_id: u32,
+    bytes_xlated: u32,
+    jited: bool,
+    bytes_jited: u32,
+    bytes_memlock: u32,
+    verified_insns: u32,
+}
+
+/// An image, as returned by image pull.
+#[derive(Serialize, Debug)]
+pub(crate) struct ImageOutput {
+    pub(crate) url: String,
+    pub(crate) pull_policy: String,
+    /// The names of the programs in the image.
+    pub(crate) programs: Vec<String>,
+}
+
+/// A dispatcher, as returned by dispatcher list and get.
+#[derive(Serialize, Debug)]
+pub(crate) struct DispatcherOutput {
+    #[serde(rename = "type")]
+    program_type: String,
+    iface: String,
+    if_index: u32,
+    direction: Option<String>,
+    netns: Option<String>,
+    revision: u32,
+    mode: String,
+    priority: Option<u32>,
+    stages: Vec<DispatcherStageOutput>,
+    foreign: Vec<ForeignProgramOutput>,
+}
+
+#[derive(Serialize, Debug)]
+struct DispatcherStageOutput {
+    handle: Option<u32>,
+    num_progs_enabled: u32,
+    chain_call_actions: Vec<u32>,
+    slots: Vec<DispatcherSlotOutput>,
+}
+
+#[derive(Serialize, Debug)]
+struct ForeignProgramOutput {
+    program_id: u32,
+    name: String,
+    adopted: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct DispatcherSlotOutput {
+    slot: u32,
+    program_id: u32,
+    name: String,
+    priority: i32,
+    /// The actions decoded from the slot's chain call actions mask.
+    proceed_on: Vec<String>,
+    link_id: Option<u32>,
+    disabled: bool,
+    libxdp: bool,
+}
+
+impl DispatcherOutput {
+    pub(crate) fn new(d: &DispatcherInfo) -> anyhow::Result<Self> {
+        let program_type = ProgramType::try_from(d.program_type)?;
+        Ok(DispatcherOutput {
+            program_type: program_type.to_string(),
+            iface: d.iface.clone(),
+            if_index: d.if_index,
+            direction: Some(d.direction.clone()).filter(|d| !d.is_empty()),
+            netns: d.netns.clone(),
+            revision: d.revision,
+            mode: d.mode.clone(),
+            priority: d.priority,
+            stages: d
+                .stages
+                .iter()
+                .map(|s| {
+                    Ok(DispatcherStageOutput {
+                        handle: s.handle,
+                        num_progs_enabled: s.num_progs_enabled,
+                        chain_call_actions: s.chain_call_actions.clone(),
+                        slots: s
+                            .slots
+                            .iter()
+                            .enumerate()
+                            .map(|(i, p)| {
+                                let mask = s.chain_call_actions.get(i).copied().unwrap_or(0);
+                                Ok(DispatcherSlotOutput {
+                                    slot: p.slot,
+                                    program_id: p.program_id,
+                                    name: p.name.clone(),
+                                    priority: p.priority,
+                                    proceed_on: proceed_on_names(program_type, mask)?,
+                                    link_id: p.link_id,
+                                    disabled: p.disabled,
+                                    libxdp: p.libxdp,
+                                })
+                            })
+                            .collect::<anyhow::Result<_>>()?,
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+            foreign: d
+                .foreign
+                .iter()
+                .map(|f| ForeignProgramOutput {
+                    program_id: f.program_id,
+                    name: f.name.clone(),
+                    adopted: f.adopted,
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Decodes a proceed-on mask of a dispatcher slot into the names of its
+/// actions. TC masks are shifted by one so that TC_ACT_UNSPEC (-1) has a bit.
+pub(crate) fn proceed_on_names(
+    program_type: ProgramType,
+    mask: u32,
+) -> anyhow::Result<Vec<String>> {
+    (0..u32::BITS as i32)
+        .filter(|bit| mask & (1 << bit) != 0)
+        .map(|bit| match program_type {
+            ProgramType::Tc => Ok(TcProceedOnEntry::try_from(bit - 1)?.to_string()),
+            _ => Ok(XdpProceedOnEntry::try_from(bit)?.to_string()),
+        })
+        .collect()
+}
+
+impl ProgramOutput {
+    pub(crate) fn new(
+        info: Option<&ProgramInfo>,
+        kernel_info: &KernelProgramInfo,
+    ) -> anyhow::Result<Self> {
+        Ok(ProgramOutput {
+            bpfman: info.map(BpfmanOutput::new).transpose()?,
+            kernel: KernelOutput::new(kernel_info)?,
+        })
+    }
+}
+
+impl BpfmanOutput {
+    fn new(info: &ProgramInfo) -> anyhow::Result<Self> {
+        let location = match info.bytecode.as_ref().and_then(|b| b.location.as_ref()) {
+            Some(Location::File(p)) => LocationOutput::File(p.clone()),
+            Some(Location::Image(i)) => LocationOutput::Image {
+                url: i.url.clone(),
+                pull_policy: ImagePullPolicy::try_from(i.image_pull_policy)?.to_string(),
+            },
+            None => bail!("program {} has no bytecode location", info.name),
+        };
+
+        Ok(BpfmanOutput {
+            name: info.name.clone(),
+            location,
+            global_data: info
+                .global_data
+                .iter()
+                .map(|(k, v)| (k.clone(), encode_upper(v)))
+                .collect(),
+            metadata: info.metadata.clone().into_iter().collect(),
+            map_pin_path: Some(info.map_pin_path.clone()).filter(|p| !p.is_empty()),
+            map_owner_id: info.map_owner_id,
+            maps_used_by: info
+                .map_used_by
+                .iter()
+                .map(|id| id.parse())
+                .collect::<Result<_, _>>()?,
+            attach: info
+                .attach
+                .as_ref()
+                .and_then(|a| a.info.as_ref())
+                .map(AttachOutput::new)
+                .transpose()?,
+            links: info
+                .links
+                .iter()
+                .map(|l| {
+                    Ok(LinkOutput {
+                        id: l.id,
+                        attach: l
+                            .attach
+                            .as_ref()
+                            .and_then(|a| a.info.as_ref())
+                            .map(AttachOutput::new)
+                            .transpose()?,
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+            restore_pending: info.restore_pending,
+        })
+    }
+}
+
+impl AttachOutput {
+    fn new(info: &Info) -> anyhow::Result<Self> {
+        Ok(match info.clone() {
+            Info::XdpAttachInfo(i) => AttachOutput::Xdp {
+                iface: i.iface,
+                iface_pattern: i.iface_pattern,
+                priority: i.priority,
+                position: i.position,
+                proceed_on: i
+                    .proceed_on
+                    .into_iter()
+                    .map(|p| XdpProceedOnEntry::try_from(p).map(|e| e.to_string()))
+                    .collect::<Result<_, _>>()?,
+                netns: i.netns,
+                disabled: i.disabled,
+                detached: i.detached,
+                frags: i.frags,
+                mode: i.mode,
+                mode_fallback: i.mode_fallback,
+                effective_mode: i.effective_mode,
+                foreign_program_ids: i.foreign_program_ids,
+                adopted_program_ids: i.adopted_program_ids,
+                use_run_config: i.use_run_config,
+            },
+            Info::TcAttachInfo(i) => AttachOutput::Tc {
+                iface: i.iface,
+                iface_pattern: i.iface_pattern,
+                direction: i.direction,
+                priority: i.priority,
+                position: i.position,
+                proceed_on: i
+                    .proceed_on
+                    .into_iter()
+                    .map(|p| TcProceedOnEntry::try_from(p).map(|e| e.to_string()))
+                    .collect::<Result<_, _>>()?,
+                netns: i.netns,
+                disabled: i.disabled,
+                detached: i.detached,
+                foreign_program_ids: i.foreign_program_ids,
+                adopted_program_ids: i.adopted_program_ids,
+            },
+            Info::TracepointAttachInfo(i) => AttachOutput::Tracepoint {
+                tracepoint: i.tracepoint,
+            },
+            Info::KprobeAttachInfo(i) => AttachOutput::Kprobe {
+                fn_name: i.fn_name,
+                offset: i.offset,
+                retprobe: i.retprobe,
+                container_pid: i.container_pid,
+            },
+            Info::UprobeAttachInfo(i) => AttachOutput::Uprobe {
+                fn_name: i.fn_name,
+                offset: i.offset,
+                target: i.target,
+                retprobe: i.retprobe,
+                pid: i.pid,
+                container_pid: i.container_pid,
+            },
+            Info::FentryAttachInfo(i) => AttachOutput::Fentry { fn_name: i.fn_name },
+            Info::FexitAttachInfo(i) => AttachOutput::Fexit { fn_name: i.fn_name },
+            Info::CgroupSkbAttachInfo(i) => AttachOutput::CgroupSkb {
+                cgroup_path: i.cgroup_path,
+                direction: i.direction,
+            },
+            Info::CgroupSockAddrAttachInfo(i) => AttachOutput::CgroupSockAddr {
+                cgroup_path: i.cgroup_path,
+                attach_type: i.attach_type,
+            },
+            Info::SockOpsAttachInfo(i) => AttachOutput::Sockops {
+                cgroup_path: i.cgroup_path,
+            },
+            Info::SkMsgAttachInfo(i) => AttachOutput::SkMsg {
+                map_name: i.map_name,
+            },
+        })
+    }
+}
+
+impl KernelOutput {
+    fn new(k: &KernelProgramInfo) -> anyhow::Result<Self> {
+        Ok(KernelOutput {
+            id: k.id,
+            name: k.name.clone(),
+            program_type: ProgramType::try_from(k.program_type)?.to_string(),
+            loaded_at: k.loaded_at.clone(),
+            tag: k.tag.clone(),
+            gpl_compatible: k.gpl_compatible,
+            map_ids: k.map_ids.clone(),
+            btf_id: k.btf_id,
+            bytes_xlated: k.bytes_xlated,
+            jited: k.jited,
+            bytes_jited: k.bytes_jited,
+            bytes_memlock: k.bytes_memlock,
+            verified_insns: k.verified_insns,
+        })
+    }
+}
+
+/// Prints the value as JSON or YAML. Tables are printed by the commands
+/// themselves.
+pub(crate) fn print<T: Serialize>(value: &T, format: OutputFormat) -> anyhow::Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+        OutputFormat::Yaml => print!("{}", serde_yaml::to_string(value)?),
+        OutputFormat::Table | OutputFormat::Wide => bail!("{format:?} is not a serialized format"),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_proceed_on_names() {
+        use bpfman::types::{TcProceedOn, XdpProceedOn};
+
+        let xdp = XdpProceedOn::from_strings(["pass".to_string(), "dispatcher_return".to_string()])
+            .unwrap();
+        assert_eq!(
+            proceed_on_names(ProgramType::Xdp, xdp.mask()).unwrap(),
+            vec!["pass", "dispatcher_return"]
+        );
+
+        let tc = TcProceedOn::from_strings(["unspec".to_string(), "pipe".to_string()]).unwrap();
+        assert_eq!(
+            proceed_on_names(ProgramType::Tc, tc.mask()).unwrap(),
+            vec!["unspec", "pipe"]
+        );
+    }
+}
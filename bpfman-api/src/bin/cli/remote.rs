@@ -0,0 +1,40 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use anyhow::{anyhow, Context};
+use bpfman_api::v1::bpfman_client::BpfmanClient;
+use tokio::net::UnixStream;
+use tonic::{
+    transport::{Channel, Endpoint, Uri},
+    Status,
+};
+use tower::service_fn;
+
+/// Connects to the bpfman-rpc listening on the given endpoint, which is either
+/// a unix socket, given as a path optionally prefixed with `unix:`, or an
+/// `http://` URL.
+pub(crate) async fn connect(endpoint: &str) -> anyhow::Result<BpfmanClient<Channel>> {
+    let channel = if endpoint.starts_with("http://") {
+        Endpoint::from_shared(endpoint.to_string())?.connect().await
+    } else {
+        let path = endpoint
+            .strip_prefix("unix://")
+            .or(endpoint.strip_prefix("unix:"))
+            .unwrap_or(endpoint)
+            .to_string();
+
+        // The URI is ignored, since the connector always dials the socket.
+        Endpoint::try_from("http://[::]:50051")?
+            .connect_with_connector(service_fn(move |_: Uri| UnixStream::connect(path.clone())))
+            .await
+    }
+    .with_context(|| format!("unable to connect to bpfman at {endpoint}"))?;
+
+    Ok(BpfmanClient::new(channel))
+}
+
+/// Returns the error bpfman-rpc reported, without the gRPC status code, so
+/// failures read the same as when the CLI manages programs itself.
+pub(crate) fn status_error(status: Status) -> anyhow::Error {
+    anyhow!("{}", status.message())
+}
@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use anyhow::bail;
+use bpfman::{
+    get_program, reorder_program,
+    types::{ProgramType, TcProceedOn, XdpProceedOn},
+};
+use bpfman_api::v1::{GetRequest, ReorderRequest};
+
+use crate::{
+    args::ReorderArgs,
+    remote::{connect, status_error},
+    table::ProgTable,
+};
+
+pub(crate) async fn execute_reorder(
+    args: &ReorderArgs,
+    remote: Option<&str>,
+) -> anyhow::Result<()> {
+    let (info, kernel_info) = match remote {
+        None => {
+            let proceed_on = if args.proceed_on.is_empty() {
+                None
+            } else {
+                let kind = get_program(args.program_id).await?.kind();
+                Some(proceed_on_actions(kind, &args.proceed_on)?)
+            };
+            let program = reorder_program(args.program_id, args.priority, proceed_on).await?;
+            ((&program).try_into()?, (&program).try_into()?)
+        }
+        Some(endpoint) => {
+            let mut client = connect(endpoint).await?;
+            let proceed_on = if args.proceed_on.is_empty() {
+                vec![]
+            } else {
+                let kind = client
+                    .get(GetRequest {
+                        id: args.program_id,
+                    })
+                    .await
+                    .map_err(status_error)?
+                    .into_inner()
+                    .kernel_info
+                    .unwrap_or_default()
+                    .program_type
+                    .try_into()?;
+                proceed_on_actions(kind, &args.proceed_on)?
+            };
+            let response = client
+                .reorder(ReorderRequest {
+                    id: args.program_id,
+                    priority: args.priority,
+                    proceed_on,
+                })
+                .await
+                .map_err(status_error)?
+                .into_inner();
+            (
+                response.info.unwrap_or_default(),
+                response.kernel_info.unwrap_or_default(),
+            )
+        }
+    };
+
+    ProgTable::new_program(&info)?.print();
+    ProgTable::new_kernel_info(&kernel_info)?.print();
+    Ok(())
+}
+
+// Proceed-on names differ between XDP and TC, so they're parsed for the type
+// of the program being reordered.
+fn proceed_on_actions(kind: ProgramType, proceed_on: &[String]) -> anyhow::Result<Vec<i32>> {
+    let actions = match kind {
+        ProgramType::Xdp => XdpProceedOn::from_strings(proceed_on).map(|p| p.as_action_vec()),
+        ProgramType::Tc => TcProceedOn::from_strings(proceed_on).map(|p| p.as_action_vec()),
+        other => bail!("{other} programs don't have proceed-on actions"),
+    };
+    actions.map_err(|e| anyhow::anyhow!("error parsing proceed_on {e}"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_proceed_on_actions() {
+        let names = |n: &[&str]| n.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            proceed_on_actions(ProgramType::Xdp, &names(&["pass", "dispatcher_return"])).unwrap(),
+            vec![2, 31]
+        );
+        assert_eq!(
+            proceed_on_actions(ProgramType::Tc, &names(&["ok", "dispatcher_return"])).unwrap(),
+            vec![0, 30]
+        );
+        // TC actions aren't valid for XDP programs.
+        assert!(proceed_on_actions(ProgramType::Xdp, &names(&["ok"])).is_err());
+        assert!(proceed_on_actions(ProgramType::Tracepoint, &names(&["pass"])).is_err());
+    }
+}
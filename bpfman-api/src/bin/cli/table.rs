@@ -0,0 +1,684 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use bpfman::types::{
+    ImagePullPolicy, MapType, ProbeType::*, ProgramType, TcProceedOn, XdpProceedOn,
+};
+use bpfman_api::v1::{
+    attach_info::Info, bytecode_location::Location, DispatcherInfo, KernelProgramInfo, LinkInfo,
+    MapEntry, MapInfo, ProgramInfo,
+};
+use comfy_table::{Cell, Color, Table};
+use hex::encode_upper;
+
+use crate::output::{proceed_on_names, ImageOutput};
+pub(crate) struct ProgTable(Table);
+
+impl ProgTable {
+    pub(crate) fn new_program(info: &ProgramInfo) -> Result<Self, anyhow::Error> {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec![Cell::new("Bpfman State")
+            .add_attribute(comfy_table::Attribute::Bold)
+            .add_attribute(comfy_table::Attribute::Underlined)
+            .fg(Color::Green)]);
+
+        if info.name.is_empty() {
+            table.add_row(vec!["Name:", "None"]);
+        } else {
+            table.add_row(vec!["Name:", &info.name]);
+        }
+
+        if info.restore_pending {
+            table.add_row(vec!["State:", "pending restore"]);
+        }
+
+        match info.bytecode.as_ref().and_then(|b| b.location.as_ref()) {
+            Some(Location::Image(i)) => {
+                table.add_row(vec!["Image URL:", &i.url]);
+                table.add_row(vec![
+                    "Pull Policy:",
+                    &format! { "{}", TryInto::<ImagePullPolicy>::try_into(i.image_pull_policy)?},
+                ]);
+            }
+            Some(Location::File(p)) => {
+                table.add_row(vec!["Path:", p]);
+            }
+            None => (),
+        };
+
+        if info.global_data.is_empty() {
+            table.add_row(vec!["Global:", "None"]);
+        } else {
+            let mut first = true;
+            for (key, value) in &info.global_data {
+                let data = &format! {"{key}={}", encode_upper(value)};
+                if first {
+                    first = false;
+                    table.add_row(vec!["Global:", data]);
+                } else {
+                    table.add_row(vec!["", data]);
+                }
+            }
+        }
+
+        if info.metadata.is_empty() {
+            table.add_row(vec!["Metadata:", "None"]);
+        } else {
+            let mut first = true;
+            for (key, value) in &info.metadata {
+                let data = &format! {"{key}={value}"};
+                if first {
+                    first = false;
+                    table.add_row(vec!["Metadata:", data]);
+                } else {
+                    table.add_row(vec!["", data]);
+                }
+            }
+        }
+
+        if info.map_pin_path.is_empty() {
+            table.add_row(vec!["Map Pin Path:", "None"]);
+        } else {
+            table.add_row(vec!["Map Pin Path:", &info.map_pin_path]);
+        }
+
+        match info.map_owner_id {
+            Some(id) => table.add_row(vec!["Map Owner ID:", &id.to_string()]),
+            None => table.add_row(vec!["Map Owner ID:", "None"]),
+        };
+
+        if info.map_used_by.is_empty() {
+            table.add_row(vec!["Maps Used By:", "None"]);
+        } else {
+            let mut first = true;
+            for prog_id in &info.map_used_by {
+                if first {
+                    first = false;
+                    table.add_row(vec!["Maps Used By:", prog_id]);
+                } else {
+                    table.add_row(vec!["", prog_id]);
+                }
+            }
+        };
+
+        match info.attach.as_ref().and_then(|a| a.info.as_ref()) {
+            Some(Info::XdpAttachInfo(p)) => {
+                table.add_row(vec!["Priority:", &p.priority.to_string()]);
+                table.add_row(vec!["Iface:", &p.iface]);
+                if let Some(pattern) = &p.iface_pattern {
+                    table.add_row(vec!["Iface Pattern:", pattern]);
+                }
+                if let Some(netns) = &p.netns {
+                    table.add_row(vec!["Network Namespace:", netns]);
+                }
+                table.add_row(vec!["Position:", &p.position.to_string()]);
+                table.add_row(vec![
+                    "Proceed On:",
+                    &format!("{}", XdpProceedOn::from_int32s(&p.proceed_on)?),
+                ]);
+                if p.disabled {
+                    table.add_row(vec!["Disabled:", "true"]);
+                }
+                if p.detached {
+                    table.add_row(vec!["Detached:", "true (interface removed)"]);
+                }
+                if p.frags {
+                    table.add_row(vec!["Frags:", "true"]);
+                }
+                if p.use_run_config {
+                    table.add_row(vec!["Run Config:", "true"]);
+                }
+                match (&p.effective_mode, &p.mode) {
+                    (Some(effective), Some(mode)) if effective != mode => {
+                        table.add_row(vec![
+                            "Mode:",
+                            &format!("{effective} (fell back from {mode})"),
+                        ]);
+                    }
+                    (Some(effective), _) => {
+                        table.add_row(vec!["Mode:", effective]);
+                    }
+                    (None, Some(mode)) => {
+                        table.add_row(vec!["Mode:", &format!("{mode} (requested)")]);
+                    }
+                    (None, None) => {}
+                }
+                add_foreign_rows(&mut table, &p.foreign_program_ids, &p.adopted_program_ids);
+            }
+            Some(Info::TcAttachInfo(p)) => {
+                table.add_row(vec!["Priority:", &p.priority.to_string()]);
+                table.add_row(vec!["Iface:", &p.iface]);
+                if let Some(pattern) = &p.iface_pattern {
+                    table.add_row(vec!["Iface Pattern:", pattern]);
+                }
+                if let Some(netns) = &p.netns {
+                    table.add_row(vec!["Network Namespace:", netns]);
+                }
+                table.add_row(vec!["Position:", &p.position.to_string()]);
+                table.add_row(vec!["Direction:", &p.direction]);
+                table.add_row(vec![
+                    "Proceed On:",
+                    &format!("{}", TcProceedOn::from_int32s(&p.proceed_on)?),
+                ]);
+                if p.disabled {
+                    table.add_row(vec!["Disabled:", "true"]);
+                }
+                if p.detached {
+                    table.add_row(vec!["Detached:", "true (interface removed)"]);
+                }
+                add_foreign_rows(&mut table, &p.foreign_program_ids, &p.adopted_program_ids);
+            }
+            Some(Info::TracepointAttachInfo(p)) => {
+                table.add_row(vec!["Tracepoint:", &p.tracepoint]);
+            }
+            Some(Info::KprobeAttachInfo(p)) => {
+                let probe_type = match p.retprobe {
+                    true => Kretprobe,
+                    false => Kprobe,
+                };
+
+                table.add_row(vec!["Probe Type:", &format!["{probe_type}"]]);
+                table.add_row(vec!["Function Name:", &p.fn_name]);
+                table.add_row(vec!["Offset:", &p.offset.to_string()]);
+                table.add_row(vec!["PID:", &p.container_pid.unwrap_or(0).to_string()]);
+            }
+            Some(Info::UprobeAttachInfo(p)) => {
+                let probe_type = match p.retprobe {
+                    true => Kretprobe,
+                    false => Kprobe,
+                };
+                table.add_row(vec!["Probe Type:", &format!["{probe_type}"]]);
+                table.add_row(vec![
+                    "Function Name:",
+                    &p.fn_name.clone().unwrap_or("NONE".to_string()),
+                ]);
+                table.add_row(vec!["Offset:", &p.offset.to_string()]);
+                table.add_row(vec!["Target:", &p.target]);
+                table.add_row(vec!["PID", &p.pid.unwrap_or(0).to_string()]);
+                table.add_row(vec![
+                    "Container PID:",
+                    &p.container_pid.unwrap_or(0).to_string(),
+                ]);
+            }
+            Some(Info::FentryAttachInfo(p)) => {
+                table.add_row(vec!["Function Name:", &p.fn_name]);
+            }
+            Some(Info::FexitAttachInfo(p)) => {
+                table.add_row(vec!["Function Name:", &p.fn_name]);
+            }
+            Some(Info::CgroupSkbAttachInfo(p)) => {
+                table.add_row(vec!["Cgroup Path:", &p.cgroup_path]);
+                table.add_row(vec!["Direction:", &p.direction]);
+            }
+            Some(Info::CgroupSockAddrAttachInfo(p)) => {
+                table.add_row(vec!["Cgroup Path:", &p.cgroup_path]);
+                table.add_row(vec![
+                    "Attach Type:",
+                    &p.attach_type.clone().unwrap_or("NONE".to_string()),
+                ]);
+            }
+            Some(Info::SockOpsAttachInfo(p)) => {
+                table.add_row(vec!["Cgroup Path:", &p.cgroup_path]);
+            }
+            Some(Info::SkMsgAttachInfo(p)) => {
+                table.add_row(vec!["Map Name:", &p.map_name]);
+            }
+            None => {
+                table.add_row(vec!["Unsupported Program Type", "None"]);
+            }
+        }
+
+        let mut first = true;
+        for link in &info.links {
+            let Some(attach) = link.attach.as_ref().and_then(|a| a.info.as_ref()) else {
+                continue;
+            };
+            let data = &format!("{}: {}", link.id, attach_point(attach));
+            if first {
+                first = false;
+                table.add_row(vec!["Links:", data]);
+            } else {
+                table.add_row(vec!["", data]);
+            }
+        }
+        Ok(ProgTable(table))
+    }
+
+    pub(crate) fn new_link(link: &LinkInfo) -> Self {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec![Cell::new("Link")
+            .add_attribute(comfy_table::Attribute::Bold)
+            .add_attribute(comfy_table::Attribute::Underlined)
+            .fg(Color::Green)]);
+
+        table.add_row(vec!["Link ID:", &link.id.to_string()]);
+        table.add_row(vec!["Program ID:", &link.program_id.to_string()]);
+        if let Some(attach) = link.attach.as_ref().and_then(|a| a.info.as_ref()) {
+            table.add_row(vec!["Attached To:", &attach_point(attach)]);
+        }
+
+        ProgTable(table)
+    }
+
+    pub(crate) fn new_kernel_info(k: &KernelProgramInfo) -> Result<Self, anyhow::Error> {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec![Cell::new("Kernel State")
+            .add_attribute(comfy_table::Attribute::Bold)
+            .add_attribute(comfy_table::Attribute::Underlined)
+            .fg(Color::Green)]);
+
+        let name = if k.name.is_empty() {
+            "None".to_string()
+        } else {
+            k.name.clone()
+        };
+
+        let rows = vec![
+            vec!["Program ID:".to_string(), k.id.to_string()],
+            vec!["Name:".to_string(), name],
+            vec![
+                "Type:".to_string(),
+                format!("{}", ProgramType::try_from(k.program_type)?),
+            ],
+            vec!["Loaded At:".to_string(), k.loaded_at.clone()],
+            vec!["Tag:".to_string(), k.tag.clone()],
+            vec!["GPL Compatible:".to_string(), k.gpl_compatible.to_string()],
+            vec!["Map IDs:".to_string(), format!("{:?}", k.map_ids)],
+            vec!["BTF ID:".to_string(), k.btf_id.to_string()],
+            vec![
+                "Size Translated (bytes):".to_string(),
+                k.bytes_xlated.to_string(),
+            ],
+            vec!["JITted:".to_string(), k.jited.to_string()],
+            vec!["Size JITted:".to_string(), k.bytes_jited.to_string()],
+            vec![
+                "Kernel Allocated Memory (bytes):".to_string(),
+                k.bytes_memlock.to_string(),
+            ],
+            vec![
+                "Verified Instruction Count:".to_string(),
+                k.verified_insns.to_string(),
+            ],
+        ];
+        table.add_rows(rows);
+        Ok(ProgTable(table))
+    }
+
+    pub(crate) fn new_list(wide: bool) -> Self {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        if wide {
+            table.set_header(vec![
+                "Program ID",
+                "Name",
+                "Type",
+                "Load Time",
+                "Attached To",
+                "Metadata",
+            ]);
+        } else {
+            table.set_header(vec!["Program ID", "Name", "Type", "Load Time"]);
+        }
+        ProgTable(table)
+    }
+
+    pub(crate) fn add_row_list(
+        &mut self,
+        id: String,
+        name: String,
+        type_: String,
+        load_time: String,
+    ) {
+        self.0.add_row(vec![id, name, type_, load_time]);
+    }
+
+    pub(crate) fn add_response_prog(
+        &mut self,
+        info: Option<&ProgramInfo>,
+        k: &KernelProgramInfo,
+    ) -> anyhow::Result<()> {
+        self.add_row_list(
+            k.id.to_string(),
+            k.name.clone(),
+            ProgramType::try_from(k.program_type)?.to_string(),
+            load_time(info, k),
+        );
+
+        Ok(())
+    }
+
+    pub(crate) fn add_response_prog_wide(
+        &mut self,
+        info: Option<&ProgramInfo>,
+        k: &KernelProgramInfo,
+    ) -> anyhow::Result<()> {
+        let attached_to: Vec<String> = info
+            .map(|i| {
+                std::iter::once(&i.attach)
+                    .chain(i.links.iter().map(|l| &l.attach))
+                    .filter_map(|a| a.as_ref().and_then(|a| a.info.as_ref()))
+                    .map(attach_point)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut metadata: Vec<String> = info
+            .map(|i| i.metadata.iter().map(|(k, v)| format!("{k}={v}")).collect())
+            .unwrap_or_default();
+        metadata.sort();
+
+        self.0.add_row(vec![
+            k.id.to_string(),
+            k.name.clone(),
+            ProgramType::try_from(k.program_type)?.to_string(),
+            load_time(info, k),
+            attached_to.join(","),
+            metadata.join(","),
+        ]);
+
+        Ok(())
+    }
+
+    pub(crate) fn new_map(map: &MapInfo) -> Self {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec![Cell::new("Map")
+            .add_attribute(comfy_table::Attribute::Bold)
+            .add_attribute(comfy_table::Attribute::Underlined)
+            .fg(Color::Green)]);
+
+        let with_type = |size: u32, type_name: &Option<String>| match type_name {
+            Some(t) => format!("{size} ({t})"),
+            None => size.to_string(),
+        };
+
+        table.add_row(vec!["Name:", &map.name]);
+        table.add_row(vec!["ID:", &map.map_id.to_string()]);
+        table.add_row(vec!["Map Type:", &MapType::from(map.map_type).to_string()]);
+        table.add_row(vec!["Key Size:", &with_type(map.key_size, &map.key_type)]);
+        table.add_row(vec![
+            "Value Size:",
+            &with_type(map.value_size, &map.value_type),
+        ]);
+        table.add_row(vec!["Max Entries:", &map.max_entries.to_string()]);
+        table.add_row(vec!["Flags:", &format!("{:#x}", map.map_flags)]);
+        table.add_row(vec!["Pin Path:", &map.pin_path]);
+
+        ProgTable(table)
+    }
+
+    pub(crate) fn new_map_entries(entries: &[MapEntry]) -> Self {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec!["Key", "Value"]);
+
+        for e in entries {
+            let key = e
+                .formatted_key
+                .clone()
+                .unwrap_or_else(|| format!("0x{}", hex::encode(&e.key)));
+            let value = e
+                .formatted_value
+                .clone()
+                .unwrap_or_else(|| format!("0x{}", hex::encode(&e.value)));
+            table.add_row(vec![key, value]);
+        }
+
+        ProgTable(table)
+    }
+
+    pub(crate) fn new_image(image: &ImageOutput) -> Self {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec![Cell::new("Image")
+            .add_attribute(comfy_table::Attribute::Bold)
+            .add_attribute(comfy_table::Attribute::Underlined)
+            .fg(Color::Green)]);
+
+        table.add_row(vec!["Image URL:", &image.url]);
+        table.add_row(vec!["Pull Policy:", &image.pull_policy]);
+        if image.programs.is_empty() {
+            table.add_row(vec!["Programs:", "None"]);
+        } else {
+            let mut first = true;
+            for name in &image.programs {
+                if first {
+                    first = false;
+                    table.add_row(vec!["Programs:", name]);
+                } else {
+                    table.add_row(vec!["", name]);
+                }
+            }
+        }
+
+        ProgTable(table)
+    }
+
+    pub(crate) fn new_dispatcher_list(dispatchers: &[DispatcherInfo]) -> Self {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec![
+            "Iface",
+            "Type",
+            "Direction",
+            "Revision",
+            "Mode",
+            "Programs",
+        ]);
+
+        for d in dispatchers {
+            let num_programs: usize = d.stages.iter().map(|s| s.slots.len()).sum();
+            table.add_row(vec![
+                d.iface.clone(),
+                ProgramType::try_from(d.program_type)
+                    .map(|t| t.to_string())
+                    .unwrap_or_default(),
+                d.direction.clone(),
+                d.revision.to_string(),
+                d.mode.clone(),
+                num_programs.to_string(),
+            ]);
+        }
+
+        ProgTable(table)
+    }
+
+    pub(crate) fn new_dispatcher(d: &DispatcherInfo) -> Result<Self, anyhow::Error> {
+        let mut table = Table::new();
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        table.set_header(vec![Cell::new("Dispatcher")
+            .add_attribute(comfy_table::Attribute::Bold)
+            .add_attribute(comfy_table::Attribute::Underlined)
+            .fg(Color::Green)]);
+
+        table.add_row(vec!["Iface:", &d.iface]);
+        table.add_row(vec!["Interface Index:", &d.if_index.to_string()]);
+        if let Some(netns) = &d.netns {
+            table.add_row(vec!["Network Namespace:", netns]);
+        }
+        table.add_row(vec![
+            "Type:",
+            &ProgramType::try_from(d.program_type)?.to_string(),
+        ]);
+        if !d.direction.is_empty() {
+            table.add_row(vec!["Direction:", &d.direction]);
+        }
+        table.add_row(vec!["Revision:", &d.revision.to_string()]);
+        table.add_row(vec!["Mode:", &d.mode]);
+        if let Some(priority) = d.priority {
+            table.add_row(vec!["Priority:", &priority.to_string()]);
+        }
+        for (k, s) in d.stages.iter().enumerate() {
+            let prefix = if d.stages.len() > 1 {
+                format!("Dispatcher {k} ")
+            } else {
+                String::new()
+            };
+            if let Some(handle) = s.handle {
+                table.add_row(vec![format!("{prefix}Handle:"), format!("{handle:#x}")]);
+            }
+            table.add_row(vec![
+                format!("{prefix}Programs Enabled:"),
+                s.num_progs_enabled.to_string(),
+            ]);
+            table.add_row(vec![
+                format!("{prefix}Chain Call Actions:"),
+                s.chain_call_actions
+                    .iter()
+                    .map(|a| format!("{a:#x}"))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ]);
+        }
+        for (i, f) in d.foreign.iter().enumerate() {
+            let label = if i == 0 { "Foreign Programs:" } else { "" };
+            let name = match f.name.as_str() {
+                "" => String::new(),
+                name => format!(" {name}"),
+            };
+            let adopted = if f.adopted { " (adopted)" } else { "" };
+            table.add_row(vec![
+                label.to_string(),
+                format!("{}{name}{adopted}", f.program_id),
+            ]);
+        }
+
+        Ok(ProgTable(table))
+    }
+
+    pub(crate) fn new_dispatcher_slots(d: &DispatcherInfo) -> Result<Self, anyhow::Error> {
+        let mut table = Table::new();
+        let program_type = ProgramType::try_from(d.program_type)?;
+
+        table.load_preset(comfy_table::presets::NOTHING);
+        if d.stages.len() > 1 {
+            table.set_header(vec![
+                "Dispatcher",
+                "Slot",
+                "Program ID",
+                "Name",
+                "Priority",
+                "Proceed On",
+                "Link ID",
+            ]);
+        } else {
+            table.set_header(vec![
+                "Slot",
+                "Program ID",
+                "Name",
+                "Priority",
+                "Proceed On",
+                "Link ID",
+            ]);
+        }
+
+        for (k, s) in d.stages.iter().enumerate() {
+            for (i, p) in s.slots.iter().enumerate() {
+                let mask = s.chain_call_actions.get(i).copied().unwrap_or(0);
+                let mut row = vec![
+                    p.slot.to_string(),
+                    p.program_id.to_string(),
+                    if p.disabled {
+                        format!("{} (disabled)", p.name)
+                    } else if p.libxdp {
+                        format!("{} (libxdp)", p.name)
+                    } else {
+                        p.name.clone()
+                    },
+                    p.priority.to_string(),
+                    format!(
+                        "{} ({mask:#x})",
+                        proceed_on_names(program_type, mask)?.join(", ")
+                    ),
+                    p.link_id.map(|l| l.to_string()).unwrap_or_default(),
+                ];
+                if d.stages.len() > 1 {
+                    row.insert(0, k.to_string());
+                }
+                table.add_row(row);
+            }
+        }
+
+        Ok(ProgTable(table))
+    }
+
+    pub(crate) fn print(&self) {
+        println!("{self}\n")
+    }
+}
+
+// Lists the programs attached to an XDP or TC program's interface by something
+// other than bpfman.
+// Programs waiting to be restored aren't loaded, so the time they were last
+// loaded at isn't shown.
+fn load_time(info: Option<&ProgramInfo>, k: &KernelProgramInfo) -> String {
+    match info {
+        Some(i) if i.restore_pending => "pending restore".to_string(),
+        _ => k.loaded_at.clone(),
+    }
+}
+
+fn add_foreign_rows(table: &mut Table, foreign: &[u32], adopted: &[u32]) {
+    let join = |ids: &[u32]| {
+        ids.iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if !foreign.is_empty() {
+        table.add_row(vec!["Foreign Programs:", &join(foreign)]);
+    }
+    if !adopted.is_empty() {
+        table.add_row(vec!["Adopted Programs:", &join(adopted)]);
+    }
+}
+
+// Returns a short description of where a program is attached.
+fn attach_point(info: &Info) -> String {
+    match info {
+        Info::XdpAttachInfo(i) => format!(
+            "{} priority {}{}{}",
+            i.iface,
+            i.priority,
+            if i.disabled { " (disabled)" } else { "" },
+            if i.detached { " (detached)" } else { "" }
+        ),
+        Info::TcAttachInfo(i) => format!(
+            "{} {} priority {}{}{}",
+            i.iface,
+            i.direction,
+            i.priority,
+            if i.disabled { " (disabled)" } else { "" },
+            if i.detached { " (detached)" } else { "" }
+        ),
+        Info::TracepointAttachInfo(i) => i.tracepoint.clone(),
+        Info::KprobeAttachInfo(i) => i.fn_name.clone(),
+        Info::UprobeAttachInfo(i) => match &i.fn_name {
+            Some(f) => format!("{}:{f}", i.target),
+            None => i.target.clone(),
+        },
+        Info::FentryAttachInfo(i) => i.fn_name.clone(),
+        Info::FexitAttachInfo(i) => i.fn_name.clone(),
+        Info::CgroupSkbAttachInfo(i) => format!("{} {}", i.cgroup_path, i.direction),
+        Info::CgroupSockAddrAttachInfo(i) => i.cgroup_path.clone(),
+        Info::SockOpsAttachInfo(i) => i.cgroup_path.clone(),
+        Info::SkMsgAttachInfo(i) => i.map_name.clone(),
+    }
+}
+
+impl std::fmt::Display for ProgTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -0,0 +1,29 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use bpfman::remove_program;
+use bpfman_api::v1::UnloadRequest;
+
+use crate::{
+    args::UnloadArgs,
+    remote::{connect, status_error},
+};
+
+pub(crate) async fn execute_unload(
+    args: &UnloadArgs,
+    remote: Option<&str>,
+) -> Result<(), anyhow::Error> {
+    match remote {
+        None => remove_program(args.program_id).await?,
+        Some(endpoint) => {
+            connect(endpoint)
+                .await?
+                .unload(UnloadRequest {
+                    id: args.program_id,
+                })
+                .await
+                .map_err(status_error)?;
+        }
+    }
+    Ok(())
+}
@@ -3,12 +3,14 @@
 use std::{env, fs::create_dir_all, path::PathBuf, str::FromStr};
 
 use anyhow::Context;
+use bpfman::{load_static_programs, restore_programs};
 use clap::{Args, Parser};
-use log::debug;
+use log::{debug, error};
 use systemd_journal_logger::{connected_to_journal, JournalLog};
 
 use crate::serve::serve;
 
+mod monitor;
 mod rpc;
 mod serve;
 mod storage;
@@ -87,7 +89,17 @@ fn initialize_rpc(csi_support: bool) -> anyhow::Result<()> {
 async fn main() -> anyhow::Result<()> {
     let args = Rpc::parse();
     initialize_rpc(args.csi_support)?;
-    //TODO https://github.com/bpfman/bpfman/issues/881
+
+    // Restore the programs lost on a reboot, unless the CLI already has, and
+    // load any static programs, before accepting requests so that they are
+    // always in place whenever bpfman is running.
+    if let Err(e) = restore_programs().await {
+        error!("Failed to restore programs: {e}");
+    }
+    if let Err(e) = load_static_programs().await {
+        error!("Failed to load static programs: {e}");
+    }
+
     serve(args.csi_support, args.timeout, &args.socket_path).await?;
 
     Ok(())
@@ -0,0 +1,142 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+use std::collections::{HashMap, HashSet};
+
+use anyhow::Context;
+use bpfman::{interface_added, interface_removed, interfaces_removed};
+use futures::{
+    channel::mpsc::UnboundedReceiver,
+    stream::{StreamExt, TryStreamExt},
+};
+use log::{debug, error, info, warn};
+use netlink_packet_core::{NetlinkMessage, NetlinkPayload};
+use netlink_packet_route::{
+    link::{LinkAttribute, LinkMessage},
+    RouteNetlinkMessage,
+};
+use netlink_sys::{AsyncSocket, SocketAddr};
+use rtnetlink::{constants::RTMGRP_LINK, Handle};
+use tokio::sync::broadcast;
+
+/// Watches the interfaces of bpfman-rpc's network namespace. XDP and TC
+/// programs are detached from interfaces which are removed, and attached again
+/// when an interface with the same name is added back.
+#[derive(Default)]
+pub struct LinkMonitor {
+    // Indexes of the interfaces which exist, so that changes to an existing
+    // interface aren't mistaken for a new interface.
+    links: HashSet<u32>,
+}
+
+impl LinkMonitor {
+    pub async fn run(mut self, mut shutdown_channel: broadcast::Receiver<()>) {
+        let (handle, mut messages) = match connect() {
+            Ok(c) => c,
+            Err(e) => {
+                error!("Not monitoring interfaces: {e:#}");
+                return;
+            }
+        };
+        info!("Monitoring interfaces");
+
+        // Interfaces may have been removed or added back while bpfman-rpc
+        // wasn't running. Removed interfaces are handled first, since one may
+        // have been added back with another index.
+        let mut links = vec![];
+        let mut list = handle.link().get().execute();
+        let listed = loop {
+            match list.try_next().await {
+                Ok(Some(link)) => links.push(link),
+                Ok(None) => break true,
+                Err(e) => {
+                    warn!("Unable to list interfaces: {e}");
+                    break false;
+                }
+            }
+        };
+        if listed {
+            let interfaces: HashMap<u32, String> = links
+                .iter()
+                .filter_map(|l| Some((l.header.index, if_name(l)?.clone())))
+                .collect();
+            if let Err(e) = interfaces_removed(&interfaces).await {
+                warn!("Unable to detach programs from removed interfaces: {e}");
+            }
+        }
+        for link in links {
+            self.link_added(link).await;
+        }
+
+        loop {
+            tokio::select! {
+                Some((message, _)) = messages.next() => self.handle(message).await,
+                _ = shutdown_channel.recv() => {
+                    debug!("Link Monitor: Received shutdown signal");
+                    break;
+                }
+            }
+        }
+    }
+
+    async fn handle(&mut self, message: NetlinkMessage<RouteNetlinkMessage>) {
+        match message.payload {
+            NetlinkPayload::InnerMessage(RouteNetlinkMessage::NewLink(link)) => {
+                self.link_added(link).await
+            }
+            NetlinkPayload::InnerMessage(RouteNetlinkMessage::DelLink(link)) => {
+                self.link_removed(link).await
+            }
+            _ => {}
+        }
+    }
+
+    async fn link_added(&mut self, link: LinkMessage) {
+        let if_index = link.header.index;
+        // Changes to the state of an interface are reported as new links too.
+        if !self.links.insert(if_index) {
+            return;
+        }
+        let Some(if_name) = if_name(&link) else {
+            return;
+        };
+
+        debug!("Interface {if_name} added with index {if_index}");
+        if let Err(e) = interface_added(if_name, if_index).await {
+            warn!("Unable to attach programs to interface {if_name}: {e}");
+        }
+    }
+
+    async fn link_removed(&mut self, link: LinkMessage) {
+        let if_index = link.header.index;
+        self.links.remove(&if_index);
+
+        debug!("Interface with index {if_index} removed");
+        if let Err(e) = interface_removed(if_index).await {
+            warn!("Unable to detach programs from interface with index {if_index}: {e}");
+        }
+    }
+}
+
+fn if_name(link: &LinkMessage) -> Option<&String> {
+    link.attributes.iter().find_map(|a| match a {
+        LinkAttribute::IfName(n) => Some(n),
+        _ => None,
+    })
+}
+
+type Messages = UnboundedReceiver<(NetlinkMessage<RouteNetlinkMessage>, SocketAddr)>;
+
+// Opens a netlink socket which also receives the changes to links, and
+// spawns the task driving it.
+fn connect() -> anyhow::Result<(Handle, Messages)> {
+    let (mut connection, handle, messages) =
+        rtnetlink::new_connection().context("unable to open netlink socket")?;
+    connection
+        .socket_mut()
+        .socket_mut()
+        .bind(&SocketAddr::new(0, RTMGRP_LINK))
+        .context("unable to subscribe to link changes")?;
+    tokio::spawn(connection);
+    Ok((handle, messages))
+}
@@ -1,99 +1,159 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
+use std::collections::HashMap;
+
 use bpfman::{
-    add_program, get_program, list_programs, pull_bytecode, remove_program,
+    add_program, attach_program, delete_map_entry, detach_link, disable_program, dump_map,
+    enable_program, get_map_info, get_program, list_dispatchers, list_links, list_programs,
+    pull_bytecode, remove_program, reorder_program,
     types::{
-        FentryProgram, FexitProgram, KprobeProgram, ListFilter, Location, Program, ProgramData,
-        TcProceedOn, TcProgram, TracepointProgram, UprobeProgram, XdpProceedOn, XdpProgram,
+        AttachPoint, CgroupSkbProgram, CgroupSockAddrProgram, FentryProgram, FexitProgram,
+        KprobeProgram, ListFilter, Location, Program, ProgramData, SkMsgProgram, SockOpsProgram,
+        TcProceedOn, TcProgram, TracepointProgram, UprobeProgram, XdpMode, XdpProceedOn,
+        XdpProgram,
     },
+    update_map_entry, update_program,
+    utils::netns_path,
+    watch_programs,
 };
-use bpfman_api::v1::{
-    attach_info::Info, bpfman_server::Bpfman, bytecode_location::Location as RpcLocation,
-    list_response::ListResult, FentryAttachInfo, FexitAttachInfo, GetRequest, GetResponse,
-    KprobeAttachInfo, ListRequest, ListResponse, LoadRequest, LoadResponse, PullBytecodeRequest,
-    PullBytecodeResponse, TcAttachInfo, TracepointAttachInfo, UnloadRequest, UnloadResponse,
-    UprobeAttachInfo, XdpAttachInfo,
+use bpfman_api::{
+    status::{invalid_argument, IntoStatus},
+    v1::{
+        attach_info::Info, bpfman_server::Bpfman, bytecode_location::Location as RpcLocation,
+        list_response::ListResult, AttachInfo, AttachRequest, AttachResponse, CgroupSkbAttachInfo,
+        CgroupSockAddrAttachInfo, DeleteMapEntryRequest, DeleteMapEntryResponse, DetachRequest,
+        DetachResponse, DisableRequest, DisableResponse, DumpMapRequest, DumpMapResponse,
+        EnableRequest, EnableResponse, FentryAttachInfo, FexitAttachInfo, GetMapRequest,
+        GetMapResponse, GetRequest, GetResponse, KprobeAttachInfo, LinkInfo,
+        ListDispatchersRequest, ListDispatchersResponse, ListRequest, ListResponse, LoadRequest,
+        LoadResponse, ProgramInfo, PullBytecodeRequest, PullBytecodeResponse, ReorderRequest,
+        ReorderResponse, SkMsgAttachInfo, SockOpsAttachInfo, TcAttachInfo, TracepointAttachInfo,
+        UnloadRequest, UnloadResponse, UpdateMapEntryRequest, UpdateMapEntryResponse,
+        UpdateRequest, UpdateResponse, UprobeAttachInfo, WatchRequest, WatchResponse,
+        XdpAttachInfo,
+    },
 };
+use log::warn;
+use tokio::sync::{broadcast, broadcast::error::RecvError, mpsc};
+use tokio_stream::wrappers::ReceiverStream;
 use tonic::{Request, Response, Status};
 
-pub struct BpfmanLoader {}
+// Number of events buffered for each Watch stream.
+const WATCH_CHANNEL_SIZE: usize = 64;
+
+pub struct BpfmanLoader {
+    // Used to end Watch streams when bpfman-rpc shuts down, since streams
+    // would otherwise hold off the graceful shutdown forever.
+    shutdown_tx: broadcast::Sender<()>,
+}
 
 impl BpfmanLoader {
-    pub(crate) fn new() -> BpfmanLoader {
-        BpfmanLoader {}
+    pub(crate) fn new(shutdown_tx: broadcast::Sender<()>) -> BpfmanLoader {
+        BpfmanLoader { shutdown_tx }
     }
 }
 
-#[tonic::async_trait]
-impl Bpfman for BpfmanLoader {
-    async fn load(&self, request: Request<LoadRequest>) -> Result<Response<LoadResponse>, Status> {
-        let request = request.into_inner();
-
-        let bytecode_source = match request
-            .bytecode
-            .ok_or(Status::aborted("missing bytecode info"))?
-            .location
-            .ok_or(Status::aborted("missing location"))?
-        {
-            RpcLocation::Image(i) => Location::Image(i.into()),
-            RpcLocation::File(p) => Location::File(p),
-        };
+// Builds the Program described by a LoadRequest.
+fn program_from_load_request(request: LoadRequest) -> Result<Program, Status> {
+    let bytecode_source = match request
+        .bytecode
+        .ok_or_else(|| invalid_argument("bytecode", "missing bytecode info"))?
+        .location
+        .ok_or_else(|| invalid_argument("bytecode.location", "missing location"))?
+    {
+        RpcLocation::Image(i) => Location::Image(i.into()),
+        RpcLocation::File(p) => Location::File(p),
+    };
 
-        let data = ProgramData::new(
-            bytecode_source,
-            request.name,
-            request.metadata,
-            request.global_data,
-            request.map_owner_id,
-        )
-        .map_err(|e| Status::aborted(format!("failed to create ProgramData: {e}")))?;
+    let mut data = ProgramData::new(
+        bytecode_source,
+        request.name,
+        request.metadata,
+        request.global_data,
+        request.map_owner_id,
+    )
+    .map_err(IntoStatus::into_status)?;
+    if let Some(level) = request.verifier_log_level {
+        data.set_verifier_log_level(level)
+            .map_err(IntoStatus::into_status)?;
+    }
 
-        let program = match request
+    Ok(
+        match request
             .attach
-            .ok_or(Status::aborted("missing attach info"))?
+            .ok_or_else(|| invalid_argument("attach", "missing attach info"))?
             .info
-            .ok_or(Status::aborted("missing info"))?
+            .ok_or_else(|| invalid_argument("attach.info", "missing info"))?
         {
             Info::XdpAttachInfo(XdpAttachInfo {
                 priority,
                 iface,
                 position: _,
+                disabled: _,
+                detached: _,
+                iface_pattern: _,
+                effective_mode: _,
+                foreign_program_ids: _,
+                adopted_program_ids: _,
                 proceed_on,
-            }) => Program::Xdp(
-                XdpProgram::new(
+                netns,
+                container_pid,
+                frags,
+                mode,
+                mode_fallback,
+                use_run_config,
+            }) => {
+                let mut program = XdpProgram::new(
                     data,
                     priority,
                     iface,
-                    XdpProceedOn::from_int32s(proceed_on)
-                        .map_err(|_| Status::aborted("failed to parse proceed_on"))?,
+                    XdpProceedOn::from_int32s(proceed_on).map_err(IntoStatus::into_status)?,
+                    netns_path(netns, container_pid),
                 )
-                .map_err(|e| Status::aborted(format!("failed to create xdpprogram: {e}")))?,
-            ),
+                .map_err(IntoStatus::into_status)?;
+                program.set_frags(frags).map_err(IntoStatus::into_status)?;
+                program
+                    .set_use_run_config(use_run_config)
+                    .map_err(IntoStatus::into_status)?;
+                if let Some(mode) = mode {
+                    program
+                        .set_mode(
+                            mode.parse::<XdpMode>().map_err(IntoStatus::into_status)?,
+                            mode_fallback,
+                        )
+                        .map_err(IntoStatus::into_status)?;
+                }
+                Program::Xdp(program)
+            }
             Info::TcAttachInfo(TcAttachInfo {
                 priority,
                 iface,
                 position: _,
+                disabled: _,
+                detached: _,
+                iface_pattern: _,
+                foreign_program_ids: _,
+                adopted_program_ids: _,
                 direction,
                 proceed_on,
+                netns,
+                container_pid,
             }) => {
-                let direction = direction
-                    .try_into()
-                    .map_err(|_| Status::aborted("direction is not a string"))?;
+                let direction = direction.try_into().map_err(IntoStatus::into_status)?;
                 Program::Tc(
                     TcProgram::new(
                         data,
                         priority,
                         iface,
-                        TcProceedOn::from_int32s(proceed_on)
-                            .map_err(|_| Status::aborted("failed to parse proceed_on"))?,
+                        TcProceedOn::from_int32s(proceed_on).map_err(IntoStatus::into_status)?,
                         direction,
+                        netns_path(netns, container_pid),
                     )
-                    .map_err(|e| Status::aborted(format!("failed to create tcprogram: {e}")))?,
+                    .map_err(IntoStatus::into_status)?,
                 )
             }
             Info::TracepointAttachInfo(TracepointAttachInfo { tracepoint }) => Program::Tracepoint(
-                TracepointProgram::new(data, tracepoint)
-                    .map_err(|e| Status::aborted(format!("failed to create tcprogram: {e}")))?,
+                TracepointProgram::new(data, tracepoint).map_err(IntoStatus::into_status)?,
             ),
             Info::KprobeAttachInfo(KprobeAttachInfo {
                 fn_name,
@@ -102,7 +162,7 @@ impl Bpfman for BpfmanLoader {
                 container_pid,
             }) => Program::Kprobe(
                 KprobeProgram::new(data, fn_name, offset, retprobe, container_pid)
-                    .map_err(|e| Status::aborted(format!("failed to create kprobeprogram: {e}")))?,
+                    .map_err(IntoStatus::into_status)?,
             ),
             Info::UprobeAttachInfo(UprobeAttachInfo {
                 fn_name,
@@ -113,35 +173,220 @@ impl Bpfman for BpfmanLoader {
                 container_pid,
             }) => Program::Uprobe(
                 UprobeProgram::new(data, fn_name, offset, target, retprobe, pid, container_pid)
-                    .map_err(|e| Status::aborted(format!("failed to create uprobeprogram: {e}")))?,
-            ),
-            Info::FentryAttachInfo(FentryAttachInfo { fn_name }) => Program::Fentry(
-                FentryProgram::new(data, fn_name)
-                    .map_err(|e| Status::aborted(format!("failed to create fentryprogram: {e}")))?,
+                    .map_err(IntoStatus::into_status)?,
             ),
-            Info::FexitAttachInfo(FexitAttachInfo { fn_name }) => Program::Fexit(
-                FexitProgram::new(data, fn_name)
-                    .map_err(|e| Status::aborted(format!("failed to create fexitprogram: {e}")))?,
+            Info::FentryAttachInfo(FentryAttachInfo { fn_name }) => {
+                Program::Fentry(FentryProgram::new(data, fn_name).map_err(IntoStatus::into_status)?)
+            }
+            Info::FexitAttachInfo(FexitAttachInfo { fn_name }) => {
+                Program::Fexit(FexitProgram::new(data, fn_name).map_err(IntoStatus::into_status)?)
+            }
+            Info::CgroupSkbAttachInfo(CgroupSkbAttachInfo {
+                cgroup_path,
+                direction,
+            }) => {
+                let direction = direction.try_into().map_err(IntoStatus::into_status)?;
+                Program::CgroupSkb(
+                    CgroupSkbProgram::new(data, cgroup_path, direction)
+                        .map_err(IntoStatus::into_status)?,
+                )
+            }
+            Info::CgroupSockAddrAttachInfo(CgroupSockAddrAttachInfo { cgroup_path, .. }) => {
+                Program::CgroupSockAddr(
+                    CgroupSockAddrProgram::new(data, cgroup_path)
+                        .map_err(IntoStatus::into_status)?,
+                )
+            }
+            Info::SockOpsAttachInfo(SockOpsAttachInfo { cgroup_path }) => Program::SockOps(
+                SockOpsProgram::new(data, cgroup_path).map_err(IntoStatus::into_status)?,
             ),
+            Info::SkMsgAttachInfo(SkMsgAttachInfo { map_name }) => {
+                Program::SkMsg(SkMsgProgram::new(data, map_name).map_err(IntoStatus::into_status)?)
+            }
+        },
+    )
+}
+
+// Builds the AttachPoint described by the AttachInfo of an AttachRequest.
+fn attach_point_from_info(attach: Option<AttachInfo>) -> Result<AttachPoint, Status> {
+    Ok(
+        match attach
+            .ok_or_else(|| invalid_argument("attach", "missing attach info"))?
+            .info
+            .ok_or_else(|| invalid_argument("attach.info", "missing info"))?
+        {
+            Info::XdpAttachInfo(XdpAttachInfo {
+                priority,
+                iface,
+                proceed_on,
+                netns,
+                container_pid,
+                ..
+            }) => AttachPoint::Xdp {
+                iface,
+                priority,
+                proceed_on: XdpProceedOn::from_int32s(proceed_on)
+                    .map_err(IntoStatus::into_status)?,
+                netns: netns_path(netns, container_pid),
+            },
+            Info::TcAttachInfo(TcAttachInfo {
+                priority,
+                iface,
+                direction,
+                proceed_on,
+                netns,
+                container_pid,
+                ..
+            }) => AttachPoint::Tc {
+                iface,
+                priority,
+                direction: direction.try_into().map_err(IntoStatus::into_status)?,
+                proceed_on: TcProceedOn::from_int32s(proceed_on)
+                    .map_err(IntoStatus::into_status)?,
+                netns: netns_path(netns, container_pid),
+            },
+            Info::TracepointAttachInfo(TracepointAttachInfo { tracepoint }) => {
+                AttachPoint::Tracepoint { tracepoint }
+            }
+            Info::KprobeAttachInfo(KprobeAttachInfo {
+                fn_name, offset, ..
+            }) => AttachPoint::Kprobe { fn_name, offset },
+            Info::UprobeAttachInfo(UprobeAttachInfo {
+                fn_name,
+                offset,
+                target,
+                pid,
+                container_pid,
+                ..
+            }) => AttachPoint::Uprobe {
+                fn_name,
+                offset,
+                target,
+                pid,
+                container_pid,
+            },
+            Info::CgroupSkbAttachInfo(CgroupSkbAttachInfo { cgroup_path, .. }) => {
+                AttachPoint::CgroupSkb { cgroup_path }
+            }
+            Info::CgroupSockAddrAttachInfo(CgroupSockAddrAttachInfo { cgroup_path, .. }) => {
+                AttachPoint::CgroupSockAddr { cgroup_path }
+            }
+            Info::SockOpsAttachInfo(SockOpsAttachInfo { cgroup_path }) => {
+                AttachPoint::SockOps { cgroup_path }
+            }
+            Info::FentryAttachInfo(_) | Info::FexitAttachInfo(_) | Info::SkMsgAttachInfo(_) => {
+                return Err(invalid_argument(
+                    "attach.info",
+                    "fentry, fexit and sk_msg programs can only be attached when they are loaded",
+                ))
+            }
+        },
+    )
+}
+
+#[tonic::async_trait]
+impl Bpfman for BpfmanLoader {
+    type WatchStream = ReceiverStream<Result<WatchResponse, Status>>;
+
+    async fn load(&self, request: Request<LoadRequest>) -> Result<Response<LoadResponse>, Status> {
+        let request = request.into_inner();
+        let dry_run = request.dry_run;
+
+        let program = program_from_load_request(request)?;
+
+        let program = add_program(program, dry_run)
+            .await
+            .map_err(IntoStatus::into_status)?;
+
+        let reply_entry = LoadResponse {
+            info: Some((&program).try_into().map_err(IntoStatus::into_status)?),
+            kernel_info: Some((&program).try_into().map_err(IntoStatus::into_status)?),
         };
 
-        let program = add_program(program)
+        Ok(Response::new(reply_entry))
+    }
+
+    async fn update(
+        &self,
+        request: Request<UpdateRequest>,
+    ) -> Result<Response<UpdateResponse>, Status> {
+        let request = request.into_inner();
+
+        let program = program_from_load_request(
+            request
+                .program
+                .ok_or_else(|| invalid_argument("program", "missing replacement program"))?,
+        )?;
+
+        let program = update_program(request.id, program)
             .await
-            .map_err(|e| Status::aborted(format!("{e}")))?;
-
-        let reply_entry =
-            LoadResponse {
-                info: Some((&program).try_into().map_err(|e| {
-                    Status::aborted(format!("convert Program to GRPC program: {e}"))
-                })?),
-                kernel_info: Some((&program).try_into().map_err(|e| {
-                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
-                })?),
-            };
+            .map_err(IntoStatus::into_status)?;
+
+        let reply_entry = UpdateResponse {
+            info: Some((&program).try_into().map_err(IntoStatus::into_status)?),
+            kernel_info: Some((&program).try_into().map_err(IntoStatus::into_status)?),
+        };
 
         Ok(Response::new(reply_entry))
     }
 
+    async fn get_map(
+        &self,
+        request: Request<GetMapRequest>,
+    ) -> Result<Response<GetMapResponse>, Status> {
+        let request = request.into_inner();
+
+        let map = get_map_info(request.id, &request.map_name)
+            .await
+            .map_err(IntoStatus::into_status)?;
+
+        Ok(Response::new(GetMapResponse {
+            info: Some(map.into()),
+        }))
+    }
+
+    async fn dump_map(
+        &self,
+        request: Request<DumpMapRequest>,
+    ) -> Result<Response<DumpMapResponse>, Status> {
+        let request = request.into_inner();
+
+        let (map, entries) = dump_map(request.id, &request.map_name)
+            .await
+            .map_err(IntoStatus::into_status)?;
+
+        Ok(Response::new(DumpMapResponse {
+            info: Some(map.into()),
+            entries: entries.into_iter().map(|e| e.into()).collect(),
+        }))
+    }
+
+    async fn update_map_entry(
+        &self,
+        request: Request<UpdateMapEntryRequest>,
+    ) -> Result<Response<UpdateMapEntryResponse>, Status> {
+        let request = request.into_inner();
+
+        update_map_entry(request.id, &request.map_name, &request.key, &request.value)
+            .await
+            .map_err(IntoStatus::into_status)?;
+
+        Ok(Response::new(UpdateMapEntryResponse {}))
+    }
+
+    async fn delete_map_entry(
+        &self,
+        request: Request<DeleteMapEntryRequest>,
+    ) -> Result<Response<DeleteMapEntryResponse>, Status> {
+        let request = request.into_inner();
+
+        delete_map_entry(request.id, &request.map_name, &request.key)
+            .await
+            .map_err(IntoStatus::into_status)?;
+
+        Ok(Response::new(DeleteMapEntryResponse {}))
+    }
+
     async fn unload(
         &self,
         request: Request<UnloadRequest>,
@@ -151,32 +396,128 @@ impl Bpfman for BpfmanLoader {
 
         remove_program(request.id)
             .await
-            .map_err(|e| Status::aborted(format!("{e}")))?;
+            .map_err(IntoStatus::into_status)?;
 
         Ok(Response::new(reply))
     }
 
+    async fn attach(
+        &self,
+        request: Request<AttachRequest>,
+    ) -> Result<Response<AttachResponse>, Status> {
+        let request = request.into_inner();
+
+        let attach = attach_point_from_info(request.attach)?;
+
+        let link = attach_program(request.id, attach)
+            .await
+            .map_err(IntoStatus::into_status)?;
+
+        Ok(Response::new(AttachResponse {
+            link: Some((&link).try_into().map_err(IntoStatus::into_status)?),
+        }))
+    }
+
+    async fn detach(
+        &self,
+        request: Request<DetachRequest>,
+    ) -> Result<Response<DetachResponse>, Status> {
+        let request = request.into_inner();
+
+        detach_link(request.link_id)
+            .await
+            .map_err(IntoStatus::into_status)?;
+
+        Ok(Response::new(DetachResponse {}))
+    }
+
+    async fn list_dispatchers(
+        &self,
+        request: Request<ListDispatchersRequest>,
+    ) -> Result<Response<ListDispatchersResponse>, Status> {
+        let request = request.into_inner();
+
+        let dispatchers = list_dispatchers(request.iface.as_deref())
+            .await
+            .map_err(IntoStatus::into_status)?;
+
+        Ok(Response::new(ListDispatchersResponse {
+            dispatchers: dispatchers.into_iter().map(|d| d.into()).collect(),
+        }))
+    }
+
+    async fn reorder(
+        &self,
+        request: Request<ReorderRequest>,
+    ) -> Result<Response<ReorderResponse>, Status> {
+        let request = request.into_inner();
+
+        let proceed_on = if request.proceed_on.is_empty() {
+            None
+        } else {
+            Some(request.proceed_on)
+        };
+        let program = reorder_program(request.id, request.priority, proceed_on)
+            .await
+            .map_err(IntoStatus::into_status)?;
+
+        Ok(Response::new(ReorderResponse {
+            info: Some((&program).try_into().map_err(IntoStatus::into_status)?),
+            kernel_info: Some((&program).try_into().map_err(IntoStatus::into_status)?),
+        }))
+    }
+
+    async fn enable(
+        &self,
+        request: Request<EnableRequest>,
+    ) -> Result<Response<EnableResponse>, Status> {
+        let program = enable_program(request.into_inner().id)
+            .await
+            .map_err(IntoStatus::into_status)?;
+
+        Ok(Response::new(EnableResponse {
+            info: Some((&program).try_into().map_err(IntoStatus::into_status)?),
+            kernel_info: Some((&program).try_into().map_err(IntoStatus::into_status)?),
+        }))
+    }
+
+    async fn disable(
+        &self,
+        request: Request<DisableRequest>,
+    ) -> Result<Response<DisableResponse>, Status> {
+        let program = disable_program(request.into_inner().id)
+            .await
+            .map_err(IntoStatus::into_status)?;
+
+        Ok(Response::new(DisableResponse {
+            info: Some((&program).try_into().map_err(IntoStatus::into_status)?),
+            kernel_info: Some((&program).try_into().map_err(IntoStatus::into_status)?),
+        }))
+    }
+
     async fn get(&self, request: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
         let request = request.into_inner();
         let id = request.id;
 
-        let program = get_program(id)
-            .await
-            .map_err(|e| Status::aborted(format!("{e}")))?;
+        let program = get_program(id).await.map_err(IntoStatus::into_status)?;
 
-        let reply_entry =
-            GetResponse {
-                info: if let Program::Unsupported(_) = program {
-                    None
-                } else {
-                    Some((&program).try_into().map_err(|e| {
-                        Status::aborted(format!("failed to get program metadata: {e}"))
-                    })?)
-                },
-                kernel_info: Some((&program).try_into().map_err(|e| {
-                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
-                })?),
-            };
+        let reply_entry = GetResponse {
+            info: if let Program::Unsupported(_) = program {
+                None
+            } else {
+                let mut info: ProgramInfo =
+                    (&program).try_into().map_err(IntoStatus::into_status)?;
+                for link in list_links(Some(id))
+                    .await
+                    .map_err(IntoStatus::into_status)?
+                {
+                    info.links
+                        .push((&link).try_into().map_err(IntoStatus::into_status)?);
+                }
+                Some(info)
+            },
+            kernel_info: Some((&program).try_into().map_err(IntoStatus::into_status)?),
+        };
         Ok(Response::new(reply_entry))
     }
 
@@ -189,23 +530,31 @@ impl Bpfman for BpfmanLoader {
             request.get_ref().bpfman_programs_only(),
         );
 
+        let mut links: HashMap<u32, Vec<LinkInfo>> = HashMap::new();
+        for link in list_links(None).await.map_err(IntoStatus::into_status)? {
+            links
+                .entry(link.program_id().map_err(IntoStatus::into_status)?)
+                .or_default()
+                .push((&link).try_into().map_err(IntoStatus::into_status)?);
+        }
+
         // Await the response
         for r in list_programs(filter)
             .await
-            .map_err(|e| Status::aborted(format!("failed to list programs: {e}")))?
+            .map_err(IntoStatus::into_status)?
         {
             // Populate the response with the Program Info and the Kernel Info.
             let reply_entry = ListResult {
                 info: if let Program::Unsupported(_) = r {
                     None
                 } else {
-                    Some((&r).try_into().map_err(|e| {
-                        Status::aborted(format!("failed to get program metadata: {e}"))
-                    })?)
+                    let mut info: ProgramInfo = (&r).try_into().map_err(IntoStatus::into_status)?;
+                    if let Ok(id) = r.get_data().get_id() {
+                        info.links = links.remove(&id).unwrap_or_default();
+                    }
+                    Some(info)
                 },
-                kernel_info: Some((&r).try_into().map_err(|e| {
-                    Status::aborted(format!("convert Program to GRPC kernel program info: {e}"))
-                })?),
+                kernel_info: Some((&r).try_into().map_err(IntoStatus::into_status)?),
             };
             reply.results.push(reply_entry)
         }
@@ -219,14 +568,48 @@ impl Bpfman for BpfmanLoader {
         let request = request.into_inner();
         let image = match request.image {
             Some(i) => i.into(),
-            None => return Err(Status::aborted("Empty pull_bytecode request received")),
+            None => return Err(invalid_argument("image", "missing image")),
         };
 
-        pull_bytecode(image)
+        let programs = pull_bytecode(image)
             .await
-            .map_err(|e| Status::aborted(format!("{e}")))?;
+            .map_err(IntoStatus::into_status)?;
 
-        let reply = PullBytecodeResponse {};
+        let reply = PullBytecodeResponse { programs };
         Ok(Response::new(reply))
     }
+
+    async fn watch(
+        &self,
+        request: Request<WatchRequest>,
+    ) -> Result<Response<Self::WatchStream>, Status> {
+        let request = request.into_inner();
+        let filter = ListFilter::new(request.program_type, request.match_metadata, true);
+
+        let mut events = watch_programs();
+        let mut shutdown = self.shutdown_tx.subscribe();
+        let (tx, rx) = mpsc::channel(WATCH_CHANNEL_SIZE);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    _ = shutdown.recv() => break,
+                    _ = tx.closed() => break,
+                    event = events.recv() => match event {
+                        Ok(event) => {
+                            if filter.matches_event(&event) && tx.send(Ok(event.into())).await.is_err() {
+                                break;
+                            }
+                        }
+                        Err(RecvError::Lagged(n)) => {
+                            warn!("Watch stream is falling behind, {n} events were dropped")
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                }
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
 }
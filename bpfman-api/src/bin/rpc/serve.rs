@@ -22,14 +22,15 @@ use tokio::{
 use tokio_stream::wrappers::UnixListenerStream;
 use tonic::transport::Server;
 
-use crate::{rpc::BpfmanLoader, storage::StorageManager};
+use crate::{monitor::LinkMonitor, rpc::BpfmanLoader, storage::StorageManager};
 
 pub async fn serve(csi_support: bool, timeout: u64, socket_path: &Path) -> anyhow::Result<()> {
     let (shutdown_tx, shutdown_rx1) = broadcast::channel(32);
     let shutdown_rx3 = shutdown_tx.subscribe();
+    let shutdown_rx4 = shutdown_tx.subscribe();
+    let loader = BpfmanLoader::new(shutdown_tx.clone());
     let shutdown_handle = tokio::spawn(shutdown_handler(timeout, shutdown_tx));
 
-    let loader = BpfmanLoader::new();
     let service = BpfmanServer::new(loader);
 
     let mut listeners: Vec<_> = Vec::new();
@@ -37,6 +38,9 @@ pub async fn serve(csi_support: bool, timeout: u64, socket_path: &Path) -> anyho
     let handle = serve_unix(socket_path, service.clone(), shutdown_rx1).await?;
     listeners.push(handle);
 
+    let link_monitor = LinkMonitor::default();
+    listeners.push(tokio::spawn(link_monitor.run(shutdown_rx4)));
+
     if csi_support {
         let storage_manager = StorageManager::new();
         let storage_manager_handle =
@@ -88,6 +88,15 @@ pub struct ProgramInfo {
         ::prost::alloc::string::String,
         ::prost::alloc::string::String,
     >,
+    /// Attachments of the program made with Attach, in addition to the one it
+    /// was loaded with.
+    #[prost(message, repeated, tag = "9")]
+    pub links: ::prost::alloc::vec::Vec<LinkInfo>,
+    /// Set while the program is no longer loaded in the kernel, such as after
+    /// a reboot, and waits to be restored. Its kernel info is the one it had
+    /// when it was last loaded.
+    #[prost(bool, tag = "10")]
+    pub restore_pending: bool,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -100,6 +109,51 @@ pub struct XdpAttachInfo {
     pub position: i32,
     #[prost(int32, repeated, tag = "4")]
     pub proceed_on: ::prost::alloc::vec::Vec<i32>,
+    #[prost(string, optional, tag = "5")]
+    pub netns: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, optional, tag = "6")]
+    pub container_pid: ::core::option::Option<i32>,
+    /// Set on programs which were disabled with Disable.
+    #[prost(bool, tag = "7")]
+    pub disabled: bool,
+    /// Set to load the program with support for multi-buffer packets, as if
+    /// it were in an "xdp.frags" section. Programs in an "xdp.frags" section
+    /// are detected without it. All of the programs on an interface must agree.
+    #[prost(bool, tag = "8")]
+    pub frags: bool,
+    /// Set while the program's interface doesn't exist.
+    #[prost(bool, tag = "9")]
+    pub detached: bool,
+    /// The glob pattern, such as "ens*", the program's interfaces were selected
+    /// with. A pattern given as the iface of a LoadRequest attaches the program
+    /// to every matching interface, the first one by name with the program
+    /// itself and the others with links, and to matching interfaces which
+    /// appear later. A pattern can't be used with netns or container_pid.
+    #[prost(string, optional, tag = "10")]
+    pub iface_pattern: ::core::option::Option<::prost::alloc::string::String>,
+    /// The mode, "skb", "drv" or "hw", to attach the interface's dispatcher in,
+    /// overriding the xdp_mode configured for the interface. All of the programs
+    /// on an interface which give a mode must agree.
+    #[prost(string, optional, tag = "11")]
+    pub mode: ::core::option::Option<::prost::alloc::string::String>,
+    /// Set to attach the dispatcher in skb mode if the driver doesn't support
+    /// the requested mode.
+    #[prost(bool, tag = "12")]
+    pub mode_fallback: bool,
+    /// The mode the program's dispatcher is attached in. Output only.
+    #[prost(string, optional, tag = "13")]
+    pub effective_mode: ::core::option::Option<::prost::alloc::string::String>,
+    /// The programs attached to the interface by something other than bpfman,
+    /// and the ones its dispatcher took over from it. Output only.
+    #[prost(uint32, repeated, tag = "14")]
+    pub foreign_program_ids: ::prost::alloc::vec::Vec<u32>,
+    #[prost(uint32, repeated, tag = "15")]
+    pub adopted_program_ids: ::prost::alloc::vec::Vec<u32>,
+    /// Set to take the priority and proceed-on actions from the XDP_RUN_CONFIG
+    /// libxdp reads from the program's BTF, if it has one, instead of the
+    /// priority and proceed_on fields.
+    #[prost(bool, tag = "16")]
+    pub use_run_config: bool,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -114,6 +168,30 @@ pub struct TcAttachInfo {
     pub direction: ::prost::alloc::string::String,
     #[prost(int32, repeated, tag = "5")]
     pub proceed_on: ::prost::alloc::vec::Vec<i32>,
+    #[prost(string, optional, tag = "6")]
+    pub netns: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, optional, tag = "7")]
+    pub container_pid: ::core::option::Option<i32>,
+    /// Set on programs which were disabled with Disable.
+    #[prost(bool, tag = "8")]
+    pub disabled: bool,
+    /// Set while the program's interface doesn't exist.
+    #[prost(bool, tag = "9")]
+    pub detached: bool,
+    /// The glob pattern, such as "ens*", the program's interfaces were selected
+    /// with. A pattern given as the iface of a LoadRequest attaches the program
+    /// to every matching interface, the first one by name with the program
+    /// itself and the others with links, and to matching interfaces which
+    /// appear later. A pattern can't be used with netns or container_pid.
+    #[prost(string, optional, tag = "10")]
+    pub iface_pattern: ::core::option::Option<::prost::alloc::string::String>,
+    /// The programs attached to the interface in the program's direction by
+    /// something other than bpfman, and the ones its dispatcher took over from
+    /// it. Output only.
+    #[prost(uint32, repeated, tag = "11")]
+    pub foreign_program_ids: ::prost::alloc::vec::Vec<u32>,
+    #[prost(uint32, repeated, tag = "12")]
+    pub adopted_program_ids: ::prost::alloc::vec::Vec<u32>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -163,8 +241,36 @@ pub struct FexitAttachInfo {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CgroupSkbAttachInfo {
+    #[prost(string, tag = "1")]
+    pub cgroup_path: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub direction: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct CgroupSockAddrAttachInfo {
+    #[prost(string, tag = "1")]
+    pub cgroup_path: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "2")]
+    pub attach_type: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SockOpsAttachInfo {
+    #[prost(string, tag = "1")]
+    pub cgroup_path: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct SkMsgAttachInfo {
+    #[prost(string, tag = "1")]
+    pub map_name: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
 pub struct AttachInfo {
-    #[prost(oneof = "attach_info::Info", tags = "2, 3, 4, 5, 6, 7, 8")]
+    #[prost(oneof = "attach_info::Info", tags = "2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12")]
     pub info: ::core::option::Option<attach_info::Info>,
 }
 /// Nested message and enum types in `AttachInfo`.
@@ -186,6 +292,14 @@ pub mod attach_info {
         FentryAttachInfo(super::FentryAttachInfo),
         #[prost(message, tag = "8")]
         FexitAttachInfo(super::FexitAttachInfo),
+        #[prost(message, tag = "9")]
+        CgroupSkbAttachInfo(super::CgroupSkbAttachInfo),
+        #[prost(message, tag = "10")]
+        CgroupSockAddrAttachInfo(super::CgroupSockAddrAttachInfo),
+        #[prost(message, tag = "11")]
+        SockOpsAttachInfo(super::SockOpsAttachInfo),
+        #[prost(message, tag = "12")]
+        SkMsgAttachInfo(super::SkMsgAttachInfo),
     }
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
@@ -213,6 +327,25 @@ pub struct LoadRequest {
     pub uuid: ::core::option::Option<::prost::alloc::string::String>,
     #[prost(uint32, optional, tag = "8")]
     pub map_owner_id: ::core::option::Option<u32>,
+    /// verifier_log_level is passed to the kernel as the log_level of the
+    /// program load: 1 logs the instructions the program is rejected over, 2
+    /// (which needs 1) logs every instruction checked and 4 logs statistics.
+    /// When not set, 5 is used. The log is returned in the details of the
+    /// error when the verifier rejects the program. It isn't available for a
+    /// program which loads, since aya, which loads the programs, only asks the
+    /// kernel for the log when a load fails. The verified instruction count of
+    /// a program which loads is in its KernelProgramInfo, but its stack depth
+    /// isn't available.
+    #[prost(uint32, optional, tag = "9")]
+    pub verifier_log_level: ::core::option::Option<u32>,
+    /// dry_run only loads the program to have it checked by the verifier, then
+    /// unloads it again without attaching it or saving any state. The response
+    /// holds the kernel's information about the verified program, whose id is
+    /// no longer in use. XDP and TC programs are checked as standalone programs
+    /// rather than as extensions of a dispatcher, so they can still be rejected
+    /// when they're loaded.
+    #[prost(bool, tag = "10")]
+    pub dry_run: bool,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -269,7 +402,10 @@ pub struct PullBytecodeRequest {
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
-pub struct PullBytecodeResponse {}
+pub struct PullBytecodeResponse {
+    #[prost(string, repeated, tag = "1")]
+    pub programs: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GetRequest {
@@ -284,11 +420,377 @@ pub struct GetResponse {
     #[prost(message, optional, tag = "2")]
     pub kernel_info: ::core::option::Option<KernelProgramInfo>,
 }
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(message, optional, tag = "2")]
+    pub program: ::core::option::Option<LoadRequest>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateResponse {
+    #[prost(message, optional, tag = "1")]
+    pub info: ::core::option::Option<ProgramInfo>,
+    #[prost(message, optional, tag = "2")]
+    pub kernel_info: ::core::option::Option<KernelProgramInfo>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchRequest {
+    #[prost(uint32, optional, tag = "1")]
+    pub program_type: ::core::option::Option<u32>,
+    #[prost(map = "string, string", tag = "2")]
+    pub match_metadata: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct WatchResponse {
+    #[prost(enumeration = "WatchEventType", tag = "1")]
+    pub event_type: i32,
+    #[prost(uint32, optional, tag = "2")]
+    pub id: ::core::option::Option<u32>,
+    #[prost(string, tag = "3")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(uint32, optional, tag = "4")]
+    pub program_type: ::core::option::Option<u32>,
+    #[prost(map = "string, string", tag = "5")]
+    pub metadata: ::std::collections::HashMap<
+        ::prost::alloc::string::String,
+        ::prost::alloc::string::String,
+    >,
+    #[prost(string, tag = "6")]
+    pub message: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MapInfo {
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "2")]
+    pub map_id: u32,
+    #[prost(uint32, tag = "3")]
+    pub map_type: u32,
+    #[prost(uint32, tag = "4")]
+    pub key_size: u32,
+    #[prost(uint32, tag = "5")]
+    pub value_size: u32,
+    #[prost(uint32, tag = "6")]
+    pub max_entries: u32,
+    #[prost(uint32, tag = "7")]
+    pub map_flags: u32,
+    #[prost(string, tag = "8")]
+    pub pin_path: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "9")]
+    pub key_type: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "10")]
+    pub value_type: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct MapEntry {
+    #[prost(bytes = "vec", tag = "1")]
+    pub key: ::prost::alloc::vec::Vec<u8>,
+    #[prost(bytes = "vec", tag = "2")]
+    pub value: ::prost::alloc::vec::Vec<u8>,
+    #[prost(string, optional, tag = "3")]
+    pub formatted_key: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "4")]
+    pub formatted_value: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetMapRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(string, tag = "2")]
+    pub map_name: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct GetMapResponse {
+    #[prost(message, optional, tag = "1")]
+    pub info: ::core::option::Option<MapInfo>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DumpMapRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(string, tag = "2")]
+    pub map_name: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DumpMapResponse {
+    #[prost(message, optional, tag = "1")]
+    pub info: ::core::option::Option<MapInfo>,
+    #[prost(message, repeated, tag = "2")]
+    pub entries: ::prost::alloc::vec::Vec<MapEntry>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateMapEntryRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(string, tag = "2")]
+    pub map_name: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub key: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub value: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpdateMapEntryResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteMapEntryRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(string, tag = "2")]
+    pub map_name: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub key: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DeleteMapEntryResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AttachRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(message, optional, tag = "2")]
+    pub attach: ::core::option::Option<AttachInfo>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AttachResponse {
+    #[prost(message, optional, tag = "1")]
+    pub link: ::core::option::Option<LinkInfo>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LinkInfo {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(uint32, tag = "2")]
+    pub program_id: u32,
+    #[prost(message, optional, tag = "3")]
+    pub attach: ::core::option::Option<AttachInfo>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DetachRequest {
+    #[prost(uint32, tag = "1")]
+    pub link_id: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DetachResponse {}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListDispatchersRequest {
+    /// Only list the dispatchers of the interface with this name.
+    #[prost(string, optional, tag = "1")]
+    pub iface: ::core::option::Option<::prost::alloc::string::String>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DispatcherSlotInfo {
+    #[prost(uint32, tag = "1")]
+    pub slot: u32,
+    #[prost(uint32, tag = "2")]
+    pub program_id: u32,
+    #[prost(string, tag = "3")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(int32, tag = "4")]
+    pub priority: i32,
+    #[prost(uint32, optional, tag = "5")]
+    pub link_id: ::core::option::Option<u32>,
+    #[prost(bool, tag = "6")]
+    pub disabled: bool,
+    /// Set if the program was attached to the interface by libxdp.
+    #[prost(bool, tag = "7")]
+    pub libxdp: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DispatcherStageInfo {
+    #[prost(uint32, optional, tag = "1")]
+    pub handle: ::core::option::Option<u32>,
+    #[prost(uint32, tag = "2")]
+    pub num_progs_enabled: u32,
+    #[prost(uint32, repeated, tag = "3")]
+    pub chain_call_actions: ::prost::alloc::vec::Vec<u32>,
+    #[prost(message, repeated, tag = "4")]
+    pub slots: ::prost::alloc::vec::Vec<DispatcherSlotInfo>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ForeignProgramInfo {
+    #[prost(uint32, tag = "1")]
+    pub program_id: u32,
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    #[prost(bool, tag = "3")]
+    pub adopted: bool,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DispatcherInfo {
+    #[prost(uint32, tag = "1")]
+    pub program_type: u32,
+    #[prost(string, tag = "2")]
+    pub iface: ::prost::alloc::string::String,
+    #[prost(uint32, tag = "3")]
+    pub if_index: u32,
+    #[prost(string, tag = "4")]
+    pub direction: ::prost::alloc::string::String,
+    #[prost(string, optional, tag = "5")]
+    pub netns: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(uint32, tag = "6")]
+    pub revision: u32,
+    #[prost(string, tag = "7")]
+    pub mode: ::prost::alloc::string::String,
+    #[prost(uint32, optional, tag = "8")]
+    pub priority: ::core::option::Option<u32>,
+    #[prost(message, repeated, tag = "9")]
+    pub stages: ::prost::alloc::vec::Vec<DispatcherStageInfo>,
+    #[prost(message, repeated, tag = "10")]
+    pub foreign: ::prost::alloc::vec::Vec<ForeignProgramInfo>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ListDispatchersResponse {
+    #[prost(message, repeated, tag = "1")]
+    pub dispatchers: ::prost::alloc::vec::Vec<DispatcherInfo>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReorderRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+    #[prost(int32, optional, tag = "2")]
+    pub priority: ::core::option::Option<i32>,
+    #[prost(int32, repeated, tag = "3")]
+    pub proceed_on: ::prost::alloc::vec::Vec<i32>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ReorderResponse {
+    #[prost(message, optional, tag = "1")]
+    pub info: ::core::option::Option<ProgramInfo>,
+    #[prost(message, optional, tag = "2")]
+    pub kernel_info: ::core::option::Option<KernelProgramInfo>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DisableRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct DisableResponse {
+    #[prost(message, optional, tag = "1")]
+    pub info: ::core::option::Option<ProgramInfo>,
+    #[prost(message, optional, tag = "2")]
+    pub kernel_info: ::core::option::Option<KernelProgramInfo>,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EnableRequest {
+    #[prost(uint32, tag = "1")]
+    pub id: u32,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct EnableResponse {
+    #[prost(message, optional, tag = "1")]
+    pub info: ::core::option::Option<ProgramInfo>,
+    #[prost(message, optional, tag = "2")]
+    pub kernel_info: ::core::option::Option<KernelProgramInfo>,
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum WatchEventType {
+    Unspecified = 0,
+    /// The program was loaded and attached.
+    Loaded = 1,
+    /// The program was unloaded.
+    Unloaded = 2,
+    /// The XDP or TC dispatcher the program is attached to was rebuilt.
+    DispatcherRebuilt = 3,
+    /// The program failed to load or attach.
+    AttachFailed = 4,
+    /// The program is known to bpfman but is no longer loaded in the kernel.
+    DetectedMissing = 5,
+    /// The interface of the XDP or TC program was removed. The program stays
+    /// loaded, and is attached again when an interface with the same name
+    /// appears. Only the interfaces of bpfman-rpc's own network namespace are
+    /// watched.
+    Detached = 6,
+    /// The XDP or TC program was attached again to an interface which
+    /// reappeared.
+    Reattached = 7,
+    /// The XDP or TC program was attached to a new interface matching the
+    /// interface pattern it was loaded with.
+    Attached = 8,
+}
+impl WatchEventType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            WatchEventType::Unspecified => "WATCH_EVENT_TYPE_UNSPECIFIED",
+            WatchEventType::Loaded => "WATCH_EVENT_TYPE_LOADED",
+            WatchEventType::Unloaded => "WATCH_EVENT_TYPE_UNLOADED",
+            WatchEventType::DispatcherRebuilt => "WATCH_EVENT_TYPE_DISPATCHER_REBUILT",
+            WatchEventType::AttachFailed => "WATCH_EVENT_TYPE_ATTACH_FAILED",
+            WatchEventType::DetectedMissing => "WATCH_EVENT_TYPE_DETECTED_MISSING",
+            WatchEventType::Detached => "WATCH_EVENT_TYPE_DETACHED",
+            WatchEventType::Reattached => "WATCH_EVENT_TYPE_REATTACHED",
+            WatchEventType::Attached => "WATCH_EVENT_TYPE_ATTACHED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "WATCH_EVENT_TYPE_UNSPECIFIED" => Some(Self::Unspecified),
+            "WATCH_EVENT_TYPE_LOADED" => Some(Self::Loaded),
+            "WATCH_EVENT_TYPE_UNLOADED" => Some(Self::Unloaded),
+            "WATCH_EVENT_TYPE_DISPATCHER_REBUILT" => Some(Self::DispatcherRebuilt),
+            "WATCH_EVENT_TYPE_ATTACH_FAILED" => Some(Self::AttachFailed),
+            "WATCH_EVENT_TYPE_DETECTED_MISSING" => Some(Self::DetectedMissing),
+            "WATCH_EVENT_TYPE_DETACHED" => Some(Self::Detached),
+            "WATCH_EVENT_TYPE_REATTACHED" => Some(Self::Reattached),
+            "WATCH_EVENT_TYPE_ATTACHED" => Some(Self::Attached),
+            _ => None,
+        }
+    }
+}
 /// Generated client implementations.
 pub mod bpfman_client {
     #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
     use tonic::codegen::*;
     use tonic::codegen::http::Uri;
+    /// Failed requests return the gRPC status code that best describes the failure,
+    /// such as NOT_FOUND for an unknown program id, INVALID_ARGUMENT for an invalid
+    /// request field or a program rejected by the verifier, RESOURCE_EXHAUSTED when
+    /// an interface has no room for another program and UNAVAILABLE when bpfman is
+    /// busy. The status details follow the google.rpc error model: a
+    /// google.rpc.ErrorInfo with the domain "bpfman.io" and a reason naming the
+    /// error comes first, followed by a google.rpc.BadRequest naming the offending
+    /// field, a google.rpc.ResourceInfo naming what wasn't found or a
+    /// google.rpc.DebugInfo holding the verifier log, where they apply.
     #[derive(Debug, Clone)]
     pub struct BpfmanClient<T> {
         inner: tonic::client::Grpc<T>,
@@ -459,135 +961,778 @@ pub mod bpfman_client {
             req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Get"));
             self.inner.unary(req, path, codec).await
         }
-    }
-}
-/// Generated server implementations.
-pub mod bpfman_server {
-    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
-    use tonic::codegen::*;
-    /// Generated trait containing gRPC methods that should be implemented for use with BpfmanServer.
-    #[async_trait]
-    pub trait Bpfman: Send + Sync + 'static {
-        async fn load(
-            &self,
-            request: tonic::Request<super::LoadRequest>,
-        ) -> std::result::Result<tonic::Response<super::LoadResponse>, tonic::Status>;
-        async fn unload(
-            &self,
-            request: tonic::Request<super::UnloadRequest>,
-        ) -> std::result::Result<tonic::Response<super::UnloadResponse>, tonic::Status>;
-        async fn list(
-            &self,
-            request: tonic::Request<super::ListRequest>,
-        ) -> std::result::Result<tonic::Response<super::ListResponse>, tonic::Status>;
-        async fn pull_bytecode(
-            &self,
-            request: tonic::Request<super::PullBytecodeRequest>,
+        pub async fn watch(
+            &mut self,
+            request: impl tonic::IntoRequest<super::WatchRequest>,
         ) -> std::result::Result<
-            tonic::Response<super::PullBytecodeResponse>,
+            tonic::Response<tonic::codec::Streaming<super::WatchResponse>>,
             tonic::Status,
-        >;
-        async fn get(
-            &self,
-            request: tonic::Request<super::GetRequest>,
-        ) -> std::result::Result<tonic::Response<super::GetResponse>, tonic::Status>;
-    }
-    #[derive(Debug)]
-    pub struct BpfmanServer<T: Bpfman> {
-        inner: _Inner<T>,
-        accept_compression_encodings: EnabledCompressionEncodings,
-        send_compression_encodings: EnabledCompressionEncodings,
-        max_decoding_message_size: Option<usize>,
-        max_encoding_message_size: Option<usize>,
-    }
-    struct _Inner<T>(Arc<T>);
-    impl<T: Bpfman> BpfmanServer<T> {
-        pub fn new(inner: T) -> Self {
-            Self::from_arc(Arc::new(inner))
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Watch");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Watch"));
+            self.inner.server_streaming(req, path, codec).await
         }
-        pub fn from_arc(inner: Arc<T>) -> Self {
-            let inner = _Inner(inner);
-            Self {
-                inner,
-                accept_compression_encodings: Default::default(),
-                send_compression_encodings: Default::default(),
-                max_decoding_message_size: None,
-                max_encoding_message_size: None,
-            }
+        pub async fn update(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateRequest>,
+        ) -> std::result::Result<tonic::Response<super::UpdateResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Update");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Update"));
+            self.inner.unary(req, path, codec).await
         }
-        pub fn with_interceptor<F>(
-            inner: T,
-            interceptor: F,
-        ) -> InterceptedService<Self, F>
-        where
-            F: tonic::service::Interceptor,
-        {
-            InterceptedService::new(Self::new(inner), interceptor)
+        pub async fn get_map(
+            &mut self,
+            request: impl tonic::IntoRequest<super::GetMapRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetMapResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/GetMap");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "GetMap"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Enable decompressing requests with the given encoding.
-        #[must_use]
-        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.accept_compression_encodings.enable(encoding);
-            self
+        pub async fn dump_map(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DumpMapRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DumpMapResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/DumpMap");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "DumpMap"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Compress responses with the given encoding, if the client supports it.
-        #[must_use]
-        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
-            self.send_compression_encodings.enable(encoding);
-            self
+        pub async fn update_map_entry(
+            &mut self,
+            request: impl tonic::IntoRequest<super::UpdateMapEntryRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateMapEntryResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/bpfman.v1.Bpfman/UpdateMapEntry",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("bpfman.v1.Bpfman", "UpdateMapEntry"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Limits the maximum size of a decoded message.
-        ///
-        /// Default: `4MB`
-        #[must_use]
-        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
-            self.max_decoding_message_size = Some(limit);
-            self
+        pub async fn delete_map_entry(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DeleteMapEntryRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteMapEntryResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/bpfman.v1.Bpfman/DeleteMapEntry",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("bpfman.v1.Bpfman", "DeleteMapEntry"));
+            self.inner.unary(req, path, codec).await
         }
-        /// Limits the maximum size of an encoded message.
-        ///
-        /// Default: `usize::MAX`
-        #[must_use]
-        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
-            self.max_encoding_message_size = Some(limit);
-            self
+        pub async fn attach(
+            &mut self,
+            request: impl tonic::IntoRequest<super::AttachRequest>,
+        ) -> std::result::Result<tonic::Response<super::AttachResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Attach");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Attach"));
+            self.inner.unary(req, path, codec).await
         }
-    }
-    impl<T, B> tonic::codegen::Service<http::Request<B>> for BpfmanServer<T>
-    where
-        T: Bpfman,
-        B: Body + Send + 'static,
-        B::Error: Into<StdError> + Send + 'static,
-    {
-        type Response = http::Response<tonic::body::BoxBody>;
-        type Error = std::convert::Infallible;
-        type Future = BoxFuture<Self::Response, Self::Error>;
-        fn poll_ready(
+        pub async fn detach(
             &mut self,
-            _cx: &mut Context<'_>,
-        ) -> Poll<std::result::Result<(), Self::Error>> {
-            Poll::Ready(Ok(()))
+            request: impl tonic::IntoRequest<super::DetachRequest>,
+        ) -> std::result::Result<tonic::Response<super::DetachResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Detach");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Detach"));
+            self.inner.unary(req, path, codec).await
         }
-        fn call(&mut self, req: http::Request<B>) -> Self::Future {
-            let inner = self.inner.clone();
-            match req.uri().path() {
-                "/bpfman.v1.Bpfman/Load" => {
+        pub async fn list_dispatchers(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ListDispatchersRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListDispatchersResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static(
+                "/bpfman.v1.Bpfman/ListDispatchers",
+            );
+            let mut req = request.into_request();
+            req.extensions_mut()
+                .insert(GrpcMethod::new("bpfman.v1.Bpfman", "ListDispatchers"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn reorder(
+            &mut self,
+            request: impl tonic::IntoRequest<super::ReorderRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ReorderResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Reorder");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Reorder"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn enable(
+            &mut self,
+            request: impl tonic::IntoRequest<super::EnableRequest>,
+        ) -> std::result::Result<tonic::Response<super::EnableResponse>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Enable");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Enable"));
+            self.inner.unary(req, path, codec).await
+        }
+        pub async fn disable(
+            &mut self,
+            request: impl tonic::IntoRequest<super::DisableRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DisableResponse>,
+            tonic::Status,
+        > {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| {
+                    tonic::Status::new(
+                        tonic::Code::Unknown,
+                        format!("Service was not ready: {}", e.into()),
+                    )
+                })?;
+            let codec = tonic::codec::ProstCodec::default();
+            let path = http::uri::PathAndQuery::from_static("/bpfman.v1.Bpfman/Disable");
+            let mut req = request.into_request();
+            req.extensions_mut().insert(GrpcMethod::new("bpfman.v1.Bpfman", "Disable"));
+            self.inner.unary(req, path, codec).await
+        }
+    }
+}
+/// Generated server implementations.
+pub mod bpfman_server {
+    #![allow(unused_variables, dead_code, missing_docs, clippy::let_unit_value)]
+    use tonic::codegen::*;
+    /// Generated trait containing gRPC methods that should be implemented for use with BpfmanServer.
+    #[async_trait]
+    pub trait Bpfman: Send + Sync + 'static {
+        async fn load(
+            &self,
+            request: tonic::Request<super::LoadRequest>,
+        ) -> std::result::Result<tonic::Response<super::LoadResponse>, tonic::Status>;
+        async fn unload(
+            &self,
+            request: tonic::Request<super::UnloadRequest>,
+        ) -> std::result::Result<tonic::Response<super::UnloadResponse>, tonic::Status>;
+        async fn list(
+            &self,
+            request: tonic::Request<super::ListRequest>,
+        ) -> std::result::Result<tonic::Response<super::ListResponse>, tonic::Status>;
+        async fn pull_bytecode(
+            &self,
+            request: tonic::Request<super::PullBytecodeRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::PullBytecodeResponse>,
+            tonic::Status,
+        >;
+        async fn get(
+            &self,
+            request: tonic::Request<super::GetRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetResponse>, tonic::Status>;
+        /// Server streaming response type for the Watch method.
+        type WatchStream: tonic::codegen::tokio_stream::Stream<
+                Item = std::result::Result<super::WatchResponse, tonic::Status>,
+            >
+            + Send
+            + 'static;
+        async fn watch(
+            &self,
+            request: tonic::Request<super::WatchRequest>,
+        ) -> std::result::Result<tonic::Response<Self::WatchStream>, tonic::Status>;
+        async fn update(
+            &self,
+            request: tonic::Request<super::UpdateRequest>,
+        ) -> std::result::Result<tonic::Response<super::UpdateResponse>, tonic::Status>;
+        async fn get_map(
+            &self,
+            request: tonic::Request<super::GetMapRequest>,
+        ) -> std::result::Result<tonic::Response<super::GetMapResponse>, tonic::Status>;
+        async fn dump_map(
+            &self,
+            request: tonic::Request<super::DumpMapRequest>,
+        ) -> std::result::Result<tonic::Response<super::DumpMapResponse>, tonic::Status>;
+        async fn update_map_entry(
+            &self,
+            request: tonic::Request<super::UpdateMapEntryRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::UpdateMapEntryResponse>,
+            tonic::Status,
+        >;
+        async fn delete_map_entry(
+            &self,
+            request: tonic::Request<super::DeleteMapEntryRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::DeleteMapEntryResponse>,
+            tonic::Status,
+        >;
+        async fn attach(
+            &self,
+            request: tonic::Request<super::AttachRequest>,
+        ) -> std::result::Result<tonic::Response<super::AttachResponse>, tonic::Status>;
+        async fn detach(
+            &self,
+            request: tonic::Request<super::DetachRequest>,
+        ) -> std::result::Result<tonic::Response<super::DetachResponse>, tonic::Status>;
+        async fn list_dispatchers(
+            &self,
+            request: tonic::Request<super::ListDispatchersRequest>,
+        ) -> std::result::Result<
+            tonic::Response<super::ListDispatchersResponse>,
+            tonic::Status,
+        >;
+        async fn reorder(
+            &self,
+            request: tonic::Request<super::ReorderRequest>,
+        ) -> std::result::Result<tonic::Response<super::ReorderResponse>, tonic::Status>;
+        async fn enable(
+            &self,
+            request: tonic::Request<super::EnableRequest>,
+        ) -> std::result::Result<tonic::Response<super::EnableResponse>, tonic::Status>;
+        async fn disable(
+            &self,
+            request: tonic::Request<super::DisableRequest>,
+        ) -> std::result::Result<tonic::Response<super::DisableResponse>, tonic::Status>;
+    }
+    /// Failed requests return the gRPC status code that best describes the failure,
+    /// such as NOT_FOUND for an unknown program id, INVALID_ARGUMENT for an invalid
+    /// request field or a program rejected by the verifier, RESOURCE_EXHAUSTED when
+    /// an interface has no room for another program and UNAVAILABLE when bpfman is
+    /// busy. The status details follow the google.rpc error model: a
+    /// google.rpc.ErrorInfo with the domain "bpfman.io" and a reason naming the
+    /// error comes first, followed by a google.rpc.BadRequest naming the offending
+    /// field, a google.rpc.ResourceInfo naming what wasn't found or a
+    /// google.rpc.DebugInfo holding the verifier log, where they apply.
+    #[derive(Debug)]
+    pub struct BpfmanServer<T: Bpfman> {
+        inner: _Inner<T>,
+        accept_compression_encodings: EnabledCompressionEncodings,
+        send_compression_encodings: EnabledCompressionEncodings,
+        max_decoding_message_size: Option<usize>,
+        max_encoding_message_size: Option<usize>,
+    }
+    struct _Inner<T>(Arc<T>);
+    impl<T: Bpfman> BpfmanServer<T> {
+        pub fn new(inner: T) -> Self {
+            Self::from_arc(Arc::new(inner))
+        }
+        pub fn from_arc(inner: Arc<T>) -> Self {
+            let inner = _Inner(inner);
+            Self {
+                inner,
+                accept_compression_encodings: Default::default(),
+                send_compression_encodings: Default::default(),
+                max_decoding_message_size: None,
+                max_encoding_message_size: None,
+            }
+        }
+        pub fn with_interceptor<F>(
+            inner: T,
+            interceptor: F,
+        ) -> InterceptedService<Self, F>
+        where
+            F: tonic::service::Interceptor,
+        {
+            InterceptedService::new(Self::new(inner), interceptor)
+        }
+        /// Enable decompressing requests with the given encoding.
+        #[must_use]
+        pub fn accept_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.accept_compression_encodings.enable(encoding);
+            self
+        }
+        /// Compress responses with the given encoding, if the client supports it.
+        #[must_use]
+        pub fn send_compressed(mut self, encoding: CompressionEncoding) -> Self {
+            self.send_compression_encodings.enable(encoding);
+            self
+        }
+        /// Limits the maximum size of a decoded message.
+        ///
+        /// Default: `4MB`
+        #[must_use]
+        pub fn max_decoding_message_size(mut self, limit: usize) -> Self {
+            self.max_decoding_message_size = Some(limit);
+            self
+        }
+        /// Limits the maximum size of an encoded message.
+        ///
+        /// Default: `usize::MAX`
+        #[must_use]
+        pub fn max_encoding_message_size(mut self, limit: usize) -> Self {
+            self.max_encoding_message_size = Some(limit);
+            self
+        }
+    }
+    impl<T, B> tonic::codegen::Service<http::Request<B>> for BpfmanServer<T>
+    where
+        T: Bpfman,
+        B: Body + Send + 'static,
+        B::Error: Into<StdError> + Send + 'static,
+    {
+        type Response = http::Response<tonic::body::BoxBody>;
+        type Error = std::convert::Infallible;
+        type Future = BoxFuture<Self::Response, Self::Error>;
+        fn poll_ready(
+            &mut self,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::result::Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+        fn call(&mut self, req: http::Request<B>) -> Self::Future {
+            let inner = self.inner.clone();
+            match req.uri().path() {
+                "/bpfman.v1.Bpfman/Load" => {
+                    #[allow(non_camel_case_types)]
+                    struct LoadSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::LoadRequest>
+                    for LoadSvc<T> {
+                        type Response = super::LoadResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::LoadRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::load(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = LoadSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Unload" => {
+                    #[allow(non_camel_case_types)]
+                    struct UnloadSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::UnloadRequest>
+                    for UnloadSvc<T> {
+                        type Response = super::UnloadResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UnloadRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::unload(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UnloadSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/List" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::ListRequest>
+                    for ListSvc<T> {
+                        type Response = super::ListResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::list(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/PullBytecode" => {
+                    #[allow(non_camel_case_types)]
+                    struct PullBytecodeSvc<T: Bpfman>(pub Arc<T>);
+                    impl<
+                        T: Bpfman,
+                    > tonic::server::UnaryService<super::PullBytecodeRequest>
+                    for PullBytecodeSvc<T> {
+                        type Response = super::PullBytecodeResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::PullBytecodeRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::pull_bytecode(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = PullBytecodeSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Get" => {
+                    #[allow(non_camel_case_types)]
+                    struct GetSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::GetRequest>
+                    for GetSvc<T> {
+                        type Response = super::GetResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::GetRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::get(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = GetSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Watch" => {
+                    #[allow(non_camel_case_types)]
+                    struct WatchSvc<T: Bpfman>(pub Arc<T>);
+                    impl<
+                        T: Bpfman,
+                    > tonic::server::ServerStreamingService<super::WatchRequest>
+                    for WatchSvc<T> {
+                        type Response = super::WatchResponse;
+                        type ResponseStream = T::WatchStream;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::ResponseStream>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::WatchRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::watch(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = WatchSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.server_streaming(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Update" => {
+                    #[allow(non_camel_case_types)]
+                    struct UpdateSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::UpdateRequest>
+                    for UpdateSvc<T> {
+                        type Response = super::UpdateResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::UpdateRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::update(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = UpdateSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/GetMap" => {
                     #[allow(non_camel_case_types)]
-                    struct LoadSvc<T: Bpfman>(pub Arc<T>);
-                    impl<T: Bpfman> tonic::server::UnaryService<super::LoadRequest>
-                    for LoadSvc<T> {
-                        type Response = super::LoadResponse;
+                    struct GetMapSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::GetMapRequest>
+                    for GetMapSvc<T> {
+                        type Response = super::GetMapResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::LoadRequest>,
+                            request: tonic::Request<super::GetMapRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Bpfman>::load(&inner, request).await
+                                <T as Bpfman>::get_map(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -599,7 +1744,7 @@ pub mod bpfman_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = LoadSvc(inner);
+                        let method = GetMapSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -615,23 +1760,23 @@ pub mod bpfman_server {
                     };
                     Box::pin(fut)
                 }
-                "/bpfman.v1.Bpfman/Unload" => {
+                "/bpfman.v1.Bpfman/DumpMap" => {
                     #[allow(non_camel_case_types)]
-                    struct UnloadSvc<T: Bpfman>(pub Arc<T>);
-                    impl<T: Bpfman> tonic::server::UnaryService<super::UnloadRequest>
-                    for UnloadSvc<T> {
-                        type Response = super::UnloadResponse;
+                    struct DumpMapSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::DumpMapRequest>
+                    for DumpMapSvc<T> {
+                        type Response = super::DumpMapResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::UnloadRequest>,
+                            request: tonic::Request<super::DumpMapRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Bpfman>::unload(&inner, request).await
+                                <T as Bpfman>::dump_map(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -643,7 +1788,7 @@ pub mod bpfman_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = UnloadSvc(inner);
+                        let method = DumpMapSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -659,23 +1804,25 @@ pub mod bpfman_server {
                     };
                     Box::pin(fut)
                 }
-                "/bpfman.v1.Bpfman/List" => {
+                "/bpfman.v1.Bpfman/UpdateMapEntry" => {
                     #[allow(non_camel_case_types)]
-                    struct ListSvc<T: Bpfman>(pub Arc<T>);
-                    impl<T: Bpfman> tonic::server::UnaryService<super::ListRequest>
-                    for ListSvc<T> {
-                        type Response = super::ListResponse;
+                    struct UpdateMapEntrySvc<T: Bpfman>(pub Arc<T>);
+                    impl<
+                        T: Bpfman,
+                    > tonic::server::UnaryService<super::UpdateMapEntryRequest>
+                    for UpdateMapEntrySvc<T> {
+                        type Response = super::UpdateMapEntryResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::ListRequest>,
+                            request: tonic::Request<super::UpdateMapEntryRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Bpfman>::list(&inner, request).await
+                                <T as Bpfman>::update_map_entry(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -687,7 +1834,7 @@ pub mod bpfman_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = ListSvc(inner);
+                        let method = UpdateMapEntrySvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -703,25 +1850,25 @@ pub mod bpfman_server {
                     };
                     Box::pin(fut)
                 }
-                "/bpfman.v1.Bpfman/PullBytecode" => {
+                "/bpfman.v1.Bpfman/DeleteMapEntry" => {
                     #[allow(non_camel_case_types)]
-                    struct PullBytecodeSvc<T: Bpfman>(pub Arc<T>);
+                    struct DeleteMapEntrySvc<T: Bpfman>(pub Arc<T>);
                     impl<
                         T: Bpfman,
-                    > tonic::server::UnaryService<super::PullBytecodeRequest>
-                    for PullBytecodeSvc<T> {
-                        type Response = super::PullBytecodeResponse;
+                    > tonic::server::UnaryService<super::DeleteMapEntryRequest>
+                    for DeleteMapEntrySvc<T> {
+                        type Response = super::DeleteMapEntryResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::PullBytecodeRequest>,
+                            request: tonic::Request<super::DeleteMapEntryRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Bpfman>::pull_bytecode(&inner, request).await
+                                <T as Bpfman>::delete_map_entry(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -733,7 +1880,7 @@ pub mod bpfman_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = PullBytecodeSvc(inner);
+                        let method = DeleteMapEntrySvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -749,23 +1896,23 @@ pub mod bpfman_server {
                     };
                     Box::pin(fut)
                 }
-                "/bpfman.v1.Bpfman/Get" => {
+                "/bpfman.v1.Bpfman/Attach" => {
                     #[allow(non_camel_case_types)]
-                    struct GetSvc<T: Bpfman>(pub Arc<T>);
-                    impl<T: Bpfman> tonic::server::UnaryService<super::GetRequest>
-                    for GetSvc<T> {
-                        type Response = super::GetResponse;
+                    struct AttachSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::AttachRequest>
+                    for AttachSvc<T> {
+                        type Response = super::AttachResponse;
                         type Future = BoxFuture<
                             tonic::Response<Self::Response>,
                             tonic::Status,
                         >;
                         fn call(
                             &mut self,
-                            request: tonic::Request<super::GetRequest>,
+                            request: tonic::Request<super::AttachRequest>,
                         ) -> Self::Future {
                             let inner = Arc::clone(&self.0);
                             let fut = async move {
-                                <T as Bpfman>::get(&inner, request).await
+                                <T as Bpfman>::attach(&inner, request).await
                             };
                             Box::pin(fut)
                         }
@@ -777,7 +1924,229 @@ pub mod bpfman_server {
                     let inner = self.inner.clone();
                     let fut = async move {
                         let inner = inner.0;
-                        let method = GetSvc(inner);
+                        let method = AttachSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Detach" => {
+                    #[allow(non_camel_case_types)]
+                    struct DetachSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::DetachRequest>
+                    for DetachSvc<T> {
+                        type Response = super::DetachResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DetachRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::detach(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DetachSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/ListDispatchers" => {
+                    #[allow(non_camel_case_types)]
+                    struct ListDispatchersSvc<T: Bpfman>(pub Arc<T>);
+                    impl<
+                        T: Bpfman,
+                    > tonic::server::UnaryService<super::ListDispatchersRequest>
+                    for ListDispatchersSvc<T> {
+                        type Response = super::ListDispatchersResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ListDispatchersRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::list_dispatchers(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ListDispatchersSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Reorder" => {
+                    #[allow(non_camel_case_types)]
+                    struct ReorderSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::ReorderRequest>
+                    for ReorderSvc<T> {
+                        type Response = super::ReorderResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::ReorderRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::reorder(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = ReorderSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Enable" => {
+                    #[allow(non_camel_case_types)]
+                    struct EnableSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::EnableRequest>
+                    for EnableSvc<T> {
+                        type Response = super::EnableResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::EnableRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::enable(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = EnableSvc(inner);
+                        let codec = tonic::codec::ProstCodec::default();
+                        let mut grpc = tonic::server::Grpc::new(codec)
+                            .apply_compression_config(
+                                accept_compression_encodings,
+                                send_compression_encodings,
+                            )
+                            .apply_max_message_size_config(
+                                max_decoding_message_size,
+                                max_encoding_message_size,
+                            );
+                        let res = grpc.unary(method, req).await;
+                        Ok(res)
+                    };
+                    Box::pin(fut)
+                }
+                "/bpfman.v1.Bpfman/Disable" => {
+                    #[allow(non_camel_case_types)]
+                    struct DisableSvc<T: Bpfman>(pub Arc<T>);
+                    impl<T: Bpfman> tonic::server::UnaryService<super::DisableRequest>
+                    for DisableSvc<T> {
+                        type Response = super::DisableResponse;
+                        type Future = BoxFuture<
+                            tonic::Response<Self::Response>,
+                            tonic::Status,
+                        >;
+                        fn call(
+                            &mut self,
+                            request: tonic::Request<super::DisableRequest>,
+                        ) -> Self::Future {
+                            let inner = Arc::clone(&self.0);
+                            let fut = async move {
+                                <T as Bpfman>::disable(&inner, request).await
+                            };
+                            Box::pin(fut)
+                        }
+                    }
+                    let accept_compression_encodings = self.accept_compression_encodings;
+                    let send_compression_encodings = self.send_compression_encodings;
+                    let max_decoding_message_size = self.max_decoding_message_size;
+                    let max_encoding_message_size = self.max_encoding_message_size;
+                    let inner = self.inner.clone();
+                    let fut = async move {
+                        let inner = inner.0;
+                        let method = DisableSvc(inner);
                         let codec = tonic::codec::ProstCodec::default();
                         let mut grpc = tonic::server::Grpc::new(codec)
                             .apply_compression_config(
@@ -0,0 +1,409 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! Conversions between bpfman types and the types of the gRPC API.
+
+use bpfman::{
+    errors::BpfmanError,
+    types::{
+        AttachPoint, BytecodeImage, DispatcherSlot, DispatcherStage, DispatcherState,
+        ForeignProgram, Link, Location, MapEntry, Program, ProgramEvent, ProgramEventType,
+        ProgramMap,
+    },
+};
+
+use crate::v1::{
+    attach_info::Info, bytecode_location::Location as V1Location, AttachInfo,
+    BytecodeImage as V1BytecodeImage, BytecodeLocation, CgroupSkbAttachInfo,
+    CgroupSockAddrAttachInfo, DispatcherInfo, DispatcherSlotInfo, DispatcherStageInfo,
+    FentryAttachInfo, FexitAttachInfo, ForeignProgramInfo,
+    KernelProgramInfo as V1KernelProgramInfo, KprobeAttachInfo, LinkInfo, MapEntry as V1MapEntry,
+    MapInfo as V1MapInfo, ProgramInfo, ProgramInfo as V1ProgramInfo, SkMsgAttachInfo,
+    SockOpsAttachInfo, TcAttachInfo, TracepointAttachInfo, UprobeAttachInfo, WatchEventType,
+    WatchResponse, XdpAttachInfo,
+};
+
+impl TryFrom<&Program> for ProgramInfo {
+    type Error = BpfmanError;
+
+    fn try_from(program: &Program) -> Result<Self, Self::Error> {
+        let data = program.get_data();
+
+        let bytecode = match data.get_location()? {
+            Location::Image(m) => {
+                Some(BytecodeLocation {
+                    location: Some(V1Location::Image(V1BytecodeImage {
+                        url: m.get_url().to_string(),
+                        image_pull_policy: m.get_pull_policy().to_owned() as i32,
+                        // Never dump Plaintext Credentials
+                        username: Some(String::new()),
+                        password: Some(String::new()),
+                    })),
+                })
+            }
+            Location::File(m) => Some(BytecodeLocation {
+                location: Some(V1Location::File(m.to_string())),
+            }),
+        };
+
+        // Populate the Program Info with bpfman data
+        Ok(V1ProgramInfo {
+            name: data.get_name()?.to_string(),
+            bytecode,
+            attach: Some(program.try_into()?),
+            global_data: data.get_global_data()?,
+            map_owner_id: data.get_map_owner_id()?,
+            map_pin_path: data
+                .get_map_pin_path()?
+                .map_or(String::new(), |v| v.to_str().unwrap().to_string()),
+            map_used_by: data
+                .get_maps_used_by()?
+                .iter()
+                .map(|m| m.to_string())
+                .collect(),
+            metadata: data.get_metadata()?,
+            // Links are kept apart from their program, see list_links.
+            links: vec![],
+            restore_pending: data.restore_pending(),
+        })
+    }
+}
+
+impl TryFrom<&Program> for AttachInfo {
+    type Error = BpfmanError;
+
+    fn try_from(program: &Program) -> Result<Self, Self::Error> {
+        Ok(AttachInfo {
+            info: match program.clone() {
+                Program::Xdp(p) => Some(Info::XdpAttachInfo(XdpAttachInfo {
+                    priority: p.get_priority()?,
+                    iface: p.get_iface()?.to_string(),
+                    position: p.get_current_position()?.unwrap_or(0) as i32,
+                    proceed_on: p.get_proceed_on()?.as_action_vec(),
+                    netns: p.get_netns()?.map(|n| n.path),
+                    container_pid: None,
+                    disabled: p.get_disabled()?,
+                    frags: p.get_frags()?,
+                    detached: p.get_iface_detached()?,
+                    iface_pattern: p.get_iface_pattern()?,
+                    mode: p.get_mode()?.map(|m| m.to_string()),
+                    mode_fallback: p.get_mode_fallback()?,
+                    effective_mode: p.get_effective_mode()?.map(|m| m.to_string()),
+                    foreign_program_ids: p.get_foreign_program_ids().to_vec(),
+                    adopted_program_ids: vec![],
+                    use_run_config: p.get_use_run_config()?,
+                })),
+                Program::Tc(p) => Some(Info::TcAttachInfo(TcAttachInfo {
+                    priority: p.get_priority()?,
+                    iface: p.get_iface()?.to_string(),
+                    position: p.get_current_position()?.unwrap_or(0) as i32,
+                    direction: p.get_direction()?.to_string(),
+                    proceed_on: p.get_proceed_on()?.as_action_vec(),
+                    netns: p.get_netns()?.map(|n| n.path),
+                    container_pid: None,
+                    disabled: p.get_disabled()?,
+                    detached: p.get_iface_detached()?,
+                    iface_pattern: p.get_iface_pattern()?,
+                    foreign_program_ids: p.get_foreign_program_ids().to_vec(),
+                    adopted_program_ids: p.get_adopted_program_ids().to_vec(),
+                })),
+                Program::Tracepoint(p) => Some(Info::TracepointAttachInfo(TracepointAttachInfo {
+                    tracepoint: p.get_tracepoint()?.to_string(),
+                })),
+                Program::Kprobe(p) => Some(Info::KprobeAttachInfo(KprobeAttachInfo {
+                    fn_name: p.get_fn_name()?.to_string(),
+                    offset: p.get_offset()?,
+                    retprobe: p.get_retprobe()?,
+                    container_pid: p.get_container_pid()?,
+                })),
+                Program::Uprobe(p) => Some(Info::UprobeAttachInfo(UprobeAttachInfo {
+                    fn_name: p.get_fn_name()?.map(|v| v.to_string()),
+                    offset: p.get_offset()?,
+                    target: p.get_target()?.to_string(),
+                    retprobe: p.get_retprobe()?,
+                    pid: p.get_pid()?,
+                    container_pid: p.get_container_pid()?,
+                })),
+                Program::Fentry(p) => Some(Info::FentryAttachInfo(FentryAttachInfo {
+                    fn_name: p.get_fn_name()?.to_string(),
+                })),
+                Program::Fexit(p) => Some(Info::FexitAttachInfo(FexitAttachInfo {
+                    fn_name: p.get_fn_name()?.to_string(),
+                })),
+                Program::CgroupSkb(p) => Some(Info::CgroupSkbAttachInfo(CgroupSkbAttachInfo {
+                    cgroup_path: p.get_cgroup_path()?,
+                    direction: p.get_direction()?.to_string(),
+                })),
+                Program::CgroupSockAddr(p) => {
+                    Some(Info::CgroupSockAddrAttachInfo(CgroupSockAddrAttachInfo {
+                        cgroup_path: p.get_cgroup_path()?,
+                        attach_type: p.get_attach_type()?,
+                    }))
+                }
+                Program::SockOps(p) => Some(Info::SockOpsAttachInfo(SockOpsAttachInfo {
+                    cgroup_path: p.get_cgroup_path()?,
+                })),
+                Program::SkMsg(p) => Some(Info::SkMsgAttachInfo(SkMsgAttachInfo {
+                    map_name: p.get_map_name()?,
+                })),
+                Program::Unsupported(_) => None,
+            },
+        })
+    }
+}
+
+impl TryFrom<&Link> for LinkInfo {
+    type Error = BpfmanError;
+
+    fn try_from(link: &Link) -> Result<Self, Self::Error> {
+        Ok(LinkInfo {
+            id: link.id()?,
+            program_id: link.program_id()?,
+            attach: Some(link.program().try_into()?),
+        })
+    }
+}
+
+impl From<&AttachPoint> for AttachInfo {
+    fn from(attach: &AttachPoint) -> Self {
+        let info = match attach.clone() {
+            AttachPoint::Xdp {
+                iface,
+                priority,
+                proceed_on,
+                netns,
+            } => Info::XdpAttachInfo(XdpAttachInfo {
+                priority,
+                iface,
+                position: 0,
+                proceed_on: proceed_on.as_action_vec(),
+                netns,
+                container_pid: None,
+                disabled: false,
+                frags: false,
+                detached: false,
+                iface_pattern: None,
+                mode: None,
+                mode_fallback: false,
+                effective_mode: None,
+                foreign_program_ids: vec![],
+                adopted_program_ids: vec![],
+                use_run_config: false,
+            }),
+            AttachPoint::Tc {
+                iface,
+                priority,
+                direction,
+                proceed_on,
+                netns,
+            } => Info::TcAttachInfo(TcAttachInfo {
+                priority,
+                iface,
+                position: 0,
+                direction: direction.to_string(),
+                proceed_on: proceed_on.as_action_vec(),
+                netns,
+                container_pid: None,
+                disabled: false,
+                detached: false,
+                iface_pattern: None,
+                foreign_program_ids: vec![],
+                adopted_program_ids: vec![],
+            }),
+            AttachPoint::Tracepoint { tracepoint } => {
+                Info::TracepointAttachInfo(TracepointAttachInfo { tracepoint })
+            }
+            AttachPoint::Kprobe { fn_name, offset } => Info::KprobeAttachInfo(KprobeAttachInfo {
+                fn_name,
+                offset,
+                retprobe: false,
+                container_pid: None,
+            }),
+            AttachPoint::Uprobe {
+                fn_name,
+                offset,
+                target,
+                pid,
+                container_pid,
+            } => Info::UprobeAttachInfo(UprobeAttachInfo {
+                fn_name,
+                offset,
+                target,
+                retprobe: false,
+                pid,
+                container_pid,
+            }),
+            AttachPoint::CgroupSkb { cgroup_path } => {
+                Info::CgroupSkbAttachInfo(CgroupSkbAttachInfo {
+                    cgroup_path,
+                    direction: String::new(),
+                })
+            }
+            AttachPoint::CgroupSockAddr { cgroup_path } => {
+                Info::CgroupSockAddrAttachInfo(CgroupSockAddrAttachInfo {
+                    cgroup_path,
+                    attach_type: None,
+                })
+            }
+            AttachPoint::SockOps { cgroup_path } => {
+                Info::SockOpsAttachInfo(SockOpsAttachInfo { cgroup_path })
+            }
+        };
+        AttachInfo { info: Some(info) }
+    }
+}
+
+impl TryFrom<&Program> for V1KernelProgramInfo {
+    type Error = BpfmanError;
+
+    fn try_from(program: &Program) -> Result<Self, Self::Error> {
+        // Get the Kernel Info.
+        let data = program.get_data();
+
+        // Populate the Kernel Info.
+        Ok(V1KernelProgramInfo {
+            id: data.get_id()?,
+            name: data.get_kernel_name()?.to_string(),
+            program_type: program.kind() as u32,
+            loaded_at: data.get_kernel_loaded_at()?.to_string(),
+            tag: data.get_kernel_tag()?.to_string(),
+            gpl_compatible: data.get_kernel_gpl_compatible()?,
+            map_ids: data.get_kernel_map_ids()?,
+            btf_id: data.get_kernel_btf_id()?,
+            bytes_xlated: data.get_kernel_bytes_xlated()?,
+            jited: data.get_kernel_jited()?,
+            bytes_jited: data.get_kernel_bytes_jited()?,
+            bytes_memlock: data.get_kernel_bytes_memlock()?,
+            verified_insns: data.get_kernel_verified_insns()?,
+        })
+    }
+}
+
+impl From<V1BytecodeImage> for BytecodeImage {
+    fn from(value: V1BytecodeImage) -> Self {
+        // This function is mapping an empty string to None for
+        // username and password.
+        let username = value.username.filter(|u| !u.is_empty());
+        let password = value.password.filter(|p| !p.is_empty());
+        BytecodeImage::new(value.url, value.image_pull_policy, username, password)
+    }
+}
+
+impl From<BytecodeImage> for V1BytecodeImage {
+    fn from(value: BytecodeImage) -> Self {
+        V1BytecodeImage {
+            url: value.image_url,
+            image_pull_policy: value.image_pull_policy as i32,
+            username: value.username,
+            password: value.password,
+        }
+    }
+}
+
+impl From<ProgramEventType> for WatchEventType {
+    fn from(value: ProgramEventType) -> Self {
+        match value {
+            ProgramEventType::Loaded => WatchEventType::Loaded,
+            ProgramEventType::Unloaded => WatchEventType::Unloaded,
+            ProgramEventType::DispatcherRebuilt => WatchEventType::DispatcherRebuilt,
+            ProgramEventType::AttachFailed => WatchEventType::AttachFailed,
+            ProgramEventType::DetectedMissing => WatchEventType::DetectedMissing,
+            ProgramEventType::Detached => WatchEventType::Detached,
+            ProgramEventType::Reattached => WatchEventType::Reattached,
+            ProgramEventType::Attached => WatchEventType::Attached,
+        }
+    }
+}
+
+impl From<ProgramEvent> for WatchResponse {
+    fn from(value: ProgramEvent) -> Self {
+        WatchResponse {
+            event_type: WatchEventType::from(value.event_type).into(),
+            id: value.id,
+            name: value.name,
+            program_type: value.kind.map(|k| k.into()),
+            metadata: value.metadata,
+            message: value.message,
+        }
+    }
+}
+
+impl From<ProgramMap> for V1MapInfo {
+    fn from(value: ProgramMap) -> Self {
+        V1MapInfo {
+            name: value.name,
+            map_id: value.map_id,
+            map_type: value.map_type,
+            key_size: value.key_size,
+            value_size: value.value_size,
+            max_entries: value.max_entries,
+            map_flags: value.map_flags,
+            pin_path: value.pin_path,
+            key_type: value.key_type,
+            value_type: value.value_type,
+        }
+    }
+}
+
+impl From<DispatcherState> for DispatcherInfo {
+    fn from(value: DispatcherState) -> Self {
+        DispatcherInfo {
+            program_type: value.program_type as u32,
+            iface: value.if_name,
+            if_index: value.if_index,
+            direction: value.direction.map(|d| d.to_string()).unwrap_or_default(),
+            netns: value.netns,
+            revision: value.revision,
+            mode: value.mode,
+            priority: value.priority.map(u32::from),
+            stages: value.stages.into_iter().map(|s| s.into()).collect(),
+            foreign: value.foreign.into_iter().map(|f| f.into()).collect(),
+        }
+    }
+}
+
+impl From<ForeignProgram> for ForeignProgramInfo {
+    fn from(value: ForeignProgram) -> Self {
+        ForeignProgramInfo {
+            program_id: value.program_id,
+            name: value.name,
+            adopted: value.adopted,
+        }
+    }
+}
+
+impl From<DispatcherStage> for DispatcherStageInfo {
+    fn from(value: DispatcherStage) -> Self {
+        DispatcherStageInfo {
+            handle: value.handle,
+            num_progs_enabled: value.num_progs_enabled,
+            chain_call_actions: value.chain_call_actions,
+            slots: value.slots.into_iter().map(|s| s.into()).collect(),
+        }
+    }
+}
+
+impl From<DispatcherSlot> for DispatcherSlotInfo {
+    fn from(value: DispatcherSlot) -> Self {
+        DispatcherSlotInfo {
+            slot: value.slot,
+            program_id: value.program_id,
+            name: value.name,
+            priority: value.priority,
+            link_id: value.link_id,
+            disabled: value.disabled,
+            libxdp: value.libxdp,
+        }
+    }
+}
+
+impl From<MapEntry> for V1MapEntry {
+    fn from(value: MapEntry) -> Self {
+        V1MapEntry {
+            key: value.key,
+            value: value.value,
+            formatted_key: value.formatted_key,
+            formatted_value: value.formatted_value,
+        }
+    }
+}
@@ -0,0 +1,468 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! The errors bpfman-rpc returns. Each error carries the gRPC status code that
+//! best describes it, and details in the `google.rpc` error model, such as the
+//! verifier log of a program the kernel rejected or the request field which
+//! was invalid. The details always start with an `ErrorInfo` whose reason
+//! names the error.
+
+use std::{collections::HashMap, io};
+
+use aya::{programs::ProgramError, BpfError};
+use bpfman::errors::{BpfmanError, ImageError, ParseError};
+use prost::Message;
+use prost_types::Any;
+use tonic::{codegen::Bytes, Code, Status};
+
+use crate::google_rpc::{
+    bad_request::FieldViolation, BadRequest, DebugInfo, ErrorInfo, ResourceInfo,
+    Status as RpcStatus,
+};
+
+/// The domain of the `ErrorInfo` attached to bpfman's errors.
+pub const ERROR_DOMAIN: &str = "bpfman.io";
+
+const TYPE_URL_PREFIX: &str = "type.googleapis.com/google.rpc.";
+
+/// A detail attached to an error.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorDetail {
+    ErrorInfo(ErrorInfo),
+    DebugInfo(DebugInfo),
+    BadRequest(BadRequest),
+    ResourceInfo(ResourceInfo),
+}
+
+impl ErrorDetail {
+    fn to_any(&self) -> Any {
+        let (name, value) = match self {
+            ErrorDetail::ErrorInfo(d) => ("ErrorInfo", d.encode_to_vec()),
+            ErrorDetail::DebugInfo(d) => ("DebugInfo", d.encode_to_vec()),
+            ErrorDetail::BadRequest(d) => ("BadRequest", d.encode_to_vec()),
+            ErrorDetail::ResourceInfo(d) => ("ResourceInfo", d.encode_to_vec()),
+        };
+        Any {
+            type_url: format!("{TYPE_URL_PREFIX}{name}"),
+            value,
+        }
+    }
+
+    fn from_any(any: &Any) -> Option<Self> {
+        let value = any.value.as_slice();
+        match any.type_url.strip_prefix(TYPE_URL_PREFIX)? {
+            "ErrorInfo" => ErrorInfo::decode(value).ok().map(ErrorDetail::ErrorInfo),
+            "DebugInfo" => DebugInfo::decode(value).ok().map(ErrorDetail::DebugInfo),
+            "BadRequest" => BadRequest::decode(value).ok().map(ErrorDetail::BadRequest),
+            "ResourceInfo" => ResourceInfo::decode(value)
+                .ok()
+                .map(ErrorDetail::ResourceInfo),
+            _ => None,
+        }
+    }
+}
+
+/// Builds a status carrying the given details.
+pub fn with_details(code: Code, message: impl Into<String>, details: Vec<ErrorDetail>) -> Status {
+    let message = message.into();
+    let status = RpcStatus {
+        code: code as i32,
+        message: message.clone(),
+        details: details.iter().map(ErrorDetail::to_any).collect(),
+    };
+    Status::with_details(code, message, Bytes::from(status.encode_to_vec()))
+}
+
+/// Returns the details attached to a status. Details of types bpfman doesn't
+/// return are left out.
+pub fn details(status: &Status) -> Vec<ErrorDetail> {
+    RpcStatus::decode(status.details())
+        .map(|s| s.details.iter().filter_map(ErrorDetail::from_any).collect())
+        .unwrap_or_default()
+}
+
+/// Returns an INVALID_ARGUMENT status for a missing or invalid request field,
+/// given as a dot separated path such as `attach.info`.
+pub fn invalid_argument(field: &str, description: impl Into<String>) -> Status {
+    let description = description.into();
+    with_details(
+        Code::InvalidArgument,
+        description.clone(),
+        vec![
+            error_info("INVALID_ARGUMENT", HashMap::new()),
+            field_violation(field, &description),
+        ],
+    )
+}
+
+fn error_info(reason: &str, metadata: HashMap<String, String>) -> ErrorDetail {
+    ErrorDetail::ErrorInfo(ErrorInfo {
+        reason: reason.to_string(),
+        domain: ERROR_DOMAIN.to_string(),
+        metadata,
+    })
+}
+
+fn field_violation(field: &str, description: &str) -> ErrorDetail {
+    ErrorDetail::BadRequest(BadRequest {
+        field_violations: vec![FieldViolation {
+            field: field.to_string(),
+            description: description.to_string(),
+        }],
+    })
+}
+
+fn resource_info(resource_type: &str, resource_name: String, owner: String) -> ErrorDetail {
+    ErrorDetail::ResourceInfo(ResourceInfo {
+        resource_type: resource_type.to_string(),
+        resource_name,
+        owner,
+        description: String::new(),
+    })
+}
+
+// Picks the number of instructions processed and the stack depth out of a
+// verifier log, where the verifier logged statistics.
+fn verifier_stats(e: &BpfmanError) -> HashMap<String, String> {
+    let log = match e {
+        BpfmanError::BpfProgramError(ProgramError::LoadError { verifier_log, .. })
+        | BpfmanError::BpfLoadError(BpfError::ProgramError(ProgramError::LoadError {
+            verifier_log,
+            ..
+        })) => verifier_log.to_string(),
+        _ => return HashMap::new(),
+    };
+
+    let mut stats = HashMap::new();
+    for line in log.lines() {
+        if let Some(insns) = line
+            .strip_prefix("processed ")
+            .and_then(|l| l.split_whitespace().next())
+        {
+            stats.insert("verified_insns".to_string(), insns.to_string());
+        } else if let Some(depth) = line.strip_prefix("stack depth ") {
+            stats.insert("stack_depth".to_string(), depth.trim().to_string());
+        }
+    }
+    stats
+}
+
+// A load the verifier rejected comes with its log. Without a log, the kernel
+// refused the load for another reason, such as missing capabilities.
+fn program_error(e: &ProgramError) -> (Code, &'static str, Vec<ErrorDetail>) {
+    match e {
+        ProgramError::LoadError {
+            io_error,
+            verifier_log,
+        } => {
+            let log = verifier_log.to_string();
+            if !log.is_empty() {
+                let log = ErrorDetail::DebugInfo(DebugInfo {
+                    stack_entries: vec![],
+                    detail: log,
+                });
+                (Code::InvalidArgument, "VERIFIER_REJECTED", vec![log])
+            } else if io_error.kind() == io::ErrorKind::PermissionDenied {
+                (Code::PermissionDenied, "PERMISSION_DENIED", vec![])
+            } else {
+                (Code::Internal, "LOAD_FAILED", vec![])
+            }
+        }
+        _ => (Code::Internal, "PROGRAM_ERROR", vec![]),
+    }
+}
+
+/// Converts an error into the status bpfman-rpc returns for it.
+pub trait IntoStatus {
+    fn into_status(self) -> Status;
+}
+
+impl IntoStatus for BpfmanError {
+    fn into_status(self) -> Status {
+        let message = self.to_string();
+        let (code, reason, mut details) = match &self {
+            BpfmanError::Error(_) => (Code::Unknown, "ERROR", vec![]),
+            BpfmanError::BpfIOError(_) => (Code::Internal, "IO_ERROR", vec![]),
+            BpfmanError::BpfProgramError(e) => program_error(e),
+            BpfmanError::BpfLoadError(BpfError::ProgramError(e)) => program_error(e),
+            BpfmanError::BpfLoadError(BpfError::ParseError(_)) => (
+                Code::InvalidArgument,
+                "INVALID_BYTECODE",
+                vec![field_violation("bytecode", &message)],
+            ),
+            BpfmanError::BpfLoadError(_) => (Code::Internal, "LOAD_ERROR", vec![]),
+            BpfmanError::BpfFunctionNameNotValid(_) => (
+                Code::InvalidArgument,
+                "INVALID_FUNCTION_NAME",
+                vec![field_violation("name", &message)],
+            ),
+            BpfmanError::BytecodeMetaDataMismatch { .. } => (
+                Code::InvalidArgument,
+                "BYTECODE_MISMATCH",
+                vec![field_violation("name", &message)],
+            ),
+            BpfmanError::TooManyPrograms => (Code::ResourceExhausted, "TOO_MANY_PROGRAMS", vec![]),
+            BpfmanError::InvalidInterface => (
+                Code::InvalidArgument,
+                "INVALID_INTERFACE",
+                vec![field_violation("iface", &message)],
+            ),
+            BpfmanError::InvalidInterfacePattern(_) => (
+                Code::InvalidArgument,
+                "INVALID_INTERFACE_PATTERN",
+                vec![field_violation("iface", &message)],
+            ),
+            BpfmanError::NoMatchingInterface(_) => (
+                Code::InvalidArgument,
+                "NO_MATCHING_INTERFACE",
+                vec![field_violation("iface", &message)],
+            ),
+            BpfmanError::InterfacePatternWithNetns(_) => (
+                Code::InvalidArgument,
+                "INTERFACE_PATTERN_WITH_NETNS",
+                vec![
+                    field_violation("iface", &message),
+                    field_violation("netns", &message),
+                ],
+            ),
+            BpfmanError::InvalidNetns(_) => (
+                Code::InvalidArgument,
+                "INVALID_NETNS",
+                vec![field_violation("netns", &message)],
+            ),
+            BpfmanError::InvalidAttach(_) => (
+                Code::InvalidArgument,
+                "INVALID_ATTACH",
+                vec![field_violation("attach", &message)],
+            ),
+            BpfmanError::UnsupportedProgramType => (
+                Code::InvalidArgument,
+                "UNSUPPORTED_PROGRAM_TYPE",
+                vec![field_violation("program_type", &message)],
+            ),
+            BpfmanError::MapOwnerNotFound(_) => (
+                Code::InvalidArgument,
+                "MAP_OWNER_NOT_FOUND",
+                vec![field_violation("map_owner_id", &message)],
+            ),
+            BpfmanError::UnableToPinLink(_)
+            | BpfmanError::UnableToPinProgram(_)
+            | BpfmanError::UnableToPinMap(_) => (Code::Internal, "PIN_FAILED", vec![]),
+            BpfmanError::NotLoaded | BpfmanError::DispatcherNotRequired => {
+                (Code::Internal, "DISPATCHER_ERROR", vec![])
+            }
+            BpfmanError::BpfBytecodeError(e) => match e {
+                ImageError::InvalidImageUrl(_) => (
+                    Code::InvalidArgument,
+                    "INVALID_IMAGE_URL",
+                    vec![field_violation("bytecode.image.url", &message)],
+                ),
+                ImageError::ImageManifestPullFailure(_)
+                | ImageError::BytecodeImagePullFailure(_) => {
+                    (Code::Unavailable, "IMAGE_PULL_FAILED", vec![])
+                }
+                ImageError::ByteCodeImageNotfound(url) => (
+                    Code::NotFound,
+                    "IMAGE_NOT_FOUND",
+                    vec![resource_info("image", url.clone(), String::new())],
+                ),
+                _ => (Code::Internal, "IMAGE_ERROR", vec![]),
+            },
+            BpfmanError::ProgramNotFoundInBytecode {
+                bytecode_image,
+                expected_prog_name,
+                ..
+            } => (
+                Code::NotFound,
+                "PROGRAM_NOT_IN_BYTECODE",
+                vec![resource_info(
+                    "program",
+                    expected_prog_name.clone(),
+                    bytecode_image.clone(),
+                )],
+            ),
+            BpfmanError::ProgramNotFound(id) | BpfmanError::ProgramNotManaged(id) => (
+                Code::NotFound,
+                "PROGRAM_NOT_FOUND",
+                vec![resource_info("program", id.to_string(), String::new())],
+            ),
+            BpfmanError::LinkNotFound(id) => (
+                Code::NotFound,
+                "LINK_NOT_FOUND",
+                vec![resource_info("link", id.to_string(), String::new())],
+            ),
+            BpfmanError::XdpFragsMismatch(iface) => (
+                Code::FailedPrecondition,
+                "XDP_FRAGS_MISMATCH",
+                vec![resource_info("interface", iface.clone(), String::new())],
+            ),
+            BpfmanError::ForeignProgramsAttached { iface, .. } => (
+                Code::FailedPrecondition,
+                "FOREIGN_PROGRAMS_ATTACHED",
+                vec![resource_info("interface", iface.clone(), String::new())],
+            ),
+            BpfmanError::ForeignTakeoverFailed { iface, .. } => (
+                Code::FailedPrecondition,
+                "FOREIGN_TAKEOVER_FAILED",
+                vec![resource_info("interface", iface.clone(), String::new())],
+            ),
+            BpfmanError::XdpModeMismatch(iface) => (
+                Code::FailedPrecondition,
+                "XDP_MODE_MISMATCH",
+                vec![resource_info("interface", iface.clone(), String::new())],
+            ),
+            BpfmanError::NotInDispatcher(id) => (
+                Code::FailedPrecondition,
+                "NOT_IN_DISPATCHER",
+                vec![resource_info("program", id.to_string(), String::new())],
+            ),
+            BpfmanError::AlreadyAttached(id, _) => (
+                Code::AlreadyExists,
+                "ALREADY_ATTACHED",
+                vec![resource_info("program", id.to_string(), String::new())],
+            ),
+            BpfmanError::MapNotFound {
+                program_id,
+                map_name,
+            } => (
+                Code::NotFound,
+                "MAP_NOT_FOUND",
+                vec![resource_info(
+                    "map",
+                    map_name.clone(),
+                    program_id.to_string(),
+                )],
+            ),
+            BpfmanError::InvalidUpdate(id, _) => (
+                Code::FailedPrecondition,
+                "INVALID_UPDATE",
+                vec![resource_info("program", id.to_string(), String::new())],
+            ),
+            BpfmanError::BpfmanProgramDeleteError(_) => (Code::Internal, "DELETE_FAILED", vec![]),
+            BpfmanError::ContainerAttachError { .. } => {
+                (Code::Internal, "CONTAINER_ATTACH_FAILED", vec![])
+            }
+            BpfmanError::DatabaseError(_, _) => (Code::Internal, "DATABASE_ERROR", vec![]),
+            BpfmanError::DatabaseLockError => (Code::Unavailable, "DATABASE_LOCKED", vec![]),
+            BpfmanError::BtfError(_) => (Code::Internal, "BTF_ERROR", vec![]),
+            BpfmanError::RpcRecvError(_)
+            | BpfmanError::RpcSendError(_)
+            | BpfmanError::InternalError(_) => (Code::Internal, "INTERNAL", vec![]),
+        };
+        details.insert(0, error_info(reason, verifier_stats(&self)));
+        with_details(code, message, details)
+    }
+}
+
+impl IntoStatus for ParseError {
+    fn into_status(self) -> Status {
+        let message = self.to_string();
+        let field = match &self {
+            ParseError::InvalidProgramType { .. } => Some("program_type"),
+            ParseError::InvalidProceedOn { .. } => Some("proceed_on"),
+            ParseError::InvalidDirection { .. } => Some("direction"),
+            ParseError::BytecodeLocationParseFailure(_)
+            | ParseError::InvalidBytecodeLocation { .. } => Some("bytecode"),
+            ParseError::InvalidBytecodeImagePullPolicy { .. } => {
+                Some("bytecode.image.image_pull_policy")
+            }
+            ParseError::InvalidXdpMode { .. } => Some("mode"),
+            ParseError::InvalidMapType { .. }
+            | ParseError::InvalidProbeType { .. }
+            | ParseError::InvalidTcMode { .. }
+            | ParseError::ConfigParseError(_) => None,
+        };
+
+        let mut details = vec![error_info("INVALID_ARGUMENT", HashMap::new())];
+        details.extend(field.map(|f| field_violation(f, &message)));
+        with_details(Code::InvalidArgument, message, details)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_error_details() {
+        let status = BpfmanError::MapNotFound {
+            program_id: 42,
+            map_name: "counters".to_string(),
+        }
+        .into_status();
+        assert_eq!(status.code(), Code::NotFound);
+        assert_eq!(status.message(), "Map counters not found for program 42");
+        assert_eq!(
+            details(&status),
+            vec![
+                error_info("MAP_NOT_FOUND", HashMap::new()),
+                resource_info("map", "counters".to_string(), "42".to_string()),
+            ]
+        );
+
+        let status = BpfmanError::TooManyPrograms.into_status();
+        assert_eq!(status.code(), Code::ResourceExhausted);
+
+        let status = invalid_argument("attach.info", "missing info");
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(
+            details(&status),
+            vec![
+                error_info("INVALID_ARGUMENT", HashMap::new()),
+                field_violation("attach.info", "missing info"),
+            ]
+        );
+
+        assert!(details(&Status::aborted("no details")).is_empty());
+    }
+
+    #[test]
+    fn test_verifier_rejected() {
+        let log = "0: (b7) r0 = 2\n\
+                   stack depth 8+32\n\
+                   processed 4 insns (limit 1000000) max_states_per_insn 0\n";
+        let status = BpfmanError::BpfProgramError(ProgramError::LoadError {
+            io_error: std::io::Error::from_raw_os_error(13),
+            verifier_log: aya_obj::VerifierLog::new(log.to_string()),
+        })
+        .into_status();
+        assert_eq!(status.code(), Code::InvalidArgument);
+        assert_eq!(
+            details(&status),
+            vec![
+                error_info(
+                    "VERIFIER_REJECTED",
+                    HashMap::from([
+                        ("verified_insns".to_string(), "4".to_string()),
+                        ("stack_depth".to_string(), "8+32".to_string()),
+                    ])
+                ),
+                ErrorDetail::DebugInfo(DebugInfo {
+                    stack_entries: vec![],
+                    detail: log.to_string(),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_load_failed() {
+        let load_error = |errno| {
+            BpfmanError::BpfProgramError(ProgramError::LoadError {
+                io_error: io::Error::from_raw_os_error(errno),
+                verifier_log: aya_obj::VerifierLog::new(String::new()),
+            })
+            .into_status()
+        };
+
+        for (errno, code, reason) in [
+            (1, Code::PermissionDenied, "PERMISSION_DENIED"),
+            (13, Code::PermissionDenied, "PERMISSION_DENIED"),
+            (22, Code::Internal, "LOAD_FAILED"),
+        ] {
+            let status = load_error(errno);
+            assert_eq!(status.code(), code);
+            assert_eq!(details(&status), vec![error_info(reason, HashMap::new())]);
+        }
+    }
+}
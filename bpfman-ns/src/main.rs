@@ -20,7 +20,10 @@ struct Cli {
 enum Commands {
     /// Attach a uprobe program in the given container.
     Uprobe(UprobeArgs),
-    // TODO: add additional commands: Kprobe, etc.
+    // Kprobes aren't attached here: they fire kernel-wide whichever namespace
+    // attaches them, so bpfman attaches container kprobes itself and scopes
+    // them with the container's cgroup id.
+    // TODO: add additional commands for other container scoped programs.
 }
 
 #[derive(Debug, Args)]
@@ -29,6 +32,11 @@ struct UprobeArgs {
     #[clap(short, long, verbatim_doc_comment)]
     program_pin_path: String,
 
+    /// Optional: path to pin the link at on a bpffs.
+    /// [default: <PROGRAM_PIN_PATH>_link]
+    #[clap(short, long, verbatim_doc_comment)]
+    link_pin_path: Option<String>,
+
     /// Optional: Function to attach the uprobe to.
     #[clap(short, long)]
     fn_name: Option<String>,
@@ -136,7 +144,11 @@ fn execute_uprobe_attach(args: UprobeArgs, bpfman_pid: u32) -> anyhow::Result<()
         .try_into()
         .expect("unable to get owned uprobe attach link");
 
-    fd_link.pin(format!("{}_link", args.program_pin_path))?;
+    fd_link.pin(
+        args.link_pin_path
+            .clone()
+            .unwrap_or_else(|| format!("{}_link", args.program_pin_path)),
+    )?;
 
     Ok(())
 }
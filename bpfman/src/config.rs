@@ -1,7 +1,11 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
 use aya::programs::XdpFlags;
 use serde::{Deserialize, Serialize};
@@ -14,6 +18,8 @@ pub(crate) struct Config {
     #[serde(default)]
     signing: Option<SigningConfig>,
     database: Option<DatabaseConfig>,
+    #[serde(default)]
+    libxdp: LibxdpConfig,
 }
 
 impl Config {
@@ -28,6 +34,10 @@ impl Config {
     pub(crate) fn database(&self) -> &Option<DatabaseConfig> {
         &self.database
     }
+
+    pub(crate) fn libxdp(&self) -> &LibxdpConfig {
+        &self.libxdp
+    }
 }
 #[derive(Debug, Deserialize, Clone)]
 pub struct SigningConfig {
@@ -60,6 +70,39 @@ impl Default for DatabaseConfig {
     }
 }
 
+/// Configures cooperation with libxdp, as used by xdp-tools, on the XDP hook
+/// of interfaces in bpfman's network namespace.
+#[derive(Debug, Deserialize, Clone)]
+pub struct LibxdpConfig {
+    /// Attach XDP dispatchers the way libxdp does, so that either one can add
+    /// programs to the dispatcher of an interface.
+    #[serde(default)]
+    pub interop: bool,
+    /// The bpffs libxdp pins its dispatchers in, under "xdp".
+    #[serde(default = "default_libxdp_bpffs")]
+    pub bpffs: PathBuf,
+}
+
+fn default_libxdp_bpffs() -> PathBuf {
+    PathBuf::from("/sys/fs/bpf")
+}
+
+impl Default for LibxdpConfig {
+    fn default() -> Self {
+        Self {
+            interop: false,
+            bpffs: default_libxdp_bpffs(),
+        }
+    }
+}
+
+impl LibxdpConfig {
+    // Returns the bpffs to share with libxdp, if interoperating with it.
+    pub(crate) fn interop_bpffs(&self) -> Option<&Path> {
+        self.interop.then_some(self.bpffs.as_path())
+    }
+}
+
 impl FromStr for Config {
     type Err = ParseError;
 
@@ -70,18 +113,51 @@ impl FromStr for Config {
 
 #[derive(Debug, Deserialize, Copy, Clone)]
 pub(crate) struct InterfaceConfig {
+    #[serde(default)]
     xdp_mode: XdpMode,
+    #[serde(default)]
+    xdp_mode_fallback: bool,
+    #[serde(default)]
+    xdp_wide_dispatcher: bool,
+    #[serde(default)]
+    tc_mode: TcMode,
+    #[serde(default)]
+    foreign_policy: ForeignPolicy,
+    #[serde(default)]
+    tc_foreign_order: TcForeignOrder,
 }
 
 impl InterfaceConfig {
     pub(crate) fn xdp_mode(&self) -> &XdpMode {
         &self.xdp_mode
     }
+
+    pub(crate) fn xdp_mode_fallback(&self) -> bool {
+        self.xdp_mode_fallback
+    }
+
+    pub(crate) fn xdp_wide_dispatcher(&self) -> bool {
+        self.xdp_wide_dispatcher
+    }
+
+    pub(crate) fn tc_mode(&self) -> &TcMode {
+        &self.tc_mode
+    }
+
+    pub(crate) fn foreign_policy(&self) -> ForeignPolicy {
+        self.foreign_policy
+    }
+
+    pub(crate) fn tc_foreign_order(&self) -> TcForeignOrder {
+        self.tc_foreign_order
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq)]
+/// XdpMode selects how the XDP dispatcher is attached to an interface.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
-pub(crate) enum XdpMode {
+pub enum XdpMode {
+    #[default]
     Skb,
     Drv,
     Hw,
@@ -112,6 +188,21 @@ impl TryFrom<u32> for XdpMode {
     }
 }
 
+impl FromStr for XdpMode {
+    type Err = ParseError;
+
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "skb" => Ok(XdpMode::Skb),
+            "drv" => Ok(XdpMode::Drv),
+            "hw" => Ok(XdpMode::Hw),
+            _ => Err(ParseError::InvalidXdpMode {
+                mode: mode.to_string(),
+            }),
+        }
+    }
+}
+
 impl std::fmt::Display for XdpMode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -122,6 +213,75 @@ impl std::fmt::Display for XdpMode {
     }
 }
 
+/// TcMode selects how the TC dispatcher is attached to an interface.
+#[derive(Debug, Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TcMode {
+    /// Use netkit for netkit devices, TCX on kernels which support it, and
+    /// the qdisc otherwise.
+    #[default]
+    Auto,
+    /// Attach through a clsact qdisc and a netlink filter.
+    Qdisc,
+    /// Attach with a TCX bpf_link (kernel 6.6+).
+    Tcx,
+    /// Attach with a netkit bpf_link, for netkit devices (kernel 6.7+).
+    Netkit,
+}
+
+impl TryFrom<u32> for TcMode {
+    type Error = ParseError;
+
+    fn try_from(mode: u32) -> Result<Self, Self::Error> {
+        match mode {
+            0 => Ok(TcMode::Auto),
+            1 => Ok(TcMode::Qdisc),
+            2 => Ok(TcMode::Tcx),
+            3 => Ok(TcMode::Netkit),
+            _ => Err(ParseError::InvalidTcMode {
+                mode: mode.to_string(),
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for TcMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TcMode::Auto => write!(f, "auto"),
+            TcMode::Qdisc => write!(f, "qdisc"),
+            TcMode::Tcx => write!(f, "tcx"),
+            TcMode::Netkit => write!(f, "netkit"),
+        }
+    }
+}
+
+/// ForeignPolicy selects what a dispatcher does about XDP programs and TC
+/// filters which were attached to its hook by something other than bpfman.
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ForeignPolicy {
+    /// Don't attach a dispatcher to a hook which has foreign programs.
+    Refuse,
+    /// Attach alongside the foreign programs, where the hook allows it.
+    #[default]
+    Coexist,
+    /// Keep the foreign TC programs running in front of the dispatcher and
+    /// manage them along with it. XDP programs can't be taken over, so
+    /// loading fails instead.
+    Takeover,
+}
+
+/// TcForeignOrder selects whether a TC dispatcher which coexists with
+/// foreign programs runs before or after them.
+#[derive(Debug, Deserialize, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TcForeignOrder {
+    #[default]
+    First,
+    Last,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -173,4 +333,104 @@ mod test {
             None => panic!("expected interfaces to be present"),
         }
     }
+
+    #[test]
+    fn test_config_tc_mode() {
+        let input = r#"
+        [interfaces]
+          [interfaces.eth0]
+          tc_mode = "tcx"
+          [interfaces.eth1]
+          xdp_mode = "drv"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let interfaces = config
+            .interfaces
+            .expect("expected interfaces to be present");
+        let eth0 = interfaces.get("eth0").unwrap();
+        assert_eq!(eth0.tc_mode, TcMode::Tcx);
+        assert_eq!(eth0.xdp_mode, XdpMode::Skb);
+        let eth1 = interfaces.get("eth1").unwrap();
+        assert_eq!(eth1.tc_mode, TcMode::Auto);
+        assert_eq!(eth1.xdp_mode, XdpMode::Drv);
+    }
+
+    #[test]
+    fn test_config_foreign_policy() {
+        let input = r#"
+        [interfaces]
+          [interfaces.eth0]
+          foreign_policy = "takeover"
+          [interfaces.eth1]
+          foreign_policy = "coexist"
+          tc_foreign_order = "last"
+          [interfaces.eth2]
+          tc_mode = "qdisc"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let interfaces = config
+            .interfaces
+            .expect("expected interfaces to be present");
+        let eth0 = interfaces.get("eth0").unwrap();
+        assert_eq!(eth0.foreign_policy, ForeignPolicy::Takeover);
+        let eth1 = interfaces.get("eth1").unwrap();
+        assert_eq!(eth1.foreign_policy, ForeignPolicy::Coexist);
+        assert_eq!(eth1.tc_foreign_order, TcForeignOrder::Last);
+        let eth2 = interfaces.get("eth2").unwrap();
+        assert_eq!(eth2.foreign_policy, ForeignPolicy::Coexist);
+        assert_eq!(eth2.tc_foreign_order, TcForeignOrder::First);
+    }
+
+    #[test]
+    fn test_config_libxdp() {
+        let config: Config = toml::from_str("").expect("error parsing toml input");
+        assert_eq!(config.libxdp().interop_bpffs(), None);
+
+        let input = r#"
+        [libxdp]
+        interop = true
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        assert_eq!(
+            config.libxdp().interop_bpffs(),
+            Some(Path::new("/sys/fs/bpf"))
+        );
+    }
+
+    #[test]
+    fn test_config_xdp_mode_fallback() {
+        let input = r#"
+        [interfaces]
+          [interfaces.eth0]
+          xdp_mode = "drv"
+          xdp_mode_fallback = true
+          [interfaces.eth1]
+          xdp_mode = "drv"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let interfaces = config
+            .interfaces
+            .expect("expected interfaces to be present");
+        assert!(interfaces.get("eth0").unwrap().xdp_mode_fallback);
+        assert!(!interfaces.get("eth1").unwrap().xdp_mode_fallback);
+        assert_eq!(XdpMode::from_str("drv").unwrap(), XdpMode::Drv);
+        assert!(XdpMode::from_str("native").is_err());
+    }
+
+    #[test]
+    fn test_config_xdp_wide_dispatcher() {
+        let input = r#"
+        [interfaces]
+          [interfaces.eth0]
+          xdp_wide_dispatcher = true
+          [interfaces.eth1]
+          xdp_mode = "drv"
+        "#;
+        let config: Config = toml::from_str(input).expect("error parsing toml input");
+        let interfaces = config
+            .interfaces
+            .expect("expected interfaces to be present");
+        assert!(interfaces.get("eth0").unwrap().xdp_wide_dispatcher());
+        assert!(!interfaces.get("eth1").unwrap().xdp_wide_dispatcher());
+    }
 }
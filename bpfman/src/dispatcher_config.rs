@@ -41,11 +41,32 @@ impl XdpDispatcherConfig {
 
 unsafe impl aya::Pod for XdpDispatcherConfig {}
 
+// XDP dispatchers can't be chained, so interfaces with more programs than fit
+// in the dispatcher libxdp knows get a wider one with a config of its own.
+pub(crate) const XDP_MAX_PROGRAMS: usize = 100;
+
+#[derive(Copy, Clone, Debug)]
+#[repr(C)]
+pub(crate) struct XdpWideDispatcherConfig {
+    pub num_progs_enabled: u8,
+    pub chain_call_actions: [u32; XDP_MAX_PROGRAMS],
+    pub run_prios: [u32; XDP_MAX_PROGRAMS],
+    pub program_flags: [u32; XDP_MAX_PROGRAMS],
+}
+
+unsafe impl aya::Pod for XdpWideDispatcherConfig {}
+
 // TC Defines
 // pub (crate) const TC_METADATA_SECTION: &str = "tc_metadata";
 // pub (crate) const TC_DISPATCHER_VERSION: u32 = 1;
 // pub (crate) const TC_DISPATCHER_RETVAL: u32 = 31;
 pub(crate) const TC_MAX_DISPATCHER_ACTIONS: usize = 10;
+// TC dispatchers are chained on the hook once an interface has more programs
+// than fit in one. Every dispatcher but the last gives up a slot to hand the
+// packet on to the next one.
+pub(crate) const TC_MAX_DISPATCHERS: usize = 10;
+pub(crate) const TC_MAX_PROGRAMS: usize =
+    (TC_MAX_DISPATCHERS - 1) * (TC_MAX_DISPATCHER_ACTIONS - 1) + TC_MAX_DISPATCHER_ACTIONS;
 
 #[derive(Copy, Clone, Debug)]
 #[repr(C)]
@@ -5,7 +5,7 @@ use thiserror::Error;
 use tokio::sync::oneshot;
 use url::ParseError as urlParseError;
 
-use crate::oci_utils::ImageError;
+pub use crate::oci_utils::ImageError;
 
 #[derive(Debug, Error)]
 pub enum BpfmanError {
@@ -23,6 +23,8 @@ pub enum BpfmanError {
     TooManyPrograms,
     #[error("Invalid Interface")]
     InvalidInterface,
+    #[error("Invalid network namespace {0}")]
+    InvalidNetns(String),
     #[error("Failed to pin link {0}")]
     UnableToPinLink(#[source] aya::pin::PinError),
     #[error("Failed to pin program {0}")]
@@ -56,10 +58,11 @@ pub enum BpfmanError {
     RpcSendError(#[from] anyhow::Error),
     #[error("Failed to pin map {0}")]
     UnableToPinMap(#[source] aya::pin::PinError),
-    #[error("Unable to attach {program_type} in container with pid {container_pid}")]
+    #[error("Unable to attach {program_type} in container with pid {container_pid}: {reason}")]
     ContainerAttachError {
         program_type: String,
         container_pid: i32,
+        reason: String,
     },
     #[error("{0}: {1}")]
     DatabaseError(String, String),
@@ -69,6 +72,47 @@ pub enum BpfmanError {
     BtfError(#[from] aya::BtfError),
     #[error("Failed to acquire database lock, please try again later")]
     DatabaseLockError,
+    #[error("Programs of this type are not supported")]
+    UnsupportedProgramType,
+    #[error("Program {0} can't be replaced: {1}")]
+    InvalidUpdate(u32, String),
+    #[error("Map {map_name} not found for program {program_id}")]
+    MapNotFound { program_id: u32, map_name: String },
+    #[error("Program {0} does not exist")]
+    ProgramNotFound(u32),
+    #[error("Program {0} does not exist or was not created by bpfman")]
+    ProgramNotManaged(u32),
+    #[error("map_owner_id {0} does not exist")]
+    MapOwnerNotFound(u32),
+    #[error("Link {0} does not exist")]
+    LinkNotFound(u32),
+    #[error("Program {0} is already attached to {1}")]
+    AlreadyAttached(u32, String),
+    #[error("Program {0} is not an XDP or TC program attached through a dispatcher")]
+    NotInDispatcher(u32),
+    #[error("XDP programs with and without frags support can't be attached to interface {0}")]
+    XdpFragsMismatch(String),
+    #[error("XDP programs can't be attached to interface {0} in different modes")]
+    XdpModeMismatch(String),
+    #[error(
+        "Interface {iface} has programs attached by something other than bpfman: {program_ids:?}"
+    )]
+    ForeignProgramsAttached {
+        iface: String,
+        program_ids: Vec<u32>,
+    },
+    #[error("Unable to take over program {program_id} on interface {iface}: {reason}")]
+    ForeignTakeoverFailed {
+        iface: String,
+        program_id: u32,
+        reason: String,
+    },
+    #[error("{0} is not a valid interface pattern")]
+    InvalidInterfacePattern(String),
+    #[error("No interface matches {0}")]
+    NoMatchingInterface(String),
+    #[error("Interface pattern {0} can't be used with a network namespace")]
+    InterfacePatternWithNetns(String),
 }
 
 #[derive(Error, Debug)]
@@ -91,6 +135,8 @@ pub enum ParseError {
     InvalidProbeType { probe: String },
     #[error("Invalid XdpMode: {mode}")]
     InvalidXdpMode { mode: String },
+    #[error("Invalid TcMode: {mode}")]
+    InvalidTcMode { mode: String },
     #[error("Error parsing config file: {0}")]
     ConfigParseError(#[from] toml::de::Error),
 }
@@ -2,54 +2,84 @@
 // Copyright Authors of bpfman
 
 use std::{
-    collections::HashMap,
-    fs::{create_dir_all, remove_dir_all},
+    collections::{HashMap, HashSet},
+    fs::{create_dir_all, remove_dir_all, remove_file, rename, File},
+    os::fd::{AsFd, BorrowedFd},
     path::{Path, PathBuf},
 };
 
 use aya::{
+    maps::Map,
     programs::{
         fentry::FEntryLink, fexit::FExitLink, kprobe::KProbeLink, links::FdLink, loaded_programs,
-        trace_point::TracePointLink, uprobe::UProbeLink, FEntry, FExit, KProbe, TracePoint, UProbe,
+        trace_point::TracePointLink, uprobe::UProbeLink, CgroupSkb, CgroupSockAddr, FEntry, FExit,
+        KProbe, ProbeKind, SchedClassifier, SkMsg, SockOps, TracePoint, UProbe, Xdp,
     },
     BpfLoader, Btf,
 };
-use log::{debug, info, warn};
+use aya_obj::{generated::bpf_attach_type, programs::CgroupSockAddrAttachType, ProgramSection};
+use lazy_static::lazy_static;
+use log::{debug, error, info, warn};
+use object::{
+    Endianness, Object, ObjectSection, ObjectSymbol, RelocationTarget, SectionKind, SymbolKind,
+};
 use sled::{Config as SledConfig, Db};
-use tokio::time::{sleep, Duration};
+use tokio::{
+    sync::broadcast,
+    time::{sleep, Duration},
+};
 use utils::initialize_bpfman;
 
 use crate::{
-    config::Config,
+    config::{Config, XdpMode},
     directories::*,
+    dispatcher_config::{TC_MAX_PROGRAMS, XDP_MAX_PROGRAMS},
     errors::BpfmanError,
+    maps::PinnedMap,
     multiprog::{
-        Dispatcher, DispatcherId, DispatcherInfo, TC_DISPATCHER_PREFIX, XDP_DISPATCHER_PREFIX,
+        if_key, Dispatcher, DispatcherId, DispatcherInfo, TC_DISPATCHER_PREFIX,
+        XDP_DISPATCHER_PREFIX,
     },
     oci_utils::image_manager::ImageManager,
+    static_program::STATIC_PROGRAM_METADATA_KEY,
     types::{
-        BytecodeImage, Direction, ListFilter,
+        AttachPoint, BytecodeImage, Direction, DispatcherState, ForeignProgram, Link, ListFilter,
+        MapEntry,
         ProbeType::{self, *},
-        Program, ProgramData, ProgramType, PROGRAM_PREFIX,
+        Program, ProgramData, ProgramEvent, ProgramEventType, ProgramMap, ProgramType, LINK_PREFIX,
+        LINK_RESTORE_PREFIX, PROGRAM_PREFIX, PROGRAM_PRE_LOAD_PREFIX, PROGRAM_RESTORE_PREFIX,
     },
     utils::{
-        bytes_to_string, bytes_to_u32, get_error_msg_from_stderr, get_ifindex, open_config_file,
-        set_dir_permissions, should_map_be_pinned, sled_insert,
+        bytes_to_string, bytes_to_u32, get_cgroup_id, get_error_msg_from_stderr, get_ifindex,
+        get_matching_ifaces, iface_matches, is_iface_pattern, open_config_file,
+        set_dir_permissions, should_map_be_pinned, sled_insert, with_netns,
     },
 };
 
 mod config;
 mod dispatcher_config;
 pub mod errors;
+mod maps;
 mod multiprog;
 mod oci_utils;
 mod static_program;
+mod sys;
 pub mod types;
 pub mod utils;
 
 const MAPS_MODE: u32 = 0o0660;
 const MAP_PREFIX: &str = "map_";
 const MAPS_USED_BY_PREFIX: &str = "map_used_by_";
+// Number of program events buffered for each watcher before the oldest are
+// dropped.
+const PROGRAM_EVENTS_CAPACITY: usize = 256;
+// Global set to the container's cgroup id in kprobes loaded for a container.
+const CONTAINER_CGROUP_ID_GLOBAL: &str = "bpfman_container_cgroup_id";
+
+lazy_static! {
+    static ref PROGRAM_EVENTS: broadcast::Sender<ProgramEvent> =
+        broadcast::channel(PROGRAM_EVENTS_CAPACITY).0;
+}
 
 pub(crate) mod directories {
     // The following directories are used by bpfman. They should be created by bpfman service
@@ -76,11 +106,15 @@ pub(crate) mod directories {
     pub(crate) const RTDIR_PROGRAMS: &str = "/run/bpfman/programs";
     // The TUF repository is used to store Rekor and Fulcio public keys.
     pub(crate) const RTDIR_TUF: &str = "/run/bpfman/tuf";
+    // Created once the programs lost on a reboot have been restored.
+    pub(crate) const RTDIR_RESTORED: &str = "/run/bpfman/restored";
     // StateDirectory: /var/lib/bpfman/
     pub(crate) const STDIR_MODE: u32 = 0o6770;
     pub(crate) const STDIR: &str = "/var/lib/bpfman";
     #[cfg(not(test))]
     pub(crate) const STDIR_DB: &str = "/var/lib/bpfman/db";
+    // The cgroup v2 hierarchy, used to resolve container cgroup ids.
+    pub(crate) const CGROUP2_FS: &str = "/sys/fs/cgroup";
 }
 
 #[cfg(not(test))]
@@ -94,11 +128,21 @@ pub(crate) fn get_db_config() -> SledConfig {
 }
 
 /// Loads an ebpf program.
-pub async fn add_program(mut program: Program) -> Result<Program, BpfmanError> {
+///
+/// With `dry_run`, the program is only loaded to be checked by the verifier,
+/// and is unloaded again without being attached or saved. The returned program
+/// holds the kernel's information about the verified program, whose id is no
+/// longer in use.
+///
+/// XDP and TC programs are checked as standalone XDP and TC programs, not as
+/// the extensions of a dispatcher they run as once attached. The verifier
+/// checks extensions against the dispatcher function they replace, so a
+/// program which passes a dry run can still be rejected when it's loaded,
+/// such as when its function doesn't match the dispatcher's, and the
+/// instruction count of the dry run may differ.
+pub async fn add_program(mut program: Program, dry_run: bool) -> Result<Program, BpfmanError> {
     let (config, root_db) = &setup().await?;
     let mut image_manager = init_image_manager().await;
-    // This is only required in the add_program api
-    program.get_data_mut().load(root_db)?;
 
     let map_owner_id = program.get_data().get_map_owner_id()?;
     // Set map_pin_path if we're using another program's maps
@@ -112,9 +156,50 @@ pub async fn add_program(mut program: Program) -> Result<Program, BpfmanError> {
         .set_program_bytes(root_db, &mut image_manager)
         .await?;
 
+    if dry_run {
+        verify_program(&mut program)?;
+        info!(
+            "Verified {} program with name: {}",
+            program.kind(),
+            program.get_data().get_name()?
+        );
+        return Ok(program);
+    }
+
+    // A program given an interface pattern is loaded on the first matching
+    // interface, and attached to the others once it's loaded. Only bpfman's
+    // own network namespace is watched for interfaces matching it.
+    let mut other_ifaces = vec![];
+    if let Program::Xdp(_) | Program::Tc(_) = program {
+        let pattern = program.if_name()?;
+        if is_iface_pattern(&pattern) {
+            if program.netns()?.is_some() {
+                return Err(BpfmanError::InterfacePatternWithNetns(pattern));
+            }
+            let mut ifaces = get_matching_ifaces(&pattern)?.into_iter();
+            let first = ifaces
+                .next()
+                .ok_or_else(|| BpfmanError::NoMatchingInterface(pattern.clone()))?;
+            program.set_iface_pattern(&pattern)?;
+            program.set_if_name(&first)?;
+            other_ifaces = ifaces.collect();
+        }
+    }
+
+    // This is only required in the add_program api
+    program.get_data_mut().load(root_db)?;
+
+    // Snapshot the program for the failure event up front, since a failed
+    // attach may have already removed the program's data.
+    let mut failed_event =
+        ProgramEvent::new(ProgramEventType::AttachFailed, &program, String::new());
+
     let result = match program {
         Program::Xdp(_) | Program::Tc(_) => {
-            program.set_if_index(get_ifindex(&program.if_name().unwrap())?)?;
+            let if_index = with_netns(program.netns()?.as_ref(), || {
+                get_ifindex(&program.if_name().unwrap())
+            })?;
+            program.set_if_index(if_index)?;
 
             add_multi_attach_program(root_db, &mut program, &mut image_manager, config).await
         }
@@ -122,8 +207,12 @@ pub async fn add_program(mut program: Program) -> Result<Program, BpfmanError> {
         | Program::Kprobe(_)
         | Program::Uprobe(_)
         | Program::Fentry(_)
-        | Program::Fexit(_) => add_single_attach_program(root_db, &mut program),
-        Program::Unsupported(_) => panic!("Cannot add unsupported program"),
+        | Program::Fexit(_)
+        | Program::CgroupSkb(_)
+        | Program::CgroupSockAddr(_)
+        | Program::SockOps(_)
+        | Program::SkMsg(_) => add_single_attach_program(root_db, &mut program),
+        Program::Unsupported(_) => Err(BpfmanError::UnsupportedProgramType),
     };
 
     match result {
@@ -142,9 +231,21 @@ pub async fn add_program(mut program: Program) -> Result<Program, BpfmanError> {
             // by the kernel.
             program.get_data_mut().swap_tree(root_db, id)?;
 
+            emit_event(ProgramEventType::Loaded, &program, String::new());
+
+            for if_name in other_ifaces {
+                attach_to_matching_iface(root_db, config, &program, &if_name).await;
+            }
+
             Ok(program)
         }
         Err(e) => {
+            // The snapshot holds the temporary id used before the program is
+            // loaded, which means nothing to watchers.
+            failed_event.id = None;
+            failed_event.message = e.to_string();
+            let _ = PROGRAM_EVENTS.send(failed_event);
+
             // Cleanup any directories associated with the map_pin_path.
             // map_pin_path may or may not exist depending on where the original
             // error occured, so don't error if not there and preserve original error.
@@ -160,6 +261,218 @@ pub async fn add_program(mut program: Program) -> Result<Program, BpfmanError> {
     }
 }
 
+/// Replaces a loaded ebpf program with a new one.
+///
+/// XDP and TC programs are swapped in a single dispatcher rebuild, so the
+/// replacement takes over the position of the old program without a window in
+/// which neither program is attached. Other program types are attached before
+/// the old program is detached. The replacement keeps the priority of the
+/// program it replaces and uses the same maps. If the old program owned the
+/// maps, the replacement becomes their owner.
+/// If the replacement can't take over the old program's maps, it's removed and
+/// the old program is put back in its place. If the old program can't be
+/// removed once the replacement has taken its place, the error is returned and
+/// the replacement is left in place.
+pub async fn update_program(id: u32, mut program: Program) -> Result<Program, BpfmanError> {
+    let (config, root_db) = &setup().await?;
+
+    info!("Updating program with id: {id}");
+    let old = match get(root_db, &id) {
+        Some(p) => p,
+        None => {
+            return Err(BpfmanError::ProgramNotManaged(id));
+        }
+    };
+
+    if get_links(root_db).any(|l| l.program_id().ok() == Some(id)) {
+        return Err(BpfmanError::InvalidUpdate(
+            id,
+            "the program has links, which must be detached first".to_string(),
+        ));
+    }
+
+    // A replacement given the interface pattern of the old program takes over
+    // its interface.
+    if let Some(pattern) = old.iface_pattern()? {
+        if program.if_name()? == pattern {
+            program.set_if_name(&old.if_name()?)?;
+            program.set_iface_pattern(&pattern)?;
+        }
+    }
+
+    let old_map_owner_id = old.get_data().get_map_owner_id()?;
+    let map_index = old_map_owner_id.unwrap_or(id);
+    validate_update(&old, &program, map_index)?;
+
+    // The replacement takes the place of the old program on its interface.
+    if let Program::Xdp(_) | Program::Tc(_) = program {
+        let if_index = old.if_index()?.ok_or_else(|| {
+            BpfmanError::InvalidUpdate(id, "the program has no interface index".to_string())
+        })?;
+        program.set_if_index(if_index)?;
+        program.set_priority(old.priority()?)?;
+    }
+
+    // The replacement is loaded as a user of the old program's maps.
+    let map_pin_path = is_map_owner_id_valid(root_db, map_index)?;
+    program.get_data_mut().set_map_owner_id(map_index)?;
+    program.get_data_mut().set_map_pin_path(&map_pin_path)?;
+
+    let mut image_manager = init_image_manager().await;
+    program
+        .get_data_mut()
+        .set_program_bytes(root_db, &mut image_manager)
+        .await?;
+
+    program.get_data_mut().load(root_db)?;
+
+    let mut failed_event =
+        ProgramEvent::new(ProgramEventType::AttachFailed, &program, String::new());
+    let unloaded_event = ProgramEvent::new(
+        ProgramEventType::Unloaded,
+        &old,
+        "program was replaced".to_string(),
+    );
+
+    let result = match program {
+        Program::Xdp(_) | Program::Tc(_) => {
+            replace_multi_attach_program(root_db, &old, &mut program, &mut image_manager, config)
+                .await
+        }
+        Program::Tracepoint(_)
+        | Program::Kprobe(_)
+        | Program::Uprobe(_)
+        | Program::Fentry(_)
+        | Program::Fexit(_)
+        | Program::CgroupSkb(_)
+        | Program::CgroupSockAddr(_)
+        | Program::SockOps(_)
+        | Program::SkMsg(_) => add_single_attach_program(root_db, &mut program),
+        Program::Unsupported(_) => Err(BpfmanError::UnsupportedProgramType),
+    };
+
+    let new_id = match result {
+        Ok(new_id) => new_id,
+        Err(e) => {
+            failed_event.id = None;
+            failed_event.message = e.to_string();
+            let _ = PROGRAM_EVENTS.send(failed_event);
+
+            // Cleanup the replacement. The old program is left untouched.
+            let _ = program.delete(root_db);
+
+            return Err(e);
+        }
+    };
+
+    if let Err(e) = take_over_program(
+        root_db,
+        &mut program,
+        id,
+        map_index,
+        old_map_owner_id.is_none(),
+    ) {
+        failed_event.id = None;
+        failed_event.message = e.to_string();
+        let _ = PROGRAM_EVENTS.send(failed_event);
+
+        restore_replaced_program(
+            root_db,
+            config,
+            &old,
+            &program,
+            map_index,
+            &mut image_manager,
+        )
+        .await;
+
+        return Err(e);
+    }
+
+    info!(
+        "Replaced {} program {id} with name: {} and id: {new_id}",
+        program.kind(),
+        program.get_data().get_name()?
+    );
+
+    // Remove the old program now that the replacement has taken its place. The
+    // replacement stays in use if that fails, so it's announced either way.
+    let deleted = old.delete(root_db);
+    if deleted.is_ok() {
+        let _ = PROGRAM_EVENTS.send(unloaded_event);
+    }
+    emit_event(
+        ProgramEventType::Loaded,
+        &program,
+        format!("replaced program {id}"),
+    );
+    deleted.map_err(BpfmanError::BpfmanProgramDeleteError)?;
+
+    Ok(program)
+}
+
+// Moves the replacement of program id to its own id in the database, and hands
+// it the old program's place among the users of its maps, or their ownership.
+fn take_over_program(
+    root_db: &Db,
+    program: &mut Program,
+    id: u32,
+    map_index: u32,
+    map_owner: bool,
+) -> Result<(), BpfmanError> {
+    let new_id = program.get_data().get_id()?;
+    save_map(root_db, program, new_id, Some(map_index))?;
+    program.get_data_mut().swap_tree(root_db, new_id)?;
+    delete_map(root_db, id, Some(map_index))?;
+    if map_owner {
+        transfer_map_owner(root_db, id, new_id)?;
+        *program = get(root_db, &new_id).ok_or(BpfmanError::ProgramNotManaged(new_id))?;
+    }
+    Ok(())
+}
+
+// Puts the old program back after its replacement failed to take it over. The
+// replacement is removed, and the old program rejoins the users of its maps
+// and its place in the dispatcher. Errors are ignored, since the update has
+// already failed.
+async fn restore_replaced_program(
+    root_db: &Db,
+    config: &Config,
+    old: &Program,
+    program: &Program,
+    map_index: u32,
+    image_manager: &mut ImageManager,
+) {
+    let Ok(id) = old.get_data().get_id() else {
+        return;
+    };
+    if let Some(map) = get_map(map_index, root_db) {
+        if !get_maps_used_by(map).is_ok_and(|used_by| used_by.contains(&id)) {
+            let _ = save_map(root_db, &mut old.clone(), id, Some(map_index));
+        }
+    }
+
+    if let Ok(new_id) = program.get_data().get_id() {
+        let _ = program.delete(root_db);
+        let _ = delete_map(root_db, new_id, Some(map_index));
+    }
+
+    if let (Program::Xdp(_) | Program::Tc(_), Ok(Some(did)), Ok(if_name)) =
+        (old, old.dispatcher_id(), old.if_name())
+    {
+        let old_dispatcher = get_dispatcher(&did, root_db);
+        let _ = rebuild_dispatcher(
+            root_db,
+            config,
+            &did,
+            &if_name,
+            old_dispatcher,
+            image_manager,
+        )
+        .await;
+    }
+}
+
 /// Unloads and ebpf program.
 pub async fn remove_program(id: u32) -> Result<(), BpfmanError> {
     let (config, root_db) = &setup().await?;
@@ -168,44 +481,49 @@ pub async fn remove_program(id: u32) -> Result<(), BpfmanError> {
     let prog = match get(root_db, &id) {
         Some(p) => p,
         None => {
-            return Err(BpfmanError::Error(format!(
-                "Program {0} does not exist or was not created by bpfman",
-                id,
-            )));
+            return match get_staged(root_db, &id) {
+                Some(p) => remove_staged_program(root_db, p),
+                None => Err(BpfmanError::ProgramNotManaged(id)),
+            };
         }
     };
 
     let map_owner_id = prog.get_data().get_map_owner_id()?;
+    // Take the event snapshot now since the program's data is deleted below.
+    let event = ProgramEvent::new(ProgramEventType::Unloaded, &prog, String::new());
+
+    let links: Vec<Link> = get_links(root_db)
+        .filter(|l| l.program_id().ok() == Some(id))
+        .collect();
+    for link in links {
+        remove_link(root_db, config, link).await?;
+    }
 
     match prog {
         Program::Xdp(_) | Program::Tc(_) => {
             let did = prog
                 .dispatcher_id()?
                 .ok_or(BpfmanError::DispatcherNotRequired)?;
-            let program_type = prog.kind();
-            let if_index = prog.if_index()?;
             let if_name = prog.if_name().unwrap();
-            let direction = prog.direction()?;
+            let iface_detached = prog.iface_detached();
 
             prog.delete(root_db)
                 .map_err(BpfmanError::BpfmanProgramDeleteError)?;
 
-            remove_multi_attach_program(
-                root_db,
-                config,
-                did,
-                program_type,
-                if_index,
-                if_name,
-                direction,
-            )
-            .await?
+            // The dispatcher of a removed interface is already gone.
+            if !iface_detached {
+                remove_multi_attach_program(root_db, config, did, if_name).await?
+            }
         }
         Program::Tracepoint(_)
         | Program::Kprobe(_)
         | Program::Uprobe(_)
         | Program::Fentry(_)
         | Program::Fexit(_)
+        | Program::CgroupSkb(_)
+        | Program::CgroupSockAddr(_)
+        | Program::SockOps(_)
+        | Program::SkMsg(_)
         | Program::Unsupported(_) => {
             prog.delete(root_db)
                 .map_err(BpfmanError::BpfmanProgramDeleteError)?;
@@ -214,179 +532,949 @@ pub async fn remove_program(id: u32) -> Result<(), BpfmanError> {
 
     delete_map(root_db, id, map_owner_id)?;
 
+    let _ = PROGRAM_EVENTS.send(event);
+
     Ok(())
 }
 
-/// Lists the currently loaded ebpf programs.
-pub async fn list_programs(filter: ListFilter) -> Result<Vec<Program>, BpfmanError> {
-    let (_, root_db) = &setup().await?;
-
-    debug!("BpfManager::list_programs()");
+// Removes a program which is waiting to be restored, along with its links.
+// Nothing of it is loaded or pinned, and the maps it owned were already removed
+// when it was staged.
+fn remove_staged_program(root_db: &Db, prog: Program) -> Result<(), BpfmanError> {
+    let id = prog.get_data().get_id()?;
+    let map_owner_id = prog.get_data().get_map_owner_id()?;
+    let event = ProgramEvent::new(ProgramEventType::Unloaded, &prog, String::new());
 
-    // Get an iterator for the bpfman load programs, a hash map indexed by program id.
-    let mut bpfman_progs: HashMap<u32, Program> = get_programs_iter(root_db).collect();
+    for name in root_db.tree_names() {
+        if !bytes_to_string(&name).starts_with(LINK_RESTORE_PREFIX) {
+            continue;
+        }
+        let tree = root_db
+            .open_tree(&name)
+            .expect("unable to open database tree");
+        if Link::new_from_db(tree).is_ok_and(|l| l.program_id().ok() == Some(id)) {
+            root_db
+                .drop_tree(name)
+                .expect("unable to drop link database tree");
+        }
+    }
 
-    // Call Aya to get ALL the loaded eBPF programs, and loop through each one.
-    Ok(loaded_programs()
-        .filter_map(|p| p.ok())
-        .map(|prog| {
-            let prog_id = prog.id();
+    root_db
+        .drop_tree(format!("{PROGRAM_RESTORE_PREFIX}{id}"))
+        .expect("unable to drop program database tree");
 
-            // If the program was loaded by bpfman (check the hash map), then use it.
-            // Otherwise, convert the data returned from Aya into an Unsupported Program Object.
-            match bpfman_progs.remove(&prog_id) {
-                Some(p) => p.to_owned(),
-                None => {
-                    let db_tree = root_db
-                        .open_tree(prog_id.to_string())
-                        .expect("Unable to open program database tree for listing programs");
+    // The maps of a map owner which is waiting to be restored are gone too.
+    if let Some(owner) = map_owner_id.filter(|owner| get_map(*owner, root_db).is_some()) {
+        delete_map(root_db, id, Some(owner))?;
+    }
 
-                    let mut data = ProgramData::new_empty(db_tree);
-                    if let Err(e) = data.set_kernel_info(&prog) {
-                        warn!("Unable to set kernal info for prog {prog_id}, error: {e}");
-                    };
+    let _ = PROGRAM_EVENTS.send(event);
 
-                    Program::Unsupported(data)
-                }
-            }
-        })
-        .filter(|p| filter.matches(p))
-        .collect())
+    Ok(())
 }
 
-/// Fetches more information regarding a currently loaded ebpf program.
-pub async fn get_program(id: u32) -> Result<Program, BpfmanError> {
-    let (_, root_db) = &setup().await?;
-
-    debug!("Getting program with id: {id}");
-    // If the program was loaded by bpfman, then use it.
-    // Otherwise, call Aya to get ALL the loaded eBPF programs, and convert the data
-    // returned from Aya into an Unsupported Program Object.
-    match get(root_db, &id) {
-        Some(p) => Ok(p.to_owned()),
-        None => loaded_programs()
-            .find_map(|p| {
-                let prog = p.ok()?;
-                if prog.id() == id {
-                    let db_tree = root_db
-                        .open_tree(prog.id().to_string())
-                        .expect("Unable to open program database tree for listing programs");
+/// Attaches a program loaded by bpfman to another attach point, in addition to
+/// the one it was loaded with.
+///
+/// XDP and TC programs are added to the dispatcher of the interface, which
+/// can only hold a program once. Fentry, fexit and sk_msg programs can only be
+/// attached when they are loaded.
+pub async fn attach_program(id: u32, attach: AttachPoint) -> Result<Link, BpfmanError> {
+    let (config, root_db) = &setup().await?;
 
-                    let mut data = ProgramData::new_empty(db_tree);
-                    data.set_kernel_info(&prog)
-                        .expect("unable to set kernel info");
+    info!("Attaching program {id} to {attach}");
+    let program = get(root_db, &id).ok_or(BpfmanError::ProgramNotManaged(id))?;
 
-                    Some(Program::Unsupported(data))
-                } else {
-                    None
-                }
-            })
-            .ok_or(BpfmanError::Error(format!(
-                "Program {0} does not exist",
-                id
-            ))),
+    let mut link = Link::new(root_db, &program, attach)?;
+    if let Err(e) = attach_link(root_db, config, &program, &mut link).await {
+        let _ = link.delete(root_db);
+        return Err(e);
     }
+
+    info!("Attached program {id} with link id: {}", link.id()?);
+    Ok(link)
 }
 
-/// Pulls an ebpf bytecode image from a remote OCI container registry.
-pub async fn pull_bytecode(image: BytecodeImage) -> anyhow::Result<()> {
-    let (_, root_db) = &setup().await?;
-    let image_manager = &mut init_image_manager().await;
+/// Detaches a link made with [`attach_program`]. The program stays loaded and
+/// attached to the attach point it was loaded with.
+pub async fn detach_link(link_id: u32) -> Result<(), BpfmanError> {
+    let (config, root_db) = &setup().await?;
 
-    image_manager
-        .get_image(
-            root_db,
-            &image.image_url,
-            image.image_pull_policy.clone(),
-            image.username.clone(),
-            image.password.clone(),
-        )
-        .await?;
-    Ok(())
-}
+    info!("Detaching link {link_id}");
+    let link = get_links(root_db)
+        .find(|l| l.id().ok() == Some(link_id))
+        .ok_or(BpfmanError::LinkNotFound(link_id))?;
 
-pub(crate) async fn init_database(sled_config: SledConfig) -> Result<Db, BpfmanError> {
-    let database_config = open_config_file().database().to_owned().unwrap_or_default();
-    for _ in 0..=database_config.max_retries {
-        if let Ok(db) = sled_config.open() {
-            debug!("Successfully opened database");
-            return Ok(db);
-        } else {
-            info!(
-                "Database lock is already held, retrying after {} milliseconds",
-                database_config.millisec_delay
-            );
-            sleep(Duration::from_millis(database_config.millisec_delay)).await;
-        }
-    }
-    Err(BpfmanError::DatabaseLockError)
+    remove_link(root_db, config, link).await
 }
 
-// Make sure to call init_image_manger if the command requires interaction with
-// an OCI based container registry. It should ONLY be used where needed, to
-// explicitly control when bpfman blocks for network calls to both sigstore's
-// cosign tuf registries and container registries.
-pub(crate) async fn init_image_manager() -> ImageManager {
-    let config = open_config_file();
-    ImageManager::new(config.signing().as_ref().map_or(true, |s| s.allow_unsigned))
-        .await
-        .expect("failed to initialize image manager")
+/// Lists the links made with [`attach_program`], either of every program or of
+/// the program with the given id.
+pub async fn list_links(program_id: Option<u32>) -> Result<Vec<Link>, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    Ok(get_links(root_db)
+        .filter(|l| program_id.is_none() || l.program_id().ok() == program_id)
+        .collect())
 }
 
-fn get_dispatcher(id: &DispatcherId, root_db: &Db) -> Option<Dispatcher> {
-    let tree_name_prefix = match id {
-        DispatcherId::Xdp(DispatcherInfo(if_index, _)) => {
-            format!("{}_{}", XDP_DISPATCHER_PREFIX, if_index)
-        }
-        DispatcherId::Tc(DispatcherInfo(if_index, Some(direction))) => {
-            format!("{}_{}_{}", TC_DISPATCHER_PREFIX, if_index, direction)
-        }
-        _ => {
-            return None;
-        }
-    };
+/// Changes the priority and proceed-on actions of an XDP or TC program in
+/// place, keeping its id, and loads a single new revision of its dispatcher
+/// with the programs in their new order. Values which aren't given are left
+/// unchanged. Proceed-on actions are given as action values, as in
+/// [`types::XdpProceedOn::from_int32s`] and
+/// [`types::TcProceedOn::from_int32s`].
+pub async fn reorder_program(
+    id: u32,
+    priority: Option<i32>,
+    proceed_on: Option<Vec<i32>>,
+) -> Result<Program, BpfmanError> {
+    let (config, root_db) = &setup().await?;
 
-    root_db
-        .tree_names()
-        .into_iter()
-        .find(|p| bytes_to_string(p).contains(&tree_name_prefix))
-        .map(|p| {
-            let tree = root_db.open_tree(p).expect("unable to open database tree");
-            Dispatcher::new_from_db(tree)
-        })
-}
+    info!("Reordering program {id}");
+    let (mut program, did) = get_dispatcher_program(root_db, id)?;
 
-/// Returns the number of extension programs currently attached to the dispatcher that
-/// would be used to attach the provided [`Program`].
-fn num_attached_programs(did: &DispatcherId, root_db: &Db) -> usize {
-    if let Some(d) = get_dispatcher(did, root_db) {
-        d.num_extensions()
-    } else {
-        0
+    let old_priority = program.priority()?;
+    let old_proceed_on = program.proceed_on()?;
+    if let Some(priority) = priority {
+        program.set_priority(priority)?;
+    }
+    if let Some(proceed_on) = proceed_on {
+        program.set_proceed_on(&proceed_on)?;
     }
-}
 
-fn get(root_db: &Db, id: &u32) -> Option<Program> {
-    let prog_tree: sled::IVec = (PROGRAM_PREFIX.to_string() + &id.to_string())
-        .as_bytes()
-        .into();
-    if root_db.tree_names().contains(&prog_tree) {
-        let tree = root_db
-            .open_tree(prog_tree)
-            .expect("unable to open database tree");
-        Some(Program::new_from_db(*id, tree).expect("Failed to build program from database"))
-    } else {
-        None
+    let mut image_manager = init_image_manager().await;
+    let old_dispatcher = get_dispatcher(&did, root_db);
+    if let Err(e) = rebuild_dispatcher(
+        root_db,
+        config,
+        &did,
+        &program.if_name()?,
+        old_dispatcher,
+        &mut image_manager,
+    )
+    .await
+    {
+        // The old dispatcher is still attached, so the program keeps its old
+        // place in it.
+        program.set_priority(old_priority)?;
+        program.set_proceed_on(&old_proceed_on)?;
+        set_program_positions(root_db, &did);
+        return Err(e);
     }
+
+    info!("Reordered program {id}");
+    Ok(program)
 }
 
-fn filter(
-    root_db: &'_ Db,
-    program_type: ProgramType,
-    if_index: Option<u32>,
-    direction: Option<Direction>,
-) -> impl Iterator<Item = Program> + '_ {
-    root_db
+/// Disables an XDP or TC program, so that its dispatcher stops running it,
+/// while it stays loaded with its maps. The program is moved after the
+/// enabled programs on its dispatcher, and a new revision of the dispatcher is
+/// loaded which doesn't run it. Disabled programs stay disabled across
+/// restarts until they're enabled again with [`enable_program`].
+pub async fn disable_program(id: u32) -> Result<Program, BpfmanError> {
+    set_program_disabled(id, true).await
+}
+
+/// Enables an XDP or TC program disabled with [`disable_program`], so that
+/// its dispatcher runs it again in the order of its priority.
+pub async fn enable_program(id: u32) -> Result<Program, BpfmanError> {
+    set_program_disabled(id, false).await
+}
+
+async fn set_program_disabled(id: u32, disabled: bool) -> Result<Program, BpfmanError> {
+    let (config, root_db) = &setup().await?;
+
+    let (mut program, did) = get_dispatcher_program(root_db, id)?;
+    if program.disabled() == disabled {
+        return Ok(program);
+    }
+    info!(
+        "{} program {id}",
+        if disabled { "Disabling" } else { "Enabling" }
+    );
+    program.set_disabled(disabled)?;
+
+    let mut image_manager = init_image_manager().await;
+    let old_dispatcher = get_dispatcher(&did, root_db);
+    if let Err(e) = rebuild_dispatcher(
+        root_db,
+        config,
+        &did,
+        &program.if_name()?,
+        old_dispatcher,
+        &mut image_manager,
+    )
+    .await
+    {
+        program.set_disabled(!disabled)?;
+        set_program_positions(root_db, &did);
+        return Err(e);
+    }
+
+    Ok(program)
+}
+
+// Returns a program attached through a dispatcher, and the id of the
+// dispatcher.
+fn get_dispatcher_program(root_db: &Db, id: u32) -> Result<(Program, DispatcherId), BpfmanError> {
+    let program = get(root_db, &id).ok_or(BpfmanError::ProgramNotManaged(id))?;
+    match program.dispatcher_id()? {
+        Some(did) if program.attached() && !program.iface_detached() => Ok((program, did)),
+        _ => Err(BpfmanError::NotInDispatcher(id)),
+    }
+}
+
+/// Detaches the XDP and TC programs, and the links to them, from an
+/// interface in bpfman's own network namespace which has been removed. The
+/// dispatchers of the interface are removed, while the programs stay loaded
+/// with their maps and are marked as detached until [`interface_added`] is
+/// called for an interface with the same name. Links made for the interface
+/// pattern of a program are removed instead.
+pub async fn interface_removed(if_index: u32) -> Result<(), BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    let on_iface = |p: &Program| {
+        p.attached()
+            && !p.iface_detached()
+            && matches!(
+                p.dispatcher_id(),
+                Ok(Some(DispatcherId::Xdp(DispatcherInfo(i, _, None))
+                    | DispatcherId::Tc(DispatcherInfo(i, _, None)))) if i == if_index
+            )
+    };
+    // Links made for an interface pattern are removed, since they're made
+    // again for any interface matching the pattern.
+    let (pattern_links, links): (Vec<Link>, Vec<Link>) = get_links(root_db)
+        .filter(|l| on_iface(l.program()))
+        .partition(|l| matches!(l.program().iface_pattern(), Ok(Some(_))));
+    let mut programs: Vec<Program> = get_programs_iter(root_db)
+        .map(|(_, p)| p)
+        .filter(on_iface)
+        .chain(links.iter().map(|l| l.program().clone()))
+        .collect();
+    if programs.is_empty() && pattern_links.is_empty() {
+        return Ok(());
+    }
+
+    let dids: HashSet<DispatcherId> = programs
+        .iter()
+        .chain(pattern_links.iter().map(|l| l.program()))
+        .filter_map(|p| p.dispatcher_id().ok().flatten())
+        .collect();
+    for did in dids {
+        if let Some(mut d) = get_dispatcher(&did, root_db) {
+            d.delete_detached(root_db)?;
+        }
+    }
+
+    for p in programs.iter_mut() {
+        p.set_iface_detached(true)?;
+        let if_name = p.if_name()?;
+        info!(
+            "Detached {} program {} from removed interface {if_name}",
+            p.kind(),
+            p.get_data().get_id()?
+        );
+        emit_event(
+            ProgramEventType::Detached,
+            p,
+            format!("interface {if_name} was removed"),
+        );
+    }
+    for link in pattern_links {
+        let if_name = link.program().if_name()?;
+        info!(
+            "Removed link {} from removed interface {if_name}",
+            link.id()?
+        );
+        link.delete(root_db)
+            .map_err(BpfmanError::BpfmanProgramDeleteError)?;
+        emit_event(
+            ProgramEventType::Detached,
+            link.program(),
+            format!("interface {if_name} was removed"),
+        );
+    }
+    Ok(())
+}
+
+/// Detaches the XDP and TC programs from the interfaces in bpfman's own
+/// network namespace which were removed while nothing called
+/// [`interface_removed`], such as while bpfman-rpc wasn't running. Takes the
+/// names of the interfaces which exist now, by their index. An interface whose
+/// index now belongs to an interface with another name was removed too, so it
+/// is handled as such before [`interface_added`] is called for the interfaces.
+pub async fn interfaces_removed(interfaces: &HashMap<u32, String>) -> Result<(), BpfmanError> {
+    let removed = {
+        let (_, root_db) = &setup().await?;
+        removed_interfaces(get_dispatcher_programs(root_db), interfaces)
+    };
+
+    for if_index in removed {
+        info!("Interface with index {if_index} was removed");
+        interface_removed(if_index).await?;
+    }
+    Ok(())
+}
+
+// Returns the indexes of the interfaces the programs are attached to which no
+// longer exist with the same name.
+fn removed_interfaces(
+    programs: impl Iterator<Item = Program>,
+    interfaces: &HashMap<u32, String>,
+) -> HashSet<u32> {
+    programs
+        .filter(|p| p.attached() && !p.iface_detached())
+        .filter_map(|p| {
+            let if_index = match p.dispatcher_id().ok()?? {
+                DispatcherId::Xdp(DispatcherInfo(i, _, None))
+                | DispatcherId::Tc(DispatcherInfo(i, _, None)) => i,
+                _ => return None,
+            };
+            let if_name = p.if_name().ok()?;
+            (interfaces.get(&if_index) != Some(&if_name)).then_some(if_index)
+        })
+        .collect()
+}
+
+/// Attaches the XDP and TC programs detached by [`interface_removed`] from an
+/// interface with the given name to the interface, which has been added back
+/// with the given index, rebuilding each of its dispatchers with all of their
+/// programs. Programs which can't be attached again stay detached. Programs
+/// loaded with an interface pattern the interface matches are attached to it
+/// too, and programs waiting to be restored are restored.
+pub async fn interface_added(if_name: &str, if_index: u32) -> Result<(), BpfmanError> {
+    let (config, root_db) = &setup().await?;
+
+    // Programs staged to be restored may have been waiting for the interface.
+    restore_staged_programs(root_db, config).await;
+
+    let mut dispatchers: HashMap<DispatcherId, Vec<Program>> = HashMap::new();
+    for mut p in get_dispatcher_programs(root_db).filter(|p| p.iface_detached()) {
+        if p.if_name()? != if_name || p.netns()?.is_some() {
+            continue;
+        }
+        p.set_if_index(if_index)?;
+        p.set_iface_detached(false)?;
+        if let Some(did) = p.dispatcher_id()? {
+            dispatchers.entry(did).or_default().push(p);
+        }
+    }
+
+    let mut result = Ok(());
+    let mut image_manager = None;
+    for (did, mut programs) in dispatchers {
+        if image_manager.is_none() {
+            image_manager = Some(init_image_manager().await);
+        }
+        let old_dispatcher = get_dispatcher(&did, root_db);
+        let reattached = async {
+            let attached: Vec<Program> = filter(root_db, &did).collect();
+            let max_programs = match did {
+                DispatcherId::Tc(_) => TC_MAX_PROGRAMS,
+                DispatcherId::Xdp(_) => XDP_MAX_PROGRAMS,
+            };
+            if attached.len() > max_programs {
+                return Err(BpfmanError::TooManyPrograms);
+            }
+            if let Some(first) = attached.first() {
+                check_xdp_frags(first, attached.iter().cloned())?;
+            }
+            rebuild_dispatcher(
+                root_db,
+                config,
+                &did,
+                if_name,
+                old_dispatcher,
+                image_manager.as_mut().unwrap(),
+            )
+            .await
+        }
+        .await;
+
+        match reattached {
+            Ok(()) => {
+                for p in programs.iter() {
+                    info!(
+                        "Attached {} program {} to interface {if_name} again",
+                        p.kind(),
+                        p.get_data().get_id()?
+                    );
+                    emit_event(
+                        ProgramEventType::Reattached,
+                        p,
+                        format!("interface {if_name} was added"),
+                    );
+                }
+            }
+            Err(e) => {
+                error!("Unable to attach programs to interface {if_name} again: {e}");
+                for p in programs.iter_mut() {
+                    p.set_iface_detached(true)?;
+                    emit_event(ProgramEventType::AttachFailed, p, e.to_string());
+                }
+                set_program_positions(root_db, &did);
+                result = Err(e);
+            }
+        }
+    }
+
+    // Programs loaded with an interface pattern are attached to new
+    // interfaces matching it.
+    let programs: Vec<Program> = get_programs_iter(root_db).map(|(_, p)| p).collect();
+    for program in programs {
+        let Some(pattern) = program.iface_pattern()? else {
+            continue;
+        };
+        if program.netns()?.is_some() || !iface_matches(&pattern, if_name)? {
+            continue;
+        }
+        let id = program.get_data().get_id()?;
+        let attached = program.if_name()? == if_name
+            || get_links(root_db).any(|l| {
+                l.program_id().ok() == Some(id)
+                    && l.program().if_name().ok().as_deref() == Some(if_name)
+            });
+        if !attached {
+            attach_to_matching_iface(root_db, config, &program, if_name).await;
+        }
+    }
+
+    result
+}
+
+// Attaches a program loaded with an interface pattern to another interface
+// matching the pattern, with a link like the ones made by [`attach_program`].
+// Failures are reported with an AttachFailed event, since the program stays
+// attached to its other interfaces.
+async fn attach_to_matching_iface(root_db: &Db, config: &Config, program: &Program, if_name: &str) {
+    let result = async {
+        let pattern = program
+            .iface_pattern()?
+            .ok_or_else(|| BpfmanError::Error("program has no interface pattern".to_string()))?;
+        let attach = match program {
+            Program::Xdp(p) => AttachPoint::Xdp {
+                iface: if_name.to_string(),
+                priority: p.get_priority()?,
+                proceed_on: p.get_proceed_on()?,
+                netns: p.get_netns()?.map(|n| n.path),
+            },
+            Program::Tc(p) => AttachPoint::Tc {
+                iface: if_name.to_string(),
+                priority: p.get_priority()?,
+                direction: p.get_direction()?,
+                proceed_on: p.get_proceed_on()?,
+                netns: p.get_netns()?.map(|n| n.path),
+            },
+            _ => {
+                return Err(BpfmanError::InvalidAttach(format!(
+                    "{} program",
+                    program.kind()
+                )))
+            }
+        };
+
+        let mut link = Link::new(root_db, program, attach)?;
+        link.program_mut().set_iface_pattern(&pattern)?;
+        if let Err(e) = attach_link(root_db, config, program, &mut link).await {
+            let _ = link.delete(root_db);
+            return Err(e);
+        }
+        Ok((link, pattern))
+    }
+    .await;
+
+    match result {
+        Ok((link, pattern)) => {
+            info!(
+                "Attached {} program {} to interface {if_name} matching {pattern} with link id: {}",
+                program.kind(),
+                link.program_id().unwrap_or_default(),
+                link.id().unwrap_or_default()
+            );
+            emit_event(
+                ProgramEventType::Attached,
+                link.program(),
+                format!("interface {if_name} matches {pattern}"),
+            );
+        }
+        Err(e) => {
+            warn!("Unable to attach program to interface {if_name}: {e}");
+            emit_event(
+                ProgramEventType::AttachFailed,
+                program,
+                format!("interface {if_name}: {e}"),
+            );
+        }
+    }
+}
+
+/// Lists the XDP and TC dispatchers, either of every interface or of the
+/// interface with the given name, with the programs they run in order.
+pub async fn list_dispatchers(if_name: Option<&str>) -> Result<Vec<DispatcherState>, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    let mut dispatchers = vec![];
+    for name in root_db.tree_names() {
+        let name_str = bytes_to_string(&name);
+        if !name_str.starts_with(XDP_DISPATCHER_PREFIX)
+            && !name_str.starts_with(TC_DISPATCHER_PREFIX)
+        {
+            continue;
+        }
+
+        let tree = root_db
+            .open_tree(&name)
+            .expect("unable to open database tree");
+        let dispatcher = Dispatcher::new_from_db(tree);
+        let did = dispatcher.id()?;
+
+        let programs = get_programs_iter(root_db)
+            .map(|(_, p)| (p, None))
+            .chain(get_links(root_db).map(|l| {
+                let link_id = l.id().ok();
+                (l.program().clone(), link_id)
+            }))
+            .filter(|(p, _)| {
+                !p.iface_detached() && p.dispatcher_id().ok().flatten().as_ref() == Some(&did)
+            })
+            .collect();
+
+        let mut state = dispatcher.state(programs)?;
+        if if_name.is_none() || if_name == Some(state.if_name.as_str()) {
+            state.foreign = foreign_programs(&dispatcher).await;
+            dispatchers.push(state);
+        }
+    }
+
+    dispatchers.sort_by(|a, b| {
+        (
+            &a.if_name,
+            a.program_type as u32,
+            a.direction.map(|d| d as u32),
+        )
+            .cmp(&(
+                &b.if_name,
+                b.program_type as u32,
+                b.direction.map(|d| d as u32),
+            ))
+    });
+    Ok(dispatchers)
+}
+
+// Describes the programs attached to a dispatcher's hook by something other
+// than bpfman. Failing to look them up isn't fatal to listing the dispatcher.
+async fn foreign_programs(dispatcher: &Dispatcher) -> Vec<ForeignProgram> {
+    let foreign = match dispatcher.foreign().await {
+        Ok(foreign) => foreign,
+        Err(e) => {
+            warn!("Unable to get the foreign programs of a dispatcher: {e}");
+            return vec![];
+        }
+    };
+    if foreign.is_empty() {
+        return vec![];
+    }
+    let names: HashMap<u32, String> = loaded_programs()
+        .filter_map(|p| p.ok())
+        .filter_map(|p| Some((p.id(), p.name_as_str()?.to_string())))
+        .collect();
+    foreign
+        .into_iter()
+        .map(|(program_id, adopted)| ForeignProgram {
+            program_id,
+            name: names.get(&program_id).cloned().unwrap_or_default(),
+            adopted,
+        })
+        .collect()
+}
+
+/// Lists the currently loaded ebpf programs.
+pub async fn list_programs(filter: ListFilter) -> Result<Vec<Program>, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    debug!("BpfManager::list_programs()");
+
+    // Get an iterator for the bpfman load programs, a hash map indexed by program id.
+    let mut bpfman_progs: HashMap<u32, Program> = get_programs_iter(root_db).collect();
+
+    // Programs waiting to be restored aren't loaded in the kernel.
+    let staged = get_staged_programs_iter(root_db).map(|(_, p)| p);
+
+    // Call Aya to get ALL the loaded eBPF programs, and loop through each one.
+    Ok(loaded_programs()
+        .filter_map(|p| p.ok())
+        .map(|prog| {
+            let prog_id = prog.id();
+
+            // If the program was loaded by bpfman (check the hash map), then use it.
+            // Otherwise, convert the data returned from Aya into an Unsupported Program Object.
+            match bpfman_progs.remove(&prog_id) {
+                Some(p) => p.to_owned(),
+                None => {
+                    let db_tree = root_db
+                        .open_tree(prog_id.to_string())
+                        .expect("Unable to open program database tree for listing programs");
+
+                    let mut data = ProgramData::new_empty(db_tree);
+                    if let Err(e) = data.set_kernel_info(&prog) {
+                        warn!("Unable to set kernal info for prog {prog_id}, error: {e}");
+                    };
+
+                    Program::Unsupported(data)
+                }
+            }
+        })
+        .chain(staged)
+        .filter(|p| filter.matches(p))
+        .collect())
+}
+
+/// Fetches more information regarding a currently loaded ebpf program.
+pub async fn get_program(id: u32) -> Result<Program, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    debug!("Getting program with id: {id}");
+    // If the program was loaded by bpfman, then use it, including when it's
+    // waiting to be restored.
+    // Otherwise, call Aya to get ALL the loaded eBPF programs, and convert the data
+    // returned from Aya into an Unsupported Program Object.
+    match get(root_db, &id).or_else(|| get_staged(root_db, &id)) {
+        Some(mut p) => {
+            // Programs may have been attached to the interface by something
+            // else since this one was.
+            let dispatcher = match p.attached() && !p.iface_detached() {
+                true => p
+                    .dispatcher_id()?
+                    .and_then(|did| get_dispatcher(&did, root_db)),
+                false => None,
+            };
+            if let Some(dispatcher) = dispatcher {
+                let foreign = foreign_programs(&dispatcher).await;
+                let (adopted, attached): (Vec<_>, Vec<_>) =
+                    foreign.into_iter().partition(|f| f.adopted);
+                p.set_foreign_programs(
+                    &attached.iter().map(|f| f.program_id).collect::<Vec<_>>(),
+                    &adopted.iter().map(|f| f.program_id).collect::<Vec<_>>(),
+                );
+            }
+            Ok(p)
+        }
+        None => loaded_programs()
+            .find_map(|p| {
+                let prog = p.ok()?;
+                if prog.id() == id {
+                    let db_tree = root_db
+                        .open_tree(prog.id().to_string())
+                        .expect("Unable to open program database tree for listing programs");
+
+                    let mut data = ProgramData::new_empty(db_tree);
+                    data.set_kernel_info(&prog)
+                        .expect("unable to set kernel info");
+
+                    Some(Program::Unsupported(data))
+                } else {
+                    None
+                }
+            })
+            .ok_or(BpfmanError::ProgramNotFound(id)),
+    }
+}
+
+/// Returns information about a map used by a program loaded by bpfman.
+pub async fn get_map_info(id: u32, map_name: &str) -> Result<ProgramMap, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    Ok(open_map(root_db, id, map_name)?.info())
+}
+
+/// Returns information about a map used by a program loaded by bpfman, and
+/// all of its entries.
+pub async fn dump_map(id: u32, map_name: &str) -> Result<(ProgramMap, Vec<MapEntry>), BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    let map = open_map(root_db, id, map_name)?;
+    Ok((map.info(), map.entries()?))
+}
+
+/// Sets the value of an entry in a map used by a program loaded by bpfman,
+/// creating the entry if it doesn't exist. The key and value are given as
+/// JSON if the map has BTF for them, and as hex otherwise. A "0x" prefixed hex
+/// string is always accepted.
+pub async fn update_map_entry(
+    id: u32,
+    map_name: &str,
+    key: &str,
+    value: &str,
+) -> Result<(), BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    open_map(root_db, id, map_name)?.update(key, value)
+}
+
+/// Deletes an entry from a map used by a program loaded by bpfman. The key is
+/// given in the same way as for [`update_map_entry`].
+pub async fn delete_map_entry(id: u32, map_name: &str, key: &str) -> Result<(), BpfmanError> {
+    let (_, root_db) = &setup().await?;
+
+    open_map(root_db, id, map_name)?.delete(key)
+}
+
+/// Pulls an ebpf bytecode image from a remote OCI container registry, and
+/// returns the names of the programs in it.
+pub async fn pull_bytecode(image: BytecodeImage) -> Result<Vec<String>, BpfmanError> {
+    let (_, root_db) = &setup().await?;
+    let image_manager = &mut init_image_manager().await;
+
+    let (_, mut programs) = image_manager
+        .get_image(
+            root_db,
+            &image.image_url,
+            image.image_pull_policy.clone(),
+            image.username.clone(),
+            image.password.clone(),
+        )
+        .await?;
+    programs.sort();
+    Ok(programs)
+}
+
+/// Subscribes to lifecycle events for the programs managed by bpfman.
+///
+/// Only changes made by the current process are reported, so this is intended
+/// for long running processes such as bpfman-rpc. Slow receivers lose the
+/// oldest events once PROGRAM_EVENTS_CAPACITY events are buffered.
+pub fn watch_programs() -> broadcast::Receiver<ProgramEvent> {
+    PROGRAM_EVENTS.subscribe()
+}
+
+/// Loads the static programs defined in the bpfman static program directory
+/// (/etc/bpfman/programs.d).
+///
+/// Static programs which are already loaded are left untouched, so this can be
+/// called every time bpfman starts without creating duplicate programs.
+/// Previously loaded static programs whose definition has been changed or
+/// removed are unloaded. Failures are logged per program and do not prevent
+/// the remaining programs from being loaded.
+pub async fn load_static_programs() -> Result<(), BpfmanError> {
+    let static_programs = static_program::get_static_programs(CFGDIR_STATIC_PROGRAMS);
+
+    let loaded: Vec<(u32, String)> = {
+        let (_, root_db) = &setup().await?;
+        // Programs waiting to be restored are loaded again by bpfman, so they
+        // mustn't be loaded a second time.
+        get_programs_iter(root_db)
+            .chain(get_staged_programs_iter(root_db))
+            .filter_map(|(id, p)| {
+                p.get_data()
+                    .get_metadata()
+                    .ok()?
+                    .remove(STATIC_PROGRAM_METADATA_KEY)
+                    .map(|digest| (id, digest))
+            })
+            .collect()
+    };
+
+    for (id, digest) in loaded.iter() {
+        if !static_programs.iter().any(|(d, _)| d == digest) {
+            info!("Static program {id} is no longer defined, unloading it");
+            if let Err(e) = remove_program(*id).await {
+                warn!("Failed to unload static program {id}: {e}");
+            }
+        }
+    }
+
+    for (digest, program) in static_programs {
+        let name = program.get_data().get_name()?;
+        if let Some((id, _)) = loaded.iter().find(|(_, d)| *d == digest) {
+            debug!("Static program {name} is already loaded with id {id}");
+            continue;
+        }
+
+        match add_program(program, false).await {
+            Ok(p) => info!(
+                "Loaded static program {name} with id {}",
+                p.get_data().get_id()?
+            ),
+            Err(e) => warn!("Failed to load static program {name}: {e}"),
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn init_database(sled_config: SledConfig) -> Result<Db, BpfmanError> {
+    let database_config = open_config_file().database().to_owned().unwrap_or_default();
+    for _ in 0..=database_config.max_retries {
+        if let Ok(db) = sled_config.open() {
+            debug!("Successfully opened database");
+            return Ok(db);
+        } else {
+            info!(
+                "Database lock is already held, retrying after {} milliseconds",
+                database_config.millisec_delay
+            );
+            sleep(Duration::from_millis(database_config.millisec_delay)).await;
+        }
+    }
+    Err(BpfmanError::DatabaseLockError)
+}
+
+// Make sure to call init_image_manger if the command requires interaction with
+// an OCI based container registry. It should ONLY be used where needed, to
+// explicitly control when bpfman blocks for network calls to both sigstore's
+// cosign tuf registries and container registries.
+pub(crate) async fn init_image_manager() -> ImageManager {
+    let config = open_config_file();
+    ImageManager::new(config.signing().as_ref().map_or(true, |s| s.allow_unsigned))
+        .await
+        .expect("failed to initialize image manager")
+}
+
+fn get_dispatcher(id: &DispatcherId, root_db: &Db) -> Option<Dispatcher> {
+    let tree_name_prefix = match id {
+        DispatcherId::Xdp(DispatcherInfo(if_index, _, netns_id)) => {
+            format!(
+                "{}_{}_",
+                XDP_DISPATCHER_PREFIX,
+                if_key(*if_index, *netns_id)
+            )
+        }
+        DispatcherId::Tc(DispatcherInfo(if_index, Some(direction), netns_id)) => {
+            format!(
+                "{}_{}_{}_",
+                TC_DISPATCHER_PREFIX,
+                if_key(*if_index, *netns_id),
+                direction
+            )
+        }
+        _ => {
+            return None;
+        }
+    };
+
+    root_db
+        .tree_names()
+        .into_iter()
+        .find(|p| bytes_to_string(p).starts_with(&tree_name_prefix))
+        .map(|p| {
+            let tree = root_db.open_tree(p).expect("unable to open database tree");
+            Dispatcher::new_from_db(tree)
+        })
+}
+
+/// Returns the number of extension programs currently attached to the dispatcher that
+/// would be used to attach the provided [`Program`].
+fn num_attached_programs(did: &DispatcherId, root_db: &Db) -> usize {
+    if let Some(d) = get_dispatcher(did, root_db) {
+        d.num_extensions()
+    } else {
+        0
+    }
+}
+
+fn get(root_db: &Db, id: &u32) -> Option<Program> {
+    let prog_tree: sled::IVec = (PROGRAM_PREFIX.to_string() + &id.to_string())
+        .as_bytes()
+        .into();
+    if root_db.tree_names().contains(&prog_tree) {
+        let tree = root_db
+            .open_tree(prog_tree)
+            .expect("unable to open database tree");
+        Some(Program::new_from_db(*id, tree).expect("Failed to build program from database"))
+    } else {
+        None
+    }
+}
+
+// Returns a program staged by stage_restore() by the id it was last loaded
+// with.
+fn get_staged(root_db: &Db, id: &u32) -> Option<Program> {
+    let name = format!("{PROGRAM_RESTORE_PREFIX}{id}");
+    if root_db.tree_names().iter().any(|n| n == name.as_bytes()) {
+        let tree = root_db
+            .open_tree(name)
+            .expect("unable to open database tree");
+        Program::new_from_db(*id, tree).ok()
+    } else {
+        None
+    }
+}
+
+fn emit_event(event_type: ProgramEventType, program: &Program, message: String) {
+    // An error only means that there are no watchers.
+    let _ = PROGRAM_EVENTS.send(ProgramEvent::new(event_type, program, message));
+}
+
+// Emits a DispatcherRebuilt event for each of the attached programs in a
+// dispatcher that was just built. Programs which aren't attached yet are being
+// added and will get a Loaded event instead.
+fn emit_dispatcher_rebuilt(programs: &[Program]) {
+    for p in programs.iter().filter(|p| p.attached()) {
+        let message = format!(
+            "{} dispatcher rebuilt on {}",
+            p.kind(),
+            p.if_name().unwrap_or_default()
+        );
+        emit_event(ProgramEventType::DispatcherRebuilt, p, message);
+    }
+}
+
+// Returns the programs attached, or being attached, to the dispatcher. Links
+// to programs are returned as the program with the attach point of the link.
+// Programs detached from a removed interface are left out, since the index of
+// the interface may be reused.
+fn filter<'a>(root_db: &'a Db, did: &'a DispatcherId) -> impl Iterator<Item = Program> + 'a {
+    root_db
+        .tree_names()
+        .into_iter()
+        .filter_map(|p| {
+            let name = bytes_to_string(&p);
+            if name.starts_with(LINK_PREFIX) {
+                let tree = root_db.open_tree(p).expect("unable to open database tree");
+                return Link::new_from_db(tree).ok().map(|l| l.program().clone());
+            }
+            if !name.contains(PROGRAM_PREFIX) {
+                return None;
+            }
+            let id = name.split('_').last().unwrap().parse::<u32>().unwrap();
+            let tree = root_db.open_tree(p).expect("unable to open database tree");
+            Some(Program::new_from_db(id, tree).expect("Failed to build program from database"))
+        })
+        .filter(move |p| {
+            !p.iface_detached() && p.dispatcher_id().ok().flatten().as_ref() == Some(did)
+        })
+}
+
+// Adds a new program and sets the positions of programs that are to be attached via a dispatcher.
+// Positions are set based on order of priority, with disabled programs after
+// all of the enabled ones. Ties are broken based on:
+// - Already attached programs are preferred
+// - Program name. Lowest lexical order wins.
+// Sets the positions of programs that are to be attached via a dispatcher.
+// Positions are set based on order of priority, with disabled programs after
+// all of the enabled ones. Ties are broken based on:
+// - Already attached programs are preferred
+// - Program name. Lowest lexical order wins.
+fn set_program_positions(root_db: &Db, did: &DispatcherId) {
+    let mut extensions = filter(root_db, did).collect::<Vec<Program>>();
+
+    extensions.sort_by_key(|b| {
+        (
+            b.disabled(),
+            b.priority().unwrap(),
+            b.attached(),
+            b.get_data().get_name().unwrap().to_owned(),
+        )
+    });
+    for (i, v) in extensions.iter_mut().enumerate() {
+        v.set_position(i).expect("unable to set program position");
+    }
+}
+
+fn get_programs_iter(root_db: &Db) -> impl Iterator<Item = (u32, Program)> + '_ {
+    root_db
         .tree_names()
         .into_iter()
         .filter(|p| bytes_to_string(p).contains(PROGRAM_PREFIX))
@@ -398,88 +1486,687 @@ fn filter(
                 .parse::<u32>()
                 .unwrap();
             let tree = root_db.open_tree(p).expect("unable to open database tree");
-            Program::new_from_db(id, tree).expect("Failed to build program from database")
+            (
+                id,
+                Program::new_from_db(id, tree).expect("Failed to build program from database"),
+            )
         })
-        .filter(move |p| {
-            p.kind() == program_type
-                && p.if_index().unwrap() == if_index
-                && p.direction().unwrap() == direction
+}
+
+// Returns the programs staged by stage_restore(), by the id they were last
+// loaded with.
+fn get_staged_programs_iter(root_db: &Db) -> impl Iterator<Item = (u32, Program)> + '_ {
+    root_db.tree_names().into_iter().filter_map(|name| {
+        let id = bytes_to_string(&name)
+            .strip_prefix(PROGRAM_RESTORE_PREFIX)?
+            .parse::<u32>()
+            .ok()?;
+        let tree = root_db
+            .open_tree(name)
+            .expect("unable to open database tree");
+        Some((id, Program::new_from_db(id, tree).ok()?))
+    })
+}
+
+fn get_links(root_db: &Db) -> impl Iterator<Item = Link> + '_ {
+    root_db
+        .tree_names()
+        .into_iter()
+        .filter(|p| bytes_to_string(p).starts_with(LINK_PREFIX))
+        .filter_map(|p| {
+            let tree = root_db.open_tree(p).expect("unable to open database tree");
+            Link::new_from_db(tree).ok()
         })
 }
 
-// Adds a new program and sets the positions of programs that are to be attached via a dispatcher.
-// Positions are set based on order of priority. Ties are broken based on:
-// - Already attached programs are preferred
-// - Program name. Lowest lexical order wins.
-fn add_and_set_program_positions(root_db: &Db, program: Program) {
-    let program_type = program.kind();
-    let if_index = program.if_index().unwrap();
-    let direction = program.direction().unwrap();
+// Returns the XDP and TC programs and the links to them. Links are returned
+// as the program with the attach point of the link.
+fn get_dispatcher_programs(root_db: &Db) -> impl Iterator<Item = Program> + '_ {
+    get_programs_iter(root_db)
+        .map(|(_, p)| p)
+        .chain(get_links(root_db).map(|l| l.program().clone()))
+        .filter(|p| matches!(p, Program::Xdp(_) | Program::Tc(_)))
+}
+
+async fn setup() -> Result<(Config, Db), BpfmanError> {
+    initialize_bpfman()?;
+
+    let config = open_config_file();
+    let root_db = init_database(get_db_config()).await?;
+
+    // Whichever of bpfman-rpc or the CLI in local mode is the first to open
+    // the database after a reboot restores the programs lost with /run/bpfman.
+    if !Path::new(RTDIR_RESTORED).exists() {
+        restore_missing_programs(&root_db, &config).await;
+        File::create(RTDIR_RESTORED)?;
+    }
+
+    Ok((config, root_db))
+}
+
+/// Restores the programs in the database which are no longer loaded in the
+/// kernel, such as after a reboot.
+///
+/// This is done once per boot, the first time bpfman opens its database, so
+/// calling this is only needed to restore programs before anything else is
+/// done, such as when bpfman-rpc starts. Programs which couldn't be restored
+/// since the reboot stay staged and are tried again when this is called.
+pub async fn restore_programs() -> Result<(), BpfmanError> {
+    let restored = Path::new(RTDIR_RESTORED).exists();
+    let (config, root_db) = &setup().await?;
+    if restored {
+        restore_staged_programs(root_db, config).await;
+    }
+    Ok(())
+}
+
+// Programs are persisted in the database, but their pins and dispatchers live
+// under /run/bpfman, which doesn't survive a reboot. restore_missing_programs()
+// finds programs in the database which are no longer loaded in the kernel and
+// loads and attaches them again, rebuilding the XDP and TC dispatchers they
+// were attached to. Restored programs are assigned new kernel ids. Programs
+// which can't be restored stay staged, and XDP and TC programs wait there for
+// their interface to be added.
+async fn restore_missing_programs(root_db: &Db, config: &Config) {
+    // Map kernel program ids to their tags so that a program id which has been
+    // reused by the kernel isn't mistaken for a program loaded by bpfman.
+    let kernel_programs: HashMap<u32, String> = loaded_programs()
+        .filter_map(|p| p.ok())
+        .map(|p| (p.id(), format!("{:x}", p.tag())))
+        .collect();
+
+    for name in root_db.tree_names() {
+        let Some(id) = bytes_to_string(&name)
+            .strip_prefix(PROGRAM_PREFIX)
+            .and_then(|id| id.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let tree = root_db
+            .open_tree(name)
+            .expect("unable to open database tree");
+        let Ok(mut program) = Program::new_from_db(id, tree) else {
+            continue;
+        };
+        if is_program_loaded(&program, &kernel_programs) {
+            continue;
+        }
+
+        warn!(
+            "{} program {id} is no longer loaded, restoring it",
+            program.kind()
+        );
+        emit_event(
+            ProgramEventType::DetectedMissing,
+            &program,
+            "program is no longer loaded in the kernel".to_string(),
+        );
+        let event = ProgramEvent::new(ProgramEventType::AttachFailed, &program, String::new());
+        if let Err(e) = stage_restore(root_db, &mut program) {
+            restore_failed(root_db, &mut program, id, event, e);
+        }
+    }
+
+    restore_staged_programs(root_db, config).await;
+}
+
+// Restores the programs staged by restore_missing_programs(), except for XDP
+// and TC programs whose interface doesn't exist yet.
+async fn restore_staged_programs(root_db: &Db, config: &Config) {
+    let mut pending: Vec<Program> = get_staged_programs_iter(root_db)
+        .filter_map(|(_, mut program)| (!restore_waits_for_iface(&mut program)).then_some(program))
+        .collect();
+
+    if pending.is_empty() {
+        return;
+    }
+
+    remove_stale_dispatchers(root_db);
+
+    let mut image_manager = None;
+    // Maps the id a program was loaded with previously to its new id.
+    let mut restored: HashMap<u32, u32> = HashMap::new();
+
+    // Programs using another program's maps are restored after the map owner,
+    // so that they can be pointed at the new map pin path.
+    while !pending.is_empty() {
+        let pending_ids: Vec<u32> = pending
+            .iter()
+            .filter_map(|p| p.get_data().get_id().ok())
+            .collect();
+        let (ready, blocked): (Vec<Program>, Vec<Program>) =
+            pending
+                .into_iter()
+                .partition(|p| match p.get_data().get_map_owner_id() {
+                    Ok(Some(owner)) => !pending_ids.contains(&owner),
+                    _ => true,
+                });
+
+        if ready.is_empty() {
+            for mut p in blocked {
+                let old_id = p.get_data().get_id().unwrap_or_default();
+                let event = ProgramEvent::new(ProgramEventType::AttachFailed, &p, String::new());
+                let e = BpfmanError::Error("map owner could not be restored".to_string());
+                restore_failed(root_db, &mut p, old_id, event, e);
+            }
+            break;
+        }
+        pending = blocked;
+
+        let mut dispatchers: HashMap<DispatcherId, Vec<Program>> = HashMap::new();
+        for mut p in ready {
+            let old_id = p.get_data().get_id().unwrap_or_default();
+            let event = ProgramEvent::new(ProgramEventType::AttachFailed, &p, String::new());
+            let result = match p {
+                Program::Xdp(_) | Program::Tc(_) => match p.dispatcher_id() {
+                    Ok(Some(did)) => {
+                        dispatchers.entry(did).or_default().push(p);
+                        continue;
+                    }
+                    Ok(None) => Err(BpfmanError::DispatcherNotRequired),
+                    Err(e) => Err(e),
+                },
+                _ => restore_single_attach_program(root_db, &mut p, &restored),
+            };
+
+            match result {
+                Ok(new_id) => {
+                    restore_succeeded(&p, old_id, new_id);
+                    restored.insert(old_id, new_id);
+                }
+                Err(e) => restore_failed(root_db, &mut p, old_id, event, e),
+            }
+        }
+
+        for (did, mut programs) in dispatchers {
+            if image_manager.is_none() {
+                image_manager = Some(init_image_manager().await);
+            }
+            let old_ids: Vec<u32> = programs
+                .iter()
+                .map(|p| p.get_data().get_id().unwrap_or_default())
+                .collect();
+            let events: Vec<ProgramEvent> = programs
+                .iter()
+                .map(|p| ProgramEvent::new(ProgramEventType::AttachFailed, p, String::new()))
+                .collect();
+
+            match restore_multi_attach_programs(
+                root_db,
+                config,
+                &did,
+                &mut programs,
+                &restored,
+                image_manager.as_mut().unwrap(),
+            )
+            .await
+            {
+                Ok(new_ids) => {
+                    for ((p, old_id), new_id) in programs.iter().zip(old_ids).zip(new_ids) {
+                        restore_succeeded(p, old_id, new_id);
+                        restored.insert(old_id, new_id);
+                    }
+                }
+                Err(e) => {
+                    for ((p, old_id), event) in programs.iter_mut().zip(old_ids).zip(events) {
+                        let e = BpfmanError::Error(e.to_string());
+                        restore_failed(root_db, p, old_id, event, e);
+                    }
+                }
+            }
+        }
+    }
+
+    restore_links(root_db, config, &restored).await;
+}
+
+// Attaches the links of restored programs again. Links of programs which
+// couldn't be restored are removed.
+async fn restore_links(root_db: &Db, config: &Config, restored: &HashMap<u32, u32>) {
+    let links: Vec<Link> = root_db
+        .tree_names()
+        .into_iter()
+        .filter(|name| bytes_to_string(name).starts_with(LINK_RESTORE_PREFIX))
+        .filter_map(|name| {
+            let tree = root_db
+                .open_tree(name)
+                .expect("unable to open database tree");
+            Link::new_from_db(tree).ok()
+        })
+        .collect();
+
+    for mut link in links {
+        let result = async {
+            let link_id = link.id()?;
+            let old_id = link.program_id()?;
+            let new_id = *restored.get(&old_id).ok_or(BpfmanError::Error(format!(
+                "program {old_id} was not restored"
+            )))?;
+            link.set_program_id(new_id)?;
+            link.move_tree(root_db, &format!("{LINK_PREFIX}{link_id}"))?;
+            let program = get(root_db, &new_id).ok_or(BpfmanError::Error(format!(
+                "program {new_id} does not exist"
+            )))?;
+            attach_link(root_db, config, &program, &mut link).await
+        }
+        .await;
+
+        if let Err(e) = result {
+            error!("Unable to restore link: {e}");
+            if let Err(e) = link.delete(root_db) {
+                error!("Unable to remove link: {e}");
+            }
+        }
+    }
+}
+
+// A program is still loaded if its pin exists, or if the kernel still has a
+// program with the same id and tag.
+fn is_program_loaded(program: &Program, kernel_programs: &HashMap<u32, String>) -> bool {
+    let data = program.get_data();
+    let Ok(id) = data.get_id() else {
+        return false;
+    };
+
+    Path::new(&format!("{RTDIR_FS}/prog_{id}")).exists()
+        || match (kernel_programs.get(&id), data.get_kernel_tag()) {
+            (Some(kernel_tag), Ok(tag)) => *kernel_tag == tag,
+            _ => false,
+        }
+}
+
+// Moves a program which is no longer loaded out of the way of the programs
+// managed by bpfman, so that its old id can be reused by the kernel, and resets
+// the state which needs to be regenerated when the program is loaded again.
+fn stage_restore(root_db: &Db, program: &mut Program) -> Result<(), BpfmanError> {
+    let id = program.get_data().get_id()?;
+    program
+        .get_data_mut()
+        .move_tree(root_db, &format!("{PROGRAM_RESTORE_PREFIX}{id}"))?;
+
+    let data = program.get_data_mut();
+    data.clear_maps_used_by();
+    if data.get_map_owner_id()?.is_none() {
+        // This program owns its maps, so they will be recreated when it is
+        // restored.
+        data.clear_map_pin_path()?;
+        if get_map(id, root_db).is_some() {
+            root_db
+                .drop_tree(format!("{MAP_PREFIX}{id}"))
+                .expect("unable to drop maps tree");
+        }
+        let _ = remove_dir_all(calc_map_pin_path(id));
+    }
+
+    if let Program::Xdp(_) | Program::Tc(_) = program {
+        // Interfaces often come up after bpfman, so the interface is looked
+        // up when the program is restored, and the program waits for it if
+        // it isn't there yet.
+        program.set_detached();
+        program.set_iface_detached(true)?;
+    }
+
+    // The links of the program are attached again once it has been restored.
+    let links: Vec<Link> = get_links(root_db)
+        .filter(|l| l.program_id().ok() == Some(id))
+        .collect();
+    for mut link in links {
+        let link_id = link.id()?;
+        link.move_tree(root_db, &format!("{LINK_RESTORE_PREFIX}{link_id}"))?;
+        link.program_mut().set_detached();
+    }
+
+    Ok(())
+}
+
+// Prepares a staged program to be loaded by pointing it at the maps of its
+// map owner, which may have been restored with a new id, and moving it back to
+// where it's visible to the rest of bpfman.
+fn unstage_restore(
+    root_db: &Db,
+    program: &mut Program,
+    restored: &HashMap<u32, u32>,
+) -> Result<(), BpfmanError> {
+    let id = program.get_data().get_id()?;
+    let data = program.get_data_mut();
+    if let Some(owner) = data.get_map_owner_id()? {
+        let owner = restored.get(&owner).copied().unwrap_or(owner);
+        let map_pin_path = is_map_owner_id_valid(root_db, owner)?;
+        data.set_map_owner_id(owner)?;
+        data.set_map_pin_path(&map_pin_path)?;
+    }
+
+    data.move_tree(root_db, &format!("{PROGRAM_PRE_LOAD_PREFIX}{id}"))
+}
+
+// Persists a restored program under its new id.
+fn finish_restore(root_db: &Db, program: &mut Program, id: u32) -> Result<(), BpfmanError> {
+    let map_owner_id = program.get_data().get_map_owner_id()?;
+    save_map(root_db, program, id, map_owner_id)?;
+    program.get_data_mut().swap_tree(root_db, id)
+}
+
+fn restore_succeeded(program: &Program, old_id: u32, new_id: u32) {
+    info!("Restored program {old_id} with new id {new_id}");
+    emit_event(
+        ProgramEventType::Loaded,
+        program,
+        format!("restored program previously loaded with id {old_id}"),
+    );
+}
+
+// Returns true if a staged XDP or TC program is waiting for its interface to
+// be added. The interface index is looked up again once it is, since it may
+// have changed since the program was attached.
+fn restore_waits_for_iface(program: &mut Program) -> bool {
+    if !program.iface_detached() {
+        return false;
+    }
+    let if_index = program
+        .netns()
+        .and_then(|netns| with_netns(netns.as_ref(), || get_ifindex(&program.if_name()?)));
+    let attached = if_index.and_then(|if_index| {
+        program.set_if_index(if_index)?;
+        program.set_iface_detached(false)
+    });
+    match attached {
+        Ok(()) => false,
+        Err(_) => {
+            debug!(
+                "Restoring program {} when its interface is added",
+                program.get_data().get_id().unwrap_or_default()
+            );
+            true
+        }
+    }
+}
+
+// The event is a snapshot of the program taken before the restore was
+// attempted, since a failed load may have already changed the program's data.
+// The program is staged again to be retried.
+fn restore_failed(
+    root_db: &Db,
+    program: &mut Program,
+    old_id: u32,
+    mut event: ProgramEvent,
+    e: BpfmanError,
+) {
+    error!("Unable to restore program {old_id}: {e}");
+    event.message = e.to_string();
+    let _ = PROGRAM_EVENTS.send(event);
+    if let Err(e) = requeue_restore(root_db, program, old_id) {
+        error!("Unable to stage program {old_id} to be restored again: {e}");
+    }
+}
+
+// Undoes a failed attempt to restore a program, removing whatever it pinned
+// under the new id it was loaded with, and stages the program again under its
+// old id.
+fn requeue_restore(root_db: &Db, program: &mut Program, old_id: u32) -> Result<(), BpfmanError> {
+    let data = program.get_data_mut();
+    let id = data.get_id()?;
+    if id != old_id {
+        let _ = remove_file(format!("{RTDIR_FS}/prog_{id}"));
+        let _ = remove_file(format!("{RTDIR_FS}/prog_{id}_link"));
+        if data.get_map_owner_id()?.is_none() {
+            let _ = remove_dir_all(calc_map_pin_path(id));
+            data.clear_map_pin_path()?;
+        }
+        data.set_id(old_id)?;
+    }
+
+    // A program which failed before it was unstaged is still staged.
+    let name = format!("{PROGRAM_RESTORE_PREFIX}{old_id}");
+    if root_db.tree_names().iter().any(|n| n == name.as_bytes()) {
+        return Ok(());
+    }
+    data.move_tree(root_db, &name)
+}
+
+fn restore_single_attach_program(
+    root_db: &Db,
+    program: &mut Program,
+    restored: &HashMap<u32, u32>,
+) -> Result<u32, BpfmanError> {
+    unstage_restore(root_db, program, restored)?;
+    let id = add_single_attach_program(root_db, program)?;
+    finish_restore(root_db, program, id)?;
+    Ok(id)
+}
+
+async fn restore_multi_attach_programs(
+    root_db: &Db,
+    config: &Config,
+    did: &DispatcherId,
+    programs: &mut [Program],
+    restored: &HashMap<u32, u32>,
+    image_manager: &mut ImageManager,
+) -> Result<Vec<u32>, BpfmanError> {
+    for p in programs.iter_mut() {
+        unstage_restore(root_db, p, restored)?;
+    }
+
+    let p = programs
+        .first()
+        .ok_or_else(|| BpfmanError::Error("No programs to restore".to_string()))?;
+    let if_name = p.if_name()?;
+
+    set_program_positions(root_db, did);
+    let mut extensions: Vec<Program> = filter(root_db, did).collect();
+
+    let if_config = config.interfaces().as_ref().and_then(|i| i.get(&if_name));
+    let old_dispatcher = get_dispatcher(did, root_db);
+    let next_revision = old_dispatcher.as_ref().map_or(1, |old| old.next_revision());
+
+    Dispatcher::new(
+        root_db,
+        if_config,
+        &mut extensions,
+        next_revision,
+        old_dispatcher,
+        image_manager,
+        config.libxdp(),
+    )
+    .await?;
+
+    programs.iter_mut().for_each(|p| p.set_attached());
+    emit_dispatcher_rebuilt(&extensions);
+
+    let mut ids = Vec::new();
+    for p in programs.iter_mut() {
+        let id = p.get_data().get_id()?;
+        finish_restore(root_db, p, id)?;
+        ids.push(id);
+    }
+    Ok(ids)
+}
+
+// Removes the database entries of dispatchers whose bpffs state no longer
+// exists.
+fn remove_stale_dispatchers(root_db: &Db) {
+    for name in root_db.tree_names() {
+        let name_str = bytes_to_string(&name);
+        if !name_str.starts_with(XDP_DISPATCHER_PREFIX)
+            && !name_str.starts_with(TC_DISPATCHER_PREFIX)
+        {
+            continue;
+        }
+
+        let tree = root_db
+            .open_tree(&name)
+            .expect("unable to open database tree");
+        if let Ok(false) = Dispatcher::new_from_db(tree).is_pinned() {
+            debug!("Removing stale dispatcher {name_str}");
+            root_db
+                .drop_tree(name)
+                .expect("unable to drop dispatcher tree");
+        }
+    }
+}
+
+// Checks that a replacement program attaches to the same place and uses the
+// same maps as the program it replaces.
+fn validate_update(old: &Program, new: &Program, map_index: u32) -> Result<(), BpfmanError> {
+    let id = old.get_data().get_id()?;
+
+    if let Program::Unsupported(_) = new {
+        return Err(BpfmanError::UnsupportedProgramType);
+    }
+
+    if old.kind() != new.kind() {
+        return Err(BpfmanError::InvalidUpdate(
+            id,
+            format!(
+                "replacement is a {} program, expected {}",
+                new.kind(),
+                old.kind()
+            ),
+        ));
+    }
+
+    if let Program::Xdp(_) | Program::Tc(_) = old {
+        if old.if_name()? != new.if_name()? {
+            return Err(BpfmanError::InvalidUpdate(
+                id,
+                format!(
+                    "replacement must be attached to interface {}",
+                    old.if_name()?
+                ),
+            ));
+        }
+        if old.direction()? != new.direction()? {
+            return Err(BpfmanError::InvalidUpdate(
+                id,
+                "replacement must be attached in the same direction".to_string(),
+            ));
+        }
+    }
+
+    match new.get_data().get_map_owner_id()? {
+        Some(owner) if owner != map_index => Err(BpfmanError::InvalidUpdate(
+            id,
+            format!("replacement must use the maps of program {map_index}"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Verifies the BPF Function Name of an XDP or TC program is valid. The actual
+// load is performed in the XDP or TC logic, so maps aren't pinned here.
+fn check_extension_name(program: &Program) -> Result<(), BpfmanError> {
+    let name = &program.get_data().get_name()?;
+
+    let mut ext_loader = BpfLoader::new()
+        .allow_unsupported_maps()
+        .extension(name)
+        .load(&program.get_data().get_program_bytes()?)?;
 
-    let mut extensions =
-        filter(root_db, program_type, if_index, direction).collect::<Vec<Program>>();
+    match ext_loader.program_mut(name) {
+        Some(_) => Ok(()),
+        None => Err(BpfmanError::BpfFunctionNameNotValid(name.to_owned())),
+    }
+}
 
-    extensions.sort_by_key(|b| {
-        (
-            b.priority().unwrap(),
-            b.attached(),
-            b.get_data().get_name().unwrap().to_owned(),
-        )
-    });
-    for (i, v) in extensions.iter_mut().enumerate() {
-        v.set_position(i).expect("unable to set program position");
+// An XDP dispatcher is loaded either with frags support or without it, so all
+// of the programs it runs have to agree.
+fn check_xdp_frags(
+    program: &Program,
+    others: impl Iterator<Item = Program>,
+) -> Result<(), BpfmanError> {
+    let Program::Xdp(p) = program else {
+        return Ok(());
+    };
+    let frags = p.get_frags()?;
+    for other in others {
+        if let Program::Xdp(o) = other {
+            if o.get_frags()? != frags {
+                return Err(BpfmanError::XdpFragsMismatch(p.get_iface()?));
+            }
+        }
     }
+    Ok(())
 }
 
-// Sets the positions of programs that are to be attached via a dispatcher.
-// Positions are set based on order of priority. Ties are broken based on:
-// - Already attached programs are preferred
-// - Program name. Lowest lexical order wins.
-fn set_program_positions(
+// XDP programs on an interface share a dispatcher, so a mode given when one of
+// them is loaded has to match the other programs and the mode the dispatcher
+// is attached in. A dispatcher which fell back to skb mode satisfies requests
+// which allow the fallback.
+fn check_xdp_mode(
     root_db: &Db,
-    program_type: ProgramType,
-    if_index: u32,
-    direction: Option<Direction>,
-) {
-    let mut extensions =
-        filter(root_db, program_type, Some(if_index), direction).collect::<Vec<Program>>();
+    did: &DispatcherId,
+    program: &Program,
+    others: impl Iterator<Item = Program>,
+) -> Result<(), BpfmanError> {
+    let Program::Xdp(p) = program else {
+        return Ok(());
+    };
+    let Some(mode) = p.get_mode()? else {
+        return Ok(());
+    };
+    for other in others {
+        if let Program::Xdp(o) = other {
+            if o.get_mode()?.is_some_and(|m| m != mode) {
+                return Err(BpfmanError::XdpModeMismatch(p.get_iface()?));
+            }
+        }
+    }
+    if let Some(Dispatcher::Xdp(d)) = get_dispatcher(did, root_db) {
+        let current = d.get_mode()?;
+        if current != mode && !(p.get_mode_fallback()? && current == XdpMode::Skb) {
+            return Err(BpfmanError::XdpModeMismatch(p.get_iface()?));
+        }
+    }
+    Ok(())
+}
 
-    extensions.sort_by_key(|b| {
-        (
-            b.priority().unwrap(),
-            b.attached(),
-            b.get_data().get_name().unwrap().to_owned(),
-        )
-    });
-    for (i, v) in extensions.iter_mut().enumerate() {
-        v.set_position(i).expect("unable to set program position");
+// Marks XDP programs in an "xdp.frags" section as supporting frags, as the
+// kernel would for a program attached directly.
+fn detect_xdp_frags(program: &mut Program) -> Result<(), BpfmanError> {
+    if let Program::Xdp(p) = program {
+        if !p.get_frags()? {
+            let frags = xdp_frags(
+                &p.get_data().get_program_bytes()?,
+                &p.get_data().get_name()?,
+            )?;
+            p.set_frags(frags)?;
+        }
     }
+    Ok(())
 }
 
-fn get_programs_iter(root_db: &Db) -> impl Iterator<Item = (u32, Program)> + '_ {
-    root_db
-        .tree_names()
-        .into_iter()
-        .filter(|p| bytes_to_string(p).contains(PROGRAM_PREFIX))
-        .map(|p| {
-            let id = bytes_to_string(&p)
-                .split('_')
-                .last()
-                .unwrap()
-                .parse::<u32>()
-                .unwrap();
-            let tree = root_db.open_tree(p).expect("unable to open database tree");
-            (
-                id,
-                Program::new_from_db(id, tree).expect("Failed to build program from database"),
-            )
-        })
+// Takes the priority and proceed-on actions of an XDP program loaded with
+// use_run_config from the XDP_RUN_CONFIG libxdp reads, if it has one.
+fn apply_xdp_run_config(program: &mut Program) -> Result<(), BpfmanError> {
+    if let Program::Xdp(p) = program {
+        if !p.get_use_run_config()? || p.get_attached()? {
+            return Ok(());
+        }
+        let Some(run_config) = multiprog::libxdp::run_config(
+            &p.get_data().get_program_bytes()?,
+            &p.get_data().get_name()?,
+        )?
+        else {
+            return Ok(());
+        };
+        if let Some(priority) = run_config.priority {
+            p.set_priority(priority)?;
+        }
+        if let Some(proceed_on) = run_config.proceed_on {
+            p.set_proceed_on(proceed_on)?;
+        }
+    }
+    Ok(())
 }
 
-async fn setup() -> Result<(Config, Db), BpfmanError> {
-    initialize_bpfman()?;
+// Returns true if the program is in an "xdp.frags" section.
+fn xdp_frags(bytes: &[u8], name: &str) -> Result<bool, BpfmanError> {
+    let obj = aya_obj::Object::parse(bytes)
+        .map_err(|e| BpfmanError::Error(format!("unable to parse program bytes: {e}")))?;
 
-    Ok((open_config_file(), init_database(get_db_config()).await?))
+    match obj.programs.get(name).map(|p| &p.section) {
+        Some(ProgramSection::Xdp { frags, .. }) => Ok(*frags),
+        _ => Ok(false),
+    }
 }
 
 async fn add_multi_attach_program(
@@ -489,41 +2176,32 @@ async fn add_multi_attach_program(
     config: &Config,
 ) -> Result<u32, BpfmanError> {
     debug!("BpfManager::add_multi_attach_program()");
-    let name = &program.get_data().get_name()?;
-
-    // This load is just to verify the BPF Function Name is valid.
-    // The actual load is performed in the XDP or TC logic.
-    // don't pin maps here.
-    let mut ext_loader = BpfLoader::new()
-        .allow_unsupported_maps()
-        .extension(name)
-        .load(&program.get_data().get_program_bytes()?)?;
-
-    match ext_loader.program_mut(name) {
-        Some(_) => Ok(()),
-        None => Err(BpfmanError::BpfFunctionNameNotValid(name.to_owned())),
-    }?;
+    check_extension_name(program)?;
 
     let did = program
         .dispatcher_id()?
         .ok_or(BpfmanError::DispatcherNotRequired)?;
-
+    detect_xdp_frags(program)?;
+    apply_xdp_run_config(program)?;
+    check_xdp_frags(program, filter(root_db, &did))?;
+    check_xdp_mode(root_db, &did, program, filter(root_db, &did))?;
+
+    let max_programs = match program.kind() {
+        ProgramType::Tc => TC_MAX_PROGRAMS,
+        _ => XDP_MAX_PROGRAMS,
+    };
     let next_available_id = num_attached_programs(&did, root_db);
-    if next_available_id >= 10 {
+    if next_available_id >= max_programs {
         return Err(BpfmanError::TooManyPrograms);
     }
 
     debug!("next_available_id={next_available_id}");
 
-    let program_type = program.kind();
-    let if_index = program.if_index()?;
     let if_name = program.if_name().unwrap().to_string();
-    let direction = program.direction()?;
 
-    add_and_set_program_positions(root_db, program.clone());
+    set_program_positions(root_db, &did);
 
-    let mut programs: Vec<Program> =
-        filter(root_db, program_type, if_index, direction).collect::<Vec<Program>>();
+    let mut programs: Vec<Program> = filter(root_db, &did).collect::<Vec<Program>>();
 
     let old_dispatcher = get_dispatcher(&did, root_db);
 
@@ -545,6 +2223,7 @@ async fn add_multi_attach_program(
         next_revision,
         old_dispatcher,
         image_manager,
+        config.libxdp(),
     )
     .await
     .or_else(|e| {
@@ -557,22 +2236,244 @@ async fn add_multi_attach_program(
         Err(e)
     })?;
 
+    emit_dispatcher_rebuilt(&programs);
+
+    let id = program.get_data().get_id()?;
+    program.set_attached();
+
+    Ok(id)
+}
+
+// Builds a new revision of the dispatcher the old program is attached to, with
+// the replacement program in its place. The old dispatcher is only detached
+// once the new revision has been attached.
+async fn replace_multi_attach_program(
+    root_db: &Db,
+    old: &Program,
+    program: &mut Program,
+    image_manager: &mut ImageManager,
+    config: &Config,
+) -> Result<u32, BpfmanError> {
+    debug!("BpfManager::replace_multi_attach_program()");
+    check_extension_name(program)?;
+
+    let did = old
+        .dispatcher_id()?
+        .ok_or(BpfmanError::DispatcherNotRequired)?;
+    let old_id = old.get_data().get_id()?;
+    detect_xdp_frags(program)?;
+    apply_xdp_run_config(program)?;
+    check_xdp_frags(
+        program,
+        filter(root_db, &did)
+            .filter(|p| !(p.attached() && p.get_data().get_id().ok() == Some(old_id))),
+    )?;
+    check_xdp_mode(
+        root_db,
+        &did,
+        program,
+        filter(root_db, &did)
+            .filter(|p| !(p.attached() && p.get_data().get_id().ok() == Some(old_id))),
+    )?;
+
+    program.set_position(old.position()?.ok_or_else(|| {
+        BpfmanError::InvalidUpdate(old_id, "the program has no position".to_string())
+    })?)?;
+
+    let if_name = program.if_name()?;
+
+    let mut programs: Vec<Program> = filter(root_db, &did)
+        .filter(|p| !(p.attached() && p.get_data().get_id().ok() == Some(old_id)))
+        .collect();
+
+    let old_dispatcher = get_dispatcher(&did, root_db);
+    let if_config = config.interfaces().as_ref().and_then(|i| i.get(&if_name));
+    let next_revision = old_dispatcher.as_ref().map_or(1, |old| old.next_revision());
+
+    Dispatcher::new(
+        root_db,
+        if_config,
+        &mut programs,
+        next_revision,
+        old_dispatcher,
+        image_manager,
+        config.libxdp(),
+    )
+    .await?;
+
+    emit_dispatcher_rebuilt(&programs);
+
     let id = program.get_data().get_id()?;
     program.set_attached();
 
     Ok(id)
 }
 
+/// Runs bpfman-ns to attach the program pinned in the given arguments from
+/// within the container's mount namespace.
+fn attach_in_container(
+    program_type: &str,
+    args: Vec<String>,
+    container_pid: i32,
+) -> Result<(), BpfmanError> {
+    // Figure out where the bpfman-ns binary is located
+    let bpfman_ns_path = if Path::new("./target/debug/bpfman-ns").exists() {
+        // If we're running natively from the bpfman
+        // directory, use the binary in the target/debug
+        // directory
+        "./target/debug/bpfman-ns"
+    } else if Path::new("./bpfman-ns").exists() {
+        // If we're running on kubernetes, the bpfman-ns
+        // binary will be in the current directory
+        "./bpfman-ns"
+    } else {
+        // look for bpfman-ns in the PATH
+        "bpfman-ns"
+    };
+
+    let output = std::process::Command::new(bpfman_ns_path)
+        .args(args)
+        .output();
+
+    match output {
+        Ok(o) => {
+            if !o.status.success() {
+                let reason = get_error_msg_from_stderr(&o.stderr);
+                info!("Error from bpfman-ns: {:?}", reason);
+                return Err(BpfmanError::ContainerAttachError {
+                    program_type: program_type.to_string(),
+                    container_pid,
+                    reason,
+                });
+            };
+        }
+        Err(e) => {
+            info!("bpfman-ns returned error: {:?}", e);
+            return Err(BpfmanError::ContainerAttachError {
+                program_type: program_type.to_string(),
+                container_pid,
+                reason: e.to_string(),
+            });
+        }
+    };
+
+    Ok(())
+}
+
+// Loads a program into the kernel to have it checked by the verifier, without
+// attaching it or pinning anything. XDP and TC programs are loaded on their
+// own rather than as extensions of a dispatcher. The program is unloaded
+// again when the loader is dropped.
+fn verify_program(p: &mut Program) -> Result<(), BpfmanError> {
+    debug!("BpfManager::verify_program()");
+    let name = &p.get_data().get_name()?;
+    let mut bpf = BpfLoader::new();
+    bpf.verifier_log_level(p.get_data().verifier_log_level()?);
+
+    let data = &p.get_data().get_global_data()?;
+    for (key, value) in data {
+        bpf.set_global(key, value.as_slice(), true);
+    }
+
+    if let Some(map_pin_path) = p.get_data().get_map_pin_path()? {
+        bpf.map_pin_path(map_pin_path);
+    }
+
+    let mut loader = bpf
+        .allow_unsupported_maps()
+        .load(&p.get_data().get_program_bytes()?)?;
+
+    let raw_program = loader
+        .program_mut(name)
+        .ok_or(BpfmanError::BpfFunctionNameNotValid(name.to_owned()))?;
+
+    let info = match p {
+        Program::Xdp(_) => {
+            let xdp: &mut Xdp = raw_program.try_into()?;
+            xdp.load()?;
+            xdp.info()?
+        }
+        Program::Tc(_) => {
+            let classifier: &mut SchedClassifier = raw_program.try_into()?;
+            classifier.load()?;
+            classifier.info()?
+        }
+        Program::Tracepoint(_) => {
+            let tracepoint: &mut TracePoint = raw_program.try_into()?;
+            tracepoint.load()?;
+            tracepoint.info()?
+        }
+        Program::Kprobe(_) => {
+            let kprobe: &mut KProbe = raw_program.try_into()?;
+            kprobe.load()?;
+            kprobe.info()?
+        }
+        Program::Uprobe(_) => {
+            let uprobe: &mut UProbe = raw_program.try_into()?;
+            uprobe.load()?;
+            uprobe.info()?
+        }
+        Program::Fentry(ref program) => {
+            let fentry: &mut FEntry = raw_program.try_into()?;
+            fentry.load(&program.get_fn_name()?, &Btf::from_sys_fs()?)?;
+            fentry.info()?
+        }
+        Program::Fexit(ref program) => {
+            let fexit: &mut FExit = raw_program.try_into()?;
+            fexit.load(&program.get_fn_name()?, &Btf::from_sys_fs()?)?;
+            fexit.info()?
+        }
+        Program::CgroupSkb(_) => {
+            let cgroup_skb: &mut CgroupSkb = raw_program.try_into()?;
+            cgroup_skb.load()?;
+            cgroup_skb.info()?
+        }
+        Program::CgroupSockAddr(_) => {
+            let sock_addr: &mut CgroupSockAddr = raw_program.try_into()?;
+            sock_addr.load()?;
+            sock_addr.info()?
+        }
+        Program::SockOps(_) => {
+            let sock_ops: &mut SockOps = raw_program.try_into()?;
+            sock_ops.load()?;
+            sock_ops.info()?
+        }
+        Program::SkMsg(_) => {
+            let sk_msg: &mut SkMsg = raw_program.try_into()?;
+            sk_msg.load()?;
+            sk_msg.info()?
+        }
+        Program::Unsupported(_) => return Err(BpfmanError::UnsupportedProgramType),
+    };
+
+    p.get_data_mut().set_kernel_info(&info)
+}
+
 pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result<u32, BpfmanError> {
     debug!("BpfManager::add_single_attach_program()");
     let name = &p.get_data().get_name()?;
     let mut bpf = BpfLoader::new();
+    bpf.verifier_log_level(p.get_data().verifier_log_level()?);
 
     let data = &p.get_data().get_global_data()?;
     for (key, value) in data {
         bpf.set_global(key, value.as_slice(), true);
     }
 
+    // Kprobes see every process on the host, so a kprobe loaded for a
+    // container is given the container's cgroup id to filter on.
+    let container_pid = match p {
+        Program::Kprobe(ref program) => program.get_container_pid()?,
+        _ => None,
+    };
+    let container_cgroup_id = match container_pid {
+        Some(pid) => Some(get_cgroup_id(pid)?.to_ne_bytes()),
+        None => None,
+    };
+    if let Some(cgroup_id) = &container_cgroup_id {
+        bpf.set_global(CONTAINER_CGROUP_ID_GLOBAL, cgroup_id.as_slice(), true);
+    }
+
     // If map_pin_path is set already it means we need to use a pin
     // path which should already exist on the system.
     if let Some(map_pin_path) = p.get_data().get_map_pin_path()? {
@@ -583,9 +2484,41 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
         bpf.map_pin_path(map_pin_path);
     }
 
-    let mut loader = bpf
-        .allow_unsupported_maps()
-        .load(&p.get_data().get_program_bytes()?)?;
+    let program_bytes = p.get_data().get_program_bytes()?;
+    if let Some(container_pid) = container_pid {
+        if !reads_container_cgroup_id(&program_bytes, name)? {
+            return Err(BpfmanError::ContainerAttachError {
+                program_type: "kprobe".to_string(),
+                container_pid,
+                reason: format!("the program doesn't read the {CONTAINER_CGROUP_ID_GLOBAL} global"),
+            });
+        }
+    }
+
+    let mut loader = bpf.allow_unsupported_maps().load(&program_bytes)?;
+
+    // sk_msg programs are attached to one of the maps they use, so look it up
+    // before the program is borrowed from the loader. Programs sharing another
+    // program's maps use the pinned map.
+    let sock_map = match p {
+        Program::SkMsg(ref program) => {
+            let map_name = program.get_map_name()?;
+            match (
+                program.get_data().get_map_pin_path()?,
+                loader.map(&map_name),
+            ) {
+                (Some(map_pin_path), _) => Some(
+                    sys::obj_get(&map_pin_path.join(&map_name))
+                        .map_err(|_| BpfmanError::InvalidAttach(map_name))?,
+                ),
+                (None, Some(Map::SockMap(map))) | (None, Some(Map::SockHash(map))) => {
+                    Some(map.fd().as_fd().try_clone_to_owned()?)
+                }
+                _ => return Err(BpfmanError::InvalidAttach(map_name)),
+            }
+        }
+        _ => None,
+    };
 
     let raw_program = loader
         .program_mut(name)
@@ -657,6 +2590,13 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
 
             let id = program.data.get_id()?;
 
+            kprobe
+                .pin(format!("{RTDIR_FS}/prog_{}", id))
+                .map_err(BpfmanError::UnableToPinProgram)?;
+
+            // Kernel functions are the same in every mount namespace, so a
+            // kprobe for a container is attached from bpfman's own. The
+            // program filters on the container's cgroup id instead.
             let link_id = kprobe.attach(program.get_fn_name()?, program.get_offset()?)?;
 
             let owned_link: KProbeLink = kprobe.take_link(link_id)?;
@@ -668,10 +2608,6 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
                 .pin(format!("{RTDIR_FS}/prog_{}_link", id))
                 .map_err(BpfmanError::UnableToPinLink)?;
 
-            kprobe
-                .pin(format!("{RTDIR_FS}/prog_{}", id))
-                .map_err(BpfmanError::UnableToPinProgram)?;
-
             Ok(id)
         }
         Program::Uprobe(ref mut program) => {
@@ -751,47 +2687,7 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
                     }
 
                     debug!("calling bpfman-ns to attach uprobe in pid: {:?}", p);
-
-                    // Figure out where the bpfman-ns binary is located
-                    let bpfman_ns_path = if Path::new("./target/debug/bpfman-ns").exists() {
-                        // If we're running natively from the bpfman
-                        // directory, use the binary in the target/debug
-                        // directory
-                        "./target/debug/bpfman-ns"
-                    } else if Path::new("./bpfman-ns").exists() {
-                        // If we're running on kubernetes, the bpfman-ns
-                        // binary will be in the current directory
-                        "./bpfman-ns"
-                    } else {
-                        // look for bpfman-ns in the PATH
-                        "bpfman-ns"
-                    };
-
-                    let output = std::process::Command::new(bpfman_ns_path)
-                        .args(prog_args)
-                        .output();
-
-                    match output {
-                        Ok(o) => {
-                            if !o.status.success() {
-                                info!(
-                                    "Error from bpfman-ns: {:?}",
-                                    get_error_msg_from_stderr(&o.stderr)
-                                );
-                                return Err(BpfmanError::ContainerAttachError {
-                                    program_type: "uprobe".to_string(),
-                                    container_pid: program.get_container_pid()?.unwrap(),
-                                });
-                            };
-                        }
-                        Err(e) => {
-                            info!("bpfman-ns returned error: {:?}", e);
-                            return Err(BpfmanError::ContainerAttachError {
-                                program_type: "uprobe".to_string(),
-                                container_pid: program.get_container_pid()?.unwrap(),
-                            });
-                        }
-                    };
+                    attach_in_container("uprobe", prog_args, p)?;
                 }
             };
 
@@ -843,6 +2739,112 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
 
             Ok(id)
         }
+        Program::CgroupSkb(ref mut program) => {
+            let cgroup_path = program.get_cgroup_path()?;
+            let attach_type = match program.get_direction()? {
+                Direction::Ingress => bpf_attach_type::BPF_CGROUP_INET_INGRESS,
+                Direction::Egress => bpf_attach_type::BPF_CGROUP_INET_EGRESS,
+            };
+
+            let cgroup_skb: &mut CgroupSkb = raw_program.try_into()?;
+            cgroup_skb.load()?;
+            program
+                .get_data_mut()
+                .set_kernel_info(&cgroup_skb.info()?)?;
+
+            let id = program.data.get_id()?;
+
+            attach_to_cgroup(
+                cgroup_skb.fd()?.as_fd(),
+                &cgroup_path,
+                attach_type,
+                Path::new(&format!("{RTDIR_FS}/prog_{id}_link")),
+            )?;
+
+            cgroup_skb
+                .pin(format!("{RTDIR_FS}/prog_{}", id))
+                .map_err(BpfmanError::UnableToPinProgram)?;
+
+            Ok(id)
+        }
+        Program::CgroupSockAddr(ref mut program) => {
+            let cgroup_path = program.get_cgroup_path()?;
+            let (attach_type, attach_type_name) =
+                sock_addr_attach_type(&program.get_data().get_program_bytes()?, name)?;
+
+            let sock_addr: &mut CgroupSockAddr = raw_program.try_into()?;
+            sock_addr.load()?;
+            program.get_data_mut().set_kernel_info(&sock_addr.info()?)?;
+            program.set_attach_type(attach_type_name.to_string())?;
+
+            let id = program.data.get_id()?;
+
+            attach_to_cgroup(
+                sock_addr.fd()?.as_fd(),
+                &cgroup_path,
+                attach_type,
+                Path::new(&format!("{RTDIR_FS}/prog_{id}_link")),
+            )?;
+
+            sock_addr
+                .pin(format!("{RTDIR_FS}/prog_{}", id))
+                .map_err(BpfmanError::UnableToPinProgram)?;
+
+            Ok(id)
+        }
+        Program::SockOps(ref mut program) => {
+            let cgroup_path = program.get_cgroup_path()?;
+
+            let sock_ops: &mut SockOps = raw_program.try_into()?;
+            sock_ops.load()?;
+            program.get_data_mut().set_kernel_info(&sock_ops.info()?)?;
+
+            let id = program.data.get_id()?;
+
+            attach_to_cgroup(
+                sock_ops.fd()?.as_fd(),
+                &cgroup_path,
+                bpf_attach_type::BPF_CGROUP_SOCK_OPS,
+                Path::new(&format!("{RTDIR_FS}/prog_{id}_link")),
+            )?;
+
+            sock_ops
+                .pin(format!("{RTDIR_FS}/prog_{}", id))
+                .map_err(BpfmanError::UnableToPinProgram)?;
+
+            Ok(id)
+        }
+        Program::SkMsg(ref mut program) => {
+            let sk_msg: &mut SkMsg = raw_program.try_into()?;
+            sk_msg.load()?;
+            program.get_data_mut().set_kernel_info(&sk_msg.info()?)?;
+
+            let id = program.data.get_id()?;
+
+            // The map holds a reference to the program until it's detached
+            // in Program::delete, so there's no link to pin.
+            let sock_map = sock_map.ok_or_else(|| BpfmanError::MapNotFound {
+                program_id: id,
+                map_name: program.get_map_name().unwrap_or_default(),
+            })?;
+            sys::prog_attach(
+                sk_msg.fd()?.as_fd(),
+                sock_map.as_fd(),
+                bpf_attach_type::BPF_SK_MSG_VERDICT,
+            )
+            .map_err(|e| {
+                BpfmanError::Error(format!(
+                    "unable to attach sk_msg program to map {}: {e}",
+                    program.get_map_name().unwrap_or_default()
+                ))
+            })?;
+
+            sk_msg
+                .pin(format!("{RTDIR_FS}/prog_{}", id))
+                .map_err(BpfmanError::UnableToPinProgram)?;
+
+            Ok(id)
+        }
         _ => panic!("not a supported single attach program"),
     };
 
@@ -876,17 +2878,126 @@ pub(crate) fn add_single_attach_program(root_db: &Db, p: &mut Program) -> Result
         }
     };
 
-    res
+    res
+}
+
+// A kprobe loaded for a container only sees the container's events if it
+// compares the current cgroup id with CONTAINER_CGROUP_ID_GLOBAL, so the
+// function, or a subprogram in .text it may call, must read the global.
+// Relocations name either the global or, for static variables, its section,
+// with the global's offset in the instruction's immediate.
+fn reads_container_cgroup_id(bytes: &[u8], fn_name: &str) -> Result<bool, BpfmanError> {
+    let obj = object::File::parse(bytes)
+        .map_err(|e| BpfmanError::Error(format!("unable to parse program bytes: {e}")))?;
+    let Some(global) = obj
+        .symbols()
+        .find(|s| s.name() == Ok(CONTAINER_CGROUP_ID_GLOBAL))
+    else {
+        return Ok(false);
+    };
+    let function = obj
+        .symbols()
+        .find(|s| s.kind() == SymbolKind::Text && s.name() == Ok(fn_name))
+        .and_then(|s| s.section_index());
+
+    for section in obj.sections() {
+        if section.kind() != SectionKind::Text
+            || (function.is_some()
+                && function != Some(section.index())
+                && section.name() != Ok(".text"))
+        {
+            continue;
+        }
+        let data = section
+            .data()
+            .map_err(|e| BpfmanError::Error(format!("unable to read program code: {e}")))?;
+        for (offset, relocation) in section.relocations() {
+            let RelocationTarget::Symbol(index) = relocation.target() else {
+                continue;
+            };
+            let Ok(target) = obj.symbol_by_index(index) else {
+                continue;
+            };
+            if target.index() == global.index() {
+                return Ok(true);
+            }
+            let imm = data
+                .get(offset as usize + 4..offset as usize + 8)
+                .and_then(|imm| <[u8; 4]>::try_from(imm).ok())
+                .map(|imm| match obj.endianness() {
+                    Endianness::Little => u32::from_le_bytes(imm),
+                    Endianness::Big => u32::from_be_bytes(imm),
+                });
+            if target.kind() == SymbolKind::Section
+                && target.section_index() == global.section_index()
+                && imm.map(u64::from) == Some(global.address())
+            {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+// Attaches a program to the cgroup at cgroup_path with a bpf link, and pins
+// the link at link_pin_path so the program stays attached after bpfman exits.
+fn attach_to_cgroup(
+    prog_fd: BorrowedFd<'_>,
+    cgroup_path: &str,
+    attach_type: bpf_attach_type,
+    link_pin_path: &Path,
+) -> Result<(), BpfmanError> {
+    let cgroup =
+        File::open(cgroup_path).map_err(|_| BpfmanError::InvalidAttach(cgroup_path.to_string()))?;
+
+    let link = sys::link_create(prog_fd, cgroup.as_fd(), attach_type).map_err(|e| {
+        BpfmanError::Error(format!(
+            "unable to attach program to cgroup {cgroup_path}: {e}"
+        ))
+    })?;
+
+    sys::obj_pin(link.as_fd(), link_pin_path)
+        .map_err(|e| BpfmanError::Error(format!("Failed to pin link {e}")))
+}
+
+// Returns the attach type for a cgroup_sock_addr program, along with its
+// name, which the kernel only learns from the program's section, e.g.
+// "cgroup/connect4".
+fn sock_addr_attach_type(
+    bytes: &[u8],
+    name: &str,
+) -> Result<(bpf_attach_type, &'static str), BpfmanError> {
+    let obj = aya_obj::Object::parse(bytes)
+        .map_err(|e| BpfmanError::Error(format!("unable to parse program bytes: {e}")))?;
+
+    let attach_type = match obj.programs.get(name).map(|p| &p.section) {
+        Some(ProgramSection::CgroupSockAddr { attach_type }) => *attach_type,
+        _ => return Err(BpfmanError::BpfFunctionNameNotValid(name.to_owned())),
+    };
+
+    let attach_type_name = match attach_type {
+        CgroupSockAddrAttachType::Bind4 => "bind4",
+        CgroupSockAddrAttachType::Bind6 => "bind6",
+        CgroupSockAddrAttachType::Connect4 => "connect4",
+        CgroupSockAddrAttachType::Connect6 => "connect6",
+        CgroupSockAddrAttachType::GetPeerName4 => "getpeername4",
+        CgroupSockAddrAttachType::GetPeerName6 => "getpeername6",
+        CgroupSockAddrAttachType::GetSockName4 => "getsockname4",
+        CgroupSockAddrAttachType::GetSockName6 => "getsockname6",
+        CgroupSockAddrAttachType::UDPSendMsg4 => "sendmsg4",
+        CgroupSockAddrAttachType::UDPSendMsg6 => "sendmsg6",
+        CgroupSockAddrAttachType::UDPRecvMsg4 => "recvmsg4",
+        CgroupSockAddrAttachType::UDPRecvMsg6 => "recvmsg6",
+    };
+
+    Ok((attach_type.into(), attach_type_name))
 }
 
 async fn remove_multi_attach_program(
     root_db: &Db,
     config: &Config,
     did: DispatcherId,
-    program_type: ProgramType,
-    if_index: Option<u32>,
     if_name: String,
-    direction: Option<Direction>,
 ) -> Result<(), BpfmanError> {
     debug!("BpfManager::remove_multi_attach_program()");
     let mut image_manager = init_image_manager().await;
@@ -899,25 +3010,38 @@ async fn remove_multi_attach_program(
     if let Some(ref mut old) = old_dispatcher {
         if next_available_id == 0 {
             // Delete the dispatcher
-            return old.delete(root_db, true);
+            return old.remove(root_db, &mut image_manager).await;
         }
     }
 
-    set_program_positions(root_db, program_type, if_index.unwrap(), direction);
+    rebuild_dispatcher(
+        root_db,
+        config,
+        &did,
+        &if_name,
+        old_dispatcher,
+        &mut image_manager,
+    )
+    .await
+}
+
+// Loads a new revision of a dispatcher with the programs attached to it, in
+// the order of their priorities.
+async fn rebuild_dispatcher(
+    root_db: &Db,
+    config: &Config,
+    did: &DispatcherId,
+    if_name: &str,
+    old_dispatcher: Option<Dispatcher>,
+    image_manager: &mut ImageManager,
+) -> Result<(), BpfmanError> {
+    set_program_positions(root_db, did);
 
     // Intentionally don't add filter program here
-    let mut programs: Vec<Program> = filter(root_db, program_type, if_index, direction).collect();
+    let mut programs: Vec<Program> = filter(root_db, did).collect();
 
-    let if_config = if let Some(ref i) = config.interfaces() {
-        i.get(&if_name)
-    } else {
-        None
-    };
-    let next_revision = if let Some(ref old) = old_dispatcher {
-        old.next_revision()
-    } else {
-        1
-    };
+    let if_config = config.interfaces().as_ref().and_then(|i| i.get(if_name));
+    let next_revision = old_dispatcher.as_ref().map_or(1, |old| old.next_revision());
     debug!("next_revision = {next_revision}");
 
     Dispatcher::new(
@@ -926,13 +3050,309 @@ async fn remove_multi_attach_program(
         &mut programs,
         next_revision,
         old_dispatcher,
-        &mut image_manager,
+        image_manager,
+        config.libxdp(),
     )
     .await?;
 
+    emit_dispatcher_rebuilt(&programs);
+
+    Ok(())
+}
+
+// Attaches the program to the attach point of the link.
+async fn attach_link(
+    root_db: &Db,
+    config: &Config,
+    program: &Program,
+    link: &mut Link,
+) -> Result<(), BpfmanError> {
+    let link_pin_path = link.pin_path()?;
+    match link.program_mut() {
+        p @ (Program::Xdp(_) | Program::Tc(_)) => {
+            let if_index = with_netns(p.netns()?.as_ref(), || get_ifindex(&p.if_name()?))?;
+            p.set_if_index(if_index)?;
+            p.set_iface_detached(false)?;
+            attach_multi_attach_link(root_db, config, p).await
+        }
+        p => attach_single_attach_link(program, p, &link_pin_path),
+    }
+}
+
+// Adds the program to the dispatcher of the link's interface. The program's
+// extension is already loaded, so the dispatcher only attaches it.
+async fn attach_multi_attach_link(
+    root_db: &Db,
+    config: &Config,
+    link: &mut Program,
+) -> Result<(), BpfmanError> {
+    debug!("BpfManager::attach_multi_attach_link()");
+    let did = link
+        .dispatcher_id()?
+        .ok_or(BpfmanError::DispatcherNotRequired)?;
+    let id = link.get_data().get_id()?;
+    let if_name = link.if_name()?;
+
+    // The extension is pinned by its id in the dispatcher, and the link itself
+    // is already in the database.
+    if filter(root_db, &did)
+        .filter(|p| p.get_data().get_id().ok() == Some(id))
+        .count()
+        > 1
+    {
+        return Err(BpfmanError::AlreadyAttached(id, if_name));
+    }
+
+    let max_programs = match link.kind() {
+        ProgramType::Tc => TC_MAX_PROGRAMS,
+        _ => XDP_MAX_PROGRAMS,
+    };
+    if num_attached_programs(&did, root_db) >= max_programs {
+        return Err(BpfmanError::TooManyPrograms);
+    }
+    check_xdp_frags(link, filter(root_db, &did))?;
+    check_xdp_mode(root_db, &did, link, filter(root_db, &did))?;
+
+    link.set_attached();
+    let mut image_manager = init_image_manager().await;
+    let old_dispatcher = get_dispatcher(&did, root_db);
+    rebuild_dispatcher(
+        root_db,
+        config,
+        &did,
+        &if_name,
+        old_dispatcher,
+        &mut image_manager,
+    )
+    .await
+}
+
+// Attaches the pinned program to the attach point of the link, and pins the new
+// link at link_pin_path.
+fn attach_single_attach_link(
+    program: &Program,
+    link: &Program,
+    link_pin_path: &Path,
+) -> Result<(), BpfmanError> {
+    debug!("BpfManager::attach_single_attach_link()");
+    let program_pin_path =
+        PathBuf::from(format!("{RTDIR_FS}/prog_{}", program.get_data().get_id()?));
+
+    match link {
+        Program::Tracepoint(l) => {
+            let tracepoint = l.get_tracepoint()?;
+            let (category, name) = tracepoint
+                .split_once('/')
+                .ok_or_else(|| BpfmanError::InvalidAttach(tracepoint.clone()))?;
+
+            let mut tracepoint = TracePoint::from_pin(&program_pin_path)?;
+            let link_id = tracepoint.attach(category, name)?;
+            let owned_link: TracePointLink = tracepoint.take_link(link_id)?;
+            let fd_link: FdLink = owned_link
+                .try_into()
+                .expect("unable to get owned tracepoint attach link");
+            fd_link
+                .pin(link_pin_path)
+                .map_err(BpfmanError::UnableToPinLink)?;
+        }
+        Program::Kprobe(l) => {
+            let kind = match l.get_retprobe()? {
+                true => ProbeKind::KRetProbe,
+                false => ProbeKind::KProbe,
+            };
+
+            let mut kprobe = KProbe::from_pin(&program_pin_path, kind)?;
+            let link_id = kprobe.attach(l.get_fn_name()?, l.get_offset()?)?;
+            let owned_link: KProbeLink = kprobe.take_link(link_id)?;
+            let fd_link: FdLink = owned_link
+                .try_into()
+                .expect("unable to get owned kprobe attach link");
+            fd_link
+                .pin(link_pin_path)
+                .map_err(BpfmanError::UnableToPinLink)?;
+        }
+        Program::Uprobe(l) => {
+            let kind = match l.get_retprobe()? {
+                true => ProbeKind::URetProbe,
+                false => ProbeKind::UProbe,
+            };
+
+            match l.get_container_pid()? {
+                None => {
+                    let mut uprobe = UProbe::from_pin(&program_pin_path, kind)?;
+                    let link_id = uprobe.attach(
+                        l.get_fn_name()?.as_deref(),
+                        l.get_offset()?,
+                        l.get_target()?,
+                        l.get_pid()?,
+                    )?;
+                    let owned_link: UProbeLink = uprobe.take_link(link_id)?;
+                    let fd_link: FdLink = owned_link
+                        .try_into()
+                        .expect("unable to get owned uprobe attach link");
+                    fd_link
+                        .pin(link_pin_path)
+                        .map_err(BpfmanError::UnableToPinLink)?;
+                }
+                Some(p) => {
+                    let mut prog_args = vec![
+                        "uprobe".to_string(),
+                        "--program-pin-path".to_string(),
+                        program_pin_path.to_string_lossy().to_string(),
+                        "--link-pin-path".to_string(),
+                        link_pin_path.to_string_lossy().to_string(),
+                        "--offset".to_string(),
+                        l.get_offset()?.to_string(),
+                        "--target".to_string(),
+                        l.get_target()?,
+                        "--container-pid".to_string(),
+                        p.to_string(),
+                    ];
+
+                    if let Some(fn_name) = l.get_fn_name()? {
+                        prog_args.extend(["--fn-name".to_string(), fn_name])
+                    }
+
+                    if l.get_retprobe()? {
+                        prog_args.push("--retprobe".to_string());
+                    }
+
+                    if let Some(pid) = l.get_pid()? {
+                        prog_args.extend(["--pid".to_string(), pid.to_string()])
+                    }
+
+                    debug!("calling bpfman-ns to attach uprobe in pid: {:?}", p);
+                    attach_in_container("uprobe", prog_args, p)?;
+                }
+            }
+        }
+        Program::CgroupSkb(l) => {
+            let attach_type = match l.get_direction()? {
+                Direction::Ingress => bpf_attach_type::BPF_CGROUP_INET_INGRESS,
+                Direction::Egress => bpf_attach_type::BPF_CGROUP_INET_EGRESS,
+            };
+            let prog_fd = sys::obj_get(&program_pin_path)?;
+            attach_to_cgroup(
+                prog_fd.as_fd(),
+                &l.get_cgroup_path()?,
+                attach_type,
+                link_pin_path,
+            )?;
+        }
+        Program::CgroupSockAddr(l) => {
+            let (attach_type, _) = sock_addr_attach_type(
+                &program.get_data().get_program_bytes()?,
+                &program.get_data().get_name()?,
+            )?;
+            let prog_fd = sys::obj_get(&program_pin_path)?;
+            attach_to_cgroup(
+                prog_fd.as_fd(),
+                &l.get_cgroup_path()?,
+                attach_type,
+                link_pin_path,
+            )?;
+        }
+        Program::SockOps(l) => {
+            let prog_fd = sys::obj_get(&program_pin_path)?;
+            attach_to_cgroup(
+                prog_fd.as_fd(),
+                &l.get_cgroup_path()?,
+                bpf_attach_type::BPF_CGROUP_SOCK_OPS,
+                link_pin_path,
+            )?;
+        }
+        _ => {
+            return Err(BpfmanError::InvalidAttach(format!(
+                "{} program",
+                program.kind()
+            )))
+        }
+    }
+
+    Ok(())
+}
+
+// Detaches and removes a link. Links to XDP and TC programs are detached by
+// rebuilding the dispatcher without them, while other links are detached when
+// their pin is removed.
+async fn remove_link(root_db: &Db, config: &Config, link: Link) -> Result<(), BpfmanError> {
+    let multi_attach = match link.program() {
+        p @ (Program::Xdp(_) | Program::Tc(_)) if !p.iface_detached() => Some((
+            p.dispatcher_id()?
+                .ok_or(BpfmanError::DispatcherNotRequired)?,
+            p.if_name()?,
+        )),
+        _ => None,
+    };
+
+    link.delete(root_db)
+        .map_err(BpfmanError::BpfmanProgramDeleteError)?;
+
+    if let Some((did, if_name)) = multi_attach {
+        remove_multi_attach_program(root_db, config, did, if_name).await?;
+    }
+    Ok(())
+}
+
+// Hands ownership of the maps owned by a program that has been replaced over
+// to its replacement, which must already be one of the maps' users.
+fn transfer_map_owner(root_db: &Db, old_id: u32, new_id: u32) -> Result<(), BpfmanError> {
+    let old_map = get_map(old_id, root_db).ok_or(BpfmanError::MapOwnerNotFound(old_id))?;
+    let used_by = get_maps_used_by(old_map)?;
+
+    let map_pin_path = calc_map_pin_path(new_id);
+    rename(calc_map_pin_path(old_id), &map_pin_path)
+        .map_err(|e| BpfmanError::Error(format!("can't move map dir: {e}")))?;
+
+    let new_map = root_db
+        .open_tree(format!("{}{}", MAP_PREFIX, new_id))
+        .expect("Unable to open map db tree");
+    set_maps_used_by(new_map, used_by.clone())?;
+    root_db
+        .drop_tree(format!("{}{}", MAP_PREFIX, old_id))
+        .expect("unable to drop maps tree");
+
+    for used_by_id in used_by.iter() {
+        if let Some(mut program) = get(root_db, used_by_id) {
+            let data = program.get_data_mut();
+            if *used_by_id == new_id {
+                data.clear_map_owner_id()?;
+            } else {
+                data.set_map_owner_id(new_id)?;
+            }
+            data.set_map_pin_path(&map_pin_path)?;
+        }
+    }
+
     Ok(())
 }
 
+// Opens a map pinned for a program by name. Programs which share another
+// program's maps find them in the map owner's directory.
+fn open_map(root_db: &Db, id: u32, map_name: &str) -> Result<PinnedMap, BpfmanError> {
+    let program = get(root_db, &id).ok_or_else(|| BpfmanError::ProgramNotManaged(id))?;
+
+    let not_found = || BpfmanError::MapNotFound {
+        program_id: id,
+        map_name: map_name.to_string(),
+    };
+
+    // Map names are file names in the map directory, so don't let them
+    // point anywhere else.
+    if map_name.is_empty() || map_name.contains('/') || map_name.starts_with('.') {
+        return Err(not_found());
+    }
+
+    let path = program
+        .get_data()
+        .get_map_pin_path()?
+        .map(|p| p.join(map_name))
+        .filter(|p| p.exists())
+        .ok_or_else(not_found)?;
+
+    PinnedMap::open(map_name, &path)
+}
+
 // This function checks to see if the user provided map_owner_id is valid.
 fn is_map_owner_id_valid(root_db: &Db, map_owner_id: u32) -> Result<PathBuf, BpfmanError> {
     let map_pin_path = calc_map_pin_path(map_owner_id);
@@ -942,9 +3362,7 @@ fn is_map_owner_id_valid(root_db: &Db, map_owner_id: u32) -> Result<PathBuf, Bpf
         // Return the map_pin_path
         return Ok(map_pin_path);
     }
-    Err(BpfmanError::Error(
-        "map_owner_id does not exists".to_string(),
-    ))
+    Err(BpfmanError::MapOwnerNotFound(map_owner_id))
 }
 
 // This function is called if the program's map directory was created,
@@ -995,9 +3413,7 @@ fn save_map(
                     }
                 }
             } else {
-                return Err(BpfmanError::Error(
-                    "map_owner_id does not exist".to_string(),
-                ));
+                return Err(BpfmanError::MapOwnerNotFound(m));
             }
         }
         None => {
@@ -1143,3 +3559,667 @@ fn get_map(id: u32, root_db: &Db) -> Option<sled::Tree> {
         .find(|n| bytes_to_string(n) == format!("{}{}", MAP_PREFIX, id))
         .map(|n| root_db.open_tree(n).expect("unable to open map tree"))
 }
+
+#[cfg(test)]
+mod test {
+    use aya::programs::ProgramError;
+
+    use super::*;
+    use crate::types::{
+        Location, TcProceedOn, TcProgram, TracepointProgram, XdpProceedOn, XdpProgram,
+    };
+
+    fn program_data(map_owner_id: Option<u32>) -> ProgramData {
+        ProgramData::new(
+            Location::File("/tmp/prog.o".to_string()),
+            "prog".to_string(),
+            HashMap::new(),
+            HashMap::new(),
+            map_owner_id,
+        )
+        .unwrap()
+    }
+
+    fn xdp_program(iface: &str, map_owner_id: Option<u32>) -> Program {
+        Program::Xdp(
+            XdpProgram::new(
+                program_data(map_owner_id),
+                50,
+                iface.to_string(),
+                XdpProceedOn::default(),
+                None,
+            )
+            .unwrap(),
+        )
+    }
+
+    // Persists an XDP program under the given id, on the dispatcher of the
+    // given interface index.
+    fn saved_xdp_program(root_db: &Db, id: u32, if_index: u32, priority: i32) -> Program {
+        let mut program = xdp_program("eth0", None);
+        program.set_if_index(if_index).unwrap();
+        program.set_priority(priority).unwrap();
+        let data = program.get_data_mut();
+        data.set_name(&format!("prog{id}")).unwrap();
+        data.load(root_db).unwrap();
+        data.swap_tree(root_db, id).unwrap();
+        program
+    }
+
+    fn positions(root_db: &Db, ids: &[u32]) -> Vec<Option<usize>> {
+        ids.iter()
+            .map(|id| get(root_db, id).unwrap().position().unwrap())
+            .collect()
+    }
+
+    fn tc_program(iface: &str, direction: Direction) -> Program {
+        Program::Tc(
+            TcProgram::new(
+                program_data(None),
+                50,
+                iface.to_string(),
+                TcProceedOn::default(),
+                direction,
+                None,
+            )
+            .unwrap(),
+        )
+    }
+
+    // Persists a tracepoint program under the given id, as if it had been
+    // loaded with it.
+    fn saved_program(root_db: &Db, id: u32, map_owner_id: Option<u32>) -> Program {
+        let mut program = Program::Tracepoint(
+            TracepointProgram::new(
+                program_data(map_owner_id),
+                "syscalls/sys_enter_openat".to_string(),
+            )
+            .unwrap(),
+        );
+        let data = program.get_data_mut();
+        data.load(root_db).unwrap();
+        data.set_map_pin_path(&calc_map_pin_path(map_owner_id.unwrap_or(id)))
+            .unwrap();
+        data.set_maps_used_by(vec![id]).unwrap();
+        data.set_kernel_tag(0xabcd).unwrap();
+        data.swap_tree(root_db, id).unwrap();
+        program
+    }
+
+    fn has_tree(root_db: &Db, name: String) -> bool {
+        root_db.tree_names().contains(&name.as_bytes().into())
+    }
+
+    #[test]
+    fn test_is_program_loaded() {
+        let root_db = get_db_config().open().unwrap();
+        let program = saved_program(&root_db, 4_000_000_001, None);
+
+        let kernel_programs = HashMap::from([(4_000_000_001, "abcd".to_string())]);
+        assert!(is_program_loaded(&program, &kernel_programs));
+
+        // The kernel reused the id for another program.
+        let kernel_programs = HashMap::from([(4_000_000_001, "1234".to_string())]);
+        assert!(!is_program_loaded(&program, &kernel_programs));
+
+        assert!(!is_program_loaded(&program, &HashMap::new()));
+    }
+
+    #[test]
+    fn test_stage_restore() {
+        let root_db = get_db_config().open().unwrap();
+        let owner_id = 4_000_000_001;
+        let user_id = 4_000_000_002;
+        let mut owner = saved_program(&root_db, owner_id, None);
+        let mut user = saved_program(&root_db, user_id, Some(owner_id));
+        root_db
+            .open_tree(format!("{MAP_PREFIX}{owner_id}"))
+            .unwrap();
+
+        stage_restore(&root_db, &mut owner).unwrap();
+        assert!(!has_tree(&root_db, format!("{PROGRAM_PREFIX}{owner_id}")));
+        assert!(has_tree(
+            &root_db,
+            format!("{PROGRAM_RESTORE_PREFIX}{owner_id}")
+        ));
+        // The owner's maps are recreated when it's restored.
+        assert!(!has_tree(&root_db, format!("{MAP_PREFIX}{owner_id}")));
+        let data = owner.get_data();
+        assert_eq!(data.get_map_pin_path().unwrap(), None);
+        assert!(data.get_maps_used_by().unwrap().is_empty());
+
+        stage_restore(&root_db, &mut user).unwrap();
+        assert!(has_tree(
+            &root_db,
+            format!("{PROGRAM_RESTORE_PREFIX}{user_id}")
+        ));
+        let data = user.get_data();
+        assert_eq!(data.get_map_owner_id().unwrap(), Some(owner_id));
+        assert_eq!(
+            data.get_map_pin_path().unwrap(),
+            Some(calc_map_pin_path(owner_id))
+        );
+        assert!(data.get_maps_used_by().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_removed_interfaces() {
+        let attached = |iface: &str, if_index: u32| {
+            let mut p = xdp_program(iface, None);
+            p.set_if_index(if_index).unwrap();
+            p.set_attached();
+            p
+        };
+        let mut detached = attached("eth3", 4);
+        detached.set_iface_detached(true).unwrap();
+        let programs = vec![
+            attached("eth0", 1),
+            // Removed.
+            attached("eth1", 2),
+            // Removed, and the index now belongs to another interface.
+            attached("eth2", 3),
+            detached,
+        ];
+        let interfaces = HashMap::from([(1, "eth0".to_string()), (3, "veth0".to_string())]);
+
+        assert_eq!(
+            removed_interfaces(programs.into_iter(), &interfaces),
+            HashSet::from([2, 3])
+        );
+    }
+
+    #[test]
+    fn test_staged_programs() {
+        let root_db = get_db_config().open().unwrap();
+        let owner_id = 4_000_000_001;
+        let user_id = 4_000_000_002;
+        let mut owner = saved_program(&root_db, owner_id, None);
+        let mut user = saved_program(&root_db, user_id, Some(owner_id));
+        assert!(!owner.get_data().restore_pending());
+        stage_restore(&root_db, &mut owner).unwrap();
+        stage_restore(&root_db, &mut user).unwrap();
+
+        let mut ids: Vec<u32> = get_staged_programs_iter(&root_db)
+            .map(|(id, _)| id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, vec![owner_id, user_id]);
+        assert!(get(&root_db, &user_id).is_none());
+        assert!(get_staged(&root_db, &user_id)
+            .unwrap()
+            .get_data()
+            .restore_pending());
+
+        // The maps of the staged map owner are already gone.
+        remove_staged_program(&root_db, get_staged(&root_db, &user_id).unwrap()).unwrap();
+        assert!(get_staged(&root_db, &user_id).is_none());
+        assert!(get_staged(&root_db, &owner_id).is_some());
+    }
+
+    #[test]
+    fn test_unstage_restore() {
+        let root_db = get_db_config().open().unwrap();
+        let owner_id = 4_000_000_001;
+        let new_owner_id = 4_000_000_003;
+        let user_id = 4_000_000_002;
+        let mut user = saved_program(&root_db, user_id, Some(owner_id));
+        stage_restore(&root_db, &mut user).unwrap();
+
+        // The map owner hasn't been restored yet.
+        assert!(matches!(
+            unstage_restore(&root_db, &mut user, &HashMap::new()),
+            Err(BpfmanError::MapOwnerNotFound(id)) if id == owner_id
+        ));
+
+        // The map owner was restored with a new id.
+        root_db
+            .open_tree(format!("{MAP_PREFIX}{new_owner_id}"))
+            .unwrap();
+        let restored = HashMap::from([(owner_id, new_owner_id)]);
+        unstage_restore(&root_db, &mut user, &restored).unwrap();
+        assert!(!has_tree(
+            &root_db,
+            format!("{PROGRAM_RESTORE_PREFIX}{user_id}")
+        ));
+        assert!(has_tree(
+            &root_db,
+            format!("{PROGRAM_PRE_LOAD_PREFIX}{user_id}")
+        ));
+        let data = user.get_data();
+        assert_eq!(data.get_map_owner_id().unwrap(), Some(new_owner_id));
+        assert_eq!(
+            data.get_map_pin_path().unwrap(),
+            Some(calc_map_pin_path(new_owner_id))
+        );
+    }
+
+    #[test]
+    fn test_validate_update() {
+        let old = xdp_program("eth0", None);
+        let invalid = |r: Result<(), BpfmanError>| matches!(r, Err(BpfmanError::InvalidUpdate(..)));
+
+        assert!(validate_update(&old, &xdp_program("eth0", None), 7).is_ok());
+        assert!(validate_update(&old, &xdp_program("eth0", Some(7)), 7).is_ok());
+        assert!(invalid(validate_update(
+            &old,
+            &xdp_program("eth0", Some(8)),
+            7
+        )));
+        assert!(invalid(validate_update(
+            &old,
+            &xdp_program("eth1", None),
+            7
+        )));
+        assert!(invalid(validate_update(
+            &old,
+            &tc_program("eth0", Direction::Ingress),
+            7
+        )));
+
+        assert!(matches!(
+            validate_update(&old, &Program::Unsupported(program_data(None)), 7),
+            Err(BpfmanError::UnsupportedProgramType)
+        ));
+
+        let old = tc_program("eth0", Direction::Ingress);
+        assert!(validate_update(&old, &tc_program("eth0", Direction::Ingress), 7).is_ok());
+        assert!(invalid(validate_update(
+            &old,
+            &tc_program("eth0", Direction::Egress),
+            7
+        )));
+    }
+
+    // Builds an object with a my_kprobe function, or a filter subprogram it
+    // calls, which reads the container cgroup id global through a relocation
+    // against the global (Some(true)), against .rodata (Some(false)), or not at
+    // all (None), and an other_kprobe function which doesn't read it.
+    fn kprobe_object(read: Option<bool>, in_subprogram: bool) -> Vec<u8> {
+        use object::{
+            write::{Object as WriteObject, Relocation, Symbol, SymbolSection},
+            Architecture, BinaryFormat, RelocationEncoding, RelocationKind, SymbolFlags,
+            SymbolScope,
+        };
+
+        let mut obj = WriteObject::new(BinaryFormat::Elf, Architecture::Bpf, Endianness::Little);
+        let rodata = obj.add_section(vec![], b".rodata".to_vec(), SectionKind::ReadOnlyData);
+        obj.append_section_data(rodata, &[0; 16], 8);
+        let global = obj.add_symbol(Symbol {
+            name: CONTAINER_CGROUP_ID_GLOBAL.as_bytes().to_vec(),
+            value: 8,
+            size: 8,
+            kind: SymbolKind::Data,
+            scope: SymbolScope::Dynamic,
+            weak: false,
+            section: SymbolSection::Section(rodata),
+            flags: SymbolFlags::None,
+        });
+
+        // A ld_imm64 of the global followed by an exit.
+        let mut code = vec![0x18, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+        code.extend([0x95, 0, 0, 0, 0, 0, 0, 0]);
+        let mut add_function = |section_name: &str, fn_name: &str, reads: Option<bool>| {
+            let section =
+                obj.add_section(vec![], section_name.as_bytes().to_vec(), SectionKind::Text);
+            let mut code = code.clone();
+            let target = match reads {
+                Some(true) => Some(global),
+                Some(false) => {
+                    code[4] = 8;
+                    Some(obj.section_symbol(rodata))
+                }
+                None => None,
+            };
+            obj.append_section_data(section, &code, 8);
+            obj.add_symbol(Symbol {
+                name: fn_name.as_bytes().to_vec(),
+                value: 0,
+                size: code.len() as u64,
+                kind: SymbolKind::Text,
+                scope: SymbolScope::Dynamic,
+                weak: false,
+                section: SymbolSection::Section(section),
+                flags: SymbolFlags::None,
+            });
+            if let Some(symbol) = target {
+                obj.add_relocation(
+                    section,
+                    Relocation {
+                        offset: 0,
+                        size: 64,
+                        kind: RelocationKind::Absolute,
+                        encoding: RelocationEncoding::Generic,
+                        symbol,
+                        addend: 0,
+                    },
+                )
+                .unwrap();
+            }
+        };
+        if in_subprogram {
+            add_function("kprobe/try_to_wake_up", "my_kprobe", None);
+            add_function(".text", "filter", read);
+        } else {
+            add_function("kprobe/try_to_wake_up", "my_kprobe", read);
+        }
+        add_function("kprobe/other", "other_kprobe", None);
+        obj.write().unwrap()
+    }
+
+    #[test]
+    fn test_reads_container_cgroup_id() {
+        let reads =
+            |bytes: Vec<u8>, fn_name: &str| reads_container_cgroup_id(&bytes, fn_name).unwrap();
+
+        assert!(reads(kprobe_object(Some(true), false), "my_kprobe"));
+        // Static variables are relocated against their section.
+        assert!(reads(kprobe_object(Some(false), false), "my_kprobe"));
+        assert!(reads(kprobe_object(Some(true), true), "my_kprobe"));
+        // Declaring the global isn't enough, the function has to read it.
+        assert!(!reads(kprobe_object(None, false), "my_kprobe"));
+        assert!(!reads(kprobe_object(Some(true), false), "other_kprobe"));
+        assert!(reads_container_cgroup_id(b"not an object", "my_kprobe").is_err());
+    }
+
+    // Builds an object with an XDP function named prog running the given code.
+    fn xdp_object(code: &[u8]) -> Vec<u8> {
+        use object::{
+            write::{Object as WriteObject, Symbol, SymbolSection},
+            Architecture, BinaryFormat, SymbolFlags, SymbolScope,
+        };
+
+        let mut obj = WriteObject::new(BinaryFormat::Elf, Architecture::Bpf, Endianness::Little);
+        let license = obj.add_section(vec![], b"license".to_vec(), SectionKind::Data);
+        obj.append_section_data(license, b"GPL\0", 1);
+        let section = obj.add_section(vec![], b"xdp".to_vec(), SectionKind::Text);
+        obj.append_section_data(section, code, 8);
+        obj.add_symbol(Symbol {
+            name: b"prog".to_vec(),
+            value: 0,
+            size: code.len() as u64,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Dynamic,
+            weak: false,
+            section: SymbolSection::Section(section),
+            flags: SymbolFlags::None,
+        });
+        obj.write().unwrap()
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let root_db = &get_db_config().open().unwrap();
+        let snapshot = || {
+            let trees: Vec<_> = root_db
+                .tree_names()
+                .into_iter()
+                .map(|name| {
+                    let entries: Vec<_> = root_db
+                        .open_tree(&name)
+                        .unwrap()
+                        .iter()
+                        .map(|e| e.unwrap())
+                        .collect();
+                    (name, entries)
+                })
+                .collect();
+            let pins: Vec<_> = std::fs::read_dir(RTDIR_FS)
+                .map(|d| d.map(|e| e.unwrap().path()).collect())
+                .unwrap_or_default();
+            (trees, pins)
+        };
+        let program = |code: &[u8]| {
+            let mut program = xdp_program("eth0", None);
+            program
+                .get_data_mut()
+                .set_program_bytes_from(&xdp_object(code))
+                .unwrap();
+            program
+        };
+        let before = snapshot();
+
+        // r0 = XDP_PASS; exit
+        let mut accepted = program(&[
+            0xb7, 0, 0, 0, 2, 0, 0, 0, //
+            0x95, 0, 0, 0, 0, 0, 0, 0,
+        ]);
+        match verify_program(&mut accepted) {
+            // Loading programs takes CAP_BPF.
+            Err(BpfmanError::BpfProgramError(ProgramError::LoadError { io_error, .. }))
+                if io_error.raw_os_error() == Some(nix::libc::EPERM) =>
+            {
+                return;
+            }
+            result => result.unwrap(),
+        }
+        assert!(accepted.get_data().get_kernel_tag().is_ok());
+
+        // exit, without setting r0
+        let mut rejected = program(&[0x95, 0, 0, 0, 0, 0, 0, 0]);
+        match verify_program(&mut rejected) {
+            Err(BpfmanError::BpfProgramError(ProgramError::LoadError { verifier_log, .. })) => {
+                assert!(verifier_log.to_string().contains("R0 !read_ok"))
+            }
+            result => panic!("expected the verifier to reject the program: {result:?}"),
+        }
+
+        // Neither program was saved or pinned.
+        assert_eq!(snapshot(), before);
+    }
+
+    #[test]
+    fn test_check_xdp_frags() {
+        let frags = |frags: bool| {
+            let mut program = xdp_program("eth0", None);
+            if let Program::Xdp(p) = &mut program {
+                p.set_frags(frags).unwrap();
+            }
+            program
+        };
+
+        assert!(check_xdp_frags(&frags(true), [frags(true), frags(true)].into_iter()).is_ok());
+        assert!(check_xdp_frags(&frags(false), [frags(false)].into_iter()).is_ok());
+        assert!(check_xdp_frags(&frags(true), std::iter::empty()).is_ok());
+        assert!(matches!(
+            check_xdp_frags(&frags(true), [frags(true), frags(false)].into_iter()),
+            Err(BpfmanError::XdpFragsMismatch(iface)) if iface == "eth0"
+        ));
+        assert!(matches!(
+            check_xdp_frags(&frags(false), [frags(true)].into_iter()),
+            Err(BpfmanError::XdpFragsMismatch(_))
+        ));
+        // Only XDP programs share a dispatcher with frags support.
+        assert!(check_xdp_frags(
+            &tc_program("eth0", Direction::Ingress),
+            [frags(true)].into_iter()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_check_xdp_mode() {
+        let root_db = get_db_config().open().unwrap();
+        let with_mode = |mode: Option<(XdpMode, bool)>| {
+            let mut program = xdp_program("eth0", None);
+            program.set_if_index(1).unwrap();
+            if let (Program::Xdp(p), Some((mode, fallback))) = (&mut program, mode) {
+                p.set_mode(mode, fallback).unwrap();
+            }
+            program
+        };
+        let drv = with_mode(Some((XdpMode::Drv, false)));
+        let did = drv.dispatcher_id().unwrap().unwrap();
+
+        assert!(
+            check_xdp_mode(&root_db, &did, &with_mode(None), [drv.clone()].into_iter()).is_ok()
+        );
+        assert!(check_xdp_mode(&root_db, &did, &drv, [with_mode(None)].into_iter()).is_ok());
+        assert!(matches!(
+            check_xdp_mode(
+                &root_db,
+                &did,
+                &with_mode(Some((XdpMode::Skb, false))),
+                [drv.clone()].into_iter()
+            ),
+            Err(BpfmanError::XdpModeMismatch(_))
+        ));
+
+        // The dispatcher fell back to skb mode.
+        multiprog::XdpDispatcher::new(
+            &root_db,
+            &XdpMode::Skb,
+            true,
+            1,
+            "eth0".to_string(),
+            None,
+            1,
+        )
+        .unwrap();
+        assert!(matches!(
+            check_xdp_mode(&root_db, &did, &drv, std::iter::empty()),
+            Err(BpfmanError::XdpModeMismatch(_))
+        ));
+        assert!(check_xdp_mode(
+            &root_db,
+            &did,
+            &with_mode(Some((XdpMode::Drv, true))),
+            std::iter::empty()
+        )
+        .is_ok());
+        assert!(check_xdp_mode(
+            &root_db,
+            &did,
+            &with_mode(Some((XdpMode::Skb, false))),
+            std::iter::empty()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_reorder_positions() {
+        let root_db = get_db_config().open().unwrap();
+        let mut first = saved_xdp_program(&root_db, 1, 1, 30);
+        saved_xdp_program(&root_db, 2, 1, 10);
+        saved_xdp_program(&root_db, 3, 1, 20);
+        // Programs on other dispatchers aren't positioned.
+        saved_xdp_program(&root_db, 4, 2, 5);
+        let did = first.dispatcher_id().unwrap().unwrap();
+
+        set_program_positions(&root_db, &did);
+        assert_eq!(
+            positions(&root_db, &[1, 2, 3, 4]),
+            vec![Some(2), Some(0), Some(1), None]
+        );
+
+        first.set_priority(5).unwrap();
+        set_program_positions(&root_db, &did);
+        assert_eq!(
+            positions(&root_db, &[1, 2, 3]),
+            vec![Some(0), Some(1), Some(2)]
+        );
+
+        // Programs with the same priority are ordered by name.
+        first.set_priority(10).unwrap();
+        set_program_positions(&root_db, &did);
+        assert_eq!(
+            positions(&root_db, &[1, 2, 3]),
+            vec![Some(0), Some(1), Some(2)]
+        );
+    }
+
+    #[test]
+    fn test_set_proceed_on() {
+        let mut program = xdp_program("eth0", None);
+        program.set_proceed_on(&[2, 31]).unwrap();
+        assert_eq!(program.proceed_on().unwrap(), vec![2, 31]);
+        // Actions left over from a longer list aren't read back.
+        program.set_proceed_on(&[1]).unwrap();
+        assert_eq!(program.proceed_on().unwrap(), vec![1]);
+        assert!(program.set_proceed_on(&[99]).is_err());
+
+        let mut program = tc_program("eth0", Direction::Ingress);
+        program.set_proceed_on(&[0, 3]).unwrap();
+        assert_eq!(program.proceed_on().unwrap(), vec![0, 3]);
+
+        let mut program = saved_program(&get_db_config().open().unwrap(), 1, None);
+        assert!(program.set_proceed_on(&[2]).is_err());
+    }
+
+    #[test]
+    fn test_disabled_positions() {
+        let root_db = get_db_config().open().unwrap();
+        let mut first = saved_xdp_program(&root_db, 1, 1, 10);
+        saved_xdp_program(&root_db, 2, 1, 20);
+        saved_xdp_program(&root_db, 3, 1, 30);
+        let did = first.dispatcher_id().unwrap().unwrap();
+
+        // Disabled programs go after the enabled ones, whatever their
+        // priority.
+        first.set_disabled(true).unwrap();
+        set_program_positions(&root_db, &did);
+        assert_eq!(
+            positions(&root_db, &[1, 2, 3]),
+            vec![Some(2), Some(0), Some(1)]
+        );
+        assert!(get(&root_db, &1).unwrap().disabled());
+
+        first.set_disabled(false).unwrap();
+        set_program_positions(&root_db, &did);
+        assert_eq!(
+            positions(&root_db, &[1, 2, 3]),
+            vec![Some(0), Some(1), Some(2)]
+        );
+
+        let mut program = saved_program(&root_db, 4, None);
+        assert!(program.set_disabled(true).is_err());
+        assert!(!program.disabled());
+    }
+
+    #[test]
+    fn test_detached_programs_filtered() {
+        let root_db = get_db_config().open().unwrap();
+        let mut first = saved_xdp_program(&root_db, 1, 1, 10);
+        saved_xdp_program(&root_db, 2, 1, 20);
+        let other = saved_xdp_program(&root_db, 3, 2, 30);
+        let did = first.dispatcher_id().unwrap().unwrap();
+
+        // Links to programs on other interfaces run on the dispatcher they're
+        // attached to.
+        let mut link = Link::new(
+            &root_db,
+            &other,
+            AttachPoint::Xdp {
+                iface: "eth0".to_string(),
+                priority: 15,
+                proceed_on: XdpProceedOn::default(),
+                netns: None,
+            },
+        )
+        .unwrap();
+        link.program_mut().set_if_index(1).unwrap();
+        let ids = |root_db: &Db| {
+            let mut ids: Vec<u32> = filter(root_db, &did)
+                .map(|p| p.get_data().get_id().unwrap())
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids(&root_db), vec![1, 2, 3]);
+
+        // Programs detached from a removed interface don't take up a place on
+        // its dispatcher until they're attached again.
+        first.set_iface_detached(true).unwrap();
+        link.program_mut().set_iface_detached(true).unwrap();
+        assert!(get(&root_db, &1).unwrap().iface_detached());
+        assert_eq!(ids(&root_db), vec![2]);
+        set_program_positions(&root_db, &did);
+        assert_eq!(positions(&root_db, &[2]), vec![Some(0)]);
+
+        first.set_iface_detached(false).unwrap();
+        assert_eq!(ids(&root_db), vec![1, 2]);
+
+        let mut program = saved_program(&root_db, 4, None);
+        assert!(program.set_iface_detached(true).is_err());
+        assert!(!program.iface_detached());
+    }
+}
@@ -0,0 +1,757 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! A minimal BTF parser, used to format map keys and values as JSON and to
+//! build them from JSON. Only the types that can appear in map keys and values
+//! are understood, everything else is handled as raw bytes. It also reads the
+//! libbpf style declarations of a variable, such as libxdp's XDP_RUN_CONFIG.
+
+use serde_json::Value;
+
+use crate::errors::BpfmanError;
+
+const BTF_MAGIC: u16 = 0xeb9f;
+const BTF_HEADER_LEN: usize = 24;
+const BTF_TYPE_LEN: usize = 12;
+
+const BTF_KIND_INT: u32 = 1;
+const BTF_KIND_PTR: u32 = 2;
+const BTF_KIND_ARRAY: u32 = 3;
+const BTF_KIND_STRUCT: u32 = 4;
+const BTF_KIND_UNION: u32 = 5;
+const BTF_KIND_ENUM: u32 = 6;
+const BTF_KIND_TYPEDEF: u32 = 8;
+const BTF_KIND_VOLATILE: u32 = 9;
+const BTF_KIND_CONST: u32 = 10;
+const BTF_KIND_RESTRICT: u32 = 11;
+const BTF_KIND_FUNC_PROTO: u32 = 13;
+const BTF_KIND_VAR: u32 = 14;
+const BTF_KIND_DATASEC: u32 = 15;
+const BTF_KIND_FLOAT: u32 = 16;
+const BTF_KIND_DECL_TAG: u32 = 17;
+const BTF_KIND_TYPE_TAG: u32 = 18;
+const BTF_KIND_ENUM64: u32 = 19;
+
+const BTF_INT_SIGNED: u32 = 1;
+const BTF_INT_CHAR: u32 = 2;
+const BTF_INT_BOOL: u32 = 4;
+
+#[derive(Debug, Clone)]
+struct Member {
+    name: String,
+    type_id: u32,
+    bit_offset: u32,
+    bitfield_size: u32,
+}
+
+#[derive(Debug, Clone)]
+enum BtfType {
+    Void,
+    Int {
+        name: String,
+        size: u32,
+        encoding: u32,
+        offset: u32,
+        bits: u32,
+    },
+    Ptr {
+        type_id: u32,
+    },
+    Array {
+        type_id: u32,
+        nelems: u32,
+    },
+    Struct {
+        name: String,
+        size: u32,
+        members: Vec<Member>,
+        union: bool,
+    },
+    Enum {
+        name: String,
+        size: u32,
+        signed: bool,
+        variants: Vec<(String, i128)>,
+    },
+    // Typedefs and type modifiers, which only refer to another type.
+    Alias {
+        name: String,
+        type_id: u32,
+    },
+    Float {
+        name: String,
+        size: u32,
+    },
+    Var {
+        name: String,
+        type_id: u32,
+    },
+    Other,
+}
+
+#[derive(Debug)]
+pub(crate) struct Btf {
+    types: Vec<BtfType>,
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, BpfmanError> {
+    data.get(offset..offset + 4)
+        .map(|b| u32::from_ne_bytes(b.try_into().unwrap()))
+        .ok_or_else(|| BpfmanError::Error("BTF data is truncated".to_string()))
+}
+
+fn read_string(strings: &[u8], offset: u32) -> String {
+    strings
+        .get(offset as usize..)
+        .map(|s| s.split(|c| *c == 0).next().unwrap_or_default())
+        .map(|s| String::from_utf8_lossy(s).into_owned())
+        .unwrap_or_default()
+}
+
+fn invalid(type_name: &str, value: &Value) -> BpfmanError {
+    BpfmanError::Error(format!("{value} is not a valid {type_name}"))
+}
+
+// Parses a "0x" prefixed hex string.
+fn parse_hex(value: &str) -> Option<Vec<u8>> {
+    value
+        .strip_prefix("0x")
+        .and_then(|v| hex::decode(v.replace(' ', "")).ok())
+}
+
+fn to_hex(bytes: &[u8]) -> Value {
+    Value::String(format!("0x{}", hex::encode(bytes)))
+}
+
+impl Btf {
+    /// Parses raw BTF data as returned by the kernel, which is always in the
+    /// native byte order.
+    pub(crate) fn parse(data: &[u8]) -> Result<Self, BpfmanError> {
+        if data.len() < BTF_HEADER_LEN || u16::from_ne_bytes([data[0], data[1]]) != BTF_MAGIC {
+            return Err(BpfmanError::Error("invalid BTF header".to_string()));
+        }
+
+        let hdr_len = read_u32(data, 4)? as usize;
+        let type_off = hdr_len + read_u32(data, 8)? as usize;
+        let type_len = read_u32(data, 12)? as usize;
+        let str_off = hdr_len + read_u32(data, 16)? as usize;
+        let str_len = read_u32(data, 20)? as usize;
+
+        let type_data = data
+            .get(type_off..type_off + type_len)
+            .ok_or_else(|| BpfmanError::Error("BTF type section is truncated".to_string()))?;
+        let strings = data
+            .get(str_off..str_off + str_len)
+            .ok_or_else(|| BpfmanError::Error("BTF string section is truncated".to_string()))?;
+
+        let mut types = vec![BtfType::Void];
+        let mut offset = 0;
+        while offset < type_data.len() {
+            let name = read_string(strings, read_u32(type_data, offset)?);
+            let info = read_u32(type_data, offset + 4)?;
+            let size_or_type = read_u32(type_data, offset + 8)?;
+            let vlen = (info & 0xffff) as usize;
+            let kind = (info >> 24) & 0x1f;
+            let kind_flag = info >> 31 == 1;
+            offset += BTF_TYPE_LEN;
+
+            let ty = match kind {
+                BTF_KIND_INT => {
+                    let extra = read_u32(type_data, offset)?;
+                    offset += 4;
+                    BtfType::Int {
+                        name,
+                        size: size_or_type,
+                        encoding: (extra >> 24) & 0x0f,
+                        offset: (extra >> 16) & 0xff,
+                        bits: extra & 0xff,
+                    }
+                }
+                BTF_KIND_PTR => BtfType::Ptr {
+                    type_id: size_or_type,
+                },
+                BTF_KIND_ARRAY => {
+                    let ty = BtfType::Array {
+                        type_id: read_u32(type_data, offset)?,
+                        nelems: read_u32(type_data, offset + 8)?,
+                    };
+                    offset += 12;
+                    ty
+                }
+                BTF_KIND_STRUCT | BTF_KIND_UNION => {
+                    let mut members = Vec::with_capacity(vlen);
+                    for _ in 0..vlen {
+                        let member_offset = read_u32(type_data, offset + 8)?;
+                        let (bit_offset, bitfield_size) = if kind_flag {
+                            (member_offset & 0xff_ffff, member_offset >> 24)
+                        } else {
+                            (member_offset, 0)
+                        };
+                        members.push(Member {
+                            name: read_string(strings, read_u32(type_data, offset)?),
+                            type_id: read_u32(type_data, offset + 4)?,
+                            bit_offset,
+                            bitfield_size,
+                        });
+                        offset += 12;
+                    }
+                    BtfType::Struct {
+                        name,
+                        size: size_or_type,
+                        members,
+                        union: kind == BTF_KIND_UNION,
+                    }
+                }
+                BTF_KIND_ENUM | BTF_KIND_ENUM64 => {
+                    let mut variants = Vec::with_capacity(vlen);
+                    for _ in 0..vlen {
+                        let variant = read_string(strings, read_u32(type_data, offset)?);
+                        let value = if kind == BTF_KIND_ENUM {
+                            let v = read_u32(type_data, offset + 4)?;
+                            offset += 8;
+                            if kind_flag {
+                                v as i32 as i128
+                            } else {
+                                v as i128
+                            }
+                        } else {
+                            let lo = read_u32(type_data, offset + 4)? as u64;
+                            let hi = read_u32(type_data, offset + 8)? as u64;
+                            offset += 12;
+                            let v = hi << 32 | lo;
+                            if kind_flag {
+                                v as i64 as i128
+                            } else {
+                                v as i128
+                            }
+                        };
+                        variants.push((variant, value));
+                    }
+                    BtfType::Enum {
+                        name,
+                        size: size_or_type,
+                        signed: kind_flag,
+                        variants,
+                    }
+                }
+                BTF_KIND_TYPEDEF | BTF_KIND_VOLATILE | BTF_KIND_CONST | BTF_KIND_RESTRICT
+                | BTF_KIND_TYPE_TAG => BtfType::Alias {
+                    name: if kind == BTF_KIND_TYPEDEF {
+                        name
+                    } else {
+                        String::new()
+                    },
+                    type_id: size_or_type,
+                },
+                BTF_KIND_FLOAT => BtfType::Float {
+                    name,
+                    size: size_or_type,
+                },
+                BTF_KIND_FUNC_PROTO => {
+                    offset += 8 * vlen;
+                    BtfType::Other
+                }
+                BTF_KIND_VAR => {
+                    offset += 4;
+                    BtfType::Var {
+                        name,
+                        type_id: size_or_type,
+                    }
+                }
+                BTF_KIND_DECL_TAG => {
+                    offset += 4;
+                    BtfType::Other
+                }
+                BTF_KIND_DATASEC => {
+                    offset += 12 * vlen;
+                    BtfType::Other
+                }
+                _ => BtfType::Other,
+            };
+            types.push(ty);
+        }
+
+        Ok(Self { types })
+    }
+
+    fn type_by_id(&self, type_id: u32) -> Result<&BtfType, BpfmanError> {
+        self.types
+            .get(type_id as usize)
+            .ok_or_else(|| BpfmanError::Error(format!("BTF type {type_id} not found")))
+    }
+
+    // Follows typedefs and modifiers to the underlying type.
+    fn resolve(&self, mut type_id: u32) -> Result<&BtfType, BpfmanError> {
+        // Bound the number of hops in case of a malformed loop.
+        for _ in 0..32 {
+            match self.type_by_id(type_id)? {
+                BtfType::Alias { type_id: t, .. } => type_id = *t,
+                ty => return Ok(ty),
+            }
+        }
+        Err(BpfmanError::Error(format!(
+            "BTF type {type_id} could not be resolved"
+        )))
+    }
+
+    fn type_size(&self, type_id: u32) -> Result<usize, BpfmanError> {
+        Ok(match self.resolve(type_id)? {
+            BtfType::Int { size, .. }
+            | BtfType::Struct { size, .. }
+            | BtfType::Enum { size, .. }
+            | BtfType::Float { size, .. } => *size as usize,
+            BtfType::Ptr { .. } => std::mem::size_of::<usize>(),
+            BtfType::Array { type_id, nelems } => self.type_size(*type_id)? * *nelems as usize,
+            BtfType::Void | BtfType::Alias { .. } | BtfType::Var { .. } | BtfType::Other => 0,
+        })
+    }
+
+    /// Returns a C like name for a type, e.g. "struct datarec" or "__u32".
+    pub(crate) fn type_name(&self, type_id: u32) -> Result<String, BpfmanError> {
+        Ok(match self.type_by_id(type_id)? {
+            BtfType::Void => "void".to_string(),
+            BtfType::Int { name, .. } | BtfType::Float { name, .. } => name.clone(),
+            BtfType::Ptr { .. } => "pointer".to_string(),
+            BtfType::Array { type_id, nelems } => {
+                format!("{}[{nelems}]", self.type_name(*type_id)?)
+            }
+            BtfType::Struct { name, union, .. } => {
+                let kind = if *union { "union" } else { "struct" };
+                if name.is_empty() {
+                    format!("{kind} <anon>")
+                } else {
+                    format!("{kind} {name}")
+                }
+            }
+            BtfType::Enum { name, .. } => format!("enum {name}"),
+            BtfType::Alias { name, type_id } => {
+                if name.is_empty() {
+                    self.type_name(*type_id)?
+                } else {
+                    name.clone()
+                }
+            }
+            BtfType::Var { .. } | BtfType::Other => "unknown".to_string(),
+        })
+    }
+
+    /// Returns the members of the struct the named variable is declared with,
+    /// read as libbpf's `__uint(name, value)` declarations, which encode the
+    /// value as the length of the array a member points to. Members declared
+    /// otherwise are skipped. None is returned if there's no such variable.
+    pub(crate) fn uint_members(
+        &self,
+        var_name: &str,
+    ) -> Result<Option<Vec<(String, u32)>>, BpfmanError> {
+        let Some(type_id) = self.types.iter().find_map(|t| match t {
+            BtfType::Var { name, type_id } if name == var_name => Some(*type_id),
+            _ => None,
+        }) else {
+            return Ok(None);
+        };
+        let BtfType::Struct { members, .. } = self.resolve(type_id)? else {
+            return Ok(None);
+        };
+
+        let mut values = vec![];
+        for m in members {
+            let BtfType::Ptr { type_id } = self.resolve(m.type_id)? else {
+                continue;
+            };
+            if let BtfType::Array { nelems, .. } = self.resolve(*type_id)? {
+                values.push((m.name.clone(), *nelems));
+            }
+        }
+        Ok(Some(values))
+    }
+
+    fn is_char(&self, type_id: u32) -> Result<bool, BpfmanError> {
+        Ok(match self.resolve(type_id)? {
+            BtfType::Int {
+                name,
+                size,
+                encoding,
+                ..
+            } => *size == 1 && (*encoding & BTF_INT_CHAR != 0 || name == "char"),
+            _ => false,
+        })
+    }
+
+    /// Formats the bytes of a value of the given type as JSON.
+    pub(crate) fn format(&self, type_id: u32, data: &[u8]) -> Result<Value, BpfmanError> {
+        if data.len() < self.type_size(type_id)? {
+            return Ok(to_hex(data));
+        }
+
+        Ok(match self.resolve(type_id)? {
+            BtfType::Int {
+                size,
+                encoding,
+                offset,
+                bits,
+                ..
+            } => {
+                let size = *size as usize;
+                let raw = if size > 8 {
+                    None
+                } else if *offset == 0 && *bits as usize == size * 8 {
+                    Some(read_uint(&data[..size]))
+                } else {
+                    Some(read_bits(data, *offset, *bits))
+                };
+                match raw {
+                    None => to_hex(&data[..size]),
+                    Some(raw) if *encoding & BTF_INT_BOOL != 0 => Value::Bool(raw != 0),
+                    Some(raw) if *encoding & BTF_INT_SIGNED != 0 => {
+                        Value::from(sign_extend(raw, *bits))
+                    }
+                    Some(raw) => Value::from(raw),
+                }
+            }
+            BtfType::Ptr { .. } => {
+                let size = std::mem::size_of::<usize>();
+                Value::from(read_uint(&data[..size]))
+            }
+            BtfType::Array { type_id, nelems } => {
+                let elem_size = self.type_size(*type_id)?;
+                let len = elem_size * *nelems as usize;
+                if self.is_char(*type_id)? {
+                    let bytes = data[..len].split(|c| *c == 0).next().unwrap_or_default();
+                    if let Ok(s) = std::str::from_utf8(bytes) {
+                        return Ok(Value::String(s.to_string()));
+                    }
+                }
+                if elem_size == 0 {
+                    return Ok(Value::Array(vec![]));
+                }
+                Value::Array(
+                    data[..len]
+                        .chunks(elem_size)
+                        .map(|c| self.format(*type_id, c))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            BtfType::Struct { members, .. } => {
+                let mut object = serde_json::Map::new();
+                for m in members {
+                    let value = if m.bitfield_size != 0 {
+                        let raw = read_bits(data, m.bit_offset, m.bitfield_size);
+                        match self.resolve(m.type_id)? {
+                            BtfType::Int { encoding, .. } if *encoding & BTF_INT_SIGNED != 0 => {
+                                Value::from(sign_extend(raw, m.bitfield_size))
+                            }
+                            _ => Value::from(raw),
+                        }
+                    } else {
+                        let start = (m.bit_offset / 8) as usize;
+                        self.format(m.type_id, &data[start..])?
+                    };
+                    object.insert(m.name.clone(), value);
+                }
+                Value::Object(object)
+            }
+            BtfType::Enum {
+                size,
+                signed,
+                variants,
+                ..
+            } => {
+                let size = *size as usize;
+                let raw = read_uint(&data[..size]);
+                let value = if *signed {
+                    sign_extend(raw, size as u32 * 8) as i128
+                } else {
+                    raw as i128
+                };
+                match variants.iter().find(|(_, v)| *v == value) {
+                    Some((name, _)) => Value::String(name.clone()),
+                    None => Value::from(value as i64),
+                }
+            }
+            BtfType::Float { size, .. } => match size {
+                4 => Value::from(f32::from_ne_bytes(data[..4].try_into().unwrap()) as f64),
+                8 => Value::from(f64::from_ne_bytes(data[..8].try_into().unwrap())),
+                _ => to_hex(&data[..*size as usize]),
+            },
+            BtfType::Void | BtfType::Alias { .. } | BtfType::Var { .. } | BtfType::Other => {
+                to_hex(data)
+            }
+        })
+    }
+
+    /// Builds the bytes of a value of the given type from JSON. A "0x"
+    /// prefixed hex string is accepted in place of any value.
+    pub(crate) fn build(&self, type_id: u32, value: &Value) -> Result<Vec<u8>, BpfmanError> {
+        let size = self.type_size(type_id)?;
+        if let Some(bytes) = value.as_str().and_then(parse_hex) {
+            if bytes.len() != size {
+                return Err(BpfmanError::Error(format!(
+                    "{} is {size} bytes, got {} bytes",
+                    self.type_name(type_id)?,
+                    bytes.len()
+                )));
+            }
+            return Ok(bytes);
+        }
+
+        let name = self.type_name(type_id)?;
+        let mut data = vec![0; size];
+        match self.resolve(type_id)? {
+            BtfType::Int {
+                size,
+                encoding,
+                offset,
+                bits,
+                ..
+            } => {
+                let raw = if *encoding & BTF_INT_BOOL != 0 {
+                    value.as_bool().map(u64::from)
+                } else {
+                    json_to_u64(value)
+                }
+                .ok_or_else(|| invalid(&name, value))?;
+                if *offset == 0 && *bits as usize == *size as usize * 8 {
+                    write_uint(&mut data, raw);
+                } else {
+                    write_bits(&mut data, *offset, *bits, raw);
+                }
+            }
+            BtfType::Ptr { .. } => write_uint(
+                &mut data,
+                json_to_u64(value).ok_or_else(|| invalid(&name, value))?,
+            ),
+            BtfType::Array { type_id, nelems } => {
+                let elem_size = self.type_size(*type_id)?;
+                if let (Some(s), true) = (value.as_str(), self.is_char(*type_id)?) {
+                    if s.len() > *nelems as usize {
+                        return Err(invalid(&name, value));
+                    }
+                    data[..s.len()].copy_from_slice(s.as_bytes());
+                } else {
+                    let elems = value.as_array().ok_or_else(|| invalid(&name, value))?;
+                    if elems.len() > *nelems as usize {
+                        return Err(invalid(&name, value));
+                    }
+                    for (i, e) in elems.iter().enumerate() {
+                        let bytes = self.build(*type_id, e)?;
+                        data[i * elem_size..(i + 1) * elem_size].copy_from_slice(&bytes);
+                    }
+                }
+            }
+            BtfType::Struct { members, union, .. } => {
+                let object = value.as_object().ok_or_else(|| invalid(&name, value))?;
+                if *union && object.len() > 1 {
+                    return Err(BpfmanError::Error(format!(
+                        "only one member of {name} can be set"
+                    )));
+                }
+                for (key, v) in object {
+                    let m = members.iter().find(|m| m.name == *key).ok_or_else(|| {
+                        BpfmanError::Error(format!("{name} has no member named {key}"))
+                    })?;
+                    if m.bitfield_size != 0 {
+                        let raw = json_to_u64(v).ok_or_else(|| invalid(key, v))?;
+                        write_bits(&mut data, m.bit_offset, m.bitfield_size, raw);
+                    } else {
+                        let bytes = self.build(m.type_id, v)?;
+                        let start = (m.bit_offset / 8) as usize;
+                        data[start..start + bytes.len()].copy_from_slice(&bytes);
+                    }
+                }
+            }
+            BtfType::Enum { variants, .. } => {
+                let raw = match value.as_str() {
+                    Some(s) => variants
+                        .iter()
+                        .find(|(n, _)| n == s)
+                        .map(|(_, v)| *v as u64),
+                    None => json_to_u64(value),
+                }
+                .ok_or_else(|| invalid(&name, value))?;
+                write_uint(&mut data, raw);
+            }
+            BtfType::Float { size, .. } => {
+                let v = value.as_f64().ok_or_else(|| invalid(&name, value))?;
+                match size {
+                    4 => data.copy_from_slice(&(v as f32).to_ne_bytes()),
+                    8 => data.copy_from_slice(&v.to_ne_bytes()),
+                    _ => return Err(invalid(&name, value)),
+                }
+            }
+            BtfType::Void | BtfType::Alias { .. } | BtfType::Var { .. } | BtfType::Other => {
+                return Err(BpfmanError::Error(format!(
+                    "{name} values must be given as a 0x prefixed hex string"
+                )))
+            }
+        }
+        Ok(data)
+    }
+}
+
+// Signed values are accepted as well, and stored in two's complement.
+fn json_to_u64(value: &Value) -> Option<u64> {
+    value.as_u64().or_else(|| value.as_i64().map(|v| v as u64))
+}
+
+fn read_uint(data: &[u8]) -> u64 {
+    let mut bytes = [0; 8];
+    if cfg!(target_endian = "little") {
+        bytes[..data.len()].copy_from_slice(data);
+        u64::from_le_bytes(bytes)
+    } else {
+        bytes[8 - data.len()..].copy_from_slice(data);
+        u64::from_be_bytes(bytes)
+    }
+}
+
+fn write_uint(data: &mut [u8], value: u64) {
+    let len = data.len().min(8);
+    if cfg!(target_endian = "little") {
+        data[..len].copy_from_slice(&value.to_le_bytes()[..len]);
+    } else {
+        data[..len].copy_from_slice(&value.to_be_bytes()[8 - len..]);
+    }
+}
+
+// Bitfields are read and written a bit at a time, counting bits from the least
+// significant bit of the first byte, which matches the layout used by clang on
+// little endian hosts.
+fn read_bits(data: &[u8], bit_offset: u32, bits: u32) -> u64 {
+    let mut value = 0;
+    for i in 0..bits.min(64) {
+        let bit = bit_offset + i;
+        if data
+            .get((bit / 8) as usize)
+            .is_some_and(|b| b >> (bit % 8) & 1 == 1)
+        {
+            value |= 1 << i;
+        }
+    }
+    value
+}
+
+fn write_bits(data: &mut [u8], bit_offset: u32, bits: u32, value: u64) {
+    for i in 0..bits.min(64) {
+        let bit = bit_offset + i;
+        if let Some(b) = data.get_mut((bit / 8) as usize) {
+            if value >> i & 1 == 1 {
+                *b |= 1 << (bit % 8);
+            } else {
+                *b &= !(1 << (bit % 8));
+            }
+        }
+    }
+}
+
+fn sign_extend(value: u64, bits: u32) -> i64 {
+    if bits == 0 || bits >= 64 {
+        return value as i64;
+    }
+    let shift = 64 - bits;
+    ((value << shift) as i64) >> shift
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    // Encodes a BTF blob from (name_off, info, size_or_type, extra) entries.
+    fn btf_blob(types: &[(u32, u32, u32, Vec<u32>)], strings: &[u8]) -> Vec<u8> {
+        let mut type_data = vec![];
+        for (name_off, info, size_or_type, extra) in types {
+            for v in [*name_off, *info, *size_or_type].iter().chain(extra) {
+                type_data.extend_from_slice(&v.to_ne_bytes());
+            }
+        }
+        let mut data = vec![];
+        data.extend_from_slice(&BTF_MAGIC.to_ne_bytes());
+        data.extend_from_slice(&[1, 0]);
+        for v in [
+            BTF_HEADER_LEN as u32,
+            0,
+            type_data.len() as u32,
+            type_data.len() as u32,
+            strings.len() as u32,
+        ] {
+            data.extend_from_slice(&v.to_ne_bytes());
+        }
+        data.extend_from_slice(&type_data);
+        data.extend_from_slice(strings);
+        data
+    }
+
+    // struct datarec { __u32 rx_packets; char comm[4]; int delta; }
+    fn datarec_btf() -> Btf {
+        let strings = b"\0__u32\0char\0datarec\0rx_packets\0comm\0int\0delta\0";
+        let types = vec![
+            // 1: __u32
+            (1, BTF_KIND_INT << 24, 4, vec![32]),
+            // 2: char
+            (7, BTF_KIND_INT << 24, 1, vec![(BTF_INT_SIGNED << 24) | 8]),
+            // 3: char[4]
+            (0, BTF_KIND_ARRAY << 24, 0, vec![2, 1, 4]),
+            // 4: int
+            (36, BTF_KIND_INT << 24, 4, vec![(BTF_INT_SIGNED << 24) | 32]),
+            // 5: struct datarec
+            (
+                12,
+                BTF_KIND_STRUCT << 24 | 3,
+                12,
+                vec![20, 1, 0, 31, 3, 32, 40, 4, 64],
+            ),
+        ];
+        Btf::parse(&btf_blob(&types, strings)).unwrap()
+    }
+
+    #[test]
+    fn test_format_and_build_struct() {
+        let btf = datarec_btf();
+        assert_eq!(btf.type_name(5).unwrap(), "struct datarec");
+
+        let value = json!({"rx_packets": 42, "comm": "abc", "delta": -2});
+        let bytes = btf.build(5, &value).unwrap();
+        assert_eq!(bytes.len(), 12);
+        assert_eq!(btf.format(5, &bytes).unwrap(), value);
+    }
+
+    #[test]
+    fn test_uint_members() {
+        // struct { __uint(priority, 10); __uint(XDP_PASS, 1); } _xdp_pass;
+        let strings = b"\0int\0priority\0XDP_PASS\0_xdp_pass\0";
+        let types = vec![
+            // 1: int
+            (1, BTF_KIND_INT << 24, 4, vec![(BTF_INT_SIGNED << 24) | 32]),
+            // 2: int[10]
+            (0, BTF_KIND_ARRAY << 24, 0, vec![1, 1, 10]),
+            // 3: int (*)[10]
+            (0, BTF_KIND_PTR << 24, 2, vec![]),
+            // 4: int[1]
+            (0, BTF_KIND_ARRAY << 24, 0, vec![1, 1, 1]),
+            // 5: int (*)[1]
+            (0, BTF_KIND_PTR << 24, 4, vec![]),
+            // 6: struct <anon>
+            (0, BTF_KIND_STRUCT << 24 | 2, 16, vec![5, 3, 0, 14, 5, 64]),
+            // 7: _xdp_pass
+            (23, BTF_KIND_VAR << 24, 6, vec![1]),
+        ];
+        let btf = Btf::parse(&btf_blob(&types, strings)).unwrap();
+        assert_eq!(
+            btf.uint_members("_xdp_pass").unwrap(),
+            Some(vec![
+                ("priority".to_string(), 10),
+                ("XDP_PASS".to_string(), 1)
+            ])
+        );
+        assert_eq!(btf.uint_members("_xdp_drop").unwrap(), None);
+    }
+
+    #[test]
+    fn test_build_errors() {
+        let btf = datarec_btf();
+        assert!(btf.build(5, &json!({"missing": 1})).is_err());
+        assert!(btf.build(1, &json!("0x0102")).is_err());
+        assert_eq!(btf.build(1, &json!("0x01020304")).unwrap(), [1, 2, 3, 4]);
+    }
+}
@@ -0,0 +1,312 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! Access to the maps pinned by bpfman for the programs it loads.
+
+pub(crate) mod btf;
+
+use std::{
+    io, mem,
+    os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd},
+    path::Path,
+};
+
+use aya::{maps::MapData, util::nr_cpus};
+use aya_obj::generated::{bpf_attr, bpf_btf_info, bpf_cmd, bpf_map_info, bpf_map_type};
+use log::debug;
+use nix::libc::ENOENT;
+use serde_json::Value;
+
+use crate::{
+    errors::BpfmanError,
+    maps::btf::Btf,
+    sys::{obj_get_info_by_fd, sys_bpf},
+    types::{MapEntry, ProgramMap},
+};
+
+// Update the entry whether or not it already exists.
+const BPF_ANY: u64 = 0;
+
+// Loads the BTF object with the given id from the kernel.
+fn load_btf(btf_id: u32) -> io::Result<Vec<u8>> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    attr.__bindgen_anon_6.__bindgen_anon_1.btf_id = btf_id;
+    let fd = sys_bpf(bpf_cmd::BPF_BTF_GET_FD_BY_ID, &mut attr)?;
+    // SAFETY: BPF_BTF_GET_FD_BY_ID returns a new file descriptor.
+    let fd = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+
+    let mut info = unsafe { mem::zeroed::<bpf_btf_info>() };
+    obj_get_info_by_fd(fd.as_fd(), &mut info)?;
+
+    let mut data = vec![0u8; info.btf_size as usize];
+    let mut info = unsafe { mem::zeroed::<bpf_btf_info>() };
+    info.btf = data.as_mut_ptr() as u64;
+    info.btf_size = data.len() as u32;
+    obj_get_info_by_fd(fd.as_fd(), &mut info)?;
+
+    Ok(data)
+}
+
+/// A map pinned by bpfman, opened from its pin.
+pub(crate) struct PinnedMap {
+    name: String,
+    pin_path: String,
+    map: MapData,
+    info: bpf_map_info,
+    btf: Option<Btf>,
+    num_cpus: usize,
+}
+
+impl PinnedMap {
+    pub(crate) fn open(name: &str, pin_path: &Path) -> Result<Self, BpfmanError> {
+        let map = MapData::from_pin(pin_path)
+            .map_err(|e| BpfmanError::Error(format!("unable to open map {name}: {e}")))?;
+
+        let mut info = unsafe { mem::zeroed::<bpf_map_info>() };
+        obj_get_info_by_fd(map.fd().as_fd(), &mut info)?;
+
+        // Maps without BTF, or with BTF that can't be parsed, are handled as
+        // raw bytes.
+        let btf = if info.btf_id != 0 && info.btf_value_type_id != 0 {
+            match load_btf(info.btf_id)
+                .map_err(BpfmanError::from)
+                .and_then(|data| Btf::parse(&data))
+            {
+                Ok(btf) => Some(btf),
+                Err(e) => {
+                    debug!("unable to use BTF for map {name}: {e}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let num_cpus = if is_per_cpu(info.type_) {
+            nr_cpus()?
+        } else {
+            1
+        };
+
+        Ok(Self {
+            name: name.to_string(),
+            pin_path: pin_path.to_string_lossy().to_string(),
+            map,
+            info,
+            btf,
+            num_cpus,
+        })
+    }
+
+    pub(crate) fn info(&self) -> ProgramMap {
+        let type_name = |type_id| match (&self.btf, type_id) {
+            (Some(btf), id) if id != 0 => btf.type_name(id).ok(),
+            _ => None,
+        };
+
+        ProgramMap {
+            name: self.name.clone(),
+            map_id: self.info.id,
+            map_type: self.info.type_,
+            key_size: self.info.key_size,
+            value_size: self.info.value_size,
+            max_entries: self.info.max_entries,
+            map_flags: self.info.map_flags,
+            pin_path: self.pin_path.clone(),
+            key_type: type_name(self.info.btf_key_type_id),
+            value_type: type_name(self.info.btf_value_type_id),
+        }
+    }
+
+    fn fd(&self) -> BorrowedFd<'_> {
+        self.map.fd().as_fd()
+    }
+
+    // Per-CPU values are padded to 8 bytes for each possible CPU.
+    fn value_stride(&self) -> usize {
+        if is_per_cpu(self.info.type_) {
+            (self.info.value_size as usize + 7) & !7
+        } else {
+            self.info.value_size as usize
+        }
+    }
+
+    fn lookup(&self, key: &[u8]) -> Result<Option<Vec<u8>>, BpfmanError> {
+        let mut value = vec![0u8; self.value_stride() * self.num_cpus];
+        let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+        let u = unsafe { &mut attr.__bindgen_anon_2 };
+        u.map_fd = self.fd().as_raw_fd() as u32;
+        u.key = key.as_ptr() as u64;
+        u.__bindgen_anon_1.value = value.as_mut_ptr() as u64;
+
+        match sys_bpf(bpf_cmd::BPF_MAP_LOOKUP_ELEM, &mut attr) {
+            Ok(_) => Ok(Some(value)),
+            Err(e) if e.raw_os_error() == Some(ENOENT) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn next_key(&self, key: Option<&[u8]>) -> Result<Option<Vec<u8>>, BpfmanError> {
+        let mut next_key = vec![0u8; self.info.key_size as usize];
+        let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+        let u = unsafe { &mut attr.__bindgen_anon_2 };
+        u.map_fd = self.fd().as_raw_fd() as u32;
+        if let Some(key) = key {
+            u.key = key.as_ptr() as u64;
+        }
+        u.__bindgen_anon_1.next_key = next_key.as_mut_ptr() as u64;
+
+        match sys_bpf(bpf_cmd::BPF_MAP_GET_NEXT_KEY, &mut attr) {
+            Ok(_) => Ok(Some(next_key)),
+            Err(e) if e.raw_os_error() == Some(ENOENT) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Returns all of the entries in the map.
+    pub(crate) fn entries(&self) -> Result<Vec<MapEntry>, BpfmanError> {
+        let mut entries = vec![];
+        let mut key = None;
+        while let Some(next) = self.next_key(key.as_deref())? {
+            // The entry may have been deleted since the key was returned.
+            if let Some(value) = self.lookup(&next)? {
+                entries.push(self.entry(next.clone(), value));
+            }
+            key = Some(next);
+        }
+        Ok(entries)
+    }
+
+    fn entry(&self, key: Vec<u8>, value: Vec<u8>) -> MapEntry {
+        let value_size = self.info.value_size as usize;
+        let stride = self.value_stride();
+        let values: Vec<&[u8]> = value
+            .chunks(stride.max(1))
+            .map(|v| &v[..value_size.min(v.len())])
+            .collect();
+
+        let (formatted_key, formatted_value) = match &self.btf {
+            Some(btf) => {
+                let format = |type_id, data: &[u8]| {
+                    (type_id != 0)
+                        .then(|| btf.format(type_id, data).ok())
+                        .flatten()
+                };
+                let formatted_key = format(self.info.btf_key_type_id, &key);
+                let formatted_value = if is_per_cpu(self.info.type_) {
+                    values
+                        .iter()
+                        .map(|v| format(self.info.btf_value_type_id, v))
+                        .collect::<Option<Vec<Value>>>()
+                        .map(Value::Array)
+                } else {
+                    values
+                        .first()
+                        .and_then(|v| format(self.info.btf_value_type_id, v))
+                };
+                (
+                    formatted_key.map(|v| v.to_string()),
+                    formatted_value.map(|v| v.to_string()),
+                )
+            }
+            None => (None, None),
+        };
+
+        MapEntry {
+            key,
+            value: values.concat(),
+            formatted_key,
+            formatted_value,
+        }
+    }
+
+    // Parses a key or value given as JSON when the map has BTF for it, and as
+    // a hex string, with or without a "0x" prefix, otherwise.
+    fn parse(
+        &self,
+        what: &str,
+        type_id: u32,
+        size: u32,
+        input: &str,
+    ) -> Result<Vec<u8>, BpfmanError> {
+        let bytes = match (&self.btf, type_id) {
+            (Some(btf), id) if id != 0 => {
+                let value = serde_json::from_str(input)
+                    .unwrap_or_else(|_| Value::String(input.to_string()));
+                btf.build(id, &value)?
+            }
+            _ => hex::decode(input.trim_start_matches("0x").replace(' ', "")).map_err(|e| {
+                BpfmanError::Error(format!("{what} {input} is not a valid hex string: {e}"))
+            })?,
+        };
+
+        if bytes.len() != size as usize {
+            return Err(BpfmanError::Error(format!(
+                "{what} for map {} must be {size} bytes, got {} bytes",
+                self.name,
+                bytes.len()
+            )));
+        }
+        Ok(bytes)
+    }
+
+    /// Sets the value of an entry, creating it if it doesn't exist. The value
+    /// of an entry in a per-CPU map is set on every CPU.
+    pub(crate) fn update(&self, key: &str, value: &str) -> Result<(), BpfmanError> {
+        let key = self.parse("key", self.info.btf_key_type_id, self.info.key_size, key)?;
+        let value = self.parse(
+            "value",
+            self.info.btf_value_type_id,
+            self.info.value_size,
+            value,
+        )?;
+
+        let stride = self.value_stride();
+        let mut values = vec![0u8; stride * self.num_cpus];
+        for chunk in values.chunks_mut(stride) {
+            chunk[..value.len()].copy_from_slice(&value);
+        }
+
+        let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+        let u = unsafe { &mut attr.__bindgen_anon_2 };
+        u.map_fd = self.fd().as_raw_fd() as u32;
+        u.key = key.as_ptr() as u64;
+        u.__bindgen_anon_1.value = values.as_ptr() as u64;
+        u.flags = BPF_ANY;
+
+        sys_bpf(bpf_cmd::BPF_MAP_UPDATE_ELEM, &mut attr).map_err(|e| {
+            BpfmanError::Error(format!("unable to update entry in map {}: {e}", self.name))
+        })?;
+        Ok(())
+    }
+
+    /// Deletes an entry.
+    pub(crate) fn delete(&self, key: &str) -> Result<(), BpfmanError> {
+        let key = self.parse("key", self.info.btf_key_type_id, self.info.key_size, key)?;
+
+        let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+        let u = unsafe { &mut attr.__bindgen_anon_2 };
+        u.map_fd = self.fd().as_raw_fd() as u32;
+        u.key = key.as_ptr() as u64;
+
+        sys_bpf(bpf_cmd::BPF_MAP_DELETE_ELEM, &mut attr).map_err(|e| {
+            if e.raw_os_error() == Some(ENOENT) {
+                BpfmanError::Error(format!("entry not found in map {}", self.name))
+            } else {
+                BpfmanError::Error(format!("unable to delete entry in map {}: {e}", self.name))
+            }
+        })?;
+        Ok(())
+    }
+}
+
+fn is_per_cpu(map_type: u32) -> bool {
+    [
+        bpf_map_type::BPF_MAP_TYPE_PERCPU_HASH,
+        bpf_map_type::BPF_MAP_TYPE_PERCPU_ARRAY,
+        bpf_map_type::BPF_MAP_TYPE_LRU_PERCPU_HASH,
+        bpf_map_type::BPF_MAP_TYPE_PERCPU_CGROUP_STORAGE,
+    ]
+    .iter()
+    .any(|t| *t as u32 == map_type)
+}
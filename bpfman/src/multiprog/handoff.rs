@@ -0,0 +1,242 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! The program which hands a packet on from a TC dispatcher to the next one
+//! in its chain. It replaces the stub in the slot after the dispatcher's
+//! programs, which returns TC_DISPATCHER_RETVAL. cls_bpf treats that unknown
+//! code as TC_ACT_UNSPEC, but TCX and netkit stop running the hook on it, so
+//! the replacement returns TC_ACT_UNSPEC itself, which is also TCX_NEXT and
+//! NETKIT_NEXT.
+//!
+//! The program is two instructions, so instead of being pulled from an image
+//! its object is built here, along with the BTF the kernel needs to check it
+//! against the stub.
+
+use object::{
+    write::{Object, Symbol, SymbolSection},
+    Architecture, BinaryFormat, Endianness, SectionKind, SymbolFlags, SymbolKind, SymbolScope,
+};
+
+pub(crate) const HANDOFF_PROGRAM_NAME: &str = "tc_handoff";
+const HANDOFF_SECTION: &str = "classifier";
+const TC_ACT_UNSPEC: i32 = -1;
+
+const BTF_MAGIC: u16 = 0xeb9f;
+const BTF_VERSION: u8 = 1;
+const BTF_HEADER_LEN: u32 = 24;
+const BTF_KIND_INT: u32 = 1;
+const BTF_KIND_PTR: u32 = 2;
+const BTF_KIND_STRUCT: u32 = 4;
+const BTF_KIND_FUNC: u32 = 12;
+const BTF_KIND_FUNC_PROTO: u32 = 13;
+const BTF_INT_SIGNED: u32 = 1;
+const BTF_FUNC_GLOBAL: u32 = 1;
+// The id of the function in the BTF built by btf().
+const BTF_FUNC_ID: u32 = 5;
+
+/// Returns the object holding the hand-off program.
+pub(crate) fn handoff_object() -> Vec<u8> {
+    // r0 = TC_ACT_UNSPEC; exit
+    let mut code = vec![0xb7, 0, 0, 0];
+    code.extend(TC_ACT_UNSPEC.to_ne_bytes());
+    code.extend([0x95, 0, 0, 0, 0, 0, 0, 0]);
+    classifier_object(HANDOFF_PROGRAM_NAME, &code)
+}
+
+// Builds an object with a single global classifier function with the same
+// signature as the dispatcher's stubs, int (struct __sk_buff *skb).
+fn classifier_object(fn_name: &str, code: &[u8]) -> Vec<u8> {
+    let mut obj = Object::new(BinaryFormat::Elf, Architecture::Bpf, Endianness::default());
+    let license = obj.add_section(vec![], b"license".to_vec(), SectionKind::Data);
+    obj.append_section_data(license, b"GPL\0", 1);
+    let section = obj.add_section(
+        vec![],
+        HANDOFF_SECTION.as_bytes().to_vec(),
+        SectionKind::Text,
+    );
+    obj.append_section_data(section, code, 8);
+    obj.add_symbol(Symbol {
+        name: fn_name.as_bytes().to_vec(),
+        value: 0,
+        size: code.len() as u64,
+        kind: SymbolKind::Text,
+        scope: SymbolScope::Dynamic,
+        weak: false,
+        section: SymbolSection::Section(section),
+        flags: SymbolFlags::None,
+    });
+
+    let (btf, section_name_off) = btf(fn_name);
+    let btf_section = obj.add_section(vec![], b".BTF".to_vec(), SectionKind::Other);
+    obj.append_section_data(btf_section, &btf, 4);
+    let btf_ext_section = obj.add_section(vec![], b".BTF.ext".to_vec(), SectionKind::Other);
+    obj.append_section_data(btf_ext_section, &btf_ext(section_name_off), 4);
+
+    obj.write()
+        .expect("an object with one function should be writable")
+}
+
+// Returns the BTF describing the function, and the offset of the name of its
+// section in the BTF's strings.
+fn btf(fn_name: &str) -> (Vec<u8>, u32) {
+    let mut strings = vec![0];
+    let mut add_string = |s: &str| {
+        let offset = strings.len() as u32;
+        strings.extend(s.as_bytes());
+        strings.push(0);
+        offset
+    };
+    let int_name = add_string("int");
+    let sk_buff_name = add_string("__sk_buff");
+    let skb_name = add_string("skb");
+    let fn_name = add_string(fn_name);
+    let section_name = add_string(HANDOFF_SECTION);
+
+    // Each type is its name, info and size or type, followed by the data of
+    // its kind. Ids start at 1.
+    let types = [
+        // [1] int
+        int_name,
+        BTF_KIND_INT << 24,
+        4,
+        BTF_INT_SIGNED << 24 | 32,
+        // [2] struct __sk_buff, without members since the kernel only
+        // compares the names of context structs
+        sk_buff_name,
+        BTF_KIND_STRUCT << 24,
+        0,
+        // [3] struct __sk_buff *
+        0,
+        BTF_KIND_PTR << 24,
+        2,
+        // [4] int (struct __sk_buff *skb)
+        0,
+        BTF_KIND_FUNC_PROTO << 24 | 1,
+        1,
+        skb_name,
+        3,
+        // [5] the function
+        fn_name,
+        BTF_KIND_FUNC << 24 | BTF_FUNC_GLOBAL,
+        4,
+    ];
+    let types: Vec<u8> = types.iter().flat_map(|v| v.to_ne_bytes()).collect();
+
+    let mut btf = header(BTF_HEADER_LEN);
+    for v in [
+        0,
+        types.len() as u32,
+        types.len() as u32,
+        strings.len() as u32,
+    ] {
+        btf.extend(v.to_ne_bytes());
+    }
+    btf.extend(types);
+    btf.extend(strings);
+    (btf, section_name)
+}
+
+// Returns the BTF.ext with the func_info of the function, and no line_info.
+fn btf_ext(section_name_off: u32) -> Vec<u8> {
+    // The record size, then the section's name and number of records, and a
+    // record with the function's offset in bytes and type id.
+    let func_info = [8, section_name_off, 1, 0, BTF_FUNC_ID];
+    let func_info_len = (func_info.len() * 4) as u32;
+
+    let mut btf_ext = header(BTF_HEADER_LEN);
+    for v in [0, func_info_len, func_info_len, 0] {
+        btf_ext.extend(v.to_ne_bytes());
+    }
+    btf_ext.extend(func_info.iter().flat_map(|v| v.to_ne_bytes()));
+    btf_ext
+}
+
+fn header(hdr_len: u32) -> Vec<u8> {
+    let mut header = BTF_MAGIC.to_ne_bytes().to_vec();
+    header.extend([BTF_VERSION, 0]);
+    header.extend(hdr_len.to_ne_bytes());
+    header
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io, mem,
+        os::fd::{AsFd, AsRawFd},
+    };
+
+    use aya::{
+        programs::{Extension, ProgramError, SchedClassifier},
+        BpfLoader,
+    };
+    use aya_obj::generated::{bpf_attr, bpf_cmd};
+
+    use super::*;
+    use crate::sys;
+
+    // Runs the classifier on an empty ethernet frame and returns its result.
+    fn test_run(prog: &SchedClassifier) -> io::Result<i32> {
+        let data = [0u8; 64];
+        let fd = prog.fd().unwrap();
+        let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+        attr.test.prog_fd = fd.as_fd().as_raw_fd() as u32;
+        attr.test.data_in = data.as_ptr() as u64;
+        attr.test.data_size_in = data.len() as u32;
+        attr.test.repeat = 1;
+        sys::sys_bpf(bpf_cmd::BPF_PROG_TEST_RUN, &mut attr)?;
+        // SAFETY: BPF_PROG_TEST_RUN fills in the test fields.
+        Ok(unsafe { attr.test.retval } as i32)
+    }
+
+    // Loading programs takes CAP_BPF, and extensions CAP_PERFMON as well.
+    fn permitted(result: Result<(), ProgramError>) -> bool {
+        match result {
+            Err(ProgramError::LoadError { io_error, .. })
+                if io_error.raw_os_error() == Some(nix::libc::EPERM) =>
+            {
+                false
+            }
+            r => {
+                r.unwrap();
+                true
+            }
+        }
+    }
+
+    #[test]
+    fn test_handoff() {
+        // A stand-in for the dispatcher's stub: r0 = 30; exit
+        let mut code = vec![0xb7, 0, 0, 0];
+        code.extend(30i32.to_ne_bytes());
+        code.extend([0x95, 0, 0, 0, 0, 0, 0, 0]);
+        let mut stub_loader = BpfLoader::new()
+            .load(&classifier_object("prog0", &code))
+            .unwrap();
+        let stub: &mut SchedClassifier = stub_loader
+            .program_mut("prog0")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        if !permitted(stub.load()) {
+            return;
+        }
+        assert_eq!(test_run(stub).unwrap(), 30);
+
+        let mut handoff_loader = BpfLoader::new()
+            .extension(HANDOFF_PROGRAM_NAME)
+            .load(&handoff_object())
+            .unwrap();
+        let handoff: &mut Extension = handoff_loader
+            .program_mut(HANDOFF_PROGRAM_NAME)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        if !permitted(handoff.load(stub.fd().unwrap().try_clone().unwrap(), "prog0")) {
+            return;
+        }
+        handoff.attach().unwrap();
+
+        // TCX and netkit go on to the next program on TC_ACT_UNSPEC.
+        assert_eq!(test_run(stub).unwrap(), TC_ACT_UNSPEC);
+    }
+}
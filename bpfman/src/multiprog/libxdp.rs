@@ -0,0 +1,329 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! Interoperability with libxdp, which xdp-tools load XDP programs with.
+//! libxdp runs the programs on an interface behind a dispatcher with the same
+//! configuration as bpfman's, and keeps its state in bpffs:
+//!
+//! - `<bpffs>/xdp` is locked with flock while a dispatcher is changed.
+//! - `<bpffs>/xdp/dispatch-<ifindex>-<dispatcher id>` holds the program in
+//!   each enabled slot of the dispatcher as `prog<slot>-prog`, and the link
+//!   attaching it as `prog<slot>-link`.
+//! - The priorities and proceed-on masks of the slots are read back from the
+//!   dispatcher's .rodata map.
+
+use std::{
+    fs::{self, File},
+    mem,
+    os::fd::{AsFd, BorrowedFd},
+    path::{Path, PathBuf},
+};
+
+use aya::{maps::MapData, programs::ProgramInfo};
+use nix::fcntl::{Flock, FlockArg};
+use object::{Object, ObjectSection};
+
+use crate::{
+    dispatcher_config::XdpDispatcherConfig, errors::BpfmanError, maps::btf::Btf, sys,
+    types::XdpProceedOn,
+};
+
+const LIBXDP_DIR: &str = "xdp";
+const XDP_DISPATCHER_MAGIC: u8 = 236;
+const XDP_DISPATCHER_VERSION: u8 = 2;
+const XDP_DISPATCHER_RETVAL: i32 = 31;
+
+// The XDP actions a run config can proceed on, in the order of their values.
+const XDP_ACTIONS: [&str; 5] = [
+    "XDP_ABORTED",
+    "XDP_DROP",
+    "XDP_PASS",
+    "XDP_TX",
+    "XDP_REDIRECT",
+];
+
+/// Holds libxdp's lock until it's dropped.
+pub(crate) struct Lock {
+    _lock: Flock<File>,
+}
+
+/// Takes libxdp's lock, waiting for libxdp to release it.
+pub(crate) fn lock(bpffs: &Path) -> Result<Lock, BpfmanError> {
+    let dir = bpffs.join(LIBXDP_DIR);
+    let failed = |e: String| BpfmanError::Error(format!("unable to lock {}: {e}", dir.display()));
+    fs::create_dir_all(&dir).map_err(|e| failed(e.to_string()))?;
+    let file = File::open(&dir).map_err(|e| failed(e.to_string()))?;
+    Flock::lock(file, FlockArg::LockExclusive)
+        .map(|lock| Lock { _lock: lock })
+        .map_err(|(_, e)| failed(e.to_string()))
+}
+
+/// Returns the directory libxdp keeps the programs of a dispatcher in.
+pub(crate) fn dispatch_dir(bpffs: &Path, if_index: u32, dispatcher_id: u32) -> PathBuf {
+    bpffs
+        .join(LIBXDP_DIR)
+        .join(format!("dispatch-{if_index}-{dispatcher_id}"))
+}
+
+/// Pins the program in a slot of a dispatcher where libxdp expects it, and
+/// returns where the link attaching it is expected.
+pub(crate) fn pin_slot(
+    dir: &Path,
+    slot: usize,
+    prog_fd: BorrowedFd<'_>,
+) -> Result<PathBuf, BpfmanError> {
+    fs::create_dir_all(dir)?;
+    let (prog_pin, link_pin) = slot_pins(dir, slot);
+    sys::obj_pin(prog_fd, &prog_pin)?;
+    Ok(link_pin)
+}
+
+// Returns where libxdp pins the program in a slot of a dispatcher, and the
+// link attaching it.
+fn slot_pins(dir: &Path, slot: usize) -> (PathBuf, PathBuf) {
+    (
+        dir.join(format!("prog{slot}-prog")),
+        dir.join(format!("prog{slot}-link")),
+    )
+}
+
+/// A program in a slot of a dispatcher, as libxdp sees it.
+#[derive(Debug, Clone)]
+pub(crate) struct Component {
+    pub(crate) program_id: u32,
+    pub(crate) name: String,
+    pub(crate) run_prio: u32,
+    pub(crate) chain_call_actions: u32,
+    pub(crate) flags: u32,
+    pub(crate) pin_path: PathBuf,
+}
+
+/// Returns the programs in the enabled slots of a dispatcher, in the order
+/// they run, or None if libxdp doesn't know the dispatcher.
+pub(crate) fn read_chain(
+    bpffs: &Path,
+    if_index: u32,
+    dispatcher_id: u32,
+) -> Result<Option<Vec<Component>>, BpfmanError> {
+    let dir = dispatch_dir(bpffs, if_index, dispatcher_id);
+    if !dir.exists() {
+        return Ok(None);
+    }
+    let config = dispatcher_config(dispatcher_id)?;
+
+    (0..config.num_progs_enabled as usize)
+        .map(|i| {
+            let (pin_path, _) = slot_pins(&dir, i);
+            let info = ProgramInfo::from_pin(&pin_path).map_err(|e| {
+                BpfmanError::Error(format!(
+                    "unable to open {} of libxdp dispatcher {dispatcher_id}: {e}",
+                    pin_path.display()
+                ))
+            })?;
+            Ok(Component {
+                program_id: info.id(),
+                name: info.name_as_str().unwrap_or_default().to_string(),
+                run_prio: config.run_prios[i],
+                chain_call_actions: config.chain_call_actions[i],
+                flags: config.program_flags[i],
+                pin_path,
+            })
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+// Reads the configuration of a dispatcher from its .rodata map, which holds
+// nothing else.
+fn dispatcher_config(dispatcher_id: u32) -> Result<XdpDispatcherConfig, BpfmanError> {
+    let fd = sys::prog_get_fd_by_id(dispatcher_id)?;
+    for map_id in sys::prog_map_ids(fd.as_fd())? {
+        let map = MapData::from_id(map_id)
+            .map_err(|e| BpfmanError::Error(format!("unable to open map {map_id}: {e}")))?;
+        let info = map
+            .info()
+            .map_err(|e| BpfmanError::Error(format!("unable to open map {map_id}: {e}")))?;
+        if !info.name_as_str().is_some_and(|n| n.ends_with(".rodata")) {
+            continue;
+        }
+
+        let mut value = vec![0u8; info.value_size() as usize];
+        sys::map_lookup_elem(map.fd().as_fd(), &0u32.to_ne_bytes(), &mut value)?;
+        if let Some(config) = parse_config(dispatcher_id, &value)? {
+            return Ok(config);
+        }
+        break;
+    }
+    Err(BpfmanError::Error(format!(
+        "program {dispatcher_id} is not an XDP dispatcher"
+    )))
+}
+
+// Parses the configuration of a dispatcher from the value of its .rodata map,
+// or returns None if the value is too short to hold one.
+fn parse_config(
+    dispatcher_id: u32,
+    value: &[u8],
+) -> Result<Option<XdpDispatcherConfig>, BpfmanError> {
+    if value.len() < mem::size_of::<XdpDispatcherConfig>() {
+        return Ok(None);
+    }
+    // SAFETY: XdpDispatcherConfig is plain old data, and the value is at
+    // least as long.
+    let config: XdpDispatcherConfig =
+        unsafe { std::ptr::read_unaligned(value.as_ptr() as *const _) };
+    if config.magic != XDP_DISPATCHER_MAGIC || config.dispatcher_version != XDP_DISPATCHER_VERSION {
+        return Err(BpfmanError::Error(format!(
+            "XDP dispatcher {dispatcher_id} has an unsupported version {}",
+            config.dispatcher_version
+        )));
+    }
+    Ok(Some(config))
+}
+
+/// The priority and proceed-on actions from a program's XDP_RUN_CONFIG.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct RunConfig {
+    pub(crate) priority: Option<i32>,
+    pub(crate) proceed_on: Option<XdpProceedOn>,
+}
+
+/// Returns the XDP_RUN_CONFIG libxdp reads for a function of the object, if
+/// it has one. It's a variable named after the function with an underscore
+/// prefix, declared with the priority and the actions to proceed on. libxdp
+/// always proceeds on dispatcher_return too, so it's added to the actions.
+pub(crate) fn run_config(bytes: &[u8], func_name: &str) -> Result<Option<RunConfig>, BpfmanError> {
+    let obj = object::File::parse(bytes)
+        .map_err(|e| BpfmanError::Error(format!("unable to parse program bytes: {e}")))?;
+    let Some(section) = obj.section_by_name(".BTF") else {
+        return Ok(None);
+    };
+    let data = section
+        .data()
+        .map_err(|e| BpfmanError::Error(format!("unable to read BTF: {e}")))?;
+    let Some(members) = Btf::parse(data)?.uint_members(&format!("_{func_name}"))? else {
+        return Ok(None);
+    };
+    parse_run_config(members).map(Some)
+}
+
+// Builds a run config from the members of an XDP_RUN_CONFIG and their values.
+fn parse_run_config(members: Vec<(String, u32)>) -> Result<RunConfig, BpfmanError> {
+    let mut priority = None;
+    let mut actions = vec![];
+    for (name, value) in members {
+        if name == "priority" {
+            priority = Some(value as i32);
+        } else if let Some(action) = XDP_ACTIONS.iter().position(|a| *a == name) {
+            if value != 0 {
+                actions.push(action as i32);
+            }
+        }
+    }
+    let proceed_on = if actions.is_empty() {
+        None
+    } else {
+        actions.push(XDP_DISPATCHER_RETVAL);
+        Some(XdpProceedOn::from_int32s(actions).map_err(|e| BpfmanError::Error(e.to_string()))?)
+    };
+    Ok(RunConfig {
+        priority,
+        proceed_on,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::dispatcher_config::MAX_DISPATCHER_ACTIONS;
+
+    fn config_bytes(config: &XdpDispatcherConfig) -> Vec<u8> {
+        // SAFETY: XdpDispatcherConfig is plain old data.
+        unsafe {
+            std::slice::from_raw_parts(
+                (config as *const XdpDispatcherConfig).cast::<u8>(),
+                mem::size_of::<XdpDispatcherConfig>(),
+            )
+        }
+        .to_vec()
+    }
+
+    #[test]
+    fn test_pin_layout() {
+        let dir = dispatch_dir(Path::new("/sys/fs/bpf"), 3, 42);
+        assert_eq!(dir, PathBuf::from("/sys/fs/bpf/xdp/dispatch-3-42"));
+        assert_eq!(
+            slot_pins(&dir, 2),
+            (
+                PathBuf::from("/sys/fs/bpf/xdp/dispatch-3-42/prog2-prog"),
+                PathBuf::from("/sys/fs/bpf/xdp/dispatch-3-42/prog2-link"),
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_config() {
+        let mut chain_call_actions = [0; MAX_DISPATCHER_ACTIONS];
+        chain_call_actions[0] = 1 << 2 | 1 << XDP_DISPATCHER_RETVAL;
+        chain_call_actions[1] = 1 << 1;
+        let mut run_prios = [0; MAX_DISPATCHER_ACTIONS];
+        run_prios[0] = 10;
+        run_prios[1] = 20;
+        let config = XdpDispatcherConfig::new(
+            2,
+            0,
+            chain_call_actions,
+            run_prios,
+            [0; MAX_DISPATCHER_ACTIONS],
+        );
+        let mut value = config_bytes(&config);
+        // Anything after the config in .rodata is left alone.
+        value.extend([0; 8]);
+
+        let parsed = parse_config(1, &value).unwrap().unwrap();
+        assert_eq!(parsed.num_progs_enabled, 2);
+        assert_eq!(parsed.chain_call_actions, chain_call_actions);
+        assert_eq!(parsed.run_prios, run_prios);
+
+        assert!(parse_config(1, &value[..value.len() - 9])
+            .unwrap()
+            .is_none());
+
+        let mut old = config;
+        old.dispatcher_version = 1;
+        assert!(parse_config(1, &config_bytes(&old)).is_err());
+    }
+
+    #[test]
+    fn test_parse_run_config() {
+        let members = |m: &[(&str, u32)]| {
+            m.iter()
+                .map(|(n, v)| (n.to_string(), *v))
+                .collect::<Vec<_>>()
+        };
+
+        let config = parse_run_config(members(&[
+            ("priority", 10),
+            ("XDP_PASS", 1),
+            ("XDP_DROP", 0),
+            ("XDP_REDIRECT", 1),
+        ]))
+        .unwrap();
+        assert_eq!(config.priority, Some(10));
+        assert_eq!(
+            config.proceed_on.unwrap().as_action_vec(),
+            vec![2, 4, XDP_DISPATCHER_RETVAL]
+        );
+
+        let config = parse_run_config(members(&[("priority", 5), ("XDP_DROP", 0)])).unwrap();
+        assert_eq!(config.priority, Some(5));
+        assert!(config.proceed_on.is_none());
+
+        let config = parse_run_config(members(&[("XDP_ABORTED", 1)])).unwrap();
+        assert_eq!(config.priority, None);
+        assert_eq!(
+            config.proceed_on.unwrap().as_action_vec(),
+            vec![0, XDP_DISPATCHER_RETVAL]
+        );
+    }
+}
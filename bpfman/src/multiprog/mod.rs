@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
+mod handoff;
+pub(crate) mod libxdp;
 mod tc;
 mod xdp;
 
@@ -10,10 +12,10 @@ pub use tc::TcDispatcher;
 pub use xdp::XdpDispatcher;
 
 use crate::{
-    config::{InterfaceConfig, XdpMode},
+    config::{InterfaceConfig, LibxdpConfig, XdpMode},
     errors::BpfmanError,
     oci_utils::image_manager::ImageManager,
-    types::{Direction, Program, ProgramType},
+    types::{Direction, DispatcherSlot, DispatcherState, Program, ProgramType},
     utils::bytes_to_string,
 };
 
@@ -34,6 +36,7 @@ impl Dispatcher {
         revision: u32,
         old_dispatcher: Option<Dispatcher>,
         image_manager: &mut ImageManager,
+        libxdp: &LibxdpConfig,
     ) -> Result<Dispatcher, BpfmanError> {
         debug!("Dispatcher::new()");
         let p = programs
@@ -44,31 +47,59 @@ impl Dispatcher {
             .ok_or_else(|| BpfmanError::Error("missing ifindex".to_string()))?;
         let if_name = p.if_name()?;
         let direction = p.direction()?;
-        let xdp_mode = if let Some(c) = config {
-            c.xdp_mode()
-        } else {
-            &XdpMode::Skb
-        };
+        let netns = p.netns()?;
+        let (xdp_mode, xdp_mode_fallback) = xdp_mode(config, programs)?;
+        let xdp_wide_dispatcher = config.is_some_and(|c| c.xdp_wide_dispatcher());
+        let tc_mode = config.map(|c| *c.tc_mode()).unwrap_or_default();
+        let foreign_policy = config.map(|c| c.foreign_policy()).unwrap_or_default();
+        // libxdp only manages interfaces in its own network namespace, which
+        // is bpfman's.
+        let libxdp = libxdp.interop_bpffs().filter(|_| netns.is_none());
         let d = match p.kind() {
             ProgramType::Xdp => {
-                let mut x =
-                    XdpDispatcher::new(root_db, xdp_mode, if_index, if_name.to_string(), revision)?;
+                let mut x = XdpDispatcher::new(
+                    root_db,
+                    &xdp_mode,
+                    xdp_mode_fallback,
+                    if_index,
+                    if_name.to_string(),
+                    netns,
+                    revision,
+                )?;
+                x.set_wide_dispatcher(xdp_wide_dispatcher)?;
 
-                x.load(root_db, programs, old_dispatcher, image_manager)
-                    .await?;
+                x.load(
+                    root_db,
+                    programs,
+                    old_dispatcher,
+                    image_manager,
+                    foreign_policy,
+                    libxdp,
+                )
+                .await?;
                 Dispatcher::Xdp(x)
             }
             ProgramType::Tc => {
+                let tc_mode = TcDispatcher::resolve_mode(tc_mode, if_index, netns.as_ref()).await;
                 let mut t = TcDispatcher::new(
                     root_db,
+                    tc_mode,
                     direction.expect("missing direction"),
                     if_index,
                     if_name.to_string(),
+                    netns,
                     revision,
                 )?;
 
-                t.load(root_db, programs, old_dispatcher, image_manager)
-                    .await?;
+                t.load(
+                    root_db,
+                    programs,
+                    old_dispatcher,
+                    image_manager,
+                    foreign_policy,
+                    config.map(|c| c.tc_foreign_order()).unwrap_or_default(),
+                )
+                .await?;
                 Dispatcher::Tc(t)
             }
             _ => return Err(BpfmanError::DispatcherNotRequired),
@@ -92,6 +123,65 @@ impl Dispatcher {
         }
     }
 
+    // Removes a dispatcher once no programs are left on its hook, attaching
+    // the programs it took over from something else again.
+    pub(crate) async fn remove(
+        &mut self,
+        root_db: &Db,
+        image_manager: &mut ImageManager,
+    ) -> Result<(), BpfmanError> {
+        debug!("Dispatcher::remove()");
+        match self {
+            Dispatcher::Xdp(d) => d.remove(root_db, image_manager).await,
+            Dispatcher::Tc(d) => d.remove(root_db),
+        }
+    }
+
+    // Returns the ids of the programs attached to the dispatcher's hook by
+    // something other than bpfman, and whether the dispatcher took each one
+    // over.
+    pub(crate) async fn foreign(&self) -> Result<Vec<(u32, bool)>, BpfmanError> {
+        match self {
+            Dispatcher::Xdp(d) => Ok(d
+                .foreign_programs(None)
+                .await?
+                .unwrap_or_default()
+                .into_iter()
+                .map(|(id, _)| (id, false))
+                .collect()),
+            Dispatcher::Tc(d) => {
+                let adopted = d.get_adopted()?;
+                Ok(d.foreign_programs(None)
+                    .await?
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|p| (p.0, adopted.contains(&p)))
+                    .collect())
+            }
+        }
+    }
+
+    // Removes a dispatcher whose interface is gone. The kernel has already
+    // detached it from the interface, so only its database entry and bpffs
+    // state are left to remove.
+    pub(crate) fn delete_detached(&mut self, root_db: &Db) -> Result<(), BpfmanError> {
+        debug!("Dispatcher::delete_detached()");
+        match self {
+            Dispatcher::Xdp(d) => d.delete(root_db, true),
+            Dispatcher::Tc(d) => d.delete(root_db, false),
+        }
+    }
+
+    // Returns true if the dispatcher's bpffs state still exists. It's lost when
+    // /run/bpfman is cleared, for example on reboot, while the database entry
+    // for the dispatcher persists.
+    pub(crate) fn is_pinned(&self) -> Result<bool, BpfmanError> {
+        match self {
+            Dispatcher::Xdp(d) => d.is_pinned(),
+            Dispatcher::Tc(d) => d.is_pinned(),
+        }
+    }
+
     pub(crate) fn next_revision(&self) -> u32 {
         let current = match self {
             Dispatcher::Xdp(d) => d
@@ -104,6 +194,54 @@ impl Dispatcher {
         current.wrapping_add(1)
     }
 
+    pub(crate) fn id(&self) -> Result<DispatcherId, BpfmanError> {
+        Ok(match self {
+            Dispatcher::Xdp(d) => DispatcherId::Xdp(DispatcherInfo(
+                d.get_ifindex()?,
+                None,
+                d.get_netns()?.map(|n| n.id),
+            )),
+            Dispatcher::Tc(d) => DispatcherId::Tc(DispatcherInfo(
+                d.get_ifindex()?,
+                Some(d.get_direction()?),
+                d.get_netns()?.map(|n| n.id),
+            )),
+        })
+    }
+
+    // Describes the dispatcher and the programs attached to it, each with the
+    // id of the link it was attached with, if any.
+    pub(crate) fn state(
+        &self,
+        programs: Vec<(Program, Option<u32>)>,
+    ) -> Result<DispatcherState, BpfmanError> {
+        let mut slots = vec![];
+        for (p, link_id) in programs.into_iter().filter(|(p, _)| p.attached()) {
+            let position = p
+                .position()?
+                .ok_or_else(|| BpfmanError::Error("missing position".to_string()))?;
+            slots.push((
+                position,
+                DispatcherSlot {
+                    slot: position as u32,
+                    program_id: p.get_data().get_id()?,
+                    name: p.get_data().get_name()?,
+                    priority: p.priority()?,
+                    link_id,
+                    disabled: p.disabled(),
+                    libxdp: false,
+                },
+            ));
+        }
+        slots.sort_by_key(|(position, _)| *position);
+        let slots = slots.into_iter().map(|(_, s)| s).collect();
+
+        match self {
+            Dispatcher::Xdp(d) => d.state(slots),
+            Dispatcher::Tc(d) => d.state(slots),
+        }
+    }
+
     pub(crate) fn num_extensions(&self) -> usize {
         match self {
             Dispatcher::Xdp(d) => d
@@ -122,5 +260,175 @@ pub(crate) enum DispatcherId {
     Tc(DispatcherInfo),
 }
 
+/// The interface index, direction and network namespace id of the hook a
+/// dispatcher is attached to.
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
-pub(crate) struct DispatcherInfo(pub u32, pub Option<Direction>);
+pub(crate) struct DispatcherInfo(pub u32, pub Option<Direction>, pub Option<u64>);
+
+// Returns the mode to attach an XDP dispatcher in, and whether it falls back
+// to skb mode. A mode given when an XDP program was loaded overrides the one
+// configured for the interface.
+fn xdp_mode(
+    config: Option<&InterfaceConfig>,
+    programs: &[Program],
+) -> Result<(XdpMode, bool), BpfmanError> {
+    for p in programs.iter() {
+        if let Program::Xdp(x) = p {
+            if let Some(mode) = x.get_mode()? {
+                return Ok((mode, x.get_mode_fallback()?));
+            }
+        }
+    }
+    Ok(config
+        .map(|c| (*c.xdp_mode(), c.xdp_mode_fallback()))
+        .unwrap_or((XdpMode::Skb, false)))
+}
+
+/// Names the interface of a dispatcher in its database tree and bpffs paths.
+/// Interfaces in bpfman's own network namespace keep the names they had
+/// before other namespaces were supported.
+pub(crate) fn if_key(if_index: u32, netns_id: Option<u64>) -> String {
+    match netns_id {
+        Some(id) => format!("ns{id}_{if_index}"),
+        None => if_index.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::{
+        get_db_config,
+        types::{Location, ProgramData, XdpProceedOn, XdpProgram},
+    };
+
+    fn xdp_program(name: &str, priority: i32) -> Program {
+        let data = ProgramData::new(
+            Location::File("/tmp/prog.o".to_string()),
+            name.to_string(),
+            HashMap::new(),
+            HashMap::new(),
+            None,
+        )
+        .unwrap();
+        Program::Xdp(
+            XdpProgram::new(
+                data,
+                priority,
+                "eth0".to_string(),
+                XdpProceedOn::default(),
+                None,
+            )
+            .unwrap(),
+        )
+    }
+
+    // Persists an XDP program on eth0 under the given id, as if it had been
+    // loaded with it.
+    fn saved_xdp_program(root_db: &Db, id: u32, position: Option<usize>, priority: i32) -> Program {
+        let mut program = xdp_program(&format!("prog{id}"), priority);
+        if let Some(position) = position {
+            program.set_position(position).unwrap();
+            program.set_attached();
+        }
+        let data = program.get_data_mut();
+        data.load(root_db).unwrap();
+        data.swap_tree(root_db, id).unwrap();
+        program
+    }
+
+    #[test]
+    fn test_xdp_mode() {
+        let config: InterfaceConfig =
+            toml::from_str("xdp_mode = \"drv\"\nxdp_mode_fallback = true").unwrap();
+        let with_mode = |mode: Option<XdpMode>| {
+            let mut program = xdp_program("prog", 50);
+            if let (Program::Xdp(p), Some(mode)) = (&mut program, mode) {
+                p.set_mode(mode, false).unwrap();
+            }
+            program
+        };
+
+        assert_eq!(
+            xdp_mode(None, &[with_mode(None)]).unwrap(),
+            (XdpMode::Skb, false)
+        );
+        assert_eq!(
+            xdp_mode(Some(&config), &[with_mode(None)]).unwrap(),
+            (XdpMode::Drv, true)
+        );
+        // The first program loaded with a mode decides.
+        assert_eq!(
+            xdp_mode(
+                Some(&config),
+                &[
+                    with_mode(None),
+                    with_mode(Some(XdpMode::Hw)),
+                    with_mode(Some(XdpMode::Skb))
+                ]
+            )
+            .unwrap(),
+            (XdpMode::Hw, false)
+        );
+    }
+
+    #[test]
+    fn test_dispatcher_state() {
+        let root_db = get_db_config().open().unwrap();
+        let mut dispatcher = XdpDispatcher::new(
+            &root_db,
+            &XdpMode::Skb,
+            false,
+            3,
+            "eth0".to_string(),
+            None,
+            1,
+        )
+        .unwrap();
+        dispatcher.set_num_extensions(3).unwrap();
+
+        let programs = vec![
+            (saved_xdp_program(&root_db, 1, Some(1), 20), None),
+            (saved_xdp_program(&root_db, 2, Some(0), 10), Some(7)),
+            // Programs that aren't attached yet don't have a slot.
+            (saved_xdp_program(&root_db, 3, None, 30), None),
+        ];
+
+        // Dispatchers created before their config was saved ran every
+        // extension.
+        let state = Dispatcher::Xdp(dispatcher).state(programs.clone()).unwrap();
+        assert_eq!(state.stages[0].num_progs_enabled, 3);
+        assert!(state.stages[0].chain_call_actions.is_empty());
+
+        let tree = root_db
+            .open_tree(format!("{XDP_DISPATCHER_PREFIX}_3_1"))
+            .unwrap();
+        let mut dispatcher = XdpDispatcher::new_from_db(tree);
+        dispatcher.set_num_progs_enabled(2).unwrap();
+        dispatcher
+            .set_chain_call_actions(&[1 << 2, 1 << 1])
+            .unwrap();
+        let state = Dispatcher::Xdp(dispatcher).state(programs).unwrap();
+
+        assert_eq!(state.program_type, ProgramType::Xdp);
+        assert_eq!(state.if_name, "eth0");
+        assert_eq!(state.if_index, 3);
+        assert_eq!(state.revision, 1);
+        assert_eq!(state.mode, "skb");
+        assert_eq!(state.stages.len(), 1);
+        let stage = &state.stages[0];
+        assert_eq!(stage.num_progs_enabled, 2);
+        assert_eq!(stage.chain_call_actions, vec![1 << 2, 1 << 1]);
+        let slots: Vec<_> = stage
+            .slots
+            .iter()
+            .map(|s| (s.slot, s.program_id, s.name.as_str(), s.priority, s.link_id))
+            .collect();
+        assert_eq!(
+            slots,
+            vec![(0, 2, "prog2", 10, Some(7)), (1, 1, "prog1", 20, None)]
+        );
+    }
+}
@@ -1,42 +1,62 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
-use std::{fs, mem};
+use std::{
+    fs, mem,
+    os::fd::{AsFd, BorrowedFd, OwnedFd},
+    path::Path,
+};
 
 use aya::{
     programs::{
         links::FdLink,
         tc::{self, SchedClassifierLink, TcOptions},
-        Extension, Link, SchedClassifier, TcAttachType,
+        Extension, Link, ProgramFd, SchedClassifier, TcAttachType,
     },
+    util::KernelVersion,
     Bpf, BpfLoader,
 };
-use futures::stream::TryStreamExt;
-use log::debug;
-use netlink_packet_route::tc::TcAttribute;
+use futures::stream::{StreamExt, TryStreamExt};
+use log::{debug, warn};
+use netlink_packet_core::{NetlinkMessage, NetlinkPayload, NLM_F_DUMP, NLM_F_REQUEST};
+use netlink_packet_route::{
+    link::{InfoKind, LinkAttribute, LinkInfo},
+    tc::{TcAttribute, TcHandle, TcMessage, TcOption},
+    RouteNetlinkMessage,
+};
+use netlink_packet_utils::nla::{Nla, NlasIterator};
 use sled::Db;
 
 use crate::{
-    calc_map_pin_path, create_map_pin_path,
+    calc_map_pin_path,
+    config::{ForeignPolicy, TcForeignOrder, TcMode},
+    create_map_pin_path,
     directories::*,
-    dispatcher_config::TcDispatcherConfig,
+    dispatcher_config::{TcDispatcherConfig, TC_MAX_DISPATCHERS, TC_MAX_DISPATCHER_ACTIONS},
     errors::BpfmanError,
-    multiprog::{Dispatcher, TC_DISPATCHER_PREFIX},
+    multiprog::{
+        handoff::{handoff_object, HANDOFF_PROGRAM_NAME},
+        if_key, Dispatcher, TC_DISPATCHER_PREFIX,
+    },
     oci_utils::image_manager::ImageManager,
+    sys,
     types::{
         BytecodeImage, Direction,
         Direction::{Egress, Ingress},
-        ImagePullPolicy, Program, TcProgram,
+        DispatcherSlot, DispatcherStage, DispatcherState, ImagePullPolicy, Netns, Program,
+        ProgramType, TcProgram,
     },
     utils::{
-        bytes_to_string, bytes_to_u16, bytes_to_u32, bytes_to_usize, should_map_be_pinned,
-        sled_get, sled_get_option, sled_insert,
+        bytes_to_bool, bytes_to_string, bytes_to_u16, bytes_to_u32, bytes_to_u32s, bytes_to_u64,
+        bytes_to_usize, should_map_be_pinned, sled_get, sled_get_option, sled_insert,
+        u32s_to_bytes, with_netns,
     },
 };
 
 const DEFAULT_PRIORITY: u32 = 50; // Default priority for user programs in the dispatcher
 const TC_DISPATCHER_PRIORITY: u16 = 50; // Default TC priority for TC Dispatcher
 const TC_DISPATCHER_PROGRAM_NAME: &str = "tc_dispatcher";
+const TC_DISPATCHER_LINK_PREFIX: &str = "dispatcher_link";
 
 /// These constants define the key of SLED DB
 const REVISION: &str = "revision";
@@ -47,38 +67,70 @@ const DIRECTION: &str = "direction";
 const NUM_EXTENSIONS: &str = "num_extension";
 const PROGRAM_NAME: &str = "program_name";
 const HANDLE: &str = "handle";
+const MODE: &str = "mode";
+const NUM_DISPATCHERS: &str = "num_dispatchers";
+const NETNS: &str = "netns";
+const NETNS_ID: &str = "netns_id";
+const NUM_PROGS_ENABLED: &str = "num_progs_enabled";
+const CHAIN_CALL_ACTIONS: &str = "chain_call_actions";
+const PROGRAM_IDS: &str = "program_ids";
+const RUN_LAST: &str = "run_last";
+const PREFIX_ADOPTED: &str = "adopted_";
+
+// The kind of filter TC programs are attached with through a qdisc, and the
+// attribute holding the program's id in its options.
+const BPF_FILTER_KIND: &str = "bpf";
+const TCA_BPF_ID: u16 = 11;
+
+/// TcAttachment is how a program found on a TC hook is attached to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TcAttachment {
+    /// A bpf filter of the clsact qdisc.
+    Filter { priority: u16, handle: u32 },
+    /// A TCX or netkit hook.
+    Mprog { attach_type: u32 },
+}
 
 #[derive(Debug)]
 pub struct TcDispatcher {
     db_tree: sled::Tree,
-    loader: Option<Bpf>,
+    loaders: Vec<Bpf>,
 }
 
 impl TcDispatcher {
     pub(crate) fn new(
         root_db: &Db,
+        mode: TcMode,
         direction: Direction,
         if_index: u32,
         if_name: String,
+        netns: Option<Netns>,
         revision: u32,
     ) -> Result<Self, BpfmanError> {
         let db_tree = root_db
             .open_tree(format!(
                 "{}_{}_{}_{}",
-                TC_DISPATCHER_PREFIX, if_index, direction, revision
+                TC_DISPATCHER_PREFIX,
+                if_key(if_index, netns.as_ref().map(|n| n.id)),
+                direction,
+                revision
             ))
             .expect("Unable to open tc dispatcher database tree");
 
         let mut dp = Self {
             db_tree,
-            loader: None,
+            loaders: vec![],
         };
 
         dp.set_ifindex(if_index)?;
         dp.set_ifname(&if_name)?;
+        dp.set_mode(mode)?;
         dp.set_direction(direction)?;
         dp.set_revision(revision)?;
         dp.set_priority(TC_DISPATCHER_PRIORITY)?;
+        if let Some(netns) = netns {
+            dp.set_netns(&netns)?;
+        }
         Ok(dp)
     }
 
@@ -86,7 +138,7 @@ impl TcDispatcher {
     pub(crate) fn new_from_db(db_tree: sled::Tree) -> Self {
         Self {
             db_tree,
-            loader: None,
+            loaders: vec![],
         }
     }
 
@@ -96,6 +148,8 @@ impl TcDispatcher {
         programs: &mut [Program],
         old_dispatcher: Option<Dispatcher>,
         image_manager: &mut ImageManager,
+        foreign_policy: ForeignPolicy,
+        foreign_order: TcForeignOrder,
     ) -> Result<(), BpfmanError> {
         let if_index = self.get_ifindex()?;
         let revision = self.get_revision()?;
@@ -109,18 +163,14 @@ impl TcDispatcher {
                 _ => panic!("All programs should be of type TC"),
             })
             .collect();
-        let mut chain_call_actions = [0; 10];
-        for v in extensions.iter() {
-            chain_call_actions[v.get_current_position()?.unwrap()] = v.get_proceed_on()?.mask()
-        }
+        extensions.sort_by(|a, b| {
+            a.get_current_position()
+                .unwrap()
+                .cmp(&b.get_current_position().unwrap())
+        });
 
-        let config = TcDispatcherConfig {
-            num_progs_enabled: extensions.len() as u8,
-            chain_call_actions,
-            run_prios: [DEFAULT_PRIORITY; 10],
-        };
+        let chain = chain_sizes(extensions.len());
 
-        debug!("tc dispatcher config: {:?}", config);
         let image = BytecodeImage::new(
             "quay.io/bpfman/tc-dispatcher:v1".to_string(),
             ImagePullPolicy::IfNotPresent as i32,
@@ -148,36 +198,328 @@ impl TcDispatcher {
 
         let program_bytes = image_manager.get_bytecode_from_image_store(root_db, path)?;
 
-        let mut loader = BpfLoader::new()
-            .set_global("CONFIG", &config, true)
-            .load(&program_bytes)?;
+        let disabled = extensions
+            .iter()
+            .map(|p| p.get_disabled())
+            .collect::<Result<Vec<bool>, BpfmanError>>()?;
+        let chain_progs_enabled = chain_progs_enabled(&chain, &disabled);
+
+        let mut program_ids = vec![];
+        let mut first = 0;
+        for (k, size) in chain.iter().enumerate() {
+            let mut chain_call_actions = [0; TC_MAX_DISPATCHER_ACTIONS];
+            for (i, v) in extensions[first..first + size].iter().enumerate() {
+                chain_call_actions[i] = v.get_proceed_on()?.mask()
+            }
 
-        let dispatcher: &mut SchedClassifier = loader
-            .program_mut(TC_DISPATCHER_PROGRAM_NAME)
-            .unwrap()
-            .try_into()?;
+            let config = TcDispatcherConfig {
+                num_progs_enabled: chain_progs_enabled[k] as u8,
+                chain_call_actions,
+                run_prios: [DEFAULT_PRIORITY; TC_MAX_DISPATCHER_ACTIONS],
+            };
 
-        dispatcher.load()?;
+            debug!("tc dispatcher {k} config: {:?}", config);
+            self.set_num_progs_enabled(k, config.num_progs_enabled)?;
+            self.set_chain_call_actions(k, &config.chain_call_actions)?;
+            let mut loader = BpfLoader::new()
+                .set_global("CONFIG", &config, true)
+                .load(&program_bytes)?;
+
+            let dispatcher: &mut SchedClassifier = loader
+                .program_mut(TC_DISPATCHER_PROGRAM_NAME)
+                .unwrap()
+                .try_into()?;
+
+            dispatcher.load()?;
+            program_ids.push(dispatcher.info()?.id());
+            self.loaders.push(loader);
+            first += size;
+        }
+        self.set_program_ids(&program_ids)?;
 
         let base = match direction {
             Ingress => RTDIR_FS_TC_INGRESS,
             Egress => RTDIR_FS_TC_EGRESS,
         };
-        let path = format!("{base}/dispatcher_{if_index}_{revision}");
+        let path = format!("{base}/dispatcher_{}_{revision}", self.if_key()?);
         fs::create_dir_all(path).unwrap();
 
-        self.loader = Some(loader);
         self.set_num_extensions(extensions.len())?;
+        self.set_num_dispatchers(chain.len())?;
         self.set_program_name(TC_DISPATCHER_PROGRAM_NAME)?;
 
-        self.attach_extensions(&mut extensions)?;
+        let old = match old_dispatcher.as_ref() {
+            Some(Dispatcher::Tc(old)) => Some(old),
+            _ => None,
+        };
+        let foreign = self
+            .handle_foreign(foreign_policy, foreign_order, old)
+            .await?;
+
+        self.attach_extensions(&mut extensions, &chain)?;
         self.attach(root_db, old_dispatcher).await?;
+        let adopted: Vec<u32> = self.get_adopted()?.into_iter().map(|(id, _)| id).collect();
+        for p in extensions.iter_mut() {
+            p.set_foreign_programs(&foreign, &adopted);
+        }
         Ok(())
     }
 
+    // Applies the policy for foreign programs before the dispatcher is
+    // attached to the hook, and returns the ones left on it. Programs taken
+    // over by an older revision stay with the dispatcher while they're still
+    // attached. Refusing only applies to the first revision, so the programs
+    // on a hook can still be changed once something else attaches to it.
+    async fn handle_foreign(
+        &mut self,
+        policy: ForeignPolicy,
+        order: TcForeignOrder,
+        old: Option<&TcDispatcher>,
+    ) -> Result<Vec<u32>, BpfmanError> {
+        self.set_run_last(order == TcForeignOrder::Last)?;
+        let Some(attached) = self.foreign_programs(old).await? else {
+            return Ok(vec![]);
+        };
+        let adopted = match old {
+            Some(old) => old.get_adopted()?,
+            None => vec![],
+        };
+        let (adopted, mut foreign): (Vec<_>, Vec<_>) =
+            attached.into_iter().partition(|p| adopted.contains(p));
+        for (id, attachment) in adopted {
+            self.set_adopted(id, attachment)?;
+        }
+        if foreign.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let iface = self.get_ifname()?;
+        let direction = self.get_direction()?;
+        let program_ids: Vec<u32> = foreign.iter().map(|(id, _)| *id).collect();
+        match policy {
+            ForeignPolicy::Refuse if old.is_none() => {
+                Err(BpfmanError::ForeignProgramsAttached { iface, program_ids })
+            }
+            ForeignPolicy::Refuse | ForeignPolicy::Coexist => {
+                warn!("interface {iface} {direction} has TC programs attached by something other than bpfman: {program_ids:?}");
+                self.order_qdisc_priority(&foreign, order)?;
+                Ok(program_ids)
+            }
+            ForeignPolicy::Takeover => {
+                // Filters are moved in the order they run, so they keep it.
+                foreign.sort_by_key(|(_, a)| match a {
+                    TcAttachment::Filter { priority, handle } => (*priority, *handle),
+                    TcAttachment::Mprog { .. } => (0, 0),
+                });
+                let must_move = foreign
+                    .iter()
+                    .map(|(_, a)| self.must_move(a))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut left = must_move.iter().filter(|m| **m).count() as u16;
+                for ((id, attachment), must_move) in foreign.into_iter().zip(must_move) {
+                    let attachment = if must_move {
+                        left -= 1;
+                        self.move_in_front(id, attachment, left + 1)?
+                    } else {
+                        attachment
+                    };
+                    self.set_adopted(id, attachment)?;
+                }
+                Ok(vec![])
+            }
+        }
+    }
+
+    // Moves dispatchers attached through the qdisc to run before or after the
+    // foreign filters.
+    fn order_qdisc_priority(
+        &mut self,
+        foreign: &[(u32, TcAttachment)],
+        order: TcForeignOrder,
+    ) -> Result<(), BpfmanError> {
+        match qdisc_priority(foreign, order, self.loaders.len() as u16) {
+            Some(p) => self.set_priority(p),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the programs attached to the dispatcher's hook by something
+    /// other than bpfman, whether through the clsact qdisc or a TCX or netkit
+    /// hook. Dispatchers loaded before their program ids were recorded can't
+    /// be told apart from foreign programs, so None is returned for them.
+    pub(crate) async fn foreign_programs(
+        &self,
+        old: Option<&TcDispatcher>,
+    ) -> Result<Option<Vec<(u32, TcAttachment)>>, BpfmanError> {
+        let mut own = vec![];
+        for d in std::iter::once(self).chain(old) {
+            match d.get_program_ids()? {
+                Some(ids) => own.extend(ids),
+                None => return Ok(None),
+            }
+        }
+
+        let iface = self.get_ifname()?;
+        let attached = attached_programs(
+            self.get_ifindex()?,
+            self.get_netns()?.as_ref(),
+            self.get_direction()?,
+            self.get_mode()?,
+        )
+        .await
+        .map_err(|e| {
+            BpfmanError::Error(format!(
+                "unable to get the TC programs on interface {iface}: {e}"
+            ))
+        })?;
+        Ok(Some(
+            attached
+                .into_iter()
+                .filter(|(id, _)| !own.contains(id))
+                .collect(),
+        ))
+    }
+
+    // Returns true if a program attached this way has to be moved to run in
+    // front of the dispatcher. TCX runs before the clsact qdisc, and the
+    // dispatchers attached with TCX or netkit are put after the programs they
+    // take over on their hook.
+    fn must_move(&self, attachment: &TcAttachment) -> Result<bool, BpfmanError> {
+        Ok(match (self.get_mode()?, attachment) {
+            (TcMode::Auto | TcMode::Qdisc, TcAttachment::Filter { priority, .. }) => {
+                *priority >= self.get_priority()?
+            }
+            (TcMode::Auto | TcMode::Qdisc | TcMode::Tcx, TcAttachment::Mprog { .. }) => false,
+            (TcMode::Tcx, TcAttachment::Filter { .. }) => true,
+            (TcMode::Netkit, TcAttachment::Mprog { attach_type }) => {
+                *attach_type != sys::BPF_NETKIT_PRIMARY && *attach_type != sys::BPF_NETKIT_PEER
+            }
+            (TcMode::Netkit, TcAttachment::Filter { .. }) => true,
+        })
+    }
+
+    // Attaches a foreign program in front of the dispatcher, and then detaches
+    // it from where it was, so it keeps running throughout. Filters go
+    // right before the dispatcher's priority, the nth from last one taking
+    // the nth priority before it, and with TCX they're put at the end of the
+    // hook, where the dispatcher is attached after them. There's nothing in
+    // front of a netkit dispatcher to move them to.
+    fn move_in_front(
+        &mut self,
+        id: u32,
+        attachment: TcAttachment,
+        nth_from_last: u16,
+    ) -> Result<TcAttachment, BpfmanError> {
+        let iface = self.get_ifname()?;
+        let if_index = self.get_ifindex()?;
+        let failed = |reason: String| BpfmanError::ForeignTakeoverFailed {
+            iface: iface.clone(),
+            program_id: id,
+            reason,
+        };
+        debug!("TcDispatcher::move_in_front() program {id} on interface {iface}");
+
+        let (TcMode::Auto | TcMode::Qdisc | TcMode::Tcx, TcAttachment::Filter { priority, handle }) =
+            (self.get_mode()?, attachment)
+        else {
+            return Err(failed(
+                "it can't be moved in front of a netkit dispatcher".to_string(),
+            ));
+        };
+        let attach_type = self.attach_type()?;
+        let tcx_attach_type = match self.get_direction()? {
+            Direction::Ingress => sys::BPF_TCX_INGRESS,
+            Direction::Egress => sys::BPF_TCX_EGRESS,
+        };
+        let detach_filter = |priority: u16, handle: u32| {
+            SchedClassifierLink::attached(&iface, attach_type, priority, handle)
+                .map_err(|e| BpfmanError::Error(e.to_string()))?
+                .detach()
+                .map_err(|e| BpfmanError::Error(e.to_string()))
+        };
+        let fd = sys::prog_get_fd_by_id(id).map_err(|e| failed(e.to_string()))?;
+
+        match self.get_mode()? {
+            TcMode::Tcx => with_netns(self.get_netns()?.as_ref(), || {
+                sys::mprog_attach(fd.as_fd(), if_index, tcx_attach_type)?;
+                if let Err(e) = detach_filter(priority, handle) {
+                    let _ = sys::mprog_detach(fd.as_fd(), if_index, tcx_attach_type);
+                    return Err(e);
+                }
+                Ok(TcAttachment::Mprog {
+                    attach_type: tcx_attach_type,
+                })
+            })
+            .map_err(|e| failed(e.to_string())),
+            _ => {
+                let new_priority = self
+                    .get_priority()?
+                    .checked_sub(nth_from_last)
+                    .filter(|p| *p > 0)
+                    .ok_or_else(|| {
+                        failed("there's no priority left in front of the dispatcher".to_string())
+                    })?;
+                // aya only loads a program that's already in the kernel from
+                // a pin, which is removed again once the filter is attached.
+                let path = self.adopted_pin_path(id)?;
+                sys::obj_pin(fd.as_fd(), Path::new(&path)).map_err(|e| failed(e.to_string()))?;
+                let moved = with_netns(self.get_netns()?.as_ref(), || {
+                    let mut prog = SchedClassifier::from_pin(&path)?;
+                    let link_id = prog.attach_with_options(
+                        &iface,
+                        attach_type,
+                        TcOptions {
+                            priority: new_priority,
+                            handle: 0,
+                        },
+                    )?;
+                    let link = prog.take_link(link_id)?;
+                    let new_handle = link.handle();
+                    // Filters stay on the qdisc once the program is dropped.
+                    mem::forget(link);
+                    if let Err(e) = detach_filter(priority, handle) {
+                        let _ = detach_filter(new_priority, new_handle);
+                        return Err(e);
+                    }
+                    Ok(TcAttachment::Filter {
+                        priority: new_priority,
+                        handle: new_handle,
+                    })
+                });
+                let _ = fs::remove_file(&path);
+                moved.map_err(|e| failed(e.to_string()))
+            }
+        }
+    }
+
+    /// Removes the dispatcher once there are no programs left on its hook.
+    /// The programs it took over stay attached in front of where it was.
+    pub(crate) fn remove(&mut self, root_db: &Db) -> Result<(), BpfmanError> {
+        self.delete(root_db, true)
+    }
+
+    fn adopted_pin_path(&self, id: u32) -> Result<String, BpfmanError> {
+        let base = match self.get_direction()? {
+            Direction::Ingress => RTDIR_FS_TC_INGRESS,
+            Direction::Egress => RTDIR_FS_TC_EGRESS,
+        };
+        Ok(format!("{base}/adopted_{}_{id}", self.if_key()?))
+    }
+
+    fn attach_type(&self) -> Result<TcAttachType, BpfmanError> {
+        Ok(match self.get_direction()? {
+            Direction::Ingress => TcAttachType::Ingress,
+            Direction::Egress => TcAttachType::Egress,
+        })
+    }
+
     /// has_qdisc returns true if the qdisc_name is found on the if_index.
-    async fn has_qdisc(qdisc_name: String, if_index: i32) -> Result<bool, anyhow::Error> {
-        let (connection, handle, _) = rtnetlink::new_connection().unwrap();
+    async fn has_qdisc(
+        qdisc_name: String,
+        if_index: i32,
+        netns: Option<&Netns>,
+    ) -> Result<bool, anyhow::Error> {
+        let (connection, handle, _) = with_netns(netns, || Ok(rtnetlink::new_connection()?))?;
         tokio::spawn(connection);
 
         let mut qdiscs = handle.qdisc().get().execute();
@@ -194,10 +536,146 @@ impl TcDispatcher {
         Ok(false)
     }
 
+    /// Resolves TcMode::Auto to the mode to use for the interface.
+    pub(crate) async fn resolve_mode(mode: TcMode, if_index: u32, netns: Option<&Netns>) -> TcMode {
+        if mode != TcMode::Auto {
+            return mode;
+        }
+
+        match TcDispatcher::is_netkit(if_index, netns).await {
+            Ok(true) => return TcMode::Netkit,
+            Ok(false) => (),
+            Err(e) => warn!("unable to get the link kind for if_index {if_index}: {e}"),
+        }
+
+        match KernelVersion::current() {
+            Ok(v) if v >= KernelVersion::new(6, 6, 0) => TcMode::Tcx,
+            _ => TcMode::Qdisc,
+        }
+    }
+
+    /// is_netkit returns true if the interface is a netkit device.
+    async fn is_netkit(if_index: u32, netns: Option<&Netns>) -> Result<bool, anyhow::Error> {
+        let (connection, handle, _) = with_netns(netns, || Ok(rtnetlink::new_connection()?))?;
+        tokio::spawn(connection);
+
+        let mut links = handle.link().get().match_index(if_index).execute();
+        while let Some(link) = links.try_next().await? {
+            for attr in link.attributes {
+                if let LinkAttribute::LinkInfo(infos) = attr {
+                    if infos.contains(&LinkInfo::Kind(InfoKind::Other("netkit".to_string()))) {
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    fn link_pin_path(&self, k: usize) -> Result<String, BpfmanError> {
+        let if_key = self.if_key()?;
+        let revision = self.get_revision()?;
+        let base = match self.get_direction()? {
+            Direction::Ingress => RTDIR_FS_TC_INGRESS,
+            Direction::Egress => RTDIR_FS_TC_EGRESS,
+        };
+        Ok(format!(
+            "{base}/dispatcher_{if_key}_{revision}/{TC_DISPATCHER_LINK_PREFIX}_{k}"
+        ))
+    }
+
     async fn attach(
         &mut self,
         root_db: &Db,
         old_dispatcher: Option<Dispatcher>,
+    ) -> Result<(), BpfmanError> {
+        match self.get_mode()? {
+            TcMode::Tcx | TcMode::Netkit => self.attach_link(root_db, old_dispatcher),
+            TcMode::Auto | TcMode::Qdisc => self.attach_qdisc(root_db, old_dispatcher).await,
+        }
+    }
+
+    // Attaches the dispatchers with TCX or netkit bpf_links. The first one of
+    // a new revision is anchored in front of the old revision's first link, so
+    // it takes over the old one's place on the hook without a gap, and each of
+    // the others goes right after the one before it. It goes at the end of the
+    // hook instead when programs were just taken over, since they were left
+    // in front of it there. The old revision is detached when its links are
+    // unpinned.
+    fn attach_link(
+        &mut self,
+        root_db: &Db,
+        old_dispatcher: Option<Dispatcher>,
+    ) -> Result<(), BpfmanError> {
+        let if_index = self.get_ifindex()?;
+        let revision = self.get_revision()?;
+        let mode = self.get_mode()?;
+        let program_name = self.get_program_name()?;
+        let netns = self.get_netns()?;
+
+        debug!(
+            "TcDispatcher::attach_link() for if_index {}, revision {}, mode {}",
+            if_index, revision, mode
+        );
+
+        let attach_type = match (mode, self.get_direction()?) {
+            (TcMode::Netkit, Direction::Ingress) => sys::BPF_NETKIT_PRIMARY,
+            (TcMode::Netkit, Direction::Egress) => sys::BPF_NETKIT_PEER,
+            (_, Direction::Ingress) => sys::BPF_TCX_INGRESS,
+            (_, Direction::Egress) => sys::BPF_TCX_EGRESS,
+        };
+
+        let old_adopted = match &old_dispatcher {
+            Some(Dispatcher::Tc(d)) => d.get_adopted()?,
+            _ => vec![],
+        };
+        let took_over = self.get_adopted()?.iter().any(|p| !old_adopted.contains(p));
+
+        let old_anchor: Option<OwnedFd> = match &old_dispatcher {
+            Some(Dispatcher::Tc(d)) if d.get_mode()? == mode && !took_over => {
+                sys::obj_get(Path::new(&d.link_pin_path(0)?)).ok()
+            }
+            _ => None,
+        };
+
+        let mut prev: Option<OwnedFd> = None;
+        for (k, loader) in self.loaders.iter().enumerate() {
+            let (flags, anchor): (u32, Option<BorrowedFd<'_>>) = match (&prev, &old_anchor) {
+                (Some(fd), _) => (sys::BPF_F_AFTER | sys::BPF_F_LINK, Some(fd.as_fd())),
+                (None, Some(fd)) => (sys::BPF_F_BEFORE | sys::BPF_F_LINK, Some(fd.as_fd())),
+                (None, None) if took_over || self.get_run_last()? => (sys::BPF_F_AFTER, None),
+                (None, None) => (sys::BPF_F_BEFORE, None),
+            };
+
+            let new_dispatcher: &SchedClassifier =
+                loader.program(program_name.as_str()).unwrap().try_into()?;
+
+            let prog_fd = new_dispatcher.fd()?.as_fd();
+            let link = with_netns(netns.as_ref(), || {
+                sys::mprog_link_create(prog_fd, if_index, attach_type, flags, anchor).map_err(|e| {
+                    BpfmanError::Error(format!(
+                        "unable to attach tc dispatcher with {mode} to if_index {if_index}: {e}"
+                    ))
+                })
+            })?;
+
+            sys::obj_pin(link.as_fd(), Path::new(&self.link_pin_path(k)?))
+                .map_err(|e| BpfmanError::Error(format!("Failed to pin link {e}")))?;
+            prev = Some(link);
+        }
+
+        if let Some(Dispatcher::Tc(mut d)) = old_dispatcher {
+            d.delete(root_db, true)?;
+        }
+
+        Ok(())
+    }
+
+    async fn attach_qdisc(
+        &mut self,
+        root_db: &Db,
+        old_dispatcher: Option<Dispatcher>,
     ) -> Result<(), BpfmanError> {
         let if_index = self.get_ifindex()?;
         let iface = self.get_ifname()?;
@@ -205,9 +683,10 @@ impl TcDispatcher {
         let revision = self.get_revision()?;
         let direction = self.get_direction()?;
         let program_name = self.get_program_name()?;
+        let netns = self.get_netns()?;
 
         debug!(
-            "TcDispatcher::attach() for if_index {}, revision {}",
+            "TcDispatcher::attach_qdisc() for if_index {}, revision {}",
             if_index, revision
         );
 
@@ -216,14 +695,16 @@ impl TcDispatcher {
         // qdisc, we return an error. If the qdisc is a clsact qdisc, we do nothing. Otherwise, we add a clsact qdisc.
 
         // no need to add a new clsact qdisc if one already exists.
-        if TcDispatcher::has_qdisc("clsact".to_string(), if_index as i32).await? {
+        if TcDispatcher::has_qdisc("clsact".to_string(), if_index as i32, netns.as_ref()).await? {
             debug!(
                 "clsact qdisc found for if_index {}, no need to add a new clsact qdisc",
                 if_index
             );
 
         // if ingress qdisc exists, return error.
-        } else if TcDispatcher::has_qdisc("ingress".to_string(), if_index as i32).await? {
+        } else if TcDispatcher::has_qdisc("ingress".to_string(), if_index as i32, netns.as_ref())
+            .await?
+        {
             debug!("ingress qdisc found for if_index {}", if_index);
             return Err(BpfmanError::InvalidAttach(format!(
                 "Ingress qdisc found for if_index {}",
@@ -233,158 +714,213 @@ impl TcDispatcher {
         // otherwise, add a new clsact qdisc.
         } else {
             debug!("No qdisc found for if_index {}, adding clsact", if_index);
-            let _ = tc::qdisc_add_clsact(&iface);
+            with_netns(netns.as_ref(), || {
+                let _ = tc::qdisc_add_clsact(&iface);
+                Ok(())
+            })?;
         }
 
-        let new_dispatcher: &mut SchedClassifier = self
-            .loader
-            .as_mut()
-            .ok_or(BpfmanError::NotLoaded)?
-            .program_mut(program_name.as_str())
-            .unwrap()
-            .try_into()?;
-
         let attach_type = match direction {
             Direction::Ingress => TcAttachType::Ingress,
             Direction::Egress => TcAttachType::Egress,
         };
 
-        let link_id = new_dispatcher.attach_with_options(
-            &iface,
-            attach_type,
-            TcOptions {
-                priority,
-                ..Default::default()
-            },
-        )?;
-
-        let link = new_dispatcher.take_link(link_id)?;
-        self.set_handle(link.handle())?;
-        mem::forget(link);
+        // The chained dispatchers run in priority order after the first one.
+        for k in 0..self.loaders.len() {
+            let new_dispatcher: &mut SchedClassifier = self.loaders[k]
+                .program_mut(program_name.as_str())
+                .unwrap()
+                .try_into()?;
+
+            let link_id = with_netns(netns.as_ref(), || {
+                new_dispatcher
+                    .attach_with_options(
+                        &iface,
+                        attach_type,
+                        TcOptions {
+                            priority: priority + k as u16,
+                            ..Default::default()
+                        },
+                    )
+                    .map_err(BpfmanError::from)
+            })?;
+
+            let link = new_dispatcher.take_link(link_id)?;
+            let handle = link.handle();
+            mem::forget(link);
+            self.set_handle(k, handle)?;
+        }
 
         if let Some(Dispatcher::Tc(mut d)) = old_dispatcher {
             // If the old dispatcher was not attached when the new dispatcher
             // was attached above, the new dispatcher may get the same handle
-            // as the old one had.  If this happens, the new dispatcher will get
-            // detached if we do a full delete, so don't do it.
-            if d.get_handle()? != self.get_handle()? {
-                d.delete(root_db, true)?;
-            } else {
-                d.delete(root_db, false)?;
+            // as the old one had.  If this happens, detaching the old one
+            // would detach the new one, so don't do it.
+            for k in 0..d.get_num_dispatchers()? {
+                if d.get_handle(k)? != self.get_handle(k)? {
+                    d.detach_qdisc_filter(k)?;
+                }
             }
+            d.delete(root_db, false)?;
         }
 
         Ok(())
     }
 
-    fn attach_extensions(&mut self, extensions: &mut [&mut TcProgram]) -> Result<(), BpfmanError> {
+    fn attach_extensions(
+        &mut self,
+        extensions: &mut [&mut TcProgram],
+        chain: &[usize],
+    ) -> Result<(), BpfmanError> {
         let if_index = self.get_ifindex()?;
         let revision = self.get_revision()?;
-        let direction = self.get_direction()?;
         let program_name = self.get_program_name()?;
 
         debug!(
             "TcDispatcher::attach_extensions() for if_index {}, revision {}",
             if_index, revision
         );
-        let dispatcher: &mut SchedClassifier = self
-            .loader
-            .as_mut()
+
+        let mut first = 0;
+        for (k, size) in chain.iter().enumerate() {
+            let dispatcher: &SchedClassifier = self.loaders[k]
+                .program(program_name.as_str())
+                .ok_or(BpfmanError::NotLoaded)?
+                .try_into()?;
+            let dispatcher_fd = dispatcher.fd()?.try_clone()?;
+
+            for (i, v) in extensions[first..first + size].iter_mut().enumerate() {
+                self.attach_extension(v, &dispatcher_fd, i)?;
+            }
+            if k < chain.len() - 1 {
+                self.attach_handoff(&dispatcher_fd, k, *size)?;
+            }
+            first += size;
+        }
+        Ok(())
+    }
+
+    // Replaces the stub in slot i of dispatcher k with the program which hands
+    // packets on to the next dispatcher in the chain.
+    fn attach_handoff(
+        &self,
+        dispatcher_fd: &ProgramFd,
+        k: usize,
+        i: usize,
+    ) -> Result<(), BpfmanError> {
+        let mut loader = BpfLoader::new()
+            .extension(HANDOFF_PROGRAM_NAME)
+            .load(&handoff_object())?;
+        let handoff: &mut Extension = loader
+            .program_mut(HANDOFF_PROGRAM_NAME)
             .ok_or(BpfmanError::NotLoaded)?
-            .program_mut(program_name.as_str())
-            .unwrap()
             .try_into()?;
+        handoff.load(dispatcher_fd.try_clone()?, &format!("prog{i}"))?;
+        let link_id = handoff.attach()?;
+        let link: FdLink = handoff.take_link(link_id)?.into();
+        let base = match self.get_direction()? {
+            Direction::Ingress => RTDIR_FS_TC_INGRESS,
+            Direction::Egress => RTDIR_FS_TC_EGRESS,
+        };
+        link.pin(format!(
+            "{base}/dispatcher_{}_{}/handoff_{k}",
+            self.if_key()?,
+            self.get_revision()?
+        ))
+        .map_err(BpfmanError::UnableToPinLink)?;
+        Ok(())
+    }
 
-        extensions.sort_by(|a, b| {
-            a.get_current_position()
-                .unwrap()
-                .cmp(&b.get_current_position().unwrap())
-        });
+    // Attaches the program to slot i of the dispatcher.
+    fn attach_extension(
+        &self,
+        v: &mut TcProgram,
+        dispatcher_fd: &ProgramFd,
+        i: usize,
+    ) -> Result<(), BpfmanError> {
+        let if_key = self.if_key()?;
+        let revision = self.get_revision()?;
+        let direction = self.get_direction()?;
 
-        for (i, v) in extensions.iter_mut().enumerate() {
-            if v.get_attached()? {
-                let id = v.data.get_id()?;
-                debug!("program {id} was already attached loading from pin");
-                let mut ext = Extension::from_pin(format!("{RTDIR_FS}/prog_{id}"))?;
-                let target_fn = format!("prog{i}");
-                let new_link_id = ext
-                    .attach_to_program(dispatcher.fd().unwrap(), &target_fn)
-                    .unwrap();
-                let new_link: FdLink = ext.take_link(new_link_id)?.into();
-                let base = match direction {
-                    Direction::Ingress => RTDIR_FS_TC_INGRESS,
-                    Direction::Egress => RTDIR_FS_TC_EGRESS,
-                };
-                let path = format!("{base}/dispatcher_{if_index}_{}/link_{id}", revision);
-                new_link.pin(path).map_err(BpfmanError::UnableToPinLink)?;
-            } else {
-                let name = &v.data.get_name()?;
-                let global_data = &v.data.get_global_data()?;
+        if v.get_attached()? {
+            let id = v.data.get_id()?;
+            debug!("program {id} was already attached loading from pin");
+            let mut ext = Extension::from_pin(format!("{RTDIR_FS}/prog_{id}"))?;
+            let target_fn = format!("prog{i}");
+            let new_link_id = ext.attach_to_program(dispatcher_fd, &target_fn)?;
+            let new_link: FdLink = ext.take_link(new_link_id)?.into();
+            let base = match direction {
+                Direction::Ingress => RTDIR_FS_TC_INGRESS,
+                Direction::Egress => RTDIR_FS_TC_EGRESS,
+            };
+            let path = format!("{base}/dispatcher_{if_key}_{}/link_{id}", revision);
+            new_link.pin(path).map_err(BpfmanError::UnableToPinLink)?;
+        } else {
+            let name = &v.data.get_name()?;
+            let global_data = &v.data.get_global_data()?;
 
-                let mut bpf = BpfLoader::new();
+            let mut bpf = BpfLoader::new();
 
-                bpf.allow_unsupported_maps().extension(name);
+            bpf.allow_unsupported_maps()
+                .extension(name)
+                .verifier_log_level(v.data.verifier_log_level()?);
 
-                for (name, value) in global_data {
-                    bpf.set_global(name, value.as_slice(), true);
-                }
+            for (name, value) in global_data {
+                bpf.set_global(name, value.as_slice(), true);
+            }
 
-                // If map_pin_path is set already it means we need to use a pin
-                // path which should already exist on the system.
-                if let Some(map_pin_path) = v.data.get_map_pin_path()? {
-                    debug!("tc program {name} is using maps from {:?}", map_pin_path);
-                    bpf.map_pin_path(map_pin_path);
-                }
+            // If map_pin_path is set already it means we need to use a pin
+            // path which should already exist on the system.
+            if let Some(map_pin_path) = v.data.get_map_pin_path()? {
+                debug!("tc program {name} is using maps from {:?}", map_pin_path);
+                bpf.map_pin_path(map_pin_path);
+            }
 
-                let mut loader = bpf
-                    .load(&v.get_data().get_program_bytes()?)
-                    .map_err(BpfmanError::BpfLoadError)?;
+            let mut loader = bpf
+                .load(&v.get_data().get_program_bytes()?)
+                .map_err(BpfmanError::BpfLoadError)?;
 
-                let ext: &mut Extension = loader
-                    .program_mut(name)
-                    .ok_or_else(|| BpfmanError::BpfFunctionNameNotValid(name.to_string()))?
-                    .try_into()?;
+            let ext: &mut Extension = loader
+                .program_mut(name)
+                .ok_or_else(|| BpfmanError::BpfFunctionNameNotValid(name.to_string()))?
+                .try_into()?;
 
-                let target_fn = format!("prog{i}");
+            let target_fn = format!("prog{i}");
 
-                ext.load(dispatcher.fd()?.try_clone()?, &target_fn)?;
-                v.data.set_kernel_info(&ext.info()?)?;
+            ext.load(dispatcher_fd.try_clone()?, &target_fn)?;
+            v.data.set_kernel_info(&ext.info()?)?;
 
-                let id = v.get_data().get_id()?;
+            let id = v.get_data().get_id()?;
 
-                ext.pin(format!("{RTDIR_FS}/prog_{id}"))
-                    .map_err(BpfmanError::UnableToPinProgram)?;
-                let new_link_id = ext.attach()?;
-                let new_link = ext.take_link(new_link_id)?;
-                let fd_link: FdLink = new_link.into();
-                let base = match direction {
-                    Direction::Ingress => RTDIR_FS_TC_INGRESS,
-                    Direction::Egress => RTDIR_FS_TC_EGRESS,
-                };
-                fd_link
-                    .pin(format!(
-                        "{base}/dispatcher_{if_index}_{}/link_{id}",
-                        revision,
-                    ))
-                    .map_err(BpfmanError::UnableToPinLink)?;
-
-                // If this program is the map(s) owner pin all maps (except for .rodata and .bss) by name.
-                if v.data.get_map_pin_path()?.is_none() {
-                    let map_pin_path = calc_map_pin_path(id);
-                    v.data.set_map_pin_path(&map_pin_path.clone())?;
-                    create_map_pin_path(&map_pin_path)?;
-
-                    for (name, map) in loader.maps_mut() {
-                        if !should_map_be_pinned(name) {
-                            continue;
-                        }
-                        debug!(
-                            "Pinning map: {name} to path: {}",
-                            map_pin_path.join(name).display()
-                        );
-                        map.pin(map_pin_path.join(name))
-                            .map_err(BpfmanError::UnableToPinMap)?;
+            ext.pin(format!("{RTDIR_FS}/prog_{id}"))
+                .map_err(BpfmanError::UnableToPinProgram)?;
+            let new_link_id = ext.attach()?;
+            let new_link = ext.take_link(new_link_id)?;
+            let fd_link: FdLink = new_link.into();
+            let base = match direction {
+                Direction::Ingress => RTDIR_FS_TC_INGRESS,
+                Direction::Egress => RTDIR_FS_TC_EGRESS,
+            };
+            fd_link
+                .pin(format!("{base}/dispatcher_{if_key}_{}/link_{id}", revision,))
+                .map_err(BpfmanError::UnableToPinLink)?;
+
+            // If this program is the map(s) owner pin all maps (except for .rodata and .bss) by name.
+            if v.data.get_map_pin_path()?.is_none() {
+                let map_pin_path = calc_map_pin_path(id);
+                v.data.set_map_pin_path(&map_pin_path.clone())?;
+                create_map_pin_path(&map_pin_path)?;
+
+                for (name, map) in loader.maps_mut() {
+                    if !should_map_be_pinned(name) {
+                        continue;
                     }
+                    debug!(
+                        "Pinning map: {name} to path: {}",
+                        map_pin_path.join(name).display()
+                    );
+                    map.pin(map_pin_path.join(name))
+                        .map_err(BpfmanError::UnableToPinMap)?;
                 }
             }
         }
@@ -393,17 +929,22 @@ impl TcDispatcher {
 
     pub(crate) fn delete(&mut self, root_db: &Db, full: bool) -> Result<(), BpfmanError> {
         let if_index = self.get_ifindex()?;
-        let if_name = self.get_ifname()?;
         let revision = self.get_revision()?;
         let direction = self.get_direction()?;
-        let handle = self.get_handle()?;
-        let priority = self.get_priority()?;
 
         debug!(
             "TcDispatcher::delete() for if_index {}, revision {}",
             if_index, revision
         );
 
+        // Dispatchers attached with a bpf_link are detached when their link
+        // pins are removed below.
+        if full {
+            for k in 0..self.get_num_dispatchers()? {
+                self.detach_qdisc_filter(k)?;
+            }
+        }
+
         root_db.drop_tree(self.db_tree.name()).map_err(|e| {
             BpfmanError::DatabaseError(
                 format!(
@@ -418,37 +959,106 @@ impl TcDispatcher {
             Direction::Ingress => RTDIR_FS_TC_INGRESS,
             Direction::Egress => RTDIR_FS_TC_EGRESS,
         };
-        let path = format!("{base}/dispatcher_{}_{}", if_index, revision);
+        let path = format!("{base}/dispatcher_{}_{}", self.if_key()?, revision);
         fs::remove_dir_all(path)
             .map_err(|e| BpfmanError::Error(format!("unable to cleanup state: {e}")))?;
 
-        if full {
-            // Also detach the old dispatcher.
-            if let Some(old_handle) = handle {
-                let attach_type = match direction {
-                    Direction::Ingress => TcAttachType::Ingress,
-                    Direction::Egress => TcAttachType::Egress,
-                };
-                if let Ok(old_link) =
-                    SchedClassifierLink::attached(&if_name, attach_type, priority, old_handle)
-                {
-                    let detach_result = old_link.detach();
-                    match detach_result {
-                        Ok(_) => debug!(
-                            "TC dispatcher {}, {}, {}, {} successfully detached",
-                            if_name, direction, priority, old_handle
-                        ),
-                        Err(_) => debug!(
-                            "TC dispatcher {}, {}, {}, {} not attached when detach attempted",
-                            if_name, direction, priority, old_handle
-                        ),
-                    }
+        Ok(())
+    }
+
+    // Detaches the k-th dispatcher of the chain when it was attached through
+    // the qdisc.
+    fn detach_qdisc_filter(&self, k: usize) -> Result<(), BpfmanError> {
+        if self.get_mode()? != TcMode::Qdisc {
+            return Ok(());
+        }
+        let Some(handle) = self.get_handle(k)? else {
+            return Ok(());
+        };
+        let if_name = self.get_ifname()?;
+        let direction = self.get_direction()?;
+        let priority = self.get_priority()? + k as u16;
+
+        let attach_type = match direction {
+            Direction::Ingress => TcAttachType::Ingress,
+            Direction::Egress => TcAttachType::Egress,
+        };
+        let netns = self.get_netns()?;
+        // The interface went away with its network namespace if the namespace
+        // can't be entered, so there's nothing left to detach.
+        let result = with_netns(netns.as_ref(), || {
+            if let Ok(old_link) =
+                SchedClassifierLink::attached(&if_name, attach_type, priority, handle)
+            {
+                let detach_result = old_link.detach();
+                match detach_result {
+                    Ok(_) => debug!(
+                        "TC dispatcher {}, {}, {}, {} successfully detached",
+                        if_name, direction, priority, handle
+                    ),
+                    Err(_) => debug!(
+                        "TC dispatcher {}, {}, {}, {} not attached when detach attempted",
+                        if_name, direction, priority, handle
+                    ),
                 }
-            };
+            }
+            Ok(())
+        });
+        if let Err(e) = result {
+            debug!("TC dispatcher {if_name}, {direction} not detached: {e}");
         }
         Ok(())
     }
 
+    // Returns true if the bpffs state for this dispatcher revision still exists.
+    pub(crate) fn is_pinned(&self) -> Result<bool, BpfmanError> {
+        let if_key = self.if_key()?;
+        let revision = self.get_revision()?;
+        let base = match self.get_direction()? {
+            Direction::Ingress => RTDIR_FS_TC_INGRESS,
+            Direction::Egress => RTDIR_FS_TC_EGRESS,
+        };
+        Ok(Path::new(&format!("{base}/dispatcher_{if_key}_{revision}")).exists())
+    }
+
+    pub(crate) fn state(&self, slots: Vec<DispatcherSlot>) -> Result<DispatcherState, BpfmanError> {
+        let mut stages = vec![];
+        let mut slots = slots.into_iter();
+        for (k, size) in chain_sizes(slots.len()).into_iter().enumerate() {
+            stages.push(DispatcherStage {
+                handle: self.get_handle(k)?,
+                num_progs_enabled: self.get_num_progs_enabled(k, size)?,
+                chain_call_actions: self.get_chain_call_actions(k)?,
+                slots: slots
+                    .by_ref()
+                    .take(size)
+                    .enumerate()
+                    .map(|(i, s)| DispatcherSlot {
+                        slot: i as u32,
+                        ..s
+                    })
+                    .collect(),
+            });
+        }
+
+        Ok(DispatcherState {
+            program_type: ProgramType::Tc,
+            if_name: self.get_ifname()?,
+            if_index: self.get_ifindex()?,
+            direction: Some(self.get_direction()?),
+            netns: self.get_netns()?.map(|n| n.path),
+            revision: self.get_revision()?,
+            mode: self.get_mode()?.to_string(),
+            priority: Some(self.get_priority()?),
+            stages,
+            foreign: vec![],
+        })
+    }
+
+    fn if_key(&self) -> Result<String, BpfmanError> {
+        Ok(if_key(self.get_ifindex()?, self.get_netns()?.map(|n| n.id)))
+    }
+
     pub(crate) fn set_revision(&mut self, revision: u32) -> Result<(), BpfmanError> {
         sled_insert(&self.db_tree, REVISION, &revision.to_ne_bytes())
     }
@@ -507,11 +1117,431 @@ impl TcDispatcher {
         sled_get(&self.db_tree, PROGRAM_NAME).map(|v| bytes_to_string(&v))
     }
 
-    pub(crate) fn set_handle(&mut self, handle: u32) -> Result<(), BpfmanError> {
-        sled_insert(&self.db_tree, HANDLE, &handle.to_ne_bytes())
+    pub(crate) fn set_mode(&mut self, mode: TcMode) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, MODE, &(mode as u32).to_ne_bytes())
+    }
+
+    // Dispatchers created before the mode was recorded were always attached
+    // through the qdisc.
+    pub(crate) fn get_mode(&self) -> Result<TcMode, BpfmanError> {
+        match sled_get_option(&self.db_tree, MODE)? {
+            Some(v) => {
+                TcMode::try_from(bytes_to_u32(v)).map_err(|e| BpfmanError::Error(e.to_string()))
+            }
+            None => Ok(TcMode::Qdisc),
+        }
+    }
+
+    pub(crate) fn set_netns(&mut self, netns: &Netns) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, NETNS, netns.path.as_bytes())?;
+        sled_insert(&self.db_tree, NETNS_ID, &netns.id.to_ne_bytes())
+    }
+
+    pub(crate) fn get_netns(&self) -> Result<Option<Netns>, BpfmanError> {
+        let path = sled_get_option(&self.db_tree, NETNS)?;
+        let id = sled_get_option(&self.db_tree, NETNS_ID)?;
+        Ok(path.zip(id).map(|(path, id)| Netns {
+            path: bytes_to_string(&path),
+            id: bytes_to_u64(id),
+        }))
+    }
+
+    pub(crate) fn set_num_dispatchers(&mut self, num: usize) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, NUM_DISPATCHERS, &num.to_ne_bytes())
+    }
+
+    // Dispatchers created before chaining was supported were never chained.
+    pub(crate) fn get_num_dispatchers(&self) -> Result<usize, BpfmanError> {
+        sled_get_option(&self.db_tree, NUM_DISPATCHERS).map(|v| v.map_or(1, bytes_to_usize))
+    }
+
+    // The handle of the first dispatcher keeps the key it had before chaining
+    // was supported.
+    fn handle_key(k: usize) -> String {
+        match k {
+            0 => HANDLE.to_string(),
+            _ => format!("{HANDLE}_{k}"),
+        }
+    }
+
+    pub(crate) fn set_num_progs_enabled(&mut self, k: usize, num: u8) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.db_tree,
+            &format!("{NUM_PROGS_ENABLED}_{k}"),
+            &(num as u32).to_ne_bytes(),
+        )
     }
 
-    pub(crate) fn get_handle(&self) -> Result<Option<u32>, BpfmanError> {
-        sled_get_option(&self.db_tree, HANDLE).map(|v| v.map(bytes_to_u32))
+    // Dispatchers created before their config was saved ran every extension
+    // in their part of the chain.
+    pub(crate) fn get_num_progs_enabled(
+        &self,
+        k: usize,
+        num_extensions: usize,
+    ) -> Result<u32, BpfmanError> {
+        sled_get_option(&self.db_tree, &format!("{NUM_PROGS_ENABLED}_{k}"))
+            .map(|v| v.map_or(num_extensions as u32, bytes_to_u32))
+    }
+
+    pub(crate) fn set_chain_call_actions(
+        &mut self,
+        k: usize,
+        actions: &[u32],
+    ) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.db_tree,
+            &format!("{CHAIN_CALL_ACTIONS}_{k}"),
+            &u32s_to_bytes(actions),
+        )
+    }
+
+    pub(crate) fn get_chain_call_actions(&self, k: usize) -> Result<Vec<u32>, BpfmanError> {
+        sled_get_option(&self.db_tree, &format!("{CHAIN_CALL_ACTIONS}_{k}"))
+            .map(|v| v.map_or(vec![], bytes_to_u32s))
+    }
+
+    pub(crate) fn set_program_ids(&mut self, ids: &[u32]) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, PROGRAM_IDS, &u32s_to_bytes(ids))
+    }
+
+    pub(crate) fn get_program_ids(&self) -> Result<Option<Vec<u32>>, BpfmanError> {
+        Ok(sled_get_option(&self.db_tree, PROGRAM_IDS)?.map(bytes_to_u32s))
+    }
+
+    pub(crate) fn set_run_last(&mut self, run_last: bool) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, RUN_LAST, &(run_last as i8).to_ne_bytes())
+    }
+
+    pub(crate) fn get_run_last(&self) -> Result<bool, BpfmanError> {
+        Ok(sled_get_option(&self.db_tree, RUN_LAST)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
+    pub(crate) fn set_adopted(
+        &mut self,
+        id: u32,
+        attachment: TcAttachment,
+    ) -> Result<(), BpfmanError> {
+        let value = match attachment {
+            TcAttachment::Filter { priority, handle } => [0, priority as u32, handle],
+            TcAttachment::Mprog { attach_type } => [1, attach_type, 0],
+        };
+        sled_insert(
+            &self.db_tree,
+            &format!("{PREFIX_ADOPTED}{id}"),
+            &u32s_to_bytes(&value),
+        )
+    }
+
+    pub(crate) fn get_adopted(&self) -> Result<Vec<(u32, TcAttachment)>, BpfmanError> {
+        self.db_tree
+            .scan_prefix(PREFIX_ADOPTED)
+            .map(|entry| {
+                let (key, value) = entry.map_err(|e| {
+                    BpfmanError::DatabaseError(
+                        "Failed to get adopted programs".to_string(),
+                        e.to_string(),
+                    )
+                })?;
+                let id = bytes_to_string(&key)[PREFIX_ADOPTED.len()..]
+                    .parse()
+                    .map_err(|e| BpfmanError::Error(format!("invalid adopted program: {e}")))?;
+                let attachment = match bytes_to_u32s(value.to_vec())[..] {
+                    [0, priority, handle] => TcAttachment::Filter {
+                        priority: priority as u16,
+                        handle,
+                    },
+                    [1, attach_type, _] => TcAttachment::Mprog { attach_type },
+                    _ => {
+                        return Err(BpfmanError::Error(format!(
+                            "invalid attachment of adopted program {id}"
+                        )))
+                    }
+                };
+                Ok((id, attachment))
+            })
+            .collect()
+    }
+
+    pub(crate) fn set_handle(&mut self, k: usize, handle: u32) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, &Self::handle_key(k), &handle.to_ne_bytes())
+    }
+
+    pub(crate) fn get_handle(&self, k: usize) -> Result<Option<u32>, BpfmanError> {
+        sled_get_option(&self.db_tree, &Self::handle_key(k)).map(|v| v.map(bytes_to_u32))
+    }
+}
+
+// Returns the programs attached to a TC hook, whether as bpf filters of the
+// clsact qdisc or to the TCX hook, and to the netkit hook for dispatchers
+// attached there. Kernels without TCX report nothing for it.
+async fn attached_programs(
+    if_index: u32,
+    netns: Option<&Netns>,
+    direction: Direction,
+    mode: TcMode,
+) -> Result<Vec<(u32, TcAttachment)>, anyhow::Error> {
+    let mut programs = vec![];
+
+    if TcDispatcher::has_qdisc("clsact".to_string(), if_index as i32, netns).await? {
+        let (connection, mut handle, _) = with_netns(netns, || Ok(rtnetlink::new_connection()?))?;
+        tokio::spawn(connection);
+
+        let mut message = TcMessage::with_index(if_index as i32);
+        message.header.parent = TcHandle {
+            major: u16::MAX,
+            minor: match direction {
+                Direction::Ingress => TcHandle::MIN_INGRESS,
+                Direction::Egress => TcHandle::MIN_EGRESS,
+            },
+        };
+        let mut request = NetlinkMessage::from(RouteNetlinkMessage::GetTrafficFilter(message));
+        request.header.flags = NLM_F_REQUEST | NLM_F_DUMP;
+
+        let mut response = handle.request(request)?;
+        while let Some(reply) = response.next().await {
+            let filter = match reply.payload {
+                NetlinkPayload::InnerMessage(RouteNetlinkMessage::NewTrafficFilter(f)) => f,
+                NetlinkPayload::Error(e) => return Err(rtnetlink::Error::NetlinkError(e).into()),
+                _ => continue,
+            };
+            if !filter
+                .attributes
+                .contains(&TcAttribute::Kind(BPF_FILTER_KIND.to_string()))
+            {
+                continue;
+            }
+            let id = filter.attributes.iter().find_map(|a| match a {
+                TcAttribute::Options(options) => options.iter().find_map(bpf_filter_prog_id),
+                _ => None,
+            });
+            if let Some(id) = id {
+                programs.push((
+                    id,
+                    TcAttachment::Filter {
+                        priority: (filter.header.info >> 16) as u16,
+                        handle: filter.header.handle.into(),
+                    },
+                ));
+            }
+        }
+    }
+
+    let mut attach_types = vec![match direction {
+        Direction::Ingress => sys::BPF_TCX_INGRESS,
+        Direction::Egress => sys::BPF_TCX_EGRESS,
+    }];
+    if mode == TcMode::Netkit {
+        attach_types.push(match direction {
+            Direction::Ingress => sys::BPF_NETKIT_PRIMARY,
+            Direction::Egress => sys::BPF_NETKIT_PEER,
+        });
+    }
+    for attach_type in attach_types {
+        let ids = with_netns(netns, || {
+            Ok(sys::mprog_query(if_index, attach_type).unwrap_or_default())
+        })?;
+        programs.extend(
+            ids.into_iter()
+                .map(|id| (id, TcAttachment::Mprog { attach_type })),
+        );
+    }
+
+    Ok(programs)
+}
+
+// Picks the priority of a chain of dispatchers attached through the qdisc so
+// they run before or after the foreign filters. None keeps the default
+// priority, when it already does, or when there's no room on that side of
+// them.
+fn qdisc_priority(
+    foreign: &[(u32, TcAttachment)],
+    order: TcForeignOrder,
+    num_dispatchers: u16,
+) -> Option<u16> {
+    let priorities: Vec<u16> = foreign
+        .iter()
+        .filter_map(|(_, a)| match a {
+            TcAttachment::Filter { priority, .. } => Some(*priority),
+            TcAttachment::Mprog { .. } => None,
+        })
+        .collect();
+    match (order, priorities.iter().min(), priorities.iter().max()) {
+        (TcForeignOrder::First, Some(min), _)
+            if *min < TC_DISPATCHER_PRIORITY + num_dispatchers =>
+        {
+            min.checked_sub(num_dispatchers).filter(|p| *p > 0)
+        }
+        (TcForeignOrder::Last, _, Some(max)) if *max >= TC_DISPATCHER_PRIORITY => max
+            .checked_add(1)
+            .filter(|p| p.checked_add(num_dispatchers).is_some()),
+        _ => None,
+    }
+}
+
+// Returns the id of the program of a bpf filter. netlink-packet-route doesn't
+// parse the options of bpf filters, so they're left as one nested attribute.
+fn bpf_filter_prog_id(option: &TcOption) -> Option<u32> {
+    let TcOption::Other(nla) = option else {
+        return None;
+    };
+    let mut value = vec![0; nla.value_len()];
+    nla.emit_value(&mut value);
+    NlasIterator::new(&value[..])
+        .filter_map(|nla| nla.ok())
+        .find(|nla| nla.kind() == TCA_BPF_ID && nla.value().len() >= 4)
+        .map(|nla| u32::from_ne_bytes(nla.value()[..4].try_into().unwrap()))
+}
+
+// Splits the programs on a hook between a chain of dispatchers. Every
+// dispatcher but the last one keeps its final slot to run the next one.
+fn chain_sizes(num_programs: usize) -> Vec<usize> {
+    let mut sizes = vec![];
+    let mut remaining = num_programs;
+    while remaining > TC_MAX_DISPATCHER_ACTIONS && sizes.len() < TC_MAX_DISPATCHERS - 1 {
+        sizes.push(TC_MAX_DISPATCHER_ACTIONS - 1);
+        remaining -= TC_MAX_DISPATCHER_ACTIONS - 1;
+    }
+    sizes.push(remaining);
+    sizes
+}
+
+// Returns how many slots each dispatcher in a chain runs. A dispatcher
+// followed by another one also runs the slot after its programs, where the
+// hand-off program returns TC_ACT_UNSPEC. That isn't in chain_call_actions so
+// the dispatcher returns it, and qdisc, TCX and netkit hooks all go on to run
+// the next program on the hook. Disabled programs are positioned after the enabled
+// ones, so the dispatcher stops before reaching them, and the ones after it
+// aren't run once there's nothing left to run.
+fn chain_progs_enabled(chain: &[usize], disabled: &[bool]) -> Vec<usize> {
+    let mut first = 0;
+    chain
+        .iter()
+        .map(|size| {
+            let last = first + size;
+            let enabled = if disabled[last..].iter().any(|d| !d) {
+                size + 1
+            } else {
+                disabled[first..last].iter().filter(|d| !**d).count()
+            };
+            first = last;
+            enabled
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use netlink_packet_utils::nla::DefaultNla;
+
+    use super::*;
+    use crate::{dispatcher_config::TC_MAX_PROGRAMS, get_db_config};
+
+    #[test]
+    fn test_chain_sizes() {
+        assert_eq!(chain_sizes(1), vec![1]);
+        assert_eq!(chain_sizes(10), vec![10]);
+        assert_eq!(chain_sizes(11), vec![9, 2]);
+        assert_eq!(chain_sizes(19), vec![9, 10]);
+        assert_eq!(chain_sizes(20), vec![9, 9, 2]);
+        assert_eq!(chain_sizes(TC_MAX_PROGRAMS).len(), TC_MAX_DISPATCHERS);
+        assert_eq!(*chain_sizes(TC_MAX_PROGRAMS).last().unwrap(), 10);
+    }
+
+    #[test]
+    fn test_chain_progs_enabled() {
+        let disabled = |enabled: usize, disabled: usize| {
+            let mut d = vec![false; enabled];
+            d.resize(enabled + disabled, true);
+            d
+        };
+
+        assert_eq!(chain_progs_enabled(&[3], &disabled(3, 0)), vec![3]);
+        assert_eq!(chain_progs_enabled(&[3], &disabled(1, 2)), vec![1]);
+        assert_eq!(chain_progs_enabled(&[3], &disabled(0, 3)), vec![0]);
+        // The first dispatcher runs the next one while it has programs to run.
+        assert_eq!(chain_progs_enabled(&[9, 2], &disabled(10, 1)), vec![10, 1]);
+        assert_eq!(chain_progs_enabled(&[9, 2], &disabled(9, 2)), vec![9, 0]);
+        assert_eq!(chain_progs_enabled(&[9, 2], &disabled(4, 7)), vec![4, 0]);
+    }
+
+    #[test]
+    fn test_qdisc_priority() {
+        let filters = |priorities: &[u16]| {
+            priorities
+                .iter()
+                .map(|p| {
+                    (
+                        1,
+                        TcAttachment::Filter {
+                            priority: *p,
+                            handle: 1,
+                        },
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        let first = TcForeignOrder::First;
+        let last = TcForeignOrder::Last;
+
+        assert_eq!(qdisc_priority(&filters(&[50, 70]), first, 1), Some(49));
+        assert_eq!(qdisc_priority(&filters(&[52]), first, 3), Some(49));
+        // The default priority already runs first.
+        assert_eq!(qdisc_priority(&filters(&[60]), first, 1), None);
+        // There's no room in front of the foreign filter.
+        assert_eq!(qdisc_priority(&filters(&[1]), first, 1), None);
+
+        assert_eq!(qdisc_priority(&filters(&[10, 50]), last, 1), Some(51));
+        assert_eq!(qdisc_priority(&filters(&[40]), last, 1), None);
+        assert_eq!(qdisc_priority(&filters(&[u16::MAX]), last, 1), None);
+
+        // Programs on a TCX or netkit hook aren't ordered by priority.
+        let mprog = [(1, TcAttachment::Mprog { attach_type: 46 })];
+        assert_eq!(qdisc_priority(&mprog, first, 1), None);
+        assert_eq!(qdisc_priority(&mprog, last, 1), None);
+    }
+
+    #[test]
+    fn test_bpf_filter_prog_id() {
+        let nla = |kind: u16, value: u32| {
+            let mut bytes = 8u16.to_ne_bytes().to_vec();
+            bytes.extend(kind.to_ne_bytes());
+            bytes.extend(value.to_ne_bytes());
+            bytes
+        };
+        let mut options = nla(1, 7);
+        options.extend(nla(TCA_BPF_ID, 42));
+
+        let option = TcOption::Other(DefaultNla::new(2, options));
+        assert_eq!(bpf_filter_prog_id(&option), Some(42));
+        let option = TcOption::Other(DefaultNla::new(2, nla(1, 7)));
+        assert_eq!(bpf_filter_prog_id(&option), None);
+    }
+
+    #[test]
+    fn test_adopted() {
+        let root_db = get_db_config().open().unwrap();
+        let mut dispatcher = TcDispatcher::new(
+            &root_db,
+            TcMode::default(),
+            Direction::Ingress,
+            1,
+            "eth0".to_string(),
+            None,
+            1,
+        )
+        .unwrap();
+        let filter = TcAttachment::Filter {
+            priority: 49,
+            handle: 0x10,
+        };
+        let mprog = TcAttachment::Mprog { attach_type: 46 };
+        dispatcher.set_adopted(7, filter).unwrap();
+        dispatcher.set_adopted(8, mprog).unwrap();
+
+        let mut adopted = dispatcher.get_adopted().unwrap();
+        adopted.sort_by_key(|(id, _)| *id);
+        assert_eq!(adopted, vec![(7, filter), (8, mprog)]);
     }
 }
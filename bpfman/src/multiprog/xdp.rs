@@ -1,43 +1,86 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    os::fd::{AsFd, AsRawFd, RawFd},
+    path::{Path, PathBuf},
+};
 
 use aya::{
     programs::{
         links::{FdLink, PinnedLink},
-        Extension, Xdp,
+        Extension, Xdp, XdpFlags,
     },
     Bpf, BpfLoader,
 };
-use log::debug;
+use aya_obj::generated::BPF_F_XDP_HAS_FRAGS;
+use futures::stream::TryStreamExt;
+use log::{debug, warn};
+use netlink_packet_route::link::{LinkAttribute, LinkXdp, XdpAttached};
 use sled::Db;
 
 use crate::{
     calc_map_pin_path,
-    config::XdpMode,
+    config::{ForeignPolicy, XdpMode},
     create_map_pin_path,
     directories::*,
-    dispatcher_config::XdpDispatcherConfig,
+    dispatcher_config::{
+        XdpDispatcherConfig, XdpWideDispatcherConfig, MAX_DISPATCHER_ACTIONS, XDP_MAX_PROGRAMS,
+    },
     errors::BpfmanError,
-    multiprog::{Dispatcher, XDP_DISPATCHER_PREFIX},
+    multiprog::{if_key, libxdp, Dispatcher, XDP_DISPATCHER_PREFIX},
     oci_utils::image_manager::ImageManager,
-    types::{BytecodeImage, ImagePullPolicy, Program, XdpProgram},
+    sys,
+    types::{
+        BytecodeImage, DispatcherSlot, DispatcherStage, DispatcherState, ImagePullPolicy, Netns,
+        Program, ProgramType, XdpProgram,
+    },
     utils::{
-        bytes_to_string, bytes_to_u32, bytes_to_usize, should_map_be_pinned, sled_get, sled_insert,
+        bytes_to_bool, bytes_to_string, bytes_to_u32, bytes_to_u32s, bytes_to_u64, bytes_to_usize,
+        should_map_be_pinned, sled_get, sled_get_option, sled_insert, u32s_to_bytes, with_netns,
     },
 };
 
 pub(crate) const DEFAULT_PRIORITY: u32 = 50;
 const XDP_DISPATCHER_PROGRAM_NAME: &str = "xdp_dispatcher";
+// The same dispatcher in an "xdp.frags" section, for programs which support
+// multi-buffer packets.
+const XDP_DISPATCHER_FRAGS_PROGRAM_NAME: &str = "xdp_dispatcher_frags";
+// The dispatchers with room for up to XDP_MAX_PROGRAMS programs.
+const XDP_WIDE_DISPATCHER_PROGRAM_NAME: &str = "xdp_dispatcher_wide";
+const XDP_WIDE_DISPATCHER_FRAGS_PROGRAM_NAME: &str = "xdp_dispatcher_wide_frags";
+// The published image with the dispatcher libxdp knows.
+const XDP_DISPATCHER_IMAGE: &str = "quay.io/bpfman/xdp-dispatcher:v2";
+// The image which adds the dispatchers with frags support and the wide ones.
+// It's only pulled for an interface which needs one of them.
+const XDP_DISPATCHER_V3_IMAGE: &str = "quay.io/bpfman/xdp-dispatcher:v3";
 
 /// These constants define the key of SLED DB
 const REVISION: &str = "revision";
 const IF_INDEX: &str = "if_index";
 const IF_NAME: &str = "if_name";
 const MODE: &str = "mode";
+const MODE_FALLBACK: &str = "mode_fallback";
+const WIDE_DISPATCHER: &str = "wide_dispatcher";
 const NUM_EXTENSIONS: &str = "num_extension";
 const PROGRAM_NAME: &str = "program_name";
+const NETNS: &str = "netns";
+const NETNS_ID: &str = "netns_id";
+const NUM_PROGS_ENABLED: &str = "num_progs_enabled";
+const CHAIN_CALL_ACTIONS: &str = "chain_call_actions";
+const PROGRAM_ID: &str = "program_id";
+const LIBXDP_BPFFS: &str = "libxdp_bpffs";
+const LIBXDP_BPFMAN_IDS: &str = "libxdp_bpfman_ids";
+const PREFIX_LIBXDP_PROG: &str = "libxdp_prog_";
+const PREFIX_LIBXDP_NAME: &str = "libxdp_name_";
+
+// A slot of the dispatcher, holding either one of the programs bpfman loaded
+// or one libxdp attached to the interface.
+enum Slot {
+    Bpfman(usize),
+    Libxdp(libxdp::Component),
+}
 
 #[derive(Debug)]
 pub struct XdpDispatcher {
@@ -49,14 +92,18 @@ impl XdpDispatcher {
     pub(crate) fn new(
         root_db: &Db,
         mode: &XdpMode,
+        mode_fallback: bool,
         if_index: u32,
         if_name: String,
+        netns: Option<Netns>,
         revision: u32,
     ) -> Result<Self, BpfmanError> {
         let db_tree = root_db
             .open_tree(format!(
                 "{}_{}_{}",
-                XDP_DISPATCHER_PREFIX, if_index, revision
+                XDP_DISPATCHER_PREFIX,
+                if_key(if_index, netns.as_ref().map(|n| n.id)),
+                revision
             ))
             .expect("Unable to open xdp dispatcher database tree");
 
@@ -68,7 +115,11 @@ impl XdpDispatcher {
         dp.set_ifindex(if_index)?;
         dp.set_ifname(&if_name)?;
         dp.set_mode(mode)?;
+        dp.set_mode_fallback(mode_fallback)?;
         dp.set_revision(revision)?;
+        if let Some(netns) = netns {
+            dp.set_netns(&netns)?;
+        }
         Ok(dp)
     }
 
@@ -86,10 +137,16 @@ impl XdpDispatcher {
         programs: &mut [Program],
         old_dispatcher: Option<Dispatcher>,
         image_manager: &mut ImageManager,
+        foreign_policy: ForeignPolicy,
+        libxdp: Option<&Path>,
     ) -> Result<(), BpfmanError> {
         let if_index = self.get_ifindex()?;
         let revision = self.get_revision()?;
         debug!("XdpDispatcher::new() for if_index {if_index}, revision {revision}");
+        // A dispatcher attached with a bpf_link before interoperating with
+        // libxdp was enabled keeps its link, and libxdp can't replace it.
+        let link_pinned = Path::new(&self.link_pin_path()?).exists();
+        let libxdp = libxdp.filter(|_| !link_pinned);
         let mut extensions: Vec<&mut XdpProgram> = programs
             .iter_mut()
             .map(|v| match v {
@@ -97,28 +154,115 @@ impl XdpDispatcher {
                 _ => panic!("All programs should be of type XDP"),
             })
             .collect();
-
-        let mut chain_call_actions = [0; 10];
         extensions.sort_by(|a, b| {
             a.get_current_position()
                 .unwrap()
                 .cmp(&b.get_current_position().unwrap())
         });
-        for p in extensions.iter() {
-            chain_call_actions[p.get_current_position()?.unwrap()] = p.get_proceed_on()?.mask();
+
+        let old = match old_dispatcher.as_ref() {
+            Some(Dispatcher::Xdp(old)) => Some(old),
+            _ => None,
+        };
+
+        // libxdp's lock is held until the dispatcher replaced the one on the
+        // interface, so libxdp doesn't change the programs in between.
+        let _lock = libxdp.map(libxdp::lock).transpose()?;
+        let (current, components) = match libxdp {
+            Some(bpffs) => {
+                self.set_libxdp_bpffs(bpffs)?;
+                let mut own: Vec<u32> = extensions
+                    .iter()
+                    .filter_map(|p| p.get_data().get_id().ok())
+                    .collect();
+                if let Some(old) = old {
+                    own.extend(old.get_bpfman_ids()?);
+                }
+                libxdp_chain(bpffs, if_index, &own).await?
+            }
+            None => (None, vec![]),
+        };
+
+        // libxdp only knows dispatchers with up to MAX_DISPATCHER_ACTIONS
+        // programs, and the wide dispatcher is only used for more on the
+        // interfaces it was enabled for.
+        let slots = merge_slots(&extensions, components)?;
+        if slots.len() > MAX_DISPATCHER_ACTIONS {
+            check_wide_dispatcher(slots.len(), libxdp.is_some(), self.get_wide_dispatcher()?)?;
         }
+        let wide = slots.len() > MAX_DISPATCHER_ACTIONS;
+        let mut num_progs_enabled = 0;
+        let mut chain_call_actions = [0; XDP_MAX_PROGRAMS];
+        let mut run_prios = [DEFAULT_PRIORITY; XDP_MAX_PROGRAMS];
+        let mut program_flags = [0; XDP_MAX_PROGRAMS];
+        let mut libxdp_slots = vec![];
+        for (position, slot) in slots.into_iter().enumerate() {
+            match slot {
+                Slot::Bpfman(i) => {
+                    let p = &mut extensions[i];
+                    p.set_current_position(position)?;
+                    chain_call_actions[position] = p.get_proceed_on()?.mask();
+                    run_prios[position] = p.get_priority()?.max(0) as u32;
+                    // Disabled programs are positioned after the enabled
+                    // ones, so the dispatcher stops before reaching them.
+                    if !p.get_disabled()? {
+                        num_progs_enabled += 1;
+                    }
+                    if p.get_frags()? {
+                        program_flags[position] = BPF_F_XDP_HAS_FRAGS;
+                    }
+                }
+                Slot::Libxdp(c) => {
+                    chain_call_actions[position] = c.chain_call_actions;
+                    run_prios[position] = c.run_prio;
+                    program_flags[position] = c.flags;
+                    num_progs_enabled += 1;
+                    libxdp_slots.push((position, c));
+                }
+            }
+        }
+        extensions.sort_by(|a, b| {
+            a.get_current_position()
+                .unwrap()
+                .cmp(&b.get_current_position().unwrap())
+        });
+
+        // Programs with and without frags support aren't mixed on an
+        // interface, so the first one decides for the dispatcher.
+        let frags = match extensions.first() {
+            Some(p) => p.get_frags()?,
+            None => false,
+        };
+        let program_name = dispatcher_program_name(wide, frags);
 
         let config = XdpDispatcherConfig::new(
-            extensions.len() as u8,
-            0x0,
-            chain_call_actions,
-            [DEFAULT_PRIORITY; 10],
-            [0; 10],
+            num_progs_enabled,
+            frags as u8,
+            chain_call_actions[..MAX_DISPATCHER_ACTIONS]
+                .try_into()
+                .unwrap(),
+            run_prios[..MAX_DISPATCHER_ACTIONS].try_into().unwrap(),
+            program_flags[..MAX_DISPATCHER_ACTIONS].try_into().unwrap(),
         );
+        let wide_config = XdpWideDispatcherConfig {
+            num_progs_enabled,
+            chain_call_actions,
+            run_prios,
+            program_flags,
+        };
+        let mut loader = BpfLoader::new();
+        let num_slots = if wide {
+            debug!("xdp wide dispatcher config: {:?}", wide_config);
+            loader.set_global("wide_conf", &wide_config, true);
+            XDP_MAX_PROGRAMS
+        } else {
+            debug!("xdp dispatcher config: {:?}", config);
+            loader.set_global("conf", &config, true);
+            MAX_DISPATCHER_ACTIONS
+        };
 
-        debug!("xdp dispatcher config: {:?}", config);
         let image = BytecodeImage::new(
-            "quay.io/bpfman/xdp-dispatcher:v2".to_string(),
+            dispatcher_image(program_name).to_string(),
             ImagePullPolicy::IfNotPresent as i32,
             None,
             None,
@@ -132,48 +276,262 @@ impl XdpDispatcher {
                 image.username.clone(),
                 image.password.clone(),
             )
-            .await?;
+            .await
+            .map_err(|e| {
+                let e = BpfmanError::from(e);
+                if wide {
+                    wide_dispatcher_error(&image.image_url, e)
+                } else {
+                    e
+                }
+            })?;
 
-        if !bpf_program_names.contains(&XDP_DISPATCHER_PROGRAM_NAME.to_string()) {
-            return Err(BpfmanError::ProgramNotFoundInBytecode {
-                bytecode_image: image.image_url,
-                expected_prog_name: XDP_DISPATCHER_PROGRAM_NAME.to_string(),
+        if !bpf_program_names.contains(&program_name.to_string()) {
+            let e = BpfmanError::ProgramNotFoundInBytecode {
+                bytecode_image: image.image_url.clone(),
+                expected_prog_name: program_name.to_string(),
                 program_names: bpf_program_names,
+            };
+            return Err(if wide {
+                wide_dispatcher_error(&image.image_url, e)
+            } else {
+                e
             });
         }
 
         let program_bytes = image_manager.get_bytecode_from_image_store(root_db, path)?;
 
-        let mut loader = BpfLoader::new()
-            .set_global("conf", &config, true)
-            .load(&program_bytes)?;
+        let mut loader = loader.load(&program_bytes)?;
 
-        let dispatcher: &mut Xdp = loader
-            .program_mut(XDP_DISPATCHER_PROGRAM_NAME)
-            .unwrap()
-            .try_into()?;
+        let dispatcher: &mut Xdp = loader.program_mut(program_name).unwrap().try_into()?;
 
         dispatcher.load()?;
+        let program_id = dispatcher.info()?.id();
 
-        let path = format!("{RTDIR_FS_XDP}/dispatcher_{if_index}_{revision}");
+        let path = format!("{RTDIR_FS_XDP}/dispatcher_{}_{revision}", self.if_key()?);
         fs::create_dir_all(path).unwrap();
 
         self.loader = Some(loader);
         self.set_num_extensions(extensions.len())?;
-        self.set_program_name(XDP_DISPATCHER_PROGRAM_NAME)?;
+        self.set_num_progs_enabled(num_progs_enabled)?;
+        self.set_chain_call_actions(&chain_call_actions[..num_slots])?;
+        self.set_program_name(program_name)?;
+        self.set_program_id(program_id)?;
+
+        // The link of the old dispatcher is reused, along with the mode it
+        // was attached in. A dispatcher replacing libxdp's has to be in the
+        // same mode.
+        if let Some(old) = old {
+            self.set_mode(&old.get_mode()?)?;
+        }
+        if let Some((_, mode)) = current {
+            self.set_mode(&mode)?;
+        }
+        let foreign = self.handle_foreign(foreign_policy, old).await?;
 
-        self.attach_extensions(&mut extensions)?;
-        self.attach()?;
+        let libxdp_dir = libxdp.map(|bpffs| libxdp::dispatch_dir(bpffs, if_index, program_id));
+        self.attach_extensions(&mut extensions, libxdp_dir.as_deref())?;
+        match (libxdp, libxdp_dir) {
+            (Some(bpffs), Some(dir)) => {
+                // libxdp only knows a dispatcher by its directory, which is
+                // created even if no slot is enabled.
+                fs::create_dir_all(&dir)?;
+                self.attach_libxdp_programs(libxdp_slots, &dir)?;
+                self.attach_netlink(current.map(|(id, _)| id)).await?;
+                // libxdp removes the pins of a dispatcher it replaced, and
+                // so does bpfman.
+                if let Some((id, _)) = current {
+                    let _ = fs::remove_dir_all(libxdp::dispatch_dir(bpffs, if_index, id));
+                }
+            }
+            _ => self.attach()?,
+        }
+        let ids = extensions
+            .iter()
+            .map(|p| p.get_data().get_id())
+            .collect::<Result<Vec<_>, _>>()?;
+        self.set_bpfman_ids(&ids)?;
+        let mode = self.get_mode()?;
+        for p in extensions.iter_mut() {
+            p.set_effective_mode(mode)?;
+            p.set_foreign_programs(&foreign);
+        }
         if let Some(mut old) = old_dispatcher {
             old.delete(root_db, false)?;
         }
         Ok(())
     }
 
+    // Applies the policy for foreign programs before the dispatcher is
+    // attached to the interface, and returns the ones left on it. Refusing
+    // only applies to the first revision, so the programs on an interface can
+    // still be changed once something else attaches to it.
+    async fn handle_foreign(
+        &mut self,
+        policy: ForeignPolicy,
+        old: Option<&XdpDispatcher>,
+    ) -> Result<Vec<u32>, BpfmanError> {
+        let Some(foreign) = self.foreign_programs(old).await? else {
+            return Ok(vec![]);
+        };
+        if foreign.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let iface = self.get_ifname()?;
+        let program_ids: Vec<u32> = foreign.iter().map(|(id, _)| *id).collect();
+        match policy {
+            ForeignPolicy::Refuse if old.is_none() => {
+                Err(BpfmanError::ForeignProgramsAttached { iface, program_ids })
+            }
+            ForeignPolicy::Refuse | ForeignPolicy::Coexist => {
+                warn!("interface {iface} has XDP programs attached by something other than bpfman: {program_ids:?}");
+                Ok(program_ids)
+            }
+            // An interface only runs one XDP program per mode, and one that
+            // isn't an extension can't be put in a slot of the dispatcher,
+            // so a foreign program would stop running if it were taken over.
+            ForeignPolicy::Takeover => Err(BpfmanError::ForeignTakeoverFailed {
+                iface,
+                program_id: program_ids[0],
+                reason: "only one XDP program can run on an interface, and it isn't an extension the dispatcher can run".to_string(),
+            }),
+        }
+    }
+
+    /// Returns the XDP programs attached to the interface by something other
+    /// than bpfman, with the mode each one is attached in. Dispatchers loaded
+    /// before their program id was recorded can't be told apart from foreign
+    /// programs, so None is returned for them. When interoperating with
+    /// libxdp, its dispatchers aren't foreign either.
+    pub(crate) async fn foreign_programs(
+        &self,
+        old: Option<&XdpDispatcher>,
+    ) -> Result<Option<Vec<(u32, XdpMode)>>, BpfmanError> {
+        let mut own = vec![];
+        for d in std::iter::once(self).chain(old) {
+            match d.get_program_id()? {
+                Some(id) => own.push(id),
+                None => return Ok(None),
+            }
+        }
+
+        let iface = self.get_ifname()?;
+        let if_index = self.get_ifindex()?;
+        let attached = attached_programs(if_index, self.get_netns()?.as_ref())
+            .await
+            .map_err(|e| {
+                BpfmanError::Error(format!(
+                    "unable to get the XDP programs on interface {iface}: {e}"
+                ))
+            })?;
+        let libxdp = self.get_libxdp_bpffs()?;
+        Ok(Some(
+            attached
+                .into_iter()
+                .filter(|(id, _)| !own.contains(id))
+                .filter(|(id, _)| match &libxdp {
+                    Some(bpffs) => !libxdp::dispatch_dir(bpffs, if_index, *id).exists(),
+                    None => true,
+                })
+                .collect(),
+        ))
+    }
+
+    /// Removes the dispatcher once there are no programs left on the
+    /// interface. When interoperating with libxdp, the programs libxdp
+    /// attached stay on the interface behind a dispatcher of their own.
+    pub(crate) async fn remove(
+        &mut self,
+        root_db: &Db,
+        image_manager: &mut ImageManager,
+    ) -> Result<(), BpfmanError> {
+        if let Some(bpffs) = self.get_libxdp_bpffs()? {
+            if self.hand_over(root_db, image_manager, &bpffs).await? {
+                return Ok(());
+            }
+        }
+        self.delete(root_db, true)
+    }
+
+    // Leaves the programs libxdp attached to the interface behind a new
+    // dispatcher, which bpfman forgets so only libxdp's pins keep it. If
+    // there are none, a dispatcher attached through netlink is detached.
+    // Returns true if the programs were handed over to libxdp.
+    async fn hand_over(
+        &mut self,
+        root_db: &Db,
+        image_manager: &mut ImageManager,
+        bpffs: &Path,
+    ) -> Result<bool, BpfmanError> {
+        let if_index = self.get_ifindex()?;
+        let iface = self.get_ifname()?;
+        if Path::new(&self.link_pin_path()?).exists() {
+            return Ok(false);
+        }
+
+        let lock = libxdp::lock(bpffs)?;
+        let (current, components) = libxdp_chain(bpffs, if_index, &self.get_bpfman_ids()?).await?;
+        let Some((current, mode)) = current else {
+            return Ok(false);
+        };
+        if components.is_empty() {
+            debug!("XdpDispatcher::hand_over() detaching dispatcher {current} from {iface}");
+            let expected = sys::prog_get_fd_by_id(current)?;
+            set_xdp_fd(
+                if_index,
+                None,
+                -1,
+                mode.as_flags() | XdpFlags::REPLACE,
+                Some(expected.as_raw_fd()),
+            )
+            .await
+            .map_err(|e| {
+                BpfmanError::Error(format!(
+                    "dispatcher detach failed on interface {iface}: {e}"
+                ))
+            })?;
+            let _ = fs::remove_dir_all(libxdp::dispatch_dir(bpffs, if_index, current));
+            return Ok(false);
+        }
+        // The new dispatcher takes the lock while it's loaded.
+        drop(lock);
+
+        debug!("XdpDispatcher::hand_over() leaving {iface} to libxdp");
+        let mut next = XdpDispatcher::new(
+            root_db,
+            &mode,
+            false,
+            if_index,
+            iface,
+            None,
+            self.get_revision()?.wrapping_add(1),
+        )?;
+        next.load(
+            root_db,
+            &mut [],
+            Some(Dispatcher::Xdp(XdpDispatcher::new_from_db(
+                self.db_tree.clone(),
+            ))),
+            image_manager,
+            ForeignPolicy::Coexist,
+            Some(bpffs),
+        )
+        .await?;
+        next.forget(root_db)?;
+        Ok(true)
+    }
+
+    fn link_pin_path(&self) -> Result<String, BpfmanError> {
+        Ok(format!("{RTDIR_FS_XDP}/dispatcher_{}_link", self.if_key()?))
+    }
+
     pub(crate) fn attach(&mut self) -> Result<(), BpfmanError> {
         let if_index = self.get_ifindex()?;
         let revision = self.get_revision()?;
         let mode = self.get_mode()?;
+        let fallback = self.get_mode_fallback()?;
+        let mut effective_mode = mode;
         let program_name = self.get_program_name()?;
 
         debug!(
@@ -181,6 +539,8 @@ impl XdpDispatcher {
             if_index, revision
         );
         let iface = self.get_ifname()?;
+        let netns = self.get_netns()?;
+        let path = PathBuf::from(self.link_pin_path()?);
         let dispatcher: &mut Xdp = self
             .loader
             .as_mut()
@@ -189,21 +549,31 @@ impl XdpDispatcher {
             .unwrap()
             .try_into()?;
 
-        let path = PathBuf::from(format!("{RTDIR_FS_XDP}/dispatcher_{if_index}_link"));
         if path.exists() {
             let pinned_link: FdLink = PinnedLink::from_pin(path).unwrap().into();
             dispatcher
                 .attach_to_link(pinned_link.try_into().unwrap())
                 .unwrap();
         } else {
-            let flags = mode.as_flags();
-            let link = dispatcher.attach(&iface, flags).map_err(|e| {
-                BpfmanError::Error(format!(
-                    "dispatcher attach failed on interface {iface}: {e}"
-                ))
+            let link = with_netns(netns.as_ref(), || {
+                match dispatcher.attach(&iface, mode.as_flags()) {
+                    Err(e) if fallback && mode != XdpMode::Skb => {
+                        warn!(
+                            "dispatcher attach in {mode} mode failed on interface {iface}, \
+                            falling back to skb mode: {e}"
+                        );
+                        effective_mode = XdpMode::Skb;
+                        dispatcher.attach(&iface, XdpMode::Skb.as_flags())
+                    }
+                    result => result,
+                }
+                .map_err(|e| {
+                    BpfmanError::Error(format!(
+                        "dispatcher attach failed on interface {iface}: {e}"
+                    ))
+                })
             })?;
             let owned_link = dispatcher.take_link(link)?;
-            let path = format!("{RTDIR_FS_XDP}/dispatcher_{if_index}_link");
             let _ = TryInto::<FdLink>::try_into(owned_link)
                 .map_err(|e| {
                     BpfmanError::Error(format!(
@@ -213,13 +583,103 @@ impl XdpDispatcher {
                 .pin(path)
                 .map_err(BpfmanError::UnableToPinLink)?;
         }
+        self.set_mode(&effective_mode)
+    }
+
+    // Attaches the dispatcher through netlink the way libxdp does, so libxdp
+    // can replace it in turn. It replaces the dispatcher with the id if
+    // there's one, and otherwise only attaches if nothing is on the interface.
+    async fn attach_netlink(&mut self, current: Option<u32>) -> Result<(), BpfmanError> {
+        let if_index = self.get_ifindex()?;
+        let mode = self.get_mode()?;
+        let fallback = self.get_mode_fallback()?;
+        let program_name = self.get_program_name()?;
+        let iface = self.get_ifname()?;
+        debug!("XdpDispatcher::attach_netlink() for if_index {if_index}, replacing {current:?}");
+
+        let dispatcher: &mut Xdp = self
+            .loader
+            .as_mut()
+            .ok_or(BpfmanError::NotLoaded)?
+            .program_mut(program_name.as_str())
+            .unwrap()
+            .try_into()?;
+        let fd = dispatcher.fd()?.as_fd().as_raw_fd();
+        let expected = current.map(sys::prog_get_fd_by_id).transpose()?;
+        let attach = |mode: XdpMode| {
+            let flags = match expected {
+                Some(_) => mode.as_flags() | XdpFlags::REPLACE,
+                None => mode.as_flags() | XdpFlags::UPDATE_IF_NOEXIST,
+            };
+            set_xdp_fd(
+                if_index,
+                None,
+                fd,
+                flags,
+                expected.as_ref().map(|e| e.as_raw_fd()),
+            )
+        };
+
+        let mut effective_mode = mode;
+        match attach(mode).await {
+            Err(e) if fallback && mode != XdpMode::Skb && current.is_none() => {
+                warn!(
+                    "dispatcher attach in {mode} mode failed on interface {iface}, \
+                    falling back to skb mode: {e}"
+                );
+                effective_mode = XdpMode::Skb;
+                attach(XdpMode::Skb).await
+            }
+            result => result,
+        }
+        .map_err(|e| {
+            BpfmanError::Error(format!(
+                "dispatcher attach failed on interface {iface}: {e}"
+            ))
+        })?;
+        self.set_mode(&effective_mode)
+    }
+
+    // Attaches the programs libxdp loaded to their slots of the dispatcher,
+    // pinning them where libxdp expects them.
+    fn attach_libxdp_programs(
+        &mut self,
+        slots: Vec<(usize, libxdp::Component)>,
+        dir: &Path,
+    ) -> Result<(), BpfmanError> {
+        let program_name = self.get_program_name()?;
+        let dispatcher: &mut Xdp = self
+            .loader
+            .as_mut()
+            .ok_or(BpfmanError::NotLoaded)?
+            .program_mut(program_name.as_str())
+            .unwrap()
+            .try_into()?;
+        let mut attached = vec![];
+        for (slot, c) in slots {
+            let mut ext = Extension::from_pin(&c.pin_path)?;
+            let link_id = ext.attach_to_program(dispatcher.fd()?, &format!("prog{slot}"))?;
+            let link: FdLink = ext.take_link(link_id)?.into();
+            let prog = sys::obj_get(&c.pin_path)?;
+            link.pin(libxdp::pin_slot(dir, slot, prog.as_fd())?)
+                .map_err(BpfmanError::UnableToPinLink)?;
+            attached.push((slot, c));
+        }
+        for (slot, c) in attached {
+            self.set_libxdp_program(slot, &c)?;
+        }
         Ok(())
     }
 
-    fn attach_extensions(&mut self, extensions: &mut [&mut XdpProgram]) -> Result<(), BpfmanError> {
+    fn attach_extensions(
+        &mut self,
+        extensions: &mut [&mut XdpProgram],
+        libxdp_dir: Option<&Path>,
+    ) -> Result<(), BpfmanError> {
         let if_index = self.get_ifindex()?;
         let revision = self.get_revision()?;
         let program_name = self.get_program_name()?;
+        let if_key = self.if_key()?;
         debug!(
             "XdpDispatcher::attach_extensions() for if_index {}, revision {}",
             if_index, revision
@@ -236,19 +696,17 @@ impl XdpDispatcher {
                 .unwrap()
                 .cmp(&b.get_current_position().unwrap())
         });
-        for (i, v) in extensions.iter_mut().enumerate() {
+        for v in extensions.iter_mut() {
+            let i = v
+                .get_current_position()?
+                .ok_or_else(|| BpfmanError::Error("missing position".to_string()))?;
             if v.get_attached()? {
                 let id = v.get_data().get_id()?;
                 let mut ext = Extension::from_pin(format!("{RTDIR_FS}/prog_{id}"))?;
                 let target_fn = format!("prog{i}");
-                let new_link_id = ext
-                    .attach_to_program(dispatcher.fd().unwrap(), &target_fn)
-                    .unwrap();
+                let new_link_id = ext.attach_to_program(dispatcher.fd()?, &target_fn)?;
                 let new_link: FdLink = ext.take_link(new_link_id)?.into();
-                let path = format!(
-                    "{RTDIR_FS_XDP}/dispatcher_{if_index}_{}/link_{id}",
-                    revision
-                );
+                let path = format!("{RTDIR_FS_XDP}/dispatcher_{if_key}_{}/link_{id}", revision);
                 new_link.pin(path).map_err(BpfmanError::UnableToPinLink)?;
             } else {
                 let name = &v.get_data().get_name()?;
@@ -256,7 +714,9 @@ impl XdpDispatcher {
 
                 let mut bpf = BpfLoader::new();
 
-                bpf.allow_unsupported_maps().extension(name);
+                bpf.allow_unsupported_maps()
+                    .extension(name)
+                    .verifier_log_level(v.get_data().verifier_log_level()?);
 
                 for (name, value) in global_data {
                     bpf.set_global(name, value.as_slice(), true);
@@ -292,7 +752,7 @@ impl XdpDispatcher {
                 let fd_link: FdLink = new_link.into();
                 fd_link
                     .pin(format!(
-                        "{RTDIR_FS_XDP}/dispatcher_{if_index}_{}/link_{id}",
+                        "{RTDIR_FS_XDP}/dispatcher_{if_key}_{}/link_{id}",
                         revision,
                     ))
                     .map_err(BpfmanError::UnableToPinLink)?;
@@ -316,6 +776,19 @@ impl XdpDispatcher {
                     }
                 }
             }
+
+            // libxdp finds the programs in the enabled slots through pins of
+            // its own.
+            if let Some(dir) = libxdp_dir {
+                if !v.get_disabled()? {
+                    let id = v.get_data().get_id()?;
+                    let prog = sys::obj_get(Path::new(&format!("{RTDIR_FS}/prog_{id}")))?;
+                    let link = sys::obj_get(Path::new(&format!(
+                        "{RTDIR_FS_XDP}/dispatcher_{if_key}_{revision}/link_{id}"
+                    )))?;
+                    sys::obj_pin(link.as_fd(), &libxdp::pin_slot(dir, i, prog.as_fd())?)?;
+                }
+            }
         }
         Ok(())
     }
@@ -327,6 +800,25 @@ impl XdpDispatcher {
             "XdpDispatcher::delete() for if_index {}, revision {}, full {}",
             if_index, revision, full
         );
+        // libxdp may have removed the pins already when it replaced the
+        // dispatcher.
+        if let (Some(bpffs), Some(id)) = (self.get_libxdp_bpffs()?, self.get_program_id()?) {
+            let _ = fs::remove_dir_all(libxdp::dispatch_dir(&bpffs, if_index, id));
+        }
+        let path_link = self.link_pin_path()?;
+        self.forget(root_db)?;
+        if full && Path::new(&path_link).exists() {
+            fs::remove_file(path_link)
+                .map_err(|e| BpfmanError::Error(format!("unable to cleanup state: {e}")))?;
+        }
+        Ok(())
+    }
+
+    // Removes the database entry and the bpfman pins of the dispatcher, but
+    // leaves it attached.
+    fn forget(&self, root_db: &Db) -> Result<(), BpfmanError> {
+        let revision = self.get_revision()?;
+        let if_key = self.if_key()?;
         root_db.drop_tree(self.db_tree.name()).map_err(|e| {
             BpfmanError::DatabaseError(
                 format!(
@@ -337,15 +829,55 @@ impl XdpDispatcher {
             )
         })?;
 
-        let path = format!("{RTDIR_FS_XDP}/dispatcher_{}_{}", if_index, revision);
+        let path = format!("{RTDIR_FS_XDP}/dispatcher_{}_{}", if_key, revision);
         fs::remove_dir_all(path)
-            .map_err(|e| BpfmanError::Error(format!("unable to cleanup state: {e}")))?;
-        if full {
-            let path_link = format!("{RTDIR_FS_XDP}/dispatcher_{}_link", if_index);
-            fs::remove_file(path_link)
-                .map_err(|e| BpfmanError::Error(format!("unable to cleanup state: {e}")))?;
+            .map_err(|e| BpfmanError::Error(format!("unable to cleanup state: {e}")))
+    }
+
+    // Returns true if the bpffs state for this dispatcher revision still exists.
+    pub(crate) fn is_pinned(&self) -> Result<bool, BpfmanError> {
+        let if_key = self.if_key()?;
+        let revision = self.get_revision()?;
+        Ok(PathBuf::from(format!("{RTDIR_FS_XDP}/dispatcher_{if_key}_{revision}")).exists())
+    }
+
+    pub(crate) fn state(
+        &self,
+        mut slots: Vec<DispatcherSlot>,
+    ) -> Result<DispatcherState, BpfmanError> {
+        for (slot, program_id, priority, name) in self.get_libxdp_programs()? {
+            slots.push(DispatcherSlot {
+                slot,
+                program_id,
+                name,
+                priority,
+                link_id: None,
+                disabled: false,
+                libxdp: true,
+            });
         }
-        Ok(())
+        slots.sort_by_key(|s| s.slot);
+        Ok(DispatcherState {
+            program_type: ProgramType::Xdp,
+            if_name: self.get_ifname()?,
+            if_index: self.get_ifindex()?,
+            direction: None,
+            netns: self.get_netns()?.map(|n| n.path),
+            revision: self.get_revision()?,
+            mode: self.get_mode()?.to_string(),
+            priority: None,
+            stages: vec![DispatcherStage {
+                handle: None,
+                num_progs_enabled: self.get_num_progs_enabled()?,
+                chain_call_actions: self.get_chain_call_actions()?,
+                slots,
+            }],
+            foreign: vec![],
+        })
+    }
+
+    fn if_key(&self) -> Result<String, BpfmanError> {
+        Ok(if_key(self.get_ifindex()?, self.get_netns()?.map(|n| n.id)))
     }
 
     pub(crate) fn set_revision(&mut self, revision: u32) -> Result<(), BpfmanError> {
@@ -382,14 +914,134 @@ impl XdpDispatcher {
         })?
     }
 
+    pub(crate) fn set_mode_fallback(&mut self, fallback: bool) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.db_tree,
+            MODE_FALLBACK,
+            &(fallback as i8).to_ne_bytes(),
+        )
+    }
+
+    pub(crate) fn get_mode_fallback(&self) -> Result<bool, BpfmanError> {
+        Ok(sled_get_option(&self.db_tree, MODE_FALLBACK)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
+    pub(crate) fn set_wide_dispatcher(&mut self, wide: bool) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, WIDE_DISPATCHER, &(wide as i8).to_ne_bytes())
+    }
+
+    pub(crate) fn get_wide_dispatcher(&self) -> Result<bool, BpfmanError> {
+        Ok(sled_get_option(&self.db_tree, WIDE_DISPATCHER)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
     pub(crate) fn set_num_extensions(&mut self, num_extensions: usize) -> Result<(), BpfmanError> {
         sled_insert(&self.db_tree, NUM_EXTENSIONS, &num_extensions.to_ne_bytes())
     }
 
+    pub(crate) fn set_program_id(&mut self, id: u32) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, PROGRAM_ID, &id.to_ne_bytes())
+    }
+
+    pub(crate) fn get_program_id(&self) -> Result<Option<u32>, BpfmanError> {
+        Ok(sled_get_option(&self.db_tree, PROGRAM_ID)?.map(bytes_to_u32))
+    }
+
+    pub(crate) fn set_libxdp_bpffs(&mut self, bpffs: &Path) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.db_tree,
+            LIBXDP_BPFFS,
+            bpffs.to_string_lossy().as_bytes(),
+        )
+    }
+
+    pub(crate) fn get_libxdp_bpffs(&self) -> Result<Option<PathBuf>, BpfmanError> {
+        Ok(sled_get_option(&self.db_tree, LIBXDP_BPFFS)?
+            .map(|v| PathBuf::from(bytes_to_string(&v))))
+    }
+
+    // Records the programs bpfman put in the dispatcher, so they aren't taken
+    // for libxdp's once libxdp replaced it.
+    pub(crate) fn set_bpfman_ids(&mut self, ids: &[u32]) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, LIBXDP_BPFMAN_IDS, &u32s_to_bytes(ids))
+    }
+
+    pub(crate) fn get_bpfman_ids(&self) -> Result<Vec<u32>, BpfmanError> {
+        sled_get_option(&self.db_tree, LIBXDP_BPFMAN_IDS).map(|v| v.map_or(vec![], bytes_to_u32s))
+    }
+
+    pub(crate) fn set_libxdp_program(
+        &mut self,
+        slot: usize,
+        c: &libxdp::Component,
+    ) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.db_tree,
+            &format!("{PREFIX_LIBXDP_PROG}{slot}"),
+            &u32s_to_bytes(&[c.program_id, c.run_prio]),
+        )?;
+        sled_insert(
+            &self.db_tree,
+            &format!("{PREFIX_LIBXDP_NAME}{slot}"),
+            c.name.as_bytes(),
+        )
+    }
+
+    // Returns the slot, id, priority and name of the programs libxdp
+    // attached that are in the dispatcher.
+    pub(crate) fn get_libxdp_programs(&self) -> Result<Vec<(u32, u32, i32, String)>, BpfmanError> {
+        self.db_tree
+            .scan_prefix(PREFIX_LIBXDP_PROG)
+            .map(|entry| {
+                let (key, value) = entry.map_err(|e| {
+                    BpfmanError::DatabaseError(
+                        "Failed to get libxdp programs".to_string(),
+                        e.to_string(),
+                    )
+                })?;
+                let slot = bytes_to_string(&key)[PREFIX_LIBXDP_PROG.len()..]
+                    .parse()
+                    .map_err(|e| BpfmanError::Error(format!("invalid libxdp program: {e}")))?;
+                let value = bytes_to_u32s(value.to_vec());
+                let name = sled_get_option(&self.db_tree, &format!("{PREFIX_LIBXDP_NAME}{slot}"))?
+                    .map(|v| bytes_to_string(&v))
+                    .unwrap_or_default();
+                Ok((slot, value[0], value[1] as i32, name))
+            })
+            .collect()
+    }
+
     pub(crate) fn get_num_extensions(&self) -> Result<usize, BpfmanError> {
         sled_get(&self.db_tree, NUM_EXTENSIONS).map(bytes_to_usize)
     }
 
+    pub(crate) fn set_num_progs_enabled(&mut self, num: u8) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.db_tree,
+            NUM_PROGS_ENABLED,
+            &(num as u32).to_ne_bytes(),
+        )
+    }
+
+    // Dispatchers created before their config was saved ran every extension.
+    pub(crate) fn get_num_progs_enabled(&self) -> Result<u32, BpfmanError> {
+        match sled_get_option(&self.db_tree, NUM_PROGS_ENABLED)? {
+            Some(v) => Ok(bytes_to_u32(v)),
+            None => Ok(self.get_num_extensions()? as u32),
+        }
+    }
+
+    pub(crate) fn set_chain_call_actions(&mut self, actions: &[u32]) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, CHAIN_CALL_ACTIONS, &u32s_to_bytes(actions))
+    }
+
+    pub(crate) fn get_chain_call_actions(&self) -> Result<Vec<u32>, BpfmanError> {
+        sled_get_option(&self.db_tree, CHAIN_CALL_ACTIONS).map(|v| v.map_or(vec![], bytes_to_u32s))
+    }
+
     pub(crate) fn set_program_name(&mut self, program_name: &str) -> Result<(), BpfmanError> {
         sled_insert(&self.db_tree, PROGRAM_NAME, program_name.as_bytes())
     }
@@ -397,4 +1049,236 @@ impl XdpDispatcher {
     pub(crate) fn get_program_name(&self) -> Result<String, BpfmanError> {
         sled_get(&self.db_tree, PROGRAM_NAME).map(|v| bytes_to_string(&v))
     }
+
+    pub(crate) fn set_netns(&mut self, netns: &Netns) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, NETNS, netns.path.as_bytes())?;
+        sled_insert(&self.db_tree, NETNS_ID, &netns.id.to_ne_bytes())
+    }
+
+    pub(crate) fn get_netns(&self) -> Result<Option<Netns>, BpfmanError> {
+        let path = sled_get_option(&self.db_tree, NETNS)?;
+        let id = sled_get_option(&self.db_tree, NETNS_ID)?;
+        Ok(path.zip(id).map(|(path, id)| Netns {
+            path: bytes_to_string(&path),
+            id: bytes_to_u64(id),
+        }))
+    }
+}
+
+// Returns the name of the dispatcher program in the image to load.
+fn dispatcher_program_name(wide: bool, frags: bool) -> &'static str {
+    match (wide, frags) {
+        (false, false) => XDP_DISPATCHER_PROGRAM_NAME,
+        (false, true) => XDP_DISPATCHER_FRAGS_PROGRAM_NAME,
+        (true, false) => XDP_WIDE_DISPATCHER_PROGRAM_NAME,
+        (true, true) => XDP_WIDE_DISPATCHER_FRAGS_PROGRAM_NAME,
+    }
+}
+
+// Returns the image with the dispatcher program. Only the dispatcher libxdp
+// knows is in the published image, so interfaces which don't need the others
+// don't depend on the newer one.
+fn dispatcher_image(program_name: &str) -> &'static str {
+    if program_name == XDP_DISPATCHER_PROGRAM_NAME {
+        XDP_DISPATCHER_IMAGE
+    } else {
+        XDP_DISPATCHER_V3_IMAGE
+    }
+}
+
+// Explains why an interface needed the wide dispatcher when it couldn't be
+// loaded.
+fn wide_dispatcher_error(image_url: &str, e: BpfmanError) -> BpfmanError {
+    BpfmanError::Error(format!(
+        "more than {MAX_DISPATCHER_ACTIONS} XDP programs on an interface need the wide \
+         dispatcher from {image_url}: {e}"
+    ))
+}
+
+// Orders the programs bpfman loaded and the ones libxdp attached by their
+// priorities. The enabled programs bpfman loaded are in position order and
+// libxdp's are in the order they run in, so the two are merged, bpfman's
+// going first when the priorities are equal. Disabled programs come last.
+fn merge_slots(
+    extensions: &[&mut XdpProgram],
+    components: Vec<libxdp::Component>,
+) -> Result<Vec<Slot>, BpfmanError> {
+    let mut components = components.into_iter().peekable();
+    let mut slots = vec![];
+    let mut disabled = vec![];
+    for (i, p) in extensions.iter().enumerate() {
+        if p.get_disabled()? {
+            disabled.push(Slot::Bpfman(i));
+            continue;
+        }
+        let priority = p.get_priority()?;
+        while let Some(c) = components.next_if(|c| (c.run_prio as i64) < priority as i64) {
+            slots.push(Slot::Libxdp(c));
+        }
+        slots.push(Slot::Bpfman(i));
+    }
+    slots.extend(components.map(Slot::Libxdp));
+    slots.extend(disabled);
+    Ok(slots)
+}
+
+// Checks that the wide dispatcher can be used for more than
+// MAX_DISPATCHER_ACTIONS programs on an interface. It has to be enabled for
+// the interface, since it's only in the newer image and libxdp doesn't know
+// it.
+fn check_wide_dispatcher(
+    num_programs: usize,
+    libxdp: bool,
+    wide_dispatcher: bool,
+) -> Result<(), BpfmanError> {
+    if num_programs > XDP_MAX_PROGRAMS {
+        return Err(BpfmanError::TooManyPrograms);
+    }
+    if libxdp {
+        return Err(BpfmanError::Error(format!(
+            "{num_programs} XDP programs, counting the ones libxdp attached, don't fit in a \
+             dispatcher libxdp knows, which has room for {MAX_DISPATCHER_ACTIONS}"
+        )));
+    }
+    if !wide_dispatcher {
+        return Err(BpfmanError::Error(format!(
+            "{num_programs} XDP programs don't fit in the dispatcher of an interface, which has \
+             room for {MAX_DISPATCHER_ACTIONS}. Set xdp_wide_dispatcher for the interface in the \
+             configuration to use the wide dispatcher from {XDP_DISPATCHER_V3_IMAGE}, which has \
+             room for {XDP_MAX_PROGRAMS}"
+        )));
+    }
+    Ok(())
+}
+
+// Returns the dispatcher on the interface if it's one libxdp knows, along
+// with the programs in its enabled slots other than the ones with the ids.
+async fn libxdp_chain(
+    bpffs: &Path,
+    if_index: u32,
+    own: &[u32],
+) -> Result<(Option<(u32, XdpMode)>, Vec<libxdp::Component>), BpfmanError> {
+    let attached = attached_programs(if_index, None).await.map_err(|e| {
+        BpfmanError::Error(format!(
+            "unable to get the XDP programs on if_index {if_index}: {e}"
+        ))
+    })?;
+    for (id, mode) in attached {
+        if let Some(chain) = libxdp::read_chain(bpffs, if_index, id)? {
+            let components = chain
+                .into_iter()
+                .filter(|c| !own.contains(&c.program_id))
+                .collect();
+            return Ok((Some((id, mode)), components));
+        }
+    }
+    Ok((None, vec![]))
+}
+
+// Returns the XDP programs attached to the interface, with the mode each one
+// is attached in. An interface only reports the mode of its program unless
+// it has one in more than one mode.
+async fn attached_programs(
+    if_index: u32,
+    netns: Option<&Netns>,
+) -> Result<Vec<(u32, XdpMode)>, anyhow::Error> {
+    let (connection, handle, _) = with_netns(netns, || Ok(rtnetlink::new_connection()?))?;
+    tokio::spawn(connection);
+
+    let mut programs = vec![];
+    let mut links = handle.link().get().match_index(if_index).execute();
+    while let Some(link) = links.try_next().await? {
+        for attr in link.attributes {
+            let LinkAttribute::Xdp(xdp) = attr else {
+                continue;
+            };
+            let attached = xdp.iter().find_map(|x| match x {
+                LinkXdp::Attached(a) => Some(*a),
+                _ => None,
+            });
+            for x in xdp {
+                let program = match (x, attached) {
+                    (LinkXdp::ProgId(id), Some(XdpAttached::SocketBuffer))
+                    | (LinkXdp::SkbProgId(id), _) => (id, XdpMode::Skb),
+                    (LinkXdp::ProgId(id), Some(XdpAttached::Driver))
+                    | (LinkXdp::DrvProgId(id), _) => (id, XdpMode::Drv),
+                    (LinkXdp::ProgId(id), Some(XdpAttached::Hardware))
+                    | (LinkXdp::HwProgId(id), _) => (id, XdpMode::Hw),
+                    _ => continue,
+                };
+                if !programs.contains(&program) {
+                    programs.push(program);
+                }
+            }
+        }
+    }
+    Ok(programs)
+}
+
+// Attaches the program with the fd to the interface through netlink with the
+// flags, or detaches the program attached in the mode of the flags if the fd
+// is -1. With XdpFlags::REPLACE, the program with the expected fd is
+// replaced. Programs attached through netlink stay attached without a pin.
+async fn set_xdp_fd(
+    if_index: u32,
+    netns: Option<&Netns>,
+    fd: RawFd,
+    flags: XdpFlags,
+    expected: Option<RawFd>,
+) -> Result<(), anyhow::Error> {
+    let (connection, handle, _) = with_netns(netns, || Ok(rtnetlink::new_connection()?))?;
+    tokio::spawn(connection);
+
+    let mut request = handle.link().set(if_index);
+    request.message_mut().attributes.push(LinkAttribute::Xdp(
+        [LinkXdp::Fd(fd), LinkXdp::Flags(flags.bits())]
+            .into_iter()
+            .chain(expected.map(|fd| LinkXdp::ExpectedFd(fd as u32)))
+            .collect(),
+    ));
+    request.execute().await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_dispatcher_program_name() {
+        assert_eq!(dispatcher_program_name(false, false), "xdp_dispatcher");
+        assert_eq!(dispatcher_program_name(false, true), "xdp_dispatcher_frags");
+        assert_eq!(dispatcher_program_name(true, false), "xdp_dispatcher_wide");
+        assert_eq!(
+            dispatcher_program_name(true, true),
+            "xdp_dispatcher_wide_frags"
+        );
+    }
+
+    #[test]
+    fn test_dispatcher_image() {
+        assert_eq!(
+            dispatcher_image(dispatcher_program_name(false, false)),
+            "quay.io/bpfman/xdp-dispatcher:v2"
+        );
+        for (wide, frags) in [(false, true), (true, false), (true, true)] {
+            assert_eq!(
+                dispatcher_image(dispatcher_program_name(wide, frags)),
+                "quay.io/bpfman/xdp-dispatcher:v3"
+            );
+        }
+    }
+
+    #[test]
+    fn test_check_wide_dispatcher() {
+        assert!(check_wide_dispatcher(XDP_MAX_PROGRAMS, false, true).is_ok());
+        assert!(matches!(
+            check_wide_dispatcher(XDP_MAX_PROGRAMS + 1, false, true),
+            Err(BpfmanError::TooManyPrograms)
+        ));
+        let e = check_wide_dispatcher(MAX_DISPATCHER_ACTIONS + 1, true, true).unwrap_err();
+        assert!(e.to_string().contains("libxdp"));
+        let e = check_wide_dispatcher(MAX_DISPATCHER_ACTIONS + 1, false, false).unwrap_err();
+        assert!(e.to_string().contains("xdp_wide_dispatcher"));
+    }
 }
@@ -1,264 +1,612 @@
 // SPDX-License-Identifier: Apache-2.0
 // Copyright Authors of bpfman
 
-// TODO(astoycos) see issue #881
-// use std::{
-//     collections::HashMap,
-//     path::{Path, PathBuf},
-// };
-
-// use anyhow::bail;
-// use bpfman_api::{
-//     util::directories::CFGDIR_STATIC_PROGRAMS, ProgramType, TcProceedOn, XdpProceedOn,
-// };
-// use log::{info, warn};
-// use serde::Deserialize;
-// use tokio::fs;
-
-// use crate::{
-//     command::{
-//         Direction,
-//         Location::{File, Image},
-//         Program, ProgramData, TcProgram, TracepointProgram, XdpProgram,
-//     },
-//     oci_utils::image_manager::BytecodeImage,
-//     utils::read_to_string,
-// };
-
-// #[derive(Debug, Clone, Deserialize)]
-// pub(crate) struct XdpAttachInfo {
-//     pub(crate) priority: i32,
-//     pub(crate) iface: String,
-//     pub(crate) proceed_on: XdpProceedOn,
-// }
-
-// #[derive(Debug, Clone, Deserialize)]
-// pub(crate) struct TcAttachInfo {
-//     pub(crate) priority: i32,
-//     pub(crate) iface: String,
-//     pub(crate) proceed_on: TcProceedOn,
-//     pub(crate) direction: Direction,
-// }
-
-// #[derive(Debug, Clone, Deserialize)]
-// pub(crate) struct TracepointAttachInfo {
-//     pub(crate) tracepoint: String,
-// }
-
-// // TODO not yet implemented
-// // #[derive(Debug, Clone, Deserialize)]
-// // pub(crate) struct KprobeAttachInfo {
-// //     pub(crate) fn_name: String,
-// //     pub(crate) offset: u64,
-// //     pub(crate) retprobe: bool,
-// //     pub(crate) container_pid: Option<i32>,
-// // }
-
-// // #[derive(Debug, Clone, Deserialize)]
-// // pub(crate) struct UprobeAttachInfo {
-// //     pub(crate) fn_name: Option<String>,
-// //     pub(crate) offset: u64,
-// //     pub(crate) target: String,
-// //     pub(crate) retprobe: bool,
-// //     pub(crate) pid: Option<i32>,
-// //     pub(crate) container_pid: Option<i32>,
-// // }
-
-// #[derive(Debug, Deserialize, Clone)]
-// pub struct StaticProgramEntry {
-//     bytecode_image: Option<BytecodeImage>,
-//     file_path: Option<String>,
-//     name: String,
-//     global_data: HashMap<String, Vec<u8>>,
-//     program_type: ProgramType,
-//     xdp_attach: Option<XdpAttachInfo>,
-//     tc_attach: Option<TcAttachInfo>,
-//     tracepoint_attach: Option<TracepointAttachInfo>,
-// }
-
-// impl StaticProgramEntry {
-//     pub(crate) fn get_bytecode_image(self) -> Option<BytecodeImage> {
-//         self.bytecode_image
-//     }
-// }
-
-// #[derive(Debug, Deserialize)]
-// pub struct NetworkAttach {
-//     pub interface: String,
-//     pub priority: i32,
-//     pub proceed_on: Vec<String>,
-// }
-
-// #[derive(Debug, Deserialize, Clone)]
-// struct StaticProgramManager {
-//     #[serde(skip)]
-//     path: PathBuf,
-//     programs: Vec<StaticProgramEntry>,
-// }
-
-// impl StaticProgramManager {
-//     async fn programs_from_directory(mut self) -> Result<(), anyhow::Error> {
-//         if let Ok(mut entries) = fs::read_dir(self.path).await {
-//             while let Some(file) = entries.next_entry().await? {
-//                 let path = &file.path();
-//                 // ignore directories
-//                 if path.is_dir() {
-//                     continue;
-//                 }
-
-//                 if let Ok(contents) = read_to_string(path).await {
-//                     let program = toml::from_str(&contents)?;
-
-//                     self.programs.push(program);
-//                 } else {
-//                     warn!("Failed to parse program static file {:?}.", path.to_str());
-//                     continue;
-//                 }
-//             }
-//         }
-//         Ok(())
-//     }
-// }
-
-// pub(crate) async fn get_static_programs<P: AsRef<Path>>(
-//     path: P,
-// ) -> Result<Vec<Program>, anyhow::Error> {
-//     let static_program_manager = StaticProgramManager {
-//         path: path.as_ref().to_path_buf(),
-//         programs: Vec::new(),
-//     };
-
-//     static_program_manager
-//         .clone()
-//         .programs_from_directory()
-//         .await?;
-
-//     let mut programs: Vec<Program> = Vec::new();
-
-//     // Load any static programs first
-//     if !static_program_manager.programs.is_empty() {
-//         info!("Loading static programs from {CFGDIR_STATIC_PROGRAMS}");
-//         for program in static_program_manager.programs {
-//             let location = match program.file_path {
-//                 Some(p) => File(p),
-//                 None => Image(
-//                     program
-//                         .clone()
-//                         .get_bytecode_image()
-//                         .expect("static program did not provide bytecode"),
-//                 ),
-//             };
-
-//             let data = ProgramData::new(
-//                 location,
-//                 program.name,
-//                 HashMap::new(),
-//                 program.global_data,
-//                 None,
-//             );
-//             let prog = match program.program_type {
-//                 ProgramType::Xdp => {
-//                     if let Some(m) = program.xdp_attach {
-//                         Program::Xdp(XdpProgram::new(data, m.priority, m.iface, m.proceed_on))
-//                     } else {
-//                         bail!("invalid info for xdp program")
-//                     }
-//                 }
-//                 ProgramType::Tc => {
-//                     if let Some(m) = program.tc_attach {
-//                         Program::Tc(TcProgram::new(
-//                             data,
-//                             m.priority,
-//                             m.iface,
-//                             m.proceed_on,
-//                             m.direction,
-//                         ))
-//                     } else {
-//                         bail!("invalid attach type for tc program")
-//                     }
-//                 }
-//                 ProgramType::Tracepoint => {
-//                     if let Some(m) = program.tracepoint_attach {
-//                         Program::Tracepoint(TracepointProgram::new(data, m.tracepoint))
-//                     } else {
-//                         bail!("invalid attach type for tc program")
-//                     }
-//                 }
-//                 m => bail!("program type not yet supported to load statically: {:?}", m),
-//             };
-
-//             programs.push(prog)
-//         }
-//     };
-
-//     Ok(programs)
-// }
-
-// #[cfg(test)]
-// mod test {
-//     use super::*;
-
-//     #[tokio::test]
-//     async fn test_parse_program_from_invalid_path() {
-//         let static_program_manager = StaticProgramManager {
-//             path: "/tmp/file.toml".into(),
-//             programs: Vec::new(),
-//         };
-
-//         static_program_manager
-//             .clone()
-//             .programs_from_directory()
-//             .await
-//             .unwrap();
-//         assert!(static_program_manager.programs.is_empty())
-//     }
-
-//     #[test]
-//     fn test_parse_single_file() {
-//         let input: &str = r#"
-//         [[programs]]
-//         name = "firewall"
-//         file_path = "/opt/bin/myapp/lib/myebpf.o"
-//         global_data = { }
-//         program_type ="Xdp"
-//         xdp_attach = { iface = "eth0", priority = 50, proceed_on = [], position=0 }
-
-//         [[programs]]
-//         name = "pass"
-//         bytecode_image = { image_url = "quay.io/bpfman-bytecode/xdp_pass:latest", image_pull_policy="Always" }
-//         global_data = { }
-//         program_type ="Xdp"
-//         xdp_attach = { iface = "eth0", priority = 55, proceed_on = [], position=0 }
-
-//         [[programs]]
-//         name = "counter"
-//         bytecode_image = { image_url = "quay.io/bpfman-bytecode/xdp_pass:latest", image_pull_policy="Always" }
-//         global_data = { }
-//         program_type ="Tc"
-//         tc_attach = { iface = "eth0", priority = 55, proceed_on = [], position=0, direction="Ingress" }
-
-//         [[programs]]
-//         name = "tracepoint"
-//         bytecode_image = { image_url = "quay.io/bpfman-bytecode/tracepoint:latest", image_pull_policy="Always" }
-//         global_data = { }
-//         program_type ="Tracepoint"
-//         tracepoint_attach = { tracepoint = "syscalls/sys_enter_openat" }
-//         "#;
-
-//         let mut programs: StaticProgramManager =
-//             toml::from_str(input).expect("error parsing toml input");
-//         match programs.programs.pop() {
-//             Some(i) => {
-//                 if let Some(m) = i.xdp_attach {
-//                     assert_eq!(m.iface, "eth0");
-//                     assert_eq!(m.priority, 55);
-//                 } else if let Some(m) = i.tracepoint_attach {
-//                     assert_eq!(m.tracepoint, "syscalls/sys_enter_openat")
-//                 } else {
-//                     panic!("incorrect attach type")
-//                 }
-//             }
-//             None => panic!("expected programs to be present"),
-//         }
-//     }
-// }
+use std::{
+    collections::{BTreeMap, HashMap},
+    ffi::OsStr,
+    fs,
+    path::Path,
+};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    errors::BpfmanError,
+    types::{
+        BytecodeImage, CgroupSkbProgram, CgroupSockAddrProgram, Direction, FentryProgram,
+        FexitProgram, KprobeProgram,
+        Location::{File, Image},
+        Program, ProgramData, ProgramType, SkMsgProgram, SockOpsProgram, TcProceedOn, TcProgram,
+        TracepointProgram, UprobeProgram, XdpProceedOn, XdpProgram,
+    },
+};
+
+/// Metadata key added to every program loaded from a static program file. The
+/// value is a digest of the program's entry, which is used to reconcile the
+/// loaded programs with the static program files when bpfman restarts.
+pub(crate) const STATIC_PROGRAM_METADATA_KEY: &str = "bpfman.io/static-program";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct XdpAttachInfo {
+    pub(crate) priority: i32,
+    pub(crate) iface: String,
+    #[serde(default)]
+    pub(crate) proceed_on: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) netns: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub(crate) frags: bool,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct TcAttachInfo {
+    pub(crate) priority: i32,
+    pub(crate) iface: String,
+    #[serde(default)]
+    pub(crate) proceed_on: Vec<String>,
+    pub(crate) direction: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) netns: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct TracepointAttachInfo {
+    pub(crate) tracepoint: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct KprobeAttachInfo {
+    pub(crate) fn_name: String,
+    #[serde(default)]
+    pub(crate) offset: u64,
+    #[serde(default)]
+    pub(crate) retprobe: bool,
+    pub(crate) container_pid: Option<i32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct UprobeAttachInfo {
+    pub(crate) fn_name: Option<String>,
+    #[serde(default)]
+    pub(crate) offset: u64,
+    pub(crate) target: String,
+    #[serde(default)]
+    pub(crate) retprobe: bool,
+    pub(crate) pid: Option<i32>,
+    pub(crate) container_pid: Option<i32>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct TracingAttachInfo {
+    pub(crate) fn_name: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct CgroupSkbAttachInfo {
+    pub(crate) cgroup_path: String,
+    pub(crate) direction: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct CgroupAttachInfo {
+    pub(crate) cgroup_path: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct SkMsgAttachInfo {
+    pub(crate) map_name: String,
+}
+
+// BTreeMaps are used so that serializing an entry is deterministic, which
+// keeps the digest stable across restarts.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub(crate) struct StaticProgramEntry {
+    bytecode_image: Option<BytecodeImage>,
+    file_path: Option<String>,
+    name: String,
+    #[serde(default)]
+    global_data: BTreeMap<String, Vec<u8>>,
+    #[serde(default)]
+    metadata: BTreeMap<String, String>,
+    map_owner_id: Option<u32>,
+    program_type: ProgramType,
+    xdp_attach: Option<XdpAttachInfo>,
+    tc_attach: Option<TcAttachInfo>,
+    tracepoint_attach: Option<TracepointAttachInfo>,
+    kprobe_attach: Option<KprobeAttachInfo>,
+    uprobe_attach: Option<UprobeAttachInfo>,
+    fentry_attach: Option<TracingAttachInfo>,
+    fexit_attach: Option<TracingAttachInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cgroup_skb_attach: Option<CgroupSkbAttachInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cgroup_sock_addr_attach: Option<CgroupAttachInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sockops_attach: Option<CgroupAttachInfo>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sk_msg_attach: Option<SkMsgAttachInfo>,
+}
+
+impl StaticProgramEntry {
+    /// Returns a hex encoded sha256 digest of the entry.
+    ///
+    /// Fields added to the entry after it was first released aren't
+    /// serialized while they're unset, so that entries written before they
+    /// existed keep their digest.
+    pub(crate) fn digest(&self) -> Result<String, BpfmanError> {
+        let bytes = serde_json::to_vec(self).map_err(|e| {
+            BpfmanError::Error(format!(
+                "unable to serialize static program {}: {e}",
+                self.name
+            ))
+        })?;
+        Ok(base16ct::lower::encode_string(&Sha256::digest(bytes)))
+    }
+
+    /// Builds the Program described by the entry, tagging it with the
+    /// provided static program digest.
+    pub(crate) fn to_program(&self, digest: &str) -> Result<Program, BpfmanError> {
+        let location = match (&self.file_path, &self.bytecode_image) {
+            (Some(p), None) => File(p.clone()),
+            (None, Some(i)) => Image(i.clone()),
+            _ => {
+                return Err(BpfmanError::Error(format!(
+                    "static program {} must provide exactly one of file_path or bytecode_image",
+                    self.name
+                )))
+            }
+        };
+
+        let mut metadata: HashMap<String, String> = self.metadata.clone().into_iter().collect();
+        metadata.insert(STATIC_PROGRAM_METADATA_KEY.to_string(), digest.to_string());
+
+        let data = ProgramData::new(
+            location,
+            self.name.clone(),
+            metadata,
+            self.global_data.clone().into_iter().collect(),
+            self.map_owner_id,
+        )?;
+
+        let invalid_attach = || {
+            BpfmanError::InvalidAttach(format!(
+                "static program {} with program type {}",
+                self.name, self.program_type
+            ))
+        };
+
+        let program = match self.program_type {
+            ProgramType::Xdp => {
+                let m = self.xdp_attach.as_ref().ok_or_else(invalid_attach)?;
+                let proceed_on = XdpProceedOn::from_strings(&m.proceed_on)
+                    .map_err(|e| BpfmanError::Error(e.to_string()))?;
+                let mut program = XdpProgram::new(
+                    data,
+                    m.priority,
+                    m.iface.clone(),
+                    proceed_on,
+                    m.netns.clone(),
+                )?;
+                program.set_frags(m.frags)?;
+                Program::Xdp(program)
+            }
+            ProgramType::Tc => {
+                let m = self.tc_attach.as_ref().ok_or_else(invalid_attach)?;
+                let proceed_on = TcProceedOn::from_strings(&m.proceed_on)
+                    .map_err(|e| BpfmanError::Error(e.to_string()))?;
+                let direction = Direction::try_from(m.direction.to_lowercase())
+                    .map_err(|e| BpfmanError::Error(e.to_string()))?;
+                Program::Tc(TcProgram::new(
+                    data,
+                    m.priority,
+                    m.iface.clone(),
+                    proceed_on,
+                    direction,
+                    m.netns.clone(),
+                )?)
+            }
+            ProgramType::Tracepoint => {
+                let m = self.tracepoint_attach.as_ref().ok_or_else(invalid_attach)?;
+                Program::Tracepoint(TracepointProgram::new(data, m.tracepoint.clone())?)
+            }
+            ProgramType::Probe => match (&self.kprobe_attach, &self.uprobe_attach) {
+                (Some(m), None) => Program::Kprobe(KprobeProgram::new(
+                    data,
+                    m.fn_name.clone(),
+                    m.offset,
+                    m.retprobe,
+                    m.container_pid,
+                )?),
+                (None, Some(m)) => Program::Uprobe(UprobeProgram::new(
+                    data,
+                    m.fn_name.clone(),
+                    m.offset,
+                    m.target.clone(),
+                    m.retprobe,
+                    m.pid,
+                    m.container_pid,
+                )?),
+                _ => return Err(invalid_attach()),
+            },
+            ProgramType::Tracing => match (&self.fentry_attach, &self.fexit_attach) {
+                (Some(m), None) => Program::Fentry(FentryProgram::new(data, m.fn_name.clone())?),
+                (None, Some(m)) => Program::Fexit(FexitProgram::new(data, m.fn_name.clone())?),
+                _ => return Err(invalid_attach()),
+            },
+            ProgramType::CgroupSkb => {
+                let m = self.cgroup_skb_attach.as_ref().ok_or_else(invalid_attach)?;
+                let direction = Direction::try_from(m.direction.to_lowercase())
+                    .map_err(|e| BpfmanError::Error(e.to_string()))?;
+                Program::CgroupSkb(CgroupSkbProgram::new(
+                    data,
+                    m.cgroup_path.clone(),
+                    direction,
+                )?)
+            }
+            ProgramType::CgroupSockAddr => {
+                let m = self
+                    .cgroup_sock_addr_attach
+                    .as_ref()
+                    .ok_or_else(invalid_attach)?;
+                Program::CgroupSockAddr(CgroupSockAddrProgram::new(data, m.cgroup_path.clone())?)
+            }
+            ProgramType::SockOps => {
+                let m = self.sockops_attach.as_ref().ok_or_else(invalid_attach)?;
+                Program::SockOps(SockOpsProgram::new(data, m.cgroup_path.clone())?)
+            }
+            ProgramType::SkMsg => {
+                let m = self.sk_msg_attach.as_ref().ok_or_else(invalid_attach)?;
+                Program::SkMsg(SkMsgProgram::new(data, m.map_name.clone())?)
+            }
+            m => {
+                return Err(BpfmanError::Error(format!(
+                    "program type not yet supported to load statically: {m}"
+                )))
+            }
+        };
+
+        Ok(program)
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct StaticProgramManager {
+    #[serde(default)]
+    programs: Vec<StaticProgramEntry>,
+}
+
+impl StaticProgramManager {
+    fn programs_from_directory<P: AsRef<Path>>(path: P) -> Self {
+        let mut manager = StaticProgramManager::default();
+
+        let Ok(entries) = fs::read_dir(path.as_ref()) else {
+            return manager;
+        };
+
+        for file in entries.filter_map(|e| e.ok()) {
+            let path = file.path();
+            // ignore directories and anything that isn't a toml file
+            if path.is_dir() || path.extension() != Some(OsStr::new("toml")) {
+                continue;
+            }
+
+            match fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|c| toml::from_str::<Self>(&c).map_err(|e| e.to_string()))
+            {
+                Ok(mut m) => manager.programs.append(&mut m.programs),
+                Err(e) => warn!("Failed to parse program static file {:?}: {e}", path),
+            }
+        }
+
+        manager
+    }
+}
+
+/// Reads every static program file in `path` and returns the programs they
+/// describe, keyed by the digest of their entry. Entries which fail to parse
+/// are logged and skipped.
+pub(crate) fn get_static_programs<P: AsRef<Path>>(path: P) -> Vec<(String, Program)> {
+    let manager = StaticProgramManager::programs_from_directory(&path);
+
+    if !manager.programs.is_empty() {
+        info!(
+            "Found {} static programs in {:?}",
+            manager.programs.len(),
+            path.as_ref()
+        );
+    }
+
+    let mut programs: Vec<(String, Program)> = Vec::new();
+    for entry in manager.programs {
+        let digest = match entry.digest() {
+            Ok(d) => d,
+            Err(e) => {
+                warn!("{e}");
+                continue;
+            }
+        };
+
+        if programs.iter().any(|(d, _)| *d == digest) {
+            warn!(
+                "Ignoring duplicate definition of static program {}",
+                entry.name
+            );
+            continue;
+        }
+
+        match entry.to_program(&digest) {
+            Ok(p) => programs.push((digest, p)),
+            Err(e) => warn!("Invalid static program {}: {e}", entry.name),
+        }
+    }
+
+    programs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_program_from_invalid_path() {
+        let static_program_manager =
+            StaticProgramManager::programs_from_directory("/tmp/file.toml");
+        assert!(static_program_manager.programs.is_empty())
+    }
+
+    #[test]
+    fn test_parse_single_file() {
+        let input: &str = r#"
+        [[programs]]
+        name = "firewall"
+        file_path = "/opt/bin/myapp/lib/myebpf.o"
+        global_data = { }
+        program_type ="Xdp"
+        xdp_attach = { iface = "eth0", priority = 50, proceed_on = [] }
+
+        [[programs]]
+        name = "pass"
+        bytecode_image = { image_url = "quay.io/bpfman-bytecode/xdp_pass:latest", image_pull_policy="Always" }
+        program_type ="Xdp"
+        xdp_attach = { iface = "eth0", priority = 55, proceed_on = ["pass", "dispatcher_return"] }
+
+        [[programs]]
+        name = "counter"
+        bytecode_image = { image_url = "quay.io/bpfman-bytecode/xdp_pass:latest", image_pull_policy="Always" }
+        program_type ="Tc"
+        tc_attach = { iface = "eth0", priority = 55, proceed_on = [], direction="ingress" }
+
+        [[programs]]
+        name = "my_kprobe"
+        file_path = "/opt/bin/myapp/lib/kprobe.o"
+        program_type ="Probe"
+        kprobe_attach = { fn_name = "try_to_wake_up", retprobe = true }
+
+        [[programs]]
+        name = "my_fentry"
+        file_path = "/opt/bin/myapp/lib/fentry.o"
+        program_type ="Tracing"
+        fentry_attach = { fn_name = "do_unlinkat" }
+
+        [[programs]]
+        name = "tracepoint"
+        bytecode_image = { image_url = "quay.io/bpfman-bytecode/tracepoint:latest", image_pull_policy="Always" }
+        global_data = { }
+        program_type ="Tracepoint"
+        tracepoint_attach = { tracepoint = "syscalls/sys_enter_openat" }
+        "#;
+
+        let mut programs: StaticProgramManager =
+            toml::from_str(input).expect("error parsing toml input");
+        assert_eq!(programs.programs.len(), 6);
+
+        let digests: Vec<String> = programs
+            .programs
+            .iter()
+            .map(|p| p.digest().unwrap())
+            .collect();
+        assert_eq!(digests[0], programs.programs[0].digest().unwrap());
+        assert_ne!(digests[0], digests[1]);
+
+        match programs.programs.pop() {
+            Some(i) => {
+                if let Some(m) = i.xdp_attach {
+                    assert_eq!(m.iface, "eth0");
+                    assert_eq!(m.priority, 55);
+                } else if let Some(m) = i.tracepoint_attach {
+                    assert_eq!(m.tracepoint, "syscalls/sys_enter_openat")
+                } else {
+                    panic!("incorrect attach type")
+                }
+            }
+            None => panic!("expected programs to be present"),
+        }
+
+        for (entry, digest) in programs.programs.iter().zip(digests.iter()) {
+            let program = entry.to_program(digest).expect("invalid static program");
+            assert_eq!(
+                program
+                    .get_data()
+                    .get_metadata()
+                    .unwrap()
+                    .get(STATIC_PROGRAM_METADATA_KEY),
+                Some(digest)
+            );
+        }
+    }
+
+    fn entry(fields: &str) -> StaticProgramEntry {
+        let input = format!(
+            r#"
+            [[programs]]
+            name = "firewall"
+            file_path = "/opt/bin/myapp/lib/myebpf.o"
+            {fields}
+            "#
+        );
+        let mut programs: StaticProgramManager =
+            toml::from_str(&input).expect("error parsing toml input");
+        programs.programs.remove(0)
+    }
+
+    #[test]
+    fn test_digest() {
+        let xdp = r#"program_type = "Xdp"
+            xdp_attach = { iface = "eth0", priority = 50 }"#;
+        let tc = r#"program_type = "Tc"
+            tc_attach = { iface = "eth0", priority = 50, direction = "ingress" }"#;
+
+        // Each field is left out of an entry which doesn't set it, but
+        // changes the digest once set.
+        for (key, unset, set) in [
+            (
+                "frags",
+                xdp,
+                r#"program_type = "Xdp"
+                xdp_attach = { iface = "eth0", priority = 50, frags = true }"#,
+            ),
+            (
+                "netns",
+                xdp,
+                r#"program_type = "Xdp"
+                xdp_attach = { iface = "eth0", priority = 50, netns = "/var/run/netns/ns1" }"#,
+            ),
+            (
+                "netns",
+                tc,
+                r#"program_type = "Tc"
+                tc_attach = { iface = "eth0", priority = 50, direction = "ingress", netns = "/var/run/netns/ns1" }"#,
+            ),
+            (
+                "cgroup_skb_attach",
+                xdp,
+                r#"program_type = "CgroupSkb"
+                cgroup_skb_attach = { cgroup_path = "/sys/fs/cgroup", direction = "egress" }"#,
+            ),
+            (
+                "cgroup_sock_addr_attach",
+                xdp,
+                r#"program_type = "CgroupSockAddr"
+                cgroup_sock_addr_attach = { cgroup_path = "/sys/fs/cgroup" }"#,
+            ),
+            (
+                "sockops_attach",
+                xdp,
+                r#"program_type = "SockOps"
+                sockops_attach = { cgroup_path = "/sys/fs/cgroup" }"#,
+            ),
+            (
+                "sk_msg_attach",
+                xdp,
+                r#"program_type = "SkMsg"
+                sk_msg_attach = { map_name = "sock_map" }"#,
+            ),
+        ] {
+            let unset = entry(unset);
+            let set = entry(set);
+            let key = format!(r#""{key}""#);
+            assert!(!serde_json::to_string(&unset).unwrap().contains(&key));
+            assert!(serde_json::to_string(&set).unwrap().contains(&key));
+            assert_ne!(unset.digest().unwrap(), set.digest().unwrap());
+        }
+
+        // Setting a field to its default keeps the digest.
+        assert_eq!(
+            entry(xdp).digest().unwrap(),
+            entry(
+                r#"program_type = "Xdp"
+                xdp_attach = { iface = "eth0", priority = 50, frags = false }"#
+            )
+            .digest()
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_xdp_frags() {
+        for frags in [false, true] {
+            let entry = entry(&format!(
+                r#"program_type = "Xdp"
+                xdp_attach = {{ iface = "eth0", priority = 50, frags = {frags} }}"#
+            ));
+            match entry.to_program(&entry.digest().unwrap()) {
+                Ok(Program::Xdp(p)) => assert_eq!(p.get_frags().unwrap(), frags),
+                _ => panic!("expected an xdp program"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_cgroup_programs() {
+        let input: &str = r#"
+        [[programs]]
+        name = "cgroup_skb_counter"
+        file_path = "/opt/bin/myapp/lib/myebpf.o"
+        program_type ="CgroupSkb"
+        cgroup_skb_attach = { cgroup_path = "/sys/fs/cgroup/system.slice", direction = "egress" }
+
+        [[programs]]
+        name = "connect4_redirect"
+        file_path = "/opt/bin/myapp/lib/myebpf.o"
+        program_type ="CgroupSockAddr"
+        cgroup_sock_addr_attach = { cgroup_path = "/sys/fs/cgroup/system.slice" }
+
+        [[programs]]
+        name = "sockops_counter"
+        file_path = "/opt/bin/myapp/lib/myebpf.o"
+        program_type ="SockOps"
+        sockops_attach = { cgroup_path = "/sys/fs/cgroup" }
+
+        [[programs]]
+        name = "sk_msg_redirect"
+        file_path = "/opt/bin/myapp/lib/myebpf.o"
+        program_type ="SkMsg"
+        sk_msg_attach = { map_name = "sock_map" }
+        "#;
+
+        let programs: StaticProgramManager =
+            toml::from_str(input).expect("error parsing toml input");
+        let programs: Vec<Program> = programs
+            .programs
+            .iter()
+            .map(|e| e.to_program(&e.digest().unwrap()).unwrap())
+            .collect();
+        match &programs[..] {
+            [Program::CgroupSkb(skb), Program::CgroupSockAddr(sock_addr), Program::SockOps(sockops), Program::SkMsg(sk_msg)] =>
+            {
+                assert_eq!(
+                    skb.get_cgroup_path().unwrap(),
+                    "/sys/fs/cgroup/system.slice"
+                );
+                assert_eq!(skb.get_direction().unwrap(), Direction::Egress);
+                assert_eq!(
+                    sock_addr.get_cgroup_path().unwrap(),
+                    "/sys/fs/cgroup/system.slice"
+                );
+                assert_eq!(sockops.get_cgroup_path().unwrap(), "/sys/fs/cgroup");
+                assert_eq!(sk_msg.get_map_name().unwrap(), "sock_map");
+            }
+            _ => panic!("unexpected programs {programs:?}"),
+        }
+    }
+
+    #[test]
+    fn test_invalid_attach_info() {
+        // The attach table has to match the program type.
+        for fields in [
+            r#"program_type = "Xdp"
+            tracepoint_attach = { tracepoint = "syscalls/sys_enter_openat" }"#,
+            r#"program_type = "SockOps"
+            cgroup_sock_addr_attach = { cgroup_path = "/sys/fs/cgroup" }"#,
+        ] {
+            let entry = entry(fields);
+            assert!(matches!(
+                entry.to_program(&entry.digest().unwrap()),
+                Err(BpfmanError::InvalidAttach(_))
+            ));
+        }
+    }
+}
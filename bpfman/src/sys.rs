@@ -0,0 +1,229 @@
+// SPDX-License-Identifier: Apache-2.0
+// Copyright Authors of bpfman
+
+//! Thin wrappers around the bpf() syscall for the commands aya doesn't
+//! expose in a form bpfman can use.
+
+use std::{
+    ffi::CString,
+    io, mem,
+    os::{
+        fd::{AsRawFd, BorrowedFd, FromRawFd, OwnedFd},
+        unix::ffi::OsStrExt,
+    },
+    path::Path,
+};
+
+use aya_obj::generated::{bpf_attach_type, bpf_attr, bpf_cmd, bpf_prog_info};
+use nix::libc::{syscall, SYS_bpf};
+
+// Attach types and flags for multi-program hooks, from the kernel's
+// include/uapi/linux/bpf.h.
+pub(crate) const BPF_TCX_INGRESS: u32 = 46;
+pub(crate) const BPF_TCX_EGRESS: u32 = 47;
+pub(crate) const BPF_NETKIT_PRIMARY: u32 = 54;
+pub(crate) const BPF_NETKIT_PEER: u32 = 55;
+pub(crate) const BPF_F_BEFORE: u32 = 1 << 3;
+pub(crate) const BPF_F_AFTER: u32 = 1 << 4;
+pub(crate) const BPF_F_LINK: u32 = 1 << 13;
+
+pub(crate) fn sys_bpf(cmd: bpf_cmd, attr: &mut bpf_attr) -> io::Result<i64> {
+    // SAFETY: attr is a bpf_attr which has been initialized for cmd, and any
+    // pointers it holds refer to buffers which outlive the syscall.
+    let ret = unsafe {
+        syscall(
+            SYS_bpf,
+            cmd as u32,
+            attr as *mut bpf_attr,
+            mem::size_of::<bpf_attr>(),
+        )
+    };
+    if ret < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret)
+    }
+}
+
+pub(crate) fn obj_get_info_by_fd<T>(fd: BorrowedFd<'_>, info: &mut T) -> io::Result<()> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    attr.info.bpf_fd = fd.as_raw_fd() as u32;
+    attr.info.info = info as *mut T as u64;
+    attr.info.info_len = mem::size_of::<T>() as u32;
+    sys_bpf(bpf_cmd::BPF_OBJ_GET_INFO_BY_FD, &mut attr).map(|_| ())
+}
+
+fn path_to_cstring(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Pins the bpf object referred to by fd at path.
+pub(crate) fn obj_pin(fd: BorrowedFd<'_>, path: &Path) -> io::Result<()> {
+    let path = path_to_cstring(path)?;
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    attr.__bindgen_anon_4.bpf_fd = fd.as_raw_fd() as u32;
+    attr.__bindgen_anon_4.pathname = path.as_ptr() as u64;
+    sys_bpf(bpf_cmd::BPF_OBJ_PIN, &mut attr).map(|_| ())
+}
+
+/// Opens the bpf object pinned at path.
+pub(crate) fn obj_get(path: &Path) -> io::Result<OwnedFd> {
+    let path = path_to_cstring(path)?;
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    attr.__bindgen_anon_4.pathname = path.as_ptr() as u64;
+    let fd = sys_bpf(bpf_cmd::BPF_OBJ_GET, &mut attr)?;
+    // SAFETY: BPF_OBJ_GET returns a new file descriptor.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as i32) })
+}
+
+/// Creates a bpf link attaching the program to the target, which is a cgroup
+/// for all of the attach types bpfman uses.
+pub(crate) fn link_create(
+    prog_fd: BorrowedFd<'_>,
+    target_fd: BorrowedFd<'_>,
+    attach_type: bpf_attach_type,
+) -> io::Result<OwnedFd> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    attr.link_create.__bindgen_anon_1.prog_fd = prog_fd.as_raw_fd() as u32;
+    attr.link_create.__bindgen_anon_2.target_fd = target_fd.as_raw_fd() as u32;
+    attr.link_create.attach_type = attach_type as u32;
+    let fd = sys_bpf(bpf_cmd::BPF_LINK_CREATE, &mut attr)?;
+    // SAFETY: BPF_LINK_CREATE returns a new file descriptor.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as i32) })
+}
+
+/// Creates a bpf link attaching the program to a multi-program hook on an
+/// interface, such as TCX or netkit. The attach type is a raw value since
+/// these attach types are newer than the bindings. With [`BPF_F_BEFORE`] set,
+/// the program is placed before the anchor, or at the front of the hook if
+/// there is no anchor, and [`BPF_F_AFTER`] places it after the anchor.
+pub(crate) fn mprog_link_create(
+    prog_fd: BorrowedFd<'_>,
+    if_index: u32,
+    attach_type: u32,
+    flags: u32,
+    anchor: Option<BorrowedFd<'_>>,
+) -> io::Result<OwnedFd> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    attr.link_create.__bindgen_anon_1.prog_fd = prog_fd.as_raw_fd() as u32;
+    attr.link_create.__bindgen_anon_2.target_ifindex = if_index;
+    attr.link_create.attach_type = attach_type;
+    attr.link_create.flags = flags;
+    if let Some(anchor) = anchor {
+        // relative_fd shares its offset with target_btf_id at the start of
+        // the per attach type union.
+        attr.link_create.__bindgen_anon_3.target_btf_id = anchor.as_raw_fd() as u32;
+    }
+    let fd = sys_bpf(bpf_cmd::BPF_LINK_CREATE, &mut attr)?;
+    // SAFETY: BPF_LINK_CREATE returns a new file descriptor.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as i32) })
+}
+
+/// Attaches the program to the target without a link. The attachment lives
+/// until it is detached or the target goes away.
+pub(crate) fn prog_attach(
+    prog_fd: BorrowedFd<'_>,
+    target_fd: BorrowedFd<'_>,
+    attach_type: bpf_attach_type,
+) -> io::Result<()> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    attr.__bindgen_anon_5.attach_bpf_fd = prog_fd.as_raw_fd() as u32;
+    attr.__bindgen_anon_5.target_fd = target_fd.as_raw_fd() as u32;
+    attr.__bindgen_anon_5.attach_type = attach_type as u32;
+    sys_bpf(bpf_cmd::BPF_PROG_ATTACH, &mut attr).map(|_| ())
+}
+
+/// Detaches a program attached with [`prog_attach`].
+pub(crate) fn prog_detach(
+    prog_fd: BorrowedFd<'_>,
+    target_fd: BorrowedFd<'_>,
+    attach_type: bpf_attach_type,
+) -> io::Result<()> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    attr.__bindgen_anon_5.attach_bpf_fd = prog_fd.as_raw_fd() as u32;
+    attr.__bindgen_anon_5.target_fd = target_fd.as_raw_fd() as u32;
+    attr.__bindgen_anon_5.attach_type = attach_type as u32;
+    sys_bpf(bpf_cmd::BPF_PROG_DETACH, &mut attr).map(|_| ())
+}
+
+/// Opens the program with the given id.
+pub(crate) fn prog_get_fd_by_id(id: u32) -> io::Result<OwnedFd> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    attr.__bindgen_anon_6.__bindgen_anon_1.prog_id = id;
+    let fd = sys_bpf(bpf_cmd::BPF_PROG_GET_FD_BY_ID, &mut attr)?;
+    // SAFETY: BPF_PROG_GET_FD_BY_ID returns a new file descriptor.
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as i32) })
+}
+
+/// Returns the ids of the programs attached to a multi-program hook on an
+/// interface, in the order they run.
+pub(crate) fn mprog_query(if_index: u32, attach_type: u32) -> io::Result<Vec<u32>> {
+    let mut ids = [0u32; 64];
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    // target_ifindex shares its offset with target_fd.
+    attr.query.target_fd = if_index;
+    attr.query.attach_type = attach_type;
+    attr.query.prog_ids = ids.as_mut_ptr() as u64;
+    attr.query.prog_cnt = ids.len() as u32;
+    sys_bpf(bpf_cmd::BPF_PROG_QUERY, &mut attr)?;
+    // SAFETY: the kernel sets prog_cnt to the number of ids it wrote.
+    let count = unsafe { attr.query.prog_cnt } as usize;
+    Ok(ids[..count.min(ids.len())].to_vec())
+}
+
+/// Attaches the program to the end of a multi-program hook on an interface
+/// without a link, so it stays attached after bpfman exits.
+pub(crate) fn mprog_attach(
+    prog_fd: BorrowedFd<'_>,
+    if_index: u32,
+    attach_type: u32,
+) -> io::Result<()> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    attr.__bindgen_anon_5.target_fd = if_index;
+    attr.__bindgen_anon_5.attach_bpf_fd = prog_fd.as_raw_fd() as u32;
+    attr.__bindgen_anon_5.attach_type = attach_type;
+    sys_bpf(bpf_cmd::BPF_PROG_ATTACH, &mut attr).map(|_| ())
+}
+
+/// Detaches a program attached to a multi-program hook on an interface
+/// without a link. Programs attached with a link can't be detached this way.
+pub(crate) fn mprog_detach(
+    prog_fd: BorrowedFd<'_>,
+    if_index: u32,
+    attach_type: u32,
+) -> io::Result<()> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    attr.__bindgen_anon_5.target_fd = if_index;
+    attr.__bindgen_anon_5.attach_bpf_fd = prog_fd.as_raw_fd() as u32;
+    attr.__bindgen_anon_5.attach_type = attach_type;
+    sys_bpf(bpf_cmd::BPF_PROG_DETACH, &mut attr).map(|_| ())
+}
+
+/// Returns the ids of the maps the program uses.
+pub(crate) fn prog_map_ids(prog_fd: BorrowedFd<'_>) -> io::Result<Vec<u32>> {
+    let mut info = unsafe { mem::zeroed::<bpf_prog_info>() };
+    obj_get_info_by_fd(prog_fd, &mut info)?;
+    let mut ids = vec![0u32; info.nr_map_ids as usize];
+    let mut info = unsafe { mem::zeroed::<bpf_prog_info>() };
+    info.nr_map_ids = ids.len() as u32;
+    info.map_ids = ids.as_mut_ptr() as u64;
+    obj_get_info_by_fd(prog_fd, &mut info)?;
+    ids.truncate(info.nr_map_ids as usize);
+    Ok(ids)
+}
+
+/// Looks up the value of the key in the map. The value must be the size of
+/// the map's values.
+pub(crate) fn map_lookup_elem(
+    map_fd: BorrowedFd<'_>,
+    key: &[u8],
+    value: &mut [u8],
+) -> io::Result<()> {
+    let mut attr = unsafe { mem::zeroed::<bpf_attr>() };
+    let u = unsafe { &mut attr.__bindgen_anon_2 };
+    u.map_fd = map_fd.as_raw_fd() as u32;
+    u.key = key.as_ptr() as u64;
+    u.__bindgen_anon_1.value = value.as_mut_ptr() as u64;
+    sys_bpf(bpf_cmd::BPF_MAP_LOOKUP_ELEM, &mut attr).map(|_| ())
+}
@@ -5,32 +5,38 @@
 use std::{
     collections::HashMap,
     fmt, fs,
+    os::fd::AsFd,
     path::{Path, PathBuf},
     time::SystemTime,
 };
 
-use aya::programs::ProgramInfo as AyaProgInfo;
+use aya::{programs::ProgramInfo as AyaProgInfo, VerifierLogLevel as AyaVerifierLogLevel};
+use aya_obj::generated::bpf_attach_type;
 use chrono::{prelude::DateTime, Local};
-use clap::ValueEnum;
-use log::{info, warn};
+use log::{debug, info, warn};
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use sled::Db;
 
+pub use crate::config::XdpMode;
 use crate::{
     directories::RTDIR_FS,
     errors::{BpfmanError, ParseError},
     multiprog::{DispatcherId, DispatcherInfo},
     oci_utils::image_manager::ImageManager,
+    sys,
     utils::{
         bytes_to_bool, bytes_to_i32, bytes_to_string, bytes_to_u32, bytes_to_u64, bytes_to_usize,
-        sled_get, sled_get_option, sled_insert,
+        get_netns_id, sled_get, sled_get_option, sled_insert, sled_remove,
     },
 };
 
 /// These constants define the key of SLED DB
 pub(crate) const PROGRAM_PREFIX: &str = "program_";
 pub(crate) const PROGRAM_PRE_LOAD_PREFIX: &str = "pre_load_program_";
+pub(crate) const PROGRAM_RESTORE_PREFIX: &str = "restore_prog_";
+pub(crate) const LINK_PREFIX: &str = "link_";
+pub(crate) const LINK_RESTORE_PREFIX: &str = "restore_link_";
 const KIND: &str = "kind";
 const NAME: &str = "name";
 const ID: &str = "id";
@@ -45,6 +51,8 @@ const PREFIX_GLOBAL_DATA: &str = "global_data_";
 const PREFIX_METADATA: &str = "metadata_";
 const PREFIX_MAPS_USED_BY: &str = "maps_used_by_";
 const PROGRAM_BYTES: &str = "program_bytes";
+const VERIFIER_LOG_LEVEL: &str = "verifier_log_level";
+const LINK_ID: &str = "link_id";
 
 const KERNEL_NAME: &str = "kernel_name";
 const KERNEL_PROGRAM_TYPE: &str = "kernel_program_type";
@@ -64,6 +72,16 @@ const XDP_IFACE: &str = "xdp_iface";
 const XDP_CURRENT_POSITION: &str = "xdp_current_position";
 const XDP_IF_INDEX: &str = "xdp_if_index";
 const XDP_ATTACHED: &str = "xdp_attached";
+const XDP_NETNS: &str = "xdp_netns";
+const XDP_NETNS_ID: &str = "xdp_netns_id";
+const XDP_DISABLED: &str = "xdp_disabled";
+const XDP_FRAGS: &str = "xdp_frags";
+const XDP_IFACE_DETACHED: &str = "xdp_iface_detached";
+const XDP_IFACE_PATTERN: &str = "xdp_iface_pattern";
+const XDP_MODE: &str = "xdp_mode";
+const XDP_MODE_FALLBACK: &str = "xdp_mode_fallback";
+const XDP_EFFECTIVE_MODE: &str = "xdp_effective_mode";
+const XDP_USE_RUN_CONFIG: &str = "xdp_use_run_config";
 const PREFIX_XDP_PROCEED_ON: &str = "xdp_proceed_on_";
 
 const TC_PRIORITY: &str = "tc_priority";
@@ -72,6 +90,11 @@ const TC_CURRENT_POSITION: &str = "tc_current_position";
 const TC_IF_INDEX: &str = "tc_if_index";
 const TC_ATTACHED: &str = "tc_attached";
 const TC_DIRECTION: &str = "tc_direction";
+const TC_NETNS: &str = "tc_netns";
+const TC_NETNS_ID: &str = "tc_netns_id";
+const TC_DISABLED: &str = "tc_disabled";
+const TC_IFACE_DETACHED: &str = "tc_iface_detached";
+const TC_IFACE_PATTERN: &str = "tc_iface_pattern";
 const PREFIX_TC_PROCEED_ON: &str = "tc_proceed_on_";
 
 const TRACEPOINT_NAME: &str = "tracepoint_name";
@@ -91,6 +114,16 @@ const UPROBE_TARGET: &str = "uprobe_target";
 const FENTRY_FN_NAME: &str = "fentry_fn_name";
 const FEXIT_FN_NAME: &str = "fexit_fn_name";
 
+const CGROUP_SKB_CGROUP_PATH: &str = "cgroup_skb_cgroup_path";
+const CGROUP_SKB_DIRECTION: &str = "cgroup_skb_direction";
+
+const CGROUP_SOCK_ADDR_CGROUP_PATH: &str = "cgroup_sock_addr_cgroup_path";
+const CGROUP_SOCK_ADDR_ATTACH_TYPE: &str = "cgroup_sock_addr_attach_type";
+
+const SOCKOPS_CGROUP_PATH: &str = "sockops_cgroup_path";
+
+const SK_MSG_MAP_NAME: &str = "sk_msg_map_name";
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct BytecodeImage {
     pub image_url: String,
@@ -199,6 +232,91 @@ impl ListFilter {
         }
         true
     }
+
+    /// Returns true if the event is for a program which matches the filter.
+    /// Events are only generated for programs managed by bpfman, so
+    /// `bpfman_programs_only` has no effect.
+    pub fn matches_event(&self, event: &ProgramEvent) -> bool {
+        if let Some(prog_type) = self.program_type {
+            if event.kind.map(Into::<u32>::into) != Some(prog_type) {
+                return false;
+            }
+        }
+
+        self.metadata_selector
+            .iter()
+            .all(|(key, value)| event.metadata.get(key) == Some(value))
+    }
+}
+
+/// ProgramEventType is the kind of change reported by a [`ProgramEvent`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ProgramEventType {
+    /// The program was loaded and attached.
+    Loaded,
+    /// The program was unloaded.
+    Unloaded,
+    /// The dispatcher the program is attached to was rebuilt.
+    DispatcherRebuilt,
+    /// The program failed to load or attach.
+    AttachFailed,
+    /// The program is in the bpfman database but is no longer loaded in the
+    /// kernel.
+    DetectedMissing,
+    /// The interface the program is attached to was removed. The program
+    /// stays loaded. Only the interfaces of bpfman-rpc's own network namespace
+    /// are watched.
+    Detached,
+    /// The program was attached again to an interface which reappeared.
+    Reattached,
+    /// The program was attached to a new interface matching the interface
+    /// pattern it was loaded with.
+    Attached,
+}
+
+impl std::fmt::Display for ProgramEventType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let v = match self {
+            ProgramEventType::Loaded => "loaded",
+            ProgramEventType::Unloaded => "unloaded",
+            ProgramEventType::DispatcherRebuilt => "dispatcher_rebuilt",
+            ProgramEventType::AttachFailed => "attach_failed",
+            ProgramEventType::DetectedMissing => "detected_missing",
+            ProgramEventType::Detached => "detached",
+            ProgramEventType::Reattached => "reattached",
+            ProgramEventType::Attached => "attached",
+        };
+        write!(f, "{v}")
+    }
+}
+
+/// ProgramEvent describes a change in the lifecycle of a program managed by
+/// bpfman. It holds a snapshot of the program's state when the event occurred
+/// since the program may no longer exist by the time the event is received.
+#[derive(Debug, Clone)]
+pub struct ProgramEvent {
+    pub event_type: ProgramEventType,
+    /// Kernel id of the program, if it was loaded.
+    pub id: Option<u32>,
+    pub name: String,
+    pub kind: Option<ProgramType>,
+    pub metadata: HashMap<String, String>,
+    /// Additional detail, such as the error for an attach failure.
+    pub message: String,
+}
+
+impl ProgramEvent {
+    pub(crate) fn new(event_type: ProgramEventType, program: &Program, message: String) -> Self {
+        let data = program.get_data();
+        Self {
+            event_type,
+            id: data.get_id().ok(),
+            name: data.get_name().unwrap_or_default(),
+            kind: data.get_kind().ok().flatten(),
+            metadata: data.get_metadata().unwrap_or_default(),
+            message,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -210,6 +328,10 @@ pub enum Program {
     Uprobe(UprobeProgram),
     Fentry(FentryProgram),
     Fexit(FexitProgram),
+    CgroupSkb(CgroupSkbProgram),
+    CgroupSockAddr(CgroupSockAddrProgram),
+    SockOps(SockOpsProgram),
+    SkMsg(SkMsgProgram),
     Unsupported(ProgramData),
 }
 
@@ -288,6 +410,16 @@ impl std::fmt::Display for Direction {
     }
 }
 
+/// The network namespace of an interface outside of bpfman's own network
+/// namespace.
+#[derive(Debug, Clone)]
+pub struct Netns {
+    /// The path used to enter the namespace, such as `/proc/<pid>/ns/net`.
+    pub path: String,
+    /// The inode number of the namespace, which identifies it while it exists.
+    pub id: u64,
+}
+
 /// ProgramData stores information about bpf programs that are loaded and managed
 /// by bpfman.
 #[derive(Debug, Clone)]
@@ -356,8 +488,17 @@ impl ProgramData {
     }
 
     pub(crate) fn swap_tree(&mut self, root_db: &Db, new_id: u32) -> Result<(), BpfmanError> {
+        self.move_tree(root_db, &(PROGRAM_PREFIX.to_string() + &new_id.to_string()))?;
+        self.set_id(new_id)?;
+
+        Ok(())
+    }
+
+    // Moves all of the program's data into the database tree with the provided
+    // name and drops the tree that was previously used.
+    pub(crate) fn move_tree(&mut self, root_db: &Db, name: &str) -> Result<(), BpfmanError> {
         let new_tree = root_db
-            .open_tree(PROGRAM_PREFIX.to_string() + &new_id.to_string())
+            .open_tree(name)
             .expect("Unable to open program database tree");
 
         // Copy over all key's and values to new tree
@@ -376,7 +517,6 @@ impl ProgramData {
             .expect("unable to delete temporary program tree");
 
         self.db_tree = new_tree;
-        self.set_id(new_id)?;
 
         Ok(())
     }
@@ -418,6 +558,12 @@ impl ProgramData {
         sled_get(&self.db_tree, ID).map(bytes_to_u32)
     }
 
+    /// Returns true if the program is no longer loaded in the kernel, such as
+    /// after a reboot, and is waiting to be restored by bpfman.
+    pub fn restore_pending(&self) -> bool {
+        bytes_to_string(&self.db_tree.name()).starts_with(PROGRAM_RESTORE_PREFIX)
+    }
+
     pub(crate) fn set_location(&mut self, loc: Location) -> Result<(), BpfmanError> {
         match loc {
             Location::File(l) => sled_insert(&self.db_tree, LOCATION_FILENAME, l.as_bytes()),
@@ -548,10 +694,35 @@ impl ProgramData {
         sled_insert(&self.db_tree, MAP_OWNER_ID, &id.to_ne_bytes())
     }
 
+    pub(crate) fn clear_map_owner_id(&mut self) -> Result<(), BpfmanError> {
+        sled_remove(&self.db_tree, MAP_OWNER_ID)
+    }
+
     pub fn get_map_owner_id(&self) -> Result<Option<u32>, BpfmanError> {
         sled_get_option(&self.db_tree, MAP_OWNER_ID).map(|v| v.map(bytes_to_u32))
     }
 
+    /// Sets the verifier log level used when the program is loaded, as the
+    /// flags passed to the kernel. See [`VerifierLogLevel`].
+    pub fn set_verifier_log_level(&mut self, level: u32) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, VERIFIER_LOG_LEVEL, &level.to_ne_bytes())
+    }
+
+    /// Returns the verifier log level, which defaults to logging the
+    /// instructions a program is rejected over and statistics.
+    pub fn get_verifier_log_level(&self) -> Result<u32, BpfmanError> {
+        sled_get_option(&self.db_tree, VERIFIER_LOG_LEVEL).map(|v| {
+            v.map(bytes_to_u32)
+                .unwrap_or(VerifierLogLevel::Debug as u32 | VerifierLogLevel::Stats as u32)
+        })
+    }
+
+    pub(crate) fn verifier_log_level(&self) -> Result<AyaVerifierLogLevel, BpfmanError> {
+        Ok(AyaVerifierLogLevel::from_bits_retain(
+            self.get_verifier_log_level()?,
+        ))
+    }
+
     pub(crate) fn set_map_pin_path(&mut self, path: &Path) -> Result<(), BpfmanError> {
         sled_insert(
             &self.db_tree,
@@ -560,6 +731,10 @@ impl ProgramData {
         )
     }
 
+    pub(crate) fn clear_map_pin_path(&mut self) -> Result<(), BpfmanError> {
+        sled_remove(&self.db_tree, MAP_PIN_PATH)
+    }
+
     pub fn get_map_pin_path(&self) -> Result<Option<PathBuf>, BpfmanError> {
         sled_get_option(&self.db_tree, MAP_PIN_PATH)
             .map(|v| v.map(|f| PathBuf::from(bytes_to_string(&f))))
@@ -605,6 +780,12 @@ impl ProgramData {
         sled_get(&self.db_tree, PROGRAM_BYTES)
     }
 
+    // Sets the program's bytecode without reading it from its location.
+    #[cfg(test)]
+    pub(crate) fn set_program_bytes_from(&mut self, bytes: &[u8]) -> Result<(), BpfmanError> {
+        sled_insert(&self.db_tree, PROGRAM_BYTES, bytes)
+    }
+
     pub(crate) async fn set_program_bytes(
         &mut self,
         root_db: &Db,
@@ -850,6 +1031,10 @@ impl ProgramData {
 #[derive(Debug, Clone)]
 pub struct XdpProgram {
     data: ProgramData,
+    // The programs attached to the hook of the program's dispatcher by
+    // something other than bpfman. They change behind bpfman's back, so
+    // they're only looked up when the program is fetched and never stored.
+    foreign_program_ids: Vec<u32>,
 }
 
 impl XdpProgram {
@@ -858,12 +1043,19 @@ impl XdpProgram {
         priority: i32,
         iface: String,
         proceed_on: XdpProceedOn,
+        netns: Option<String>,
     ) -> Result<Self, BpfmanError> {
-        let mut xdp_prog = Self { data };
+        let mut xdp_prog = Self {
+            data,
+            foreign_program_ids: vec![],
+        };
 
         xdp_prog.set_priority(priority)?;
         xdp_prog.set_iface(iface)?;
         xdp_prog.set_proceed_on(proceed_on)?;
+        if let Some(netns) = netns {
+            xdp_prog.set_netns(&netns)?;
+        }
         xdp_prog.get_data_mut().set_kind(ProgramType::Xdp)?;
 
         Ok(xdp_prog)
@@ -886,6 +1078,13 @@ impl XdpProgram {
     }
 
     pub(crate) fn set_proceed_on(&mut self, proceed_on: XdpProceedOn) -> Result<(), BpfmanError> {
+        // Entries beyond the new actions would otherwise be read back.
+        for entry in self.data.db_tree.scan_prefix(PREFIX_XDP_PROCEED_ON) {
+            let (key, _) = entry.map_err(|e| {
+                BpfmanError::DatabaseError("Failed to clear proceed on".to_string(), e.to_string())
+            })?;
+            sled_remove(&self.data.db_tree, &bytes_to_string(&key))?;
+        }
         proceed_on
             .as_action_vec()
             .iter()
@@ -948,6 +1147,149 @@ impl XdpProgram {
             .unwrap_or(false))
     }
 
+    pub(crate) fn set_disabled(&mut self, disabled: bool) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            XDP_DISABLED,
+            &(disabled as i8).to_ne_bytes(),
+        )
+    }
+
+    pub fn get_disabled(&self) -> Result<bool, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, XDP_DISABLED)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
+    pub(crate) fn set_iface_detached(&mut self, detached: bool) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            XDP_IFACE_DETACHED,
+            &(detached as i8).to_ne_bytes(),
+        )
+    }
+
+    pub fn get_iface_detached(&self) -> Result<bool, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, XDP_IFACE_DETACHED)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
+    pub(crate) fn set_iface_pattern(&mut self, pattern: &str) -> Result<(), BpfmanError> {
+        sled_insert(&self.data.db_tree, XDP_IFACE_PATTERN, pattern.as_bytes())
+    }
+
+    pub(crate) fn set_foreign_programs(&mut self, foreign: &[u32]) {
+        self.foreign_program_ids = foreign.to_vec();
+    }
+
+    /// Returns the ids of the programs attached to the hook of the program's
+    /// dispatcher by something other than bpfman, as of when the program was
+    /// fetched.
+    pub fn get_foreign_program_ids(&self) -> &[u32] {
+        &self.foreign_program_ids
+    }
+
+    /// Returns the glob pattern the program's interfaces were selected with,
+    /// if it was loaded with one.
+    pub fn get_iface_pattern(&self) -> Result<Option<String>, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, XDP_IFACE_PATTERN)?.map(|v| bytes_to_string(&v)))
+    }
+
+    /// Sets whether the program supports XDP multi-buffer packets, as if its
+    /// section were "xdp.frags". Programs in an "xdp.frags" section are
+    /// detected when they're loaded.
+    pub fn set_frags(&mut self, frags: bool) -> Result<(), BpfmanError> {
+        sled_insert(&self.data.db_tree, XDP_FRAGS, &(frags as i8).to_ne_bytes())
+    }
+
+    pub fn get_frags(&self) -> Result<bool, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, XDP_FRAGS)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
+    /// Sets whether the priority and proceed-on actions are taken from the
+    /// XDP_RUN_CONFIG libxdp reads from the program's BTF. They're read when
+    /// the program is loaded.
+    pub fn set_use_run_config(&mut self, use_run_config: bool) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            XDP_USE_RUN_CONFIG,
+            &(use_run_config as i8).to_ne_bytes(),
+        )
+    }
+
+    pub fn get_use_run_config(&self) -> Result<bool, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, XDP_USE_RUN_CONFIG)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
+    /// Sets the mode the XDP dispatcher should be attached in, overriding
+    /// the mode configured for the interface. With `fallback`, the
+    /// dispatcher is attached in skb mode if the driver doesn't support the
+    /// requested mode.
+    pub fn set_mode(&mut self, mode: XdpMode, fallback: bool) -> Result<(), BpfmanError> {
+        sled_insert(&self.data.db_tree, XDP_MODE, &(mode as u32).to_ne_bytes())?;
+        sled_insert(
+            &self.data.db_tree,
+            XDP_MODE_FALLBACK,
+            &(fallback as i8).to_ne_bytes(),
+        )
+    }
+
+    pub fn get_mode(&self) -> Result<Option<XdpMode>, BpfmanError> {
+        sled_get_option(&self.data.db_tree, XDP_MODE)?
+            .map(|v| {
+                XdpMode::try_from(bytes_to_u32(v)).map_err(|e| BpfmanError::Error(e.to_string()))
+            })
+            .transpose()
+    }
+
+    pub fn get_mode_fallback(&self) -> Result<bool, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, XDP_MODE_FALLBACK)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
+    pub(crate) fn set_effective_mode(&mut self, mode: XdpMode) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            XDP_EFFECTIVE_MODE,
+            &(mode as u32).to_ne_bytes(),
+        )
+    }
+
+    /// Returns the mode the program's dispatcher is attached in, once the
+    /// program has been attached.
+    pub fn get_effective_mode(&self) -> Result<Option<XdpMode>, BpfmanError> {
+        sled_get_option(&self.data.db_tree, XDP_EFFECTIVE_MODE)?
+            .map(|v| {
+                XdpMode::try_from(bytes_to_u32(v)).map_err(|e| BpfmanError::Error(e.to_string()))
+            })
+            .transpose()
+    }
+
+    // A namespace which turns out to be bpfman's own isn't recorded, so its
+    // interfaces share dispatchers with the ones given without a namespace.
+    pub(crate) fn set_netns(&mut self, netns: &str) -> Result<(), BpfmanError> {
+        if let Some(id) = get_netns_id(netns)? {
+            sled_insert(&self.data.db_tree, XDP_NETNS, netns.as_bytes())?;
+            sled_insert(&self.data.db_tree, XDP_NETNS_ID, &id.to_ne_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn get_netns(&self) -> Result<Option<Netns>, BpfmanError> {
+        let path = sled_get_option(&self.data.db_tree, XDP_NETNS)?;
+        let id = sled_get_option(&self.data.db_tree, XDP_NETNS_ID)?;
+        Ok(path.zip(id).map(|(path, id)| Netns {
+            path: bytes_to_string(&path),
+            id: bytes_to_u64(id),
+        }))
+    }
+
     pub(crate) fn get_data(&self) -> &ProgramData {
         &self.data
     }
@@ -960,6 +1302,10 @@ impl XdpProgram {
 #[derive(Debug, Clone)]
 pub struct TcProgram {
     pub(crate) data: ProgramData,
+    // Like for XDP, the programs on the hook of the dispatcher which weren't
+    // attached by bpfman are only looked up when the program is fetched.
+    foreign_program_ids: Vec<u32>,
+    adopted_program_ids: Vec<u32>,
 }
 
 impl TcProgram {
@@ -969,13 +1315,21 @@ impl TcProgram {
         iface: String,
         proceed_on: TcProceedOn,
         direction: Direction,
+        netns: Option<String>,
     ) -> Result<Self, BpfmanError> {
-        let mut tc_prog = Self { data };
+        let mut tc_prog = Self {
+            data,
+            foreign_program_ids: vec![],
+            adopted_program_ids: vec![],
+        };
 
         tc_prog.set_priority(priority)?;
         tc_prog.set_iface(iface)?;
         tc_prog.set_proceed_on(proceed_on)?;
         tc_prog.set_direction(direction)?;
+        if let Some(netns) = netns {
+            tc_prog.set_netns(&netns)?;
+        }
         tc_prog.get_data_mut().set_kind(ProgramType::Tc)?;
 
         Ok(tc_prog)
@@ -998,6 +1352,13 @@ impl TcProgram {
     }
 
     pub(crate) fn set_proceed_on(&mut self, proceed_on: TcProceedOn) -> Result<(), BpfmanError> {
+        // Entries beyond the new actions would otherwise be read back.
+        for entry in self.data.db_tree.scan_prefix(PREFIX_TC_PROCEED_ON) {
+            let (key, _) = entry.map_err(|e| {
+                BpfmanError::DatabaseError("Failed to clear proceed on".to_string(), e.to_string())
+            })?;
+            sled_remove(&self.data.db_tree, &bytes_to_string(&key))?;
+        }
         proceed_on
             .as_action_vec()
             .iter()
@@ -1057,6 +1418,81 @@ impl TcProgram {
             .unwrap_or(false))
     }
 
+    pub(crate) fn set_disabled(&mut self, disabled: bool) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            TC_DISABLED,
+            &(disabled as i8).to_ne_bytes(),
+        )
+    }
+
+    pub fn get_disabled(&self) -> Result<bool, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, TC_DISABLED)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
+    pub(crate) fn set_iface_detached(&mut self, detached: bool) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            TC_IFACE_DETACHED,
+            &(detached as i8).to_ne_bytes(),
+        )
+    }
+
+    pub fn get_iface_detached(&self) -> Result<bool, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, TC_IFACE_DETACHED)?
+            .map(bytes_to_bool)
+            .unwrap_or(false))
+    }
+
+    pub(crate) fn set_iface_pattern(&mut self, pattern: &str) -> Result<(), BpfmanError> {
+        sled_insert(&self.data.db_tree, TC_IFACE_PATTERN, pattern.as_bytes())
+    }
+
+    pub(crate) fn set_foreign_programs(&mut self, foreign: &[u32], adopted: &[u32]) {
+        self.foreign_program_ids = foreign.to_vec();
+        self.adopted_program_ids = adopted.to_vec();
+    }
+
+    /// Returns the ids of the programs attached to the hook of the program's
+    /// dispatcher by something other than bpfman, as of when the program was
+    /// fetched.
+    pub fn get_foreign_program_ids(&self) -> &[u32] {
+        &self.foreign_program_ids
+    }
+
+    /// Returns the ids of the programs the program's dispatcher took over
+    /// under the takeover policy, as of when the program was fetched.
+    pub fn get_adopted_program_ids(&self) -> &[u32] {
+        &self.adopted_program_ids
+    }
+
+    /// Returns the glob pattern the program's interfaces were selected with,
+    /// if it was loaded with one.
+    pub fn get_iface_pattern(&self) -> Result<Option<String>, BpfmanError> {
+        Ok(sled_get_option(&self.data.db_tree, TC_IFACE_PATTERN)?.map(|v| bytes_to_string(&v)))
+    }
+
+    // A namespace which turns out to be bpfman's own isn't recorded, so its
+    // interfaces share dispatchers with the ones given without a namespace.
+    pub(crate) fn set_netns(&mut self, netns: &str) -> Result<(), BpfmanError> {
+        if let Some(id) = get_netns_id(netns)? {
+            sled_insert(&self.data.db_tree, TC_NETNS, netns.as_bytes())?;
+            sled_insert(&self.data.db_tree, TC_NETNS_ID, &id.to_ne_bytes())?;
+        }
+        Ok(())
+    }
+
+    pub fn get_netns(&self) -> Result<Option<Netns>, BpfmanError> {
+        let path = sled_get_option(&self.data.db_tree, TC_NETNS)?;
+        let id = sled_get_option(&self.data.db_tree, TC_NETNS_ID)?;
+        Ok(path.zip(id).map(|(path, id)| Netns {
+            path: bytes_to_string(&path),
+            id: bytes_to_u64(id),
+        }))
+    }
+
     pub(crate) fn set_direction(&mut self, direction: Direction) -> Result<(), BpfmanError> {
         sled_insert(
             &self.data.db_tree,
@@ -1348,6 +1784,201 @@ impl FexitProgram {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct CgroupSkbProgram {
+    pub(crate) data: ProgramData,
+}
+
+impl CgroupSkbProgram {
+    pub fn new(
+        data: ProgramData,
+        cgroup_path: String,
+        direction: Direction,
+    ) -> Result<Self, BpfmanError> {
+        let mut cgroup_skb_prog = Self { data };
+        cgroup_skb_prog.set_cgroup_path(cgroup_path)?;
+        cgroup_skb_prog.set_direction(direction)?;
+        cgroup_skb_prog
+            .get_data_mut()
+            .set_kind(ProgramType::CgroupSkb)?;
+
+        Ok(cgroup_skb_prog)
+    }
+
+    pub(crate) fn set_cgroup_path(&mut self, cgroup_path: String) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            CGROUP_SKB_CGROUP_PATH,
+            cgroup_path.as_bytes(),
+        )
+    }
+
+    pub fn get_cgroup_path(&self) -> Result<String, BpfmanError> {
+        sled_get(&self.data.db_tree, CGROUP_SKB_CGROUP_PATH).map(|v| bytes_to_string(&v))
+    }
+
+    pub(crate) fn set_direction(&mut self, direction: Direction) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            CGROUP_SKB_DIRECTION,
+            direction.to_string().as_bytes(),
+        )
+    }
+
+    pub fn get_direction(&self) -> Result<Direction, BpfmanError> {
+        sled_get(&self.data.db_tree, CGROUP_SKB_DIRECTION)
+            .map(|v| bytes_to_string(&v).to_string().try_into().unwrap())
+    }
+
+    pub(crate) fn get_data(&self) -> &ProgramData {
+        &self.data
+    }
+
+    pub(crate) fn get_data_mut(&mut self) -> &mut ProgramData {
+        &mut self.data
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CgroupSockAddrProgram {
+    pub(crate) data: ProgramData,
+}
+
+impl CgroupSockAddrProgram {
+    pub fn new(data: ProgramData, cgroup_path: String) -> Result<Self, BpfmanError> {
+        let mut cgroup_sock_addr_prog = Self { data };
+        cgroup_sock_addr_prog.set_cgroup_path(cgroup_path)?;
+        cgroup_sock_addr_prog
+            .get_data_mut()
+            .set_kind(ProgramType::CgroupSockAddr)?;
+
+        Ok(cgroup_sock_addr_prog)
+    }
+
+    pub(crate) fn set_cgroup_path(&mut self, cgroup_path: String) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            CGROUP_SOCK_ADDR_CGROUP_PATH,
+            cgroup_path.as_bytes(),
+        )
+    }
+
+    pub fn get_cgroup_path(&self) -> Result<String, BpfmanError> {
+        sled_get(&self.data.db_tree, CGROUP_SOCK_ADDR_CGROUP_PATH).map(|v| bytes_to_string(&v))
+    }
+
+    // The attach type comes from the program's section name, e.g.
+    // "cgroup/connect4", and is only known once the program is loaded.
+    pub(crate) fn set_attach_type(&mut self, attach_type: String) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            CGROUP_SOCK_ADDR_ATTACH_TYPE,
+            attach_type.as_bytes(),
+        )
+    }
+
+    pub fn get_attach_type(&self) -> Result<Option<String>, BpfmanError> {
+        Ok(
+            sled_get_option(&self.data.db_tree, CGROUP_SOCK_ADDR_ATTACH_TYPE)?
+                .map(|v| bytes_to_string(&v)),
+        )
+    }
+
+    pub(crate) fn get_data(&self) -> &ProgramData {
+        &self.data
+    }
+
+    pub(crate) fn get_data_mut(&mut self) -> &mut ProgramData {
+        &mut self.data
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SockOpsProgram {
+    pub(crate) data: ProgramData,
+}
+
+impl SockOpsProgram {
+    pub fn new(data: ProgramData, cgroup_path: String) -> Result<Self, BpfmanError> {
+        let mut sockops_prog = Self { data };
+        sockops_prog.set_cgroup_path(cgroup_path)?;
+        sockops_prog.get_data_mut().set_kind(ProgramType::SockOps)?;
+
+        Ok(sockops_prog)
+    }
+
+    pub(crate) fn set_cgroup_path(&mut self, cgroup_path: String) -> Result<(), BpfmanError> {
+        sled_insert(
+            &self.data.db_tree,
+            SOCKOPS_CGROUP_PATH,
+            cgroup_path.as_bytes(),
+        )
+    }
+
+    pub fn get_cgroup_path(&self) -> Result<String, BpfmanError> {
+        sled_get(&self.data.db_tree, SOCKOPS_CGROUP_PATH).map(|v| bytes_to_string(&v))
+    }
+
+    pub(crate) fn get_data(&self) -> &ProgramData {
+        &self.data
+    }
+
+    pub(crate) fn get_data_mut(&mut self) -> &mut ProgramData {
+        &mut self.data
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SkMsgProgram {
+    pub(crate) data: ProgramData,
+}
+
+impl SkMsgProgram {
+    /// Creates an sk_msg program which is attached to the sockmap or sockhash
+    /// with the given name, from the maps the program uses.
+    pub fn new(data: ProgramData, map_name: String) -> Result<Self, BpfmanError> {
+        let mut sk_msg_prog = Self { data };
+        sk_msg_prog.set_map_name(map_name)?;
+        sk_msg_prog.get_data_mut().set_kind(ProgramType::SkMsg)?;
+
+        Ok(sk_msg_prog)
+    }
+
+    pub(crate) fn set_map_name(&mut self, map_name: String) -> Result<(), BpfmanError> {
+        sled_insert(&self.data.db_tree, SK_MSG_MAP_NAME, map_name.as_bytes())
+    }
+
+    pub fn get_map_name(&self) -> Result<String, BpfmanError> {
+        sled_get(&self.data.db_tree, SK_MSG_MAP_NAME).map(|v| bytes_to_string(&v))
+    }
+
+    // sk_msg programs are attached to their map rather than through a link,
+    // so unpinning them doesn't detach them.
+    pub(crate) fn detach(&self) -> Result<(), BpfmanError> {
+        let id = self.data.get_id()?;
+        let map_pin_path = self
+            .data
+            .get_map_pin_path()?
+            .ok_or_else(|| BpfmanError::Error(format!("program {id} has no map pin path")))?;
+        let map = sys::obj_get(&map_pin_path.join(self.get_map_name()?))?;
+        let prog = sys::obj_get(Path::new(&format!("{RTDIR_FS}/prog_{id}")))?;
+        sys::prog_detach(
+            prog.as_fd(),
+            map.as_fd(),
+            bpf_attach_type::BPF_SK_MSG_VERDICT,
+        )?;
+        Ok(())
+    }
+
+    pub(crate) fn get_data(&self) -> &ProgramData {
+        &self.data
+    }
+
+    pub(crate) fn get_data_mut(&mut self) -> &mut ProgramData {
+        &mut self.data
+    }
+}
+
 impl Program {
     pub fn kind(&self) -> ProgramType {
         match self {
@@ -1358,6 +1989,10 @@ impl Program {
             Program::Uprobe(_) => ProgramType::Probe,
             Program::Fentry(_) => ProgramType::Tracing,
             Program::Fexit(_) => ProgramType::Tracing,
+            Program::CgroupSkb(_) => ProgramType::CgroupSkb,
+            Program::CgroupSockAddr(_) => ProgramType::CgroupSockAddr,
+            Program::SockOps(_) => ProgramType::SockOps,
+            Program::SkMsg(_) => ProgramType::SkMsg,
             Program::Unsupported(i) => i.get_kernel_program_type().unwrap().try_into().unwrap(),
         }
     }
@@ -1368,11 +2003,13 @@ impl Program {
                 p.get_if_index()?
                     .expect("if_index should be known at this point"),
                 None,
+                p.get_netns()?.map(|n| n.id),
             ))),
             Program::Tc(p) => Some(DispatcherId::Tc(DispatcherInfo(
                 p.get_if_index()?
                     .expect("if_index should be known at this point"),
                 Some(p.get_direction()?),
+                p.get_netns()?.map(|n| n.id),
             ))),
             _ => None,
         })
@@ -1387,6 +2024,10 @@ impl Program {
             Program::Uprobe(p) => &mut p.data,
             Program::Fentry(p) => &mut p.data,
             Program::Fexit(p) => &mut p.data,
+            Program::CgroupSkb(p) => &mut p.data,
+            Program::CgroupSockAddr(p) => &mut p.data,
+            Program::SockOps(p) => &mut p.data,
+            Program::SkMsg(p) => &mut p.data,
             Program::Unsupported(p) => p,
         }
     }
@@ -1407,6 +2048,84 @@ impl Program {
         };
     }
 
+    pub(crate) fn set_detached(&mut self) {
+        match self {
+            Program::Xdp(p) => p.set_attached(false).unwrap(),
+            Program::Tc(p) => p.set_attached(false).unwrap(),
+            _ => (),
+        };
+    }
+
+    // A disabled program stays attached to its dispatcher, in a slot the
+    // dispatcher doesn't run.
+    pub(crate) fn disabled(&self) -> bool {
+        match self {
+            Program::Xdp(p) => p.get_disabled().unwrap(),
+            Program::Tc(p) => p.get_disabled().unwrap(),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn set_disabled(&mut self, disabled: bool) -> Result<(), BpfmanError> {
+        match self {
+            Program::Xdp(p) => p.set_disabled(disabled),
+            Program::Tc(p) => p.set_disabled(disabled),
+            _ => Err(BpfmanError::Error(
+                "cannot disable programs other than TC or XDP".to_string(),
+            )),
+        }
+    }
+
+    // A program whose interface was removed keeps its extension loaded and
+    // pinned, but has no dispatcher until an interface with the same name
+    // appears again.
+    pub(crate) fn iface_detached(&self) -> bool {
+        match self {
+            Program::Xdp(p) => p.get_iface_detached().unwrap(),
+            Program::Tc(p) => p.get_iface_detached().unwrap(),
+            _ => false,
+        }
+    }
+
+    pub(crate) fn set_iface_detached(&mut self, detached: bool) -> Result<(), BpfmanError> {
+        match self {
+            Program::Xdp(p) => p.set_iface_detached(detached),
+            Program::Tc(p) => p.set_iface_detached(detached),
+            _ => Err(BpfmanError::Error(
+                "cannot detach programs other than TC or XDP from an interface".to_string(),
+            )),
+        }
+    }
+
+    // Programs loaded with an interface pattern are attached to the first
+    // matching interface, and to the others with links which carry the
+    // pattern too.
+    pub(crate) fn iface_pattern(&self) -> Result<Option<String>, BpfmanError> {
+        match self {
+            Program::Xdp(p) => p.get_iface_pattern(),
+            Program::Tc(p) => p.get_iface_pattern(),
+            _ => Ok(None),
+        }
+    }
+
+    pub(crate) fn set_iface_pattern(&mut self, pattern: &str) -> Result<(), BpfmanError> {
+        match self {
+            Program::Xdp(p) => p.set_iface_pattern(pattern),
+            Program::Tc(p) => p.set_iface_pattern(pattern),
+            _ => Err(BpfmanError::Error(
+                "cannot set an interface pattern on programs other than TC or XDP".to_string(),
+            )),
+        }
+    }
+
+    pub(crate) fn set_foreign_programs(&mut self, foreign: &[u32], adopted: &[u32]) {
+        match self {
+            Program::Xdp(p) => p.set_foreign_programs(foreign),
+            Program::Tc(p) => p.set_foreign_programs(foreign, adopted),
+            _ => (),
+        }
+    }
+
     pub(crate) fn set_position(&mut self, pos: usize) -> Result<(), BpfmanError> {
         match self {
             Program::Xdp(p) => p.set_current_position(pos),
@@ -1417,8 +2136,23 @@ impl Program {
         }
     }
 
+    pub(crate) fn position(&self) -> Result<Option<usize>, BpfmanError> {
+        match self {
+            Program::Xdp(p) => p.get_current_position(),
+            Program::Tc(p) => p.get_current_position(),
+            _ => Err(BpfmanError::Error(
+                "cannot get position on programs other than TC or XDP".to_string(),
+            )),
+        }
+    }
+
     pub(crate) fn delete(&self, root_db: &Db) -> Result<(), anyhow::Error> {
         let id = self.get_data().get_id()?;
+        if let Program::SkMsg(p) = self {
+            if let Err(e) = p.detach() {
+                debug!("unable to detach sk_msg program {id}: {e}");
+            }
+        }
         root_db.drop_tree(self.get_data().db_tree.name())?;
 
         let path = format!("{RTDIR_FS}/prog_{id}");
@@ -1452,6 +2186,26 @@ impl Program {
         }
     }
 
+    pub(crate) fn set_if_name(&mut self, if_name: &str) -> Result<(), BpfmanError> {
+        match self {
+            Program::Xdp(p) => p.set_iface(if_name.to_string()),
+            Program::Tc(p) => p.set_iface(if_name.to_string()),
+            _ => Err(BpfmanError::Error(
+                "cannot set interface on programs other than TC or XDP".to_string(),
+            )),
+        }
+    }
+
+    pub(crate) fn netns(&self) -> Result<Option<Netns>, BpfmanError> {
+        match self {
+            Program::Xdp(p) => p.get_netns(),
+            Program::Tc(p) => p.get_netns(),
+            _ => Err(BpfmanError::Error(
+                "cannot get network namespace on programs other than TC or XDP".to_string(),
+            )),
+        }
+    }
+
     pub(crate) fn if_name(&self) -> Result<String, BpfmanError> {
         match self {
             Program::Xdp(p) => p.get_iface(),
@@ -1472,6 +2226,42 @@ impl Program {
         }
     }
 
+    pub(crate) fn set_priority(&mut self, priority: i32) -> Result<(), BpfmanError> {
+        match self {
+            Program::Xdp(p) => p.set_priority(priority),
+            Program::Tc(p) => p.set_priority(priority),
+            _ => Err(BpfmanError::Error(
+                "cannot set priority on programs other than TC or XDP".to_string(),
+            )),
+        }
+    }
+
+    // Returns the proceed-on actions of an XDP or TC program as action values.
+    pub(crate) fn proceed_on(&self) -> Result<Vec<i32>, BpfmanError> {
+        match self {
+            Program::Xdp(p) => Ok(p.get_proceed_on()?.as_action_vec()),
+            Program::Tc(p) => Ok(p.get_proceed_on()?.as_action_vec()),
+            _ => Err(BpfmanError::Error(
+                "cannot get proceed on on programs other than TC or XDP".to_string(),
+            )),
+        }
+    }
+
+    pub(crate) fn set_proceed_on(&mut self, proceed_on: &[i32]) -> Result<(), BpfmanError> {
+        let invalid = |e: ParseError| BpfmanError::Error(e.to_string());
+        match self {
+            Program::Xdp(p) => {
+                p.set_proceed_on(XdpProceedOn::from_int32s(proceed_on).map_err(invalid)?)
+            }
+            Program::Tc(p) => {
+                p.set_proceed_on(TcProceedOn::from_int32s(proceed_on).map_err(invalid)?)
+            }
+            _ => Err(BpfmanError::Error(
+                "cannot set proceed on on programs other than TC or XDP".to_string(),
+            )),
+        }
+    }
+
     pub(crate) fn direction(&self) -> Result<Option<Direction>, BpfmanError> {
         match self {
             Program::Tc(p) => Ok(Some(p.get_direction()?)),
@@ -1488,6 +2278,10 @@ impl Program {
             Program::Uprobe(p) => p.get_data(),
             Program::Fentry(p) => p.get_data(),
             Program::Fexit(p) => p.get_data(),
+            Program::CgroupSkb(p) => p.get_data(),
+            Program::CgroupSockAddr(p) => p.get_data(),
+            Program::SockOps(p) => p.get_data(),
+            Program::SkMsg(p) => p.get_data(),
             Program::Unsupported(p) => p,
         }
     }
@@ -1502,8 +2296,15 @@ impl Program {
         }
         match data.get_kind()? {
             Some(p) => match p {
-                ProgramType::Xdp => Ok(Program::Xdp(XdpProgram { data })),
-                ProgramType::Tc => Ok(Program::Tc(TcProgram { data })),
+                ProgramType::Xdp => Ok(Program::Xdp(XdpProgram {
+                    data,
+                    foreign_program_ids: vec![],
+                })),
+                ProgramType::Tc => Ok(Program::Tc(TcProgram {
+                    data,
+                    foreign_program_ids: vec![],
+                    adopted_program_ids: vec![],
+                })),
                 ProgramType::Tracepoint => Ok(Program::Tracepoint(TracepointProgram { data })),
                 // kernel does not distinguish between kprobe and uprobe program types
                 ProgramType::Probe => {
@@ -1521,6 +2322,12 @@ impl Program {
                         Ok(Program::Fexit(FexitProgram { data }))
                     }
                 }
+                ProgramType::CgroupSkb => Ok(Program::CgroupSkb(CgroupSkbProgram { data })),
+                ProgramType::CgroupSockAddr => {
+                    Ok(Program::CgroupSockAddr(CgroupSockAddrProgram { data }))
+                }
+                ProgramType::SockOps => Ok(Program::SockOps(SockOpsProgram { data })),
+                ProgramType::SkMsg => Ok(Program::SkMsg(SkMsgProgram { data })),
                 _ => Err(BpfmanError::Error("Unsupported program type".to_string())),
             },
             None => Err(BpfmanError::Error("Unsupported program type".to_string())),
@@ -1528,6 +2335,331 @@ impl Program {
     }
 }
 
+/// AttachPoint is where a program loaded by bpfman is attached to by
+/// [`crate::attach_program`], in addition to the attach point it was loaded
+/// with. Whether a probe is a return probe, the container a kprobe filters on,
+/// and the direction or attach type of a cgroup program, are properties of the
+/// loaded program, so they're taken from it.
+#[derive(Debug, Clone)]
+pub enum AttachPoint {
+    Xdp {
+        iface: String,
+        priority: i32,
+        proceed_on: XdpProceedOn,
+        netns: Option<String>,
+    },
+    Tc {
+        iface: String,
+        priority: i32,
+        direction: Direction,
+        proceed_on: TcProceedOn,
+        netns: Option<String>,
+    },
+    Tracepoint {
+        tracepoint: String,
+    },
+    Kprobe {
+        fn_name: String,
+        offset: u64,
+    },
+    Uprobe {
+        fn_name: Option<String>,
+        offset: u64,
+        target: String,
+        pid: Option<i32>,
+        container_pid: Option<i32>,
+    },
+    CgroupSkb {
+        cgroup_path: String,
+    },
+    CgroupSockAddr {
+        cgroup_path: String,
+    },
+    SockOps {
+        cgroup_path: String,
+    },
+}
+
+impl std::fmt::Display for AttachPoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttachPoint::Xdp { iface, .. } | AttachPoint::Tc { iface, .. } => write!(f, "{iface}"),
+            AttachPoint::Tracepoint { tracepoint } => write!(f, "{tracepoint}"),
+            AttachPoint::Kprobe { fn_name, .. } => write!(f, "{fn_name}"),
+            AttachPoint::Uprobe {
+                fn_name, target, ..
+            } => match fn_name {
+                Some(fn_name) => write!(f, "{target}:{fn_name}"),
+                None => write!(f, "{target}"),
+            },
+            AttachPoint::CgroupSkb { cgroup_path }
+            | AttachPoint::CgroupSockAddr { cgroup_path }
+            | AttachPoint::SockOps { cgroup_path } => write!(f, "{cgroup_path}"),
+        }
+    }
+}
+
+/// Link is an attachment of a program loaded by bpfman which was made with
+/// [`crate::attach_program`].
+#[derive(Debug, Clone)]
+pub struct Link {
+    // The link's attach point is kept in a program of the same kind as the
+    // program it attaches, backed by the link's own database tree. The id of
+    // that program is the id of the attached program, so that links to XDP and
+    // TC programs take part in dispatcher rebuilds like the programs do.
+    program: Program,
+}
+
+impl Link {
+    pub(crate) fn new(
+        root_db: &Db,
+        program: &Program,
+        attach: AttachPoint,
+    ) -> Result<Self, BpfmanError> {
+        let mut rng = rand::thread_rng();
+        let (id, tree) = loop {
+            let id = rng.gen::<u32>();
+            let name = format!("{LINK_PREFIX}{id}");
+            if !root_db.tree_names().contains(&name.as_bytes().into()) {
+                break (
+                    id,
+                    root_db.open_tree(name).expect("unable to open link tree"),
+                );
+            }
+        };
+        sled_insert(&tree, LINK_ID, &id.to_ne_bytes())?;
+
+        let mut data = ProgramData::new_empty(tree);
+        data.set_id(program.get_data().get_id()?)?;
+        data.set_name(&program.get_data().get_name()?)?;
+
+        let link_program = match (program, attach) {
+            (
+                Program::Xdp(p),
+                AttachPoint::Xdp {
+                    iface,
+                    priority,
+                    proceed_on,
+                    netns,
+                },
+            ) => XdpProgram::new(data, priority, iface, proceed_on, netns).and_then(|mut x| {
+                x.set_frags(p.get_frags()?)?;
+                if let Some(mode) = p.get_mode()? {
+                    x.set_mode(mode, p.get_mode_fallback()?)?;
+                }
+                Ok(Program::Xdp(x))
+            }),
+            (
+                Program::Tc(_),
+                AttachPoint::Tc {
+                    iface,
+                    priority,
+                    direction,
+                    proceed_on,
+                    netns,
+                },
+            ) => {
+                TcProgram::new(data, priority, iface, proceed_on, direction, netns).map(Program::Tc)
+            }
+            (Program::Tracepoint(_), AttachPoint::Tracepoint { tracepoint }) => {
+                TracepointProgram::new(data, tracepoint).map(Program::Tracepoint)
+            }
+            (Program::Kprobe(p), AttachPoint::Kprobe { fn_name, offset }) => {
+                KprobeProgram::new(data, fn_name, offset, p.get_retprobe()?, None)
+                    .map(Program::Kprobe)
+            }
+            (
+                Program::Uprobe(p),
+                AttachPoint::Uprobe {
+                    fn_name,
+                    offset,
+                    target,
+                    pid,
+                    container_pid,
+                },
+            ) => UprobeProgram::new(
+                data,
+                fn_name,
+                offset,
+                target,
+                p.get_retprobe()?,
+                pid,
+                container_pid,
+            )
+            .map(Program::Uprobe),
+            (Program::CgroupSkb(p), AttachPoint::CgroupSkb { cgroup_path }) => {
+                CgroupSkbProgram::new(data, cgroup_path, p.get_direction()?).map(Program::CgroupSkb)
+            }
+            (Program::CgroupSockAddr(p), AttachPoint::CgroupSockAddr { cgroup_path }) => {
+                CgroupSockAddrProgram::new(data, cgroup_path).and_then(|mut l| {
+                    if let Some(attach_type) = p.get_attach_type()? {
+                        l.set_attach_type(attach_type)?;
+                    }
+                    Ok(Program::CgroupSockAddr(l))
+                })
+            }
+            (Program::SockOps(_), AttachPoint::SockOps { cgroup_path }) => {
+                SockOpsProgram::new(data, cgroup_path).map(Program::SockOps)
+            }
+            (_, attach) => Err(BpfmanError::InvalidAttach(attach.to_string())),
+        };
+
+        match link_program {
+            Ok(program) => Ok(Self { program }),
+            Err(e) => {
+                root_db
+                    .drop_tree(format!("{LINK_PREFIX}{id}"))
+                    .expect("unable to drop link tree");
+                Err(e)
+            }
+        }
+    }
+
+    pub(crate) fn new_from_db(tree: sled::Tree) -> Result<Self, BpfmanError> {
+        let program_id = ProgramData::new_empty(tree.clone()).get_id()?;
+        Ok(Self {
+            program: Program::new_from_db(program_id, tree)?,
+        })
+    }
+
+    /// The id bpfman gave the link.
+    pub fn id(&self) -> Result<u32, BpfmanError> {
+        sled_get(&self.program.get_data().db_tree, LINK_ID).map(bytes_to_u32)
+    }
+
+    /// The kernel id of the attached program.
+    pub fn program_id(&self) -> Result<u32, BpfmanError> {
+        self.program.get_data().get_id()
+    }
+
+    /// The attach point of the link, as a program of the same kind as the
+    /// attached program.
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub(crate) fn program_mut(&mut self) -> &mut Program {
+        &mut self.program
+    }
+
+    // The path the link is pinned at. Links to XDP and TC programs are pinned
+    // by the dispatcher instead.
+    pub(crate) fn pin_path(&self) -> Result<PathBuf, BpfmanError> {
+        Ok(PathBuf::from(format!(
+            "{RTDIR_FS}/prog_{}_link_{}",
+            self.program_id()?,
+            self.id()?
+        )))
+    }
+
+    pub(crate) fn set_program_id(&mut self, id: u32) -> Result<(), BpfmanError> {
+        self.program.get_data_mut().set_id(id)
+    }
+
+    pub(crate) fn move_tree(&mut self, root_db: &Db, name: &str) -> Result<(), BpfmanError> {
+        self.program.get_data_mut().move_tree(root_db, name)
+    }
+
+    pub(crate) fn delete(&self, root_db: &Db) -> Result<(), anyhow::Error> {
+        let path = self.pin_path()?;
+        root_db.drop_tree(self.program.get_data().db_tree.name())?;
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// DispatcherState describes the dispatcher bpfman uses to run several XDP
+/// programs on an interface, or several TC programs in one direction, as
+/// returned by [`crate::list_dispatchers`].
+#[derive(Debug, Clone)]
+pub struct DispatcherState {
+    pub program_type: ProgramType,
+    pub if_name: String,
+    pub if_index: u32,
+    pub direction: Option<Direction>,
+    pub netns: Option<String>,
+    pub revision: u32,
+    pub mode: String,
+    /// The TC priority of the dispatcher. Not set for XDP.
+    pub priority: Option<u16>,
+    /// The dispatchers on the hook, in the order they run. XDP hooks have one.
+    pub stages: Vec<DispatcherStage>,
+    /// The programs on the hook which weren't attached by bpfman.
+    pub foreign: Vec<ForeignProgram>,
+}
+
+/// DispatcherStage is one of the dispatchers chained on a hook.
+#[derive(Debug, Clone)]
+pub struct DispatcherStage {
+    /// The filter handle of a TC dispatcher attached through a qdisc.
+    pub handle: Option<u32>,
+    pub num_progs_enabled: u32,
+    /// The proceed-on mask of each slot.
+    pub chain_call_actions: Vec<u32>,
+    pub slots: Vec<DispatcherSlot>,
+}
+
+/// ForeignProgram is a program which something other than bpfman attached
+/// to the hook of a dispatcher.
+#[derive(Debug, Clone)]
+pub struct ForeignProgram {
+    pub program_id: u32,
+    pub name: String,
+    /// Set if the dispatcher took the program off the hook under the
+    /// takeover policy. It's attached again when the dispatcher is removed.
+    pub adopted: bool,
+}
+
+/// DispatcherSlot is a program run by a dispatcher.
+#[derive(Debug, Clone)]
+pub struct DispatcherSlot {
+    /// The slot of the program in its dispatcher.
+    pub slot: u32,
+    pub program_id: u32,
+    pub name: String,
+    pub priority: i32,
+    /// Set if the program was added to the dispatcher with
+    /// [`crate::attach_program`].
+    pub link_id: Option<u32>,
+    /// Set if the program was disabled with [`crate::disable_program`]. The
+    /// dispatcher doesn't run it.
+    pub disabled: bool,
+    /// Set if the program was attached to the interface by libxdp rather
+    /// than loaded by bpfman.
+    pub libxdp: bool,
+}
+
+/// ProgramMap describes a map used by a program loaded by bpfman.
+#[derive(Debug, Clone)]
+pub struct ProgramMap {
+    pub name: String,
+    pub map_id: u32,
+    pub map_type: u32,
+    pub key_size: u32,
+    pub value_size: u32,
+    pub max_entries: u32,
+    pub map_flags: u32,
+    pub pin_path: String,
+    /// The name of the key's type, if the map has BTF for it.
+    pub key_type: Option<String>,
+    /// The name of the value's type, if the map has BTF for it.
+    pub value_type: Option<String>,
+}
+
+/// MapEntry is a single entry in a map. The key and value are formatted as
+/// JSON when the map has BTF for them. The value of an entry in a per-CPU map
+/// holds the value for each CPU, one after another, and is formatted as a JSON
+/// array.
+#[derive(Debug, Clone)]
+pub struct MapEntry {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+    pub formatted_key: Option<String>,
+    pub formatted_value: Option<String>,
+}
+
 /// MapType must match the the bpf_map_type enum defined in the linux kernel.
 /// <https://elixir.bootlin.com/linux/v6.9.5/source/include/uapi/linux/bpf.h#L964>
 #[derive(Debug)]
@@ -1654,9 +2786,25 @@ impl std::fmt::Display for MapType {
     }
 }
 
+/// What the verifier logs while checking a program. Levels are combined by
+/// or-ing their values, which are the flags the kernel takes as the
+/// `log_level` of `BPF_PROG_LOAD`.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum VerifierLogLevel {
+    /// Log nothing.
+    Disable = 0,
+    /// Log the instructions a program is rejected over.
+    Debug = 1,
+    /// Log every instruction the verifier checks.
+    Verbose = 3,
+    /// Log statistics, such as the number of instructions processed and the
+    /// stack depth.
+    Stats = 4,
+}
+
 /// ProgramType must match the the bpf_prog_type enum defined in the linux kernel.
 /// <https://elixir.bootlin.com/linux/latest/source/include/uapi/linux/bpf.h#L1024>
-#[derive(ValueEnum, Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
 pub enum ProgramType {
     Unspec,
     SocketFilter,
@@ -2325,3 +3473,226 @@ impl std::fmt::Display for Location {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_list_filter_matches_event() {
+        let data = ProgramData::new(
+            Location::File("/tmp/prog.o".to_string()),
+            "prog".to_string(),
+            HashMap::from([
+                ("owner".to_string(), "acme".to_string()),
+                ("app".to_string(), "firewall".to_string()),
+            ]),
+            HashMap::new(),
+            None,
+        )
+        .unwrap();
+        let program = Program::Tracepoint(
+            TracepointProgram::new(data, "syscalls/sys_enter_openat".to_string()).unwrap(),
+        );
+        let event = ProgramEvent::new(ProgramEventType::Loaded, &program, String::new());
+        assert_eq!(event.kind, Some(ProgramType::Tracepoint));
+
+        let selector = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+        let tracepoint = Some(ProgramType::Tracepoint.into());
+        let xdp = Some(ProgramType::Xdp.into());
+
+        assert!(ListFilter::default().matches_event(&event));
+        assert!(ListFilter::new(tracepoint, HashMap::new(), true).matches_event(&event));
+        assert!(!ListFilter::new(xdp, HashMap::new(), false).matches_event(&event));
+        assert!(ListFilter::new(None, selector(&[("owner", "acme")]), false).matches_event(&event));
+        assert!(ListFilter::new(
+            tracepoint,
+            selector(&[("owner", "acme"), ("app", "firewall")]),
+            false
+        )
+        .matches_event(&event));
+        assert!(
+            !ListFilter::new(None, selector(&[("owner", "acme"), ("app", "lb")]), false)
+                .matches_event(&event)
+        );
+        assert!(!ListFilter::new(None, selector(&[("team", "acme")]), false).matches_event(&event));
+
+        // Events for programs whose type isn't known only match filters
+        // without a program type.
+        let unknown = ProgramEvent {
+            kind: None,
+            ..event.clone()
+        };
+        assert!(
+            ListFilter::new(None, selector(&[("owner", "acme")]), false).matches_event(&unknown)
+        );
+        assert!(!ListFilter::new(tracepoint, HashMap::new(), false).matches_event(&unknown));
+    }
+
+    #[test]
+    fn test_netns() {
+        let data = || {
+            ProgramData::new(
+                Location::File("/tmp/prog.o".to_string()),
+                "prog".to_string(),
+                HashMap::new(),
+                HashMap::new(),
+                None,
+            )
+            .unwrap()
+        };
+
+        let xdp = XdpProgram::new(
+            data(),
+            50,
+            "eth0".to_string(),
+            XdpProceedOn::default(),
+            Some("/proc/self/ns/net".to_string()),
+        )
+        .unwrap();
+        assert!(xdp.get_netns().unwrap().is_none());
+
+        let tc = TcProgram::new(
+            data(),
+            50,
+            "eth0".to_string(),
+            TcProceedOn::default(),
+            Direction::Ingress,
+            None,
+        )
+        .unwrap();
+        assert!(Program::Tc(tc).netns().unwrap().is_none());
+
+        assert!(matches!(
+            XdpProgram::new(
+                data(),
+                50,
+                "eth0".to_string(),
+                XdpProceedOn::default(),
+                Some("/no/such/netns".to_string()),
+            ),
+            Err(BpfmanError::InvalidNetns(_))
+        ));
+        assert!(Program::Tracepoint(
+            TracepointProgram::new(data(), "syscalls/sys_enter_openat".to_string()).unwrap()
+        )
+        .netns()
+        .is_err());
+    }
+
+    #[test]
+    fn test_kprobe_container_pid() {
+        let data = || {
+            ProgramData::new(
+                Location::File("/tmp/prog.o".to_string()),
+                "prog".to_string(),
+                HashMap::new(),
+                HashMap::new(),
+                None,
+            )
+            .unwrap()
+        };
+
+        let kprobe =
+            KprobeProgram::new(data(), "do_sys_open".to_string(), 0, false, Some(1234)).unwrap();
+        assert_eq!(kprobe.get_container_pid().unwrap(), Some(1234));
+        let kprobe = KprobeProgram::new(data(), "do_sys_open".to_string(), 0, false, None).unwrap();
+        assert_eq!(kprobe.get_container_pid().unwrap(), None);
+    }
+
+    #[test]
+    fn test_link() {
+        let root_db = sled::Config::default().temporary(true).open().unwrap();
+        let data = |name: &str| {
+            ProgramData::new(
+                Location::File("/tmp/prog.o".to_string()),
+                name.to_string(),
+                HashMap::new(),
+                HashMap::new(),
+                None,
+            )
+            .unwrap()
+        };
+
+        let tracepoint = Program::Tracepoint(
+            TracepointProgram::new(data("tp"), "syscalls/sys_enter_openat".to_string()).unwrap(),
+        );
+        let link = Link::new(
+            &root_db,
+            &tracepoint,
+            AttachPoint::Tracepoint {
+                tracepoint: "syscalls/sys_enter_close".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(
+            link.program_id().unwrap(),
+            tracepoint.get_data().get_id().unwrap()
+        );
+        match link.program() {
+            Program::Tracepoint(p) => {
+                assert_eq!(p.get_tracepoint().unwrap(), "syscalls/sys_enter_close");
+                assert_eq!(p.get_data().get_name().unwrap(), "tp");
+            }
+            _ => panic!("expected a tracepoint link"),
+        }
+
+        let tree = root_db
+            .open_tree(format!("{LINK_PREFIX}{}", link.id().unwrap()))
+            .unwrap();
+        let saved = Link::new_from_db(tree).unwrap();
+        assert_eq!(saved.id().unwrap(), link.id().unwrap());
+        assert_eq!(saved.program_id().unwrap(), link.program_id().unwrap());
+
+        // Links to XDP programs keep the properties of the loaded program.
+        let mut xdp = XdpProgram::new(
+            data("xdp"),
+            50,
+            "eth0".to_string(),
+            XdpProceedOn::default(),
+            None,
+        )
+        .unwrap();
+        xdp.set_frags(true).unwrap();
+        let link = Link::new(
+            &root_db,
+            &Program::Xdp(xdp),
+            AttachPoint::Xdp {
+                iface: "eth1".to_string(),
+                priority: 20,
+                proceed_on: XdpProceedOn::default(),
+                netns: None,
+            },
+        )
+        .unwrap();
+        match link.program() {
+            Program::Xdp(p) => {
+                assert_eq!(p.get_iface().unwrap(), "eth1");
+                assert_eq!(p.get_priority().unwrap(), 20);
+                assert!(p.get_frags().unwrap());
+            }
+            _ => panic!("expected an xdp link"),
+        }
+
+        // A program can't be attached to an attach point of another kind, and
+        // nothing is left behind.
+        let links = root_db.tree_names().len();
+        assert!(matches!(
+            Link::new(
+                &root_db,
+                &tracepoint,
+                AttachPoint::Kprobe {
+                    fn_name: "do_sys_open".to_string(),
+                    offset: 0,
+                },
+            ),
+            Err(BpfmanError::InvalidAttach(_))
+        ));
+        assert_eq!(root_db.tree_names().len(), links);
+    }
+}
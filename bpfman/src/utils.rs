@@ -2,9 +2,9 @@
 // Copyright Authors of bpfman
 
 use std::{
-    fs::{create_dir_all, set_permissions, File, OpenOptions},
+    fs::{create_dir_all, metadata, set_permissions, File, OpenOptions},
     io::{BufRead, BufReader, Read},
-    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt},
     path::Path,
 };
 
@@ -13,12 +13,13 @@ use log::{debug, info, warn};
 use nix::{
     libc::RLIM_INFINITY,
     mount::{mount, MsFlags},
-    net::if_::if_nametoindex,
+    net::if_::{if_nameindex, if_nametoindex},
+    sched::{setns, CloneFlags},
     sys::resource::{setrlimit, Resource},
 };
 use sled::Tree;
 
-use crate::{config::Config, directories::*, errors::BpfmanError};
+use crate::{config::Config, directories::*, errors::BpfmanError, types::Netns};
 
 // The bpfman socket should always allow the same users and members of the same group
 // to Read/Write to it.
@@ -50,6 +51,142 @@ pub(crate) fn get_ifindex(iface: &str) -> Result<u32, BpfmanError> {
     }
 }
 
+/// Returns true if an interface name is a glob pattern, such as "ens*", which
+/// selects every interface matching it.
+pub(crate) fn is_iface_pattern(iface: &str) -> bool {
+    iface.contains(['*', '?', '['])
+}
+
+/// Returns true if the name of an interface matches a glob pattern.
+pub(crate) fn iface_matches(pattern: &str, iface: &str) -> Result<bool, BpfmanError> {
+    glob::Pattern::new(pattern)
+        .map(|p| p.matches(iface))
+        .map_err(|_| BpfmanError::InvalidInterfacePattern(pattern.to_string()))
+}
+
+/// Returns the names of the interfaces matching a glob pattern, sorted by name.
+pub(crate) fn get_matching_ifaces(pattern: &str) -> Result<Vec<String>, BpfmanError> {
+    let mut ifaces = vec![];
+    for iface in if_nameindex()
+        .map_err(|e| BpfmanError::Error(format!("unable to list interfaces: {e}")))?
+        .iter()
+    {
+        let name = iface.name().to_string_lossy().to_string();
+        if iface_matches(pattern, &name)? {
+            ifaces.push(name);
+        }
+    }
+    ifaces.sort();
+    Ok(ifaces)
+}
+
+// Returns the /proc directory of the process with the given host PID, which is
+// under /host/proc when bpfman runs in a container with the host's /proc
+// mounted there.
+fn proc_dir(pid: i32) -> Option<String> {
+    [format!("/proc/{pid}"), format!("/host/proc/{pid}")]
+        .into_iter()
+        .find(|p| Path::new(p).exists())
+}
+
+/// Returns the path of the network namespace given either directly, or by the
+/// host PID of a process in it. The process is looked up in /host/proc when
+/// bpfman runs in a container with the host's /proc mounted there.
+pub fn netns_path(netns: Option<String>, container_pid: Option<i32>) -> Option<String> {
+    netns.or_else(|| {
+        container_pid.map(|pid| {
+            let proc = proc_dir(pid).unwrap_or_else(|| format!("/proc/{pid}"));
+            format!("{proc}/ns/net")
+        })
+    })
+}
+
+/// Returns the id of the cgroup v2 the process with the given host PID is in.
+/// The process is looked up in /host/proc when bpfman runs in a container with
+/// the host's /proc mounted there.
+///
+/// The path in /proc/<pid>/cgroup is relative to the root of the cgroup
+/// namespace of whoever reads it, which doesn't have to be the root of the
+/// cgroup2 mount bpfman sees. So the path is read from the process's own
+/// cgroup namespace, and looked up in the cgroup2 mount of the process's own
+/// root, which shows the root of that namespace.
+pub(crate) fn get_cgroup_id(pid: i32) -> Result<u64, BpfmanError> {
+    let proc = proc_dir(pid)
+        .ok_or_else(|| BpfmanError::Error(format!("process with pid {pid} not found")))?;
+    let cgroup_ns = File::open(format!("{proc}/ns/cgroup")).map_err(|e| {
+        BpfmanError::Error(format!("unable to open cgroup namespace of pid {pid}: {e}"))
+    })?;
+
+    // The thread entering the cgroup namespace exits with it, so bpfman's
+    // other threads stay where they are.
+    let cgroups = std::thread::scope(|s| {
+        s.spawn(|| {
+            setns(&cgroup_ns, CloneFlags::CLONE_NEWCGROUP)
+                .map_err(|e| format!("unable to enter cgroup namespace of pid {pid}: {e}"))?;
+            std::fs::read_to_string(format!("{proc}/cgroup"))
+                .map_err(|e| format!("unable to read cgroup of pid {pid}: {e}"))
+        })
+        .join()
+        .unwrap_or_else(|_| Err(format!("unable to read cgroup of pid {pid}")))
+    })
+    .map_err(BpfmanError::Error)?;
+    let path = cgroups
+        .lines()
+        .find_map(|l| l.strip_prefix("0::"))
+        .ok_or_else(|| BpfmanError::Error(format!("pid {pid} is not in a cgroup v2")))?;
+
+    // The id of a cgroup v2 is the inode number of its directory.
+    let dir = format!("{proc}/root{CGROUP2_FS}{path}");
+    metadata(&dir)
+        .map(|m| m.ino())
+        .map_err(|e| BpfmanError::Error(format!("unable to find cgroup {path} of pid {pid}: {e}")))
+}
+
+/// Returns the id of the network namespace at the given path, such as
+/// /proc/<pid>/ns/net, or None if it's bpfman's own network namespace.
+pub(crate) fn get_netns_id(netns: &str) -> Result<Option<u64>, BpfmanError> {
+    let id = metadata(netns)
+        .map_err(|e| BpfmanError::InvalidNetns(format!("{netns}: {e}")))?
+        .ino();
+    let own_id = metadata("/proc/self/ns/net")?.ino();
+    Ok((id != own_id).then_some(id))
+}
+
+// Moves the calling thread back into its original network namespace when
+// dropped.
+struct NetnsGuard {
+    original: File,
+}
+
+impl Drop for NetnsGuard {
+    fn drop(&mut self) {
+        // A thread left in another network namespace would attach everything it
+        // handles from now on in the wrong place.
+        setns(&self.original, CloneFlags::CLONE_NEWNET)
+            .expect("unable to return to the original network namespace");
+    }
+}
+
+/// Runs f with the calling thread in the given network namespace, or in
+/// bpfman's own one if there is none. f mustn't await, so interfaces and
+/// netlink sockets are looked up and created in the namespace.
+pub(crate) fn with_netns<T>(
+    netns: Option<&Netns>,
+    f: impl FnOnce() -> Result<T, BpfmanError>,
+) -> Result<T, BpfmanError> {
+    let Some(netns) = netns else {
+        return f();
+    };
+
+    let original = File::open("/proc/thread-self/ns/net")?;
+    let target = File::open(&netns.path)
+        .map_err(|e| BpfmanError::InvalidNetns(format!("{}: {e}", netns.path)))?;
+    setns(&target, CloneFlags::CLONE_NEWNET)
+        .map_err(|e| BpfmanError::InvalidNetns(format!("{}: {e}", netns.path)))?;
+    let _guard = NetnsGuard { original };
+    f()
+}
+
 pub fn set_file_permissions(path: &Path, mode: u32) {
     // Set the permissions on the file based on input
     if (set_permissions(path, std::fs::Permissions::from_mode(mode))).is_err() {
@@ -132,6 +269,17 @@ pub(crate) fn bytes_to_u64(bytes: Vec<u8>) -> u64 {
     )
 }
 
+pub(crate) fn bytes_to_u32s(bytes: Vec<u8>) -> Vec<u32> {
+    bytes
+        .chunks_exact(4)
+        .map(|c| u32::from_ne_bytes(c.try_into().expect("chunk should be 4 bytes")))
+        .collect()
+}
+
+pub(crate) fn u32s_to_bytes(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_ne_bytes()).collect()
+}
+
 // Sled in memory database helper functions which help with error handling and
 // data marshalling.
 
@@ -184,6 +332,18 @@ pub(crate) fn sled_insert(db_tree: &Tree, key: &str, value: &[u8]) -> Result<(),
     })
 }
 
+pub(crate) fn sled_remove(db_tree: &Tree, key: &str) -> Result<(), BpfmanError> {
+    db_tree.remove(key).map(|_| ()).map_err(|e| {
+        BpfmanError::DatabaseError(
+            format!(
+                "Unable to remove database entry {key} from tree {:?}",
+                db_tree.name()
+            ),
+            e.to_string(),
+        )
+    })
+}
+
 // Helper function to get the error message from stderr
 pub(crate) fn get_error_msg_from_stderr(stderr: &[u8]) -> String {
     // Convert to lines
@@ -279,3 +439,98 @@ pub(crate) fn initialize_bpfman() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_netns_path() {
+        let netns = Some("/var/run/netns/blue".to_string());
+        assert_eq!(netns_path(netns.clone(), Some(1)), netns);
+        assert_eq!(netns_path(None, None), None);
+
+        let pid = std::process::id() as i32;
+        assert_eq!(
+            netns_path(None, Some(pid)),
+            Some(format!("/proc/{pid}/ns/net"))
+        );
+    }
+
+    #[test]
+    fn test_is_iface_pattern() {
+        assert!(!is_iface_pattern("eth0"));
+        assert!(is_iface_pattern("eth*"));
+        assert!(is_iface_pattern("eth?"));
+        assert!(is_iface_pattern("eth[01]"));
+        assert!(is_iface_pattern("eth["));
+    }
+
+    #[test]
+    fn test_iface_matches() {
+        assert!(iface_matches("eth*", "eth0").unwrap());
+        assert!(!iface_matches("eth*", "ens3").unwrap());
+        assert!(iface_matches("eth?", "eth1").unwrap());
+        assert!(!iface_matches("eth?", "eth10").unwrap());
+        assert!(iface_matches("eth[01]", "eth1").unwrap());
+        assert!(!iface_matches("eth[01]", "eth2").unwrap());
+        assert!(matches!(
+            iface_matches("eth[", "eth0"),
+            Err(BpfmanError::InvalidInterfacePattern(p)) if p == "eth["
+        ));
+    }
+
+    #[test]
+    fn test_get_matching_ifaces() {
+        assert_eq!(get_matching_ifaces("l[o]").unwrap(), vec!["lo".to_string()]);
+        assert!(get_matching_ifaces("lo*")
+            .unwrap()
+            .contains(&"lo".to_string()));
+        assert!(get_matching_ifaces("no-such-iface*").unwrap().is_empty());
+        assert!(matches!(
+            get_matching_ifaces("lo["),
+            Err(BpfmanError::InvalidInterfacePattern(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_netns_id() {
+        // bpfman's own network namespace isn't treated as another one.
+        assert_eq!(get_netns_id("/proc/self/ns/net").unwrap(), None);
+        assert!(matches!(
+            get_netns_id("/no/such/netns"),
+            Err(BpfmanError::InvalidNetns(_))
+        ));
+    }
+
+    #[test]
+    fn test_with_netns() {
+        assert_eq!(with_netns(None, || Ok(42)).unwrap(), 42);
+
+        let netns = Netns {
+            path: "/no/such/netns".to_string(),
+            id: 1,
+        };
+        let mut called = false;
+        assert!(matches!(
+            with_netns(Some(&netns), || {
+                called = true;
+                Ok(())
+            }),
+            Err(BpfmanError::InvalidNetns(_))
+        ));
+        assert!(!called);
+    }
+
+    #[test]
+    fn test_get_cgroup_id_missing_pid() {
+        assert!(get_cgroup_id(i32::MAX).is_err());
+    }
+
+    #[test]
+    fn test_u32s_bytes() {
+        let values = [0, 1 << 2, u32::MAX];
+        assert_eq!(bytes_to_u32s(u32s_to_bytes(&values)), values);
+        assert!(bytes_to_u32s(vec![]).is_empty());
+    }
+}
@@ -4,6 +4,8 @@
 # programs) that happen to be attached to them.  This is intended to be part of
 # completely cleaning up the bpfman state on a system.  Caution should be used if
 # other applications are currently using these qdiscs for other purposes.
+# Dispatchers attached with the tcx or netkit tc_mode don't use a qdisc and are
+# not affected.
 
 interfaces=()
 for iface in $(ifconfig | cut -d ' ' -f1| tr ':' '\n' | awk NF)
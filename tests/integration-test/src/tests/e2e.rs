@@ -563,6 +563,61 @@ fn test_unload_tc() {
     verify_and_delete_programs(loaded_ids);
 }
 
+#[integration_test]
+fn test_tc_chain() {
+    // With more than 10 programs on a hook the dispatchers are chained. On
+    // kernels with TCX, which bpfman uses by default, this checks that the
+    // first dispatcher hands packets on to the second one.
+    let _namespace_guard = create_namespace().unwrap();
+    let _ping_guard = start_ping().unwrap();
+    let _trace_guard = start_trace_pipe().unwrap();
+
+    assert!(iface_exists(DEFAULT_BPFMAN_IFACE));
+
+    let mut loaded_ids = vec![];
+
+    debug!("Installing 10 tc ingress programs which proceed on ok");
+    for priority in 1..=10 {
+        let (prog_id, _) = add_tc(
+            "ingress",
+            DEFAULT_BPFMAN_IFACE,
+            priority,
+            Some([GLOBAL_1, "GLOBAL_u32=0A0B0C0D"].to_vec()),
+            Some(["ok", "dispatcher_return"].to_vec()),
+            &LoadType::Image,
+            &TC_PASS_IMAGE_LOC,
+            TC_PASS_FILE_LOC,
+            TC_PASS_NAME,
+        );
+        loaded_ids.push(prog_id.unwrap());
+    }
+
+    debug!("Installing an 11th tc ingress program, in the second dispatcher");
+    let (prog_id, _) = add_tc(
+        "ingress",
+        DEFAULT_BPFMAN_IFACE,
+        100,
+        Some([GLOBAL_2, "GLOBAL_u32=0A0B0C0D"].to_vec()),
+        None,
+        &LoadType::Image,
+        &TC_PASS_IMAGE_LOC,
+        TC_PASS_FILE_LOC,
+        TC_PASS_NAME,
+    );
+    loaded_ids.push(prog_id.unwrap());
+
+    debug!("wait for some traffic to generate logs...");
+    sleep(Duration::from_secs(2));
+
+    let trace_pipe_log = read_trace_pipe_log().unwrap();
+    assert!(!trace_pipe_log.is_empty());
+    assert!(trace_pipe_log.contains(TC_ING_GLOBAL_1_LOG));
+    assert!(trace_pipe_log.contains(TC_ING_GLOBAL_2_LOG));
+    debug!("Successfully completed tc chain test");
+
+    verify_and_delete_programs(loaded_ids);
+}
+
 #[integration_test]
 fn test_program_execution_with_global_variables() {
     let _namespace_guard = create_namespace().unwrap();
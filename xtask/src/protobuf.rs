@@ -18,7 +18,11 @@ fn build_bpfman(_opts: &Options) -> anyhow::Result<()> {
     let out_dir = root.join("bpfman-api/src");
     let proto_dir = root.join("proto");
 
-    let protos = &["bpfman.proto"];
+    let protos = &[
+        "bpfman.proto",
+        "google/rpc/status.proto",
+        "google/rpc/error_details.proto",
+    ];
     let includes = &[proto_dir.to_str().unwrap()];
     tonic_build::configure()
         .out_dir(out_dir)